@@ -1,17 +1,24 @@
 use flywheel_ml_proto::{
     control_service_client::ControlServiceClient,
+    feedback_service_client::FeedbackServiceClient,
     health_service_client::HealthServiceClient,
     inference_service_client::InferenceServiceClient,
     CreatePipelineRequest, CreatePipelineResponse, DeletePipelineRequest, DeletePipelineResponse,
     DisablePipelineRequest, DisablePipelineResponse, EnablePipelineRequest, EnablePipelineResponse,
-    FeatureValue, GetDriftStatusRequest, GetDriftStatusResponse, GetHealthRequest,
+    FeatureValue, Feedback, GetDriftStatusRequest, GetDriftStatusResponse, GetHealthRequest,
     GetHealthResponse, GetModelRequest, GetModelResponse, GetPipelineHealthRequest,
     GetPipelineHealthResponse, GetPipelineRequest, GetPipelineResponse, HealthCheckRequest,
-    HealthCheckResponse, ListDriftEventsRequest, ListDriftEventsResponse, ListModelsRequest,
-    ListModelsResponse, ListPipelinesRequest, ListPipelinesResponse, ModelInfoRequest,
-    ModelInfoResponse, PredictBatchRequest, PredictBatchResponse, PredictRequest, PredictResponse,
-    RegisterModelRequest, RegisterModelResponse, UnregisterModelRequest, UnregisterModelResponse,
-    UpdatePipelineRequest, UpdatePipelineResponse,
+    HealthCheckResponse, ListDeadLettersRequest, ListDeadLettersResponse, ListDriftEventsRequest,
+    ListDriftEventsResponse, ListModelPromotionsRequest, ListModelPromotionsResponse,
+    ListModelsRequest, ListModelsResponse, ListPipelinesRequest, ListPipelinesResponse,
+    ModelInfoRequest, ModelInfoResponse, PredictBatchRequest, PredictBatchResponse,
+    PredictRequest, PredictResponse, PromoteModelRequest, PromoteModelResponse,
+    RegisterModelRequest, RegisterModelResponse, ReplayDeadLettersRequest,
+    ReplayDeadLettersResponse, RollbackModelRequest, RollbackModelResponse,
+    SubmitFeedbackBatchRequest, SubmitFeedbackBatchResponse, SubmitFeedbackRequest,
+    SubmitFeedbackResponse,
+    UnregisterModelRequest, UnregisterModelResponse, UpdatePipelineRequest,
+    UpdatePipelineResponse,
 };
 use std::collections::HashMap;
 use std::time::Duration;
@@ -251,6 +258,7 @@ impl FlywheelClient {
         Ok(response.into_inner())
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn register_model(
         &self,
         model_id: impl Into<String>,
@@ -319,6 +327,89 @@ impl FlywheelClient {
         Ok(response.into_inner())
     }
 
+    pub async fn promote_model(
+        &self,
+        model_id: impl Into<String>,
+        version: impl Into<String>,
+        reason: Option<String>,
+    ) -> Result<PromoteModelResponse, ClientError> {
+        let mut client = ControlServiceClient::new(self.get_channel()?);
+        let response = client
+            .promote_model(PromoteModelRequest {
+                model_id: model_id.into(),
+                version: version.into(),
+                reason: reason.unwrap_or_default(),
+            })
+            .await?;
+        Ok(response.into_inner())
+    }
+
+    pub async fn rollback_model(
+        &self,
+        model_id: impl Into<String>,
+        version: Option<String>,
+        to_version: Option<String>,
+        reason: Option<String>,
+    ) -> Result<RollbackModelResponse, ClientError> {
+        let mut client = ControlServiceClient::new(self.get_channel()?);
+        let response = client
+            .rollback_model(RollbackModelRequest {
+                model_id: model_id.into(),
+                version: version.unwrap_or_default(),
+                to_version: to_version.unwrap_or_default(),
+                reason: reason.unwrap_or_default(),
+            })
+            .await?;
+        Ok(response.into_inner())
+    }
+
+    pub async fn list_model_promotions(
+        &self,
+        model_id: impl Into<String>,
+        limit: i32,
+    ) -> Result<ListModelPromotionsResponse, ClientError> {
+        let mut client = ControlServiceClient::new(self.get_channel()?);
+        let response = client
+            .list_model_promotions(ListModelPromotionsRequest {
+                model_id: model_id.into(),
+                limit,
+            })
+            .await?;
+        Ok(response.into_inner())
+    }
+
+    pub async fn list_dead_letters(
+        &self,
+        pipeline_id: impl Into<String>,
+        status: Option<String>,
+        limit: i32,
+    ) -> Result<ListDeadLettersResponse, ClientError> {
+        let mut client = ControlServiceClient::new(self.get_channel()?);
+        let response = client
+            .list_dead_letters(ListDeadLettersRequest {
+                pipeline_id: pipeline_id.into(),
+                status: status.unwrap_or_default(),
+                limit,
+            })
+            .await?;
+        Ok(response.into_inner())
+    }
+
+    pub async fn replay_dead_letters(
+        &self,
+        pipeline_id: impl Into<String>,
+        limit: i32,
+    ) -> Result<ReplayDeadLettersResponse, ClientError> {
+        let mut client = ControlServiceClient::new(self.get_channel()?);
+        let response = client
+            .replay_dead_letters(ReplayDeadLettersRequest {
+                pipeline_id: pipeline_id.into(),
+                limit,
+            })
+            .await?;
+        Ok(response.into_inner())
+    }
+
     pub async fn predict(
         &self,
         model_id: impl Into<String>,
@@ -390,6 +481,39 @@ impl FlywheelClient {
             .await?;
         Ok(response.into_inner())
     }
+
+    pub async fn submit_feedback(
+        &self,
+        feedback: Feedback,
+    ) -> Result<SubmitFeedbackResponse, ClientError> {
+        let mut client = FeedbackServiceClient::new(self.get_channel()?);
+        let response = client
+            .submit_feedback(SubmitFeedbackRequest {
+                feedback: Some(feedback),
+            })
+            .await?;
+        Ok(response.into_inner())
+    }
+
+    pub async fn submit_feedback_batch(
+        &self,
+        feedback: Vec<Feedback>,
+    ) -> Result<SubmitFeedbackBatchResponse, ClientError> {
+        let mut client = FeedbackServiceClient::new(self.get_channel()?);
+        let response = client
+            .submit_feedback_batch(SubmitFeedbackBatchRequest { feedback })
+            .await?;
+        Ok(response.into_inner())
+    }
+
+    pub async fn stream_feedback(
+        &self,
+        feedback: impl futures::Stream<Item = Feedback> + Send + 'static,
+    ) -> Result<SubmitFeedbackBatchResponse, ClientError> {
+        let mut client = FeedbackServiceClient::new(self.get_channel()?);
+        let response = client.stream_feedback(feedback).await?;
+        Ok(response.into_inner())
+    }
 }
 
 pub struct FlywheelClientBuilder {
@@ -27,7 +27,7 @@ pub enum FlywheelError {
     Serialization(#[from] serde_json::Error),
 }
 
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone)]
 pub enum ModelError {
     #[error("Model not found: {0}")]
     NotFound(String),
@@ -44,6 +44,9 @@ pub enum ModelError {
     #[error("Invalid input: {0}")]
     InvalidInput(String),
 
+    #[error("Invalid model: {0}")]
+    InvalidModel(String),
+
     #[error("Circuit breaker open for model: {0}")]
     CircuitBreakerOpen(String),
 
@@ -51,6 +54,18 @@ pub enum ModelError {
     Connection(String),
 }
 
+impl ModelError {
+    /// Whether the same request may succeed if sent again. Timeouts and an
+    /// unreachable model server are transient; a rejected input or a failed
+    /// inference will fail the same way every time.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            ModelError::Timeout(_) | ModelError::Unavailable(_) | ModelError::Connection(_)
+        )
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum FeatureError {
     #[error("Feature not found: {0}")]
@@ -93,6 +108,9 @@ pub enum FeedbackError {
     #[error("Feedback not found: {0}")]
     NotFound(String),
 
+    #[error("Feedback already exists: {0}")]
+    AlreadyExists(String),
+
     #[error("Prediction not found for feedback: {0}")]
     PredictionNotFound(String),
 
@@ -75,6 +75,16 @@ impl FeatureVector {
     }
 }
 
+/// The id a record is known by: its `id` field when that is a string or a
+/// number, otherwise a fresh UUID.
+pub fn record_id(record: &serde_json::Value) -> String {
+    match record.get("id") {
+        Some(serde_json::Value::String(id)) => id.clone(),
+        Some(serde_json::Value::Number(id)) => id.to_string(),
+        _ => uuid::Uuid::new_v4().to_string(),
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(untagged)]
 pub enum FeatureValue {
@@ -312,6 +322,11 @@ impl FeatureTransform {
                 let bucket = boundaries.iter().filter(|b| v >= *b).count();
                 Ok(FeatureValue::Int(bucket as i64))
             }
+            (FeatureTransform::Bucketize { boundaries }, FeatureValue::Int(v)) => {
+                let v = *v as f64;
+                let bucket = boundaries.iter().filter(|b| v >= **b).count();
+                Ok(FeatureValue::Int(bucket as i64))
+            }
             (FeatureTransform::OneHot { categories }, FeatureValue::String(s))
             | (FeatureTransform::OneHot { categories }, FeatureValue::Categorical(s)) => {
                 let one_hot: Vec<f64> = categories
@@ -323,10 +338,17 @@ impl FeatureTransform {
             (FeatureTransform::StandardScale { mean, std }, FeatureValue::Float(v)) => {
                 Ok(FeatureValue::Float((v - mean) / std))
             }
+            (FeatureTransform::StandardScale { mean, std }, FeatureValue::Int(v)) => {
+                Ok(FeatureValue::Float((*v as f64 - mean) / std))
+            }
             (FeatureTransform::MinMaxScale { min, max }, FeatureValue::Float(v)) => {
                 let scaled = (v - min) / (max - min);
                 Ok(FeatureValue::Float(scaled))
             }
+            (FeatureTransform::MinMaxScale { min, max }, FeatureValue::Int(v)) => {
+                let scaled = (*v as f64 - min) / (max - min);
+                Ok(FeatureValue::Float(scaled))
+            }
             _ => Err(FeatureError::InvalidValue {
                 feature: String::new(),
                 reason: format!(
@@ -50,8 +50,20 @@ impl FeedbackRecord {
     }
 }
 
+/// The true outcome of a prediction.
+///
+/// Serialized with the variant under `type` and its content under `value`,
+/// e.g. `{"type": "label", "value": "fraud"}` or `{"type": "binary", "value":
+/// true}`. Only a custom ground truth whose content is an object could be
+/// written before the content moved under `value`, as the object's fields
+/// next to `type`; such values are still read.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "type", rename_all = "snake_case")]
+#[serde(
+    tag = "type",
+    content = "value",
+    rename_all = "snake_case",
+    try_from = "serde_json::Value"
+)]
 pub enum GroundTruth {
     Label(String),
     Value(f64),
@@ -61,6 +73,43 @@ pub enum GroundTruth {
     Custom(serde_json::Value),
 }
 
+/// The current wire format of `GroundTruth`.
+#[derive(Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+enum TaggedGroundTruth {
+    Label(String),
+    Value(f64),
+    Binary(bool),
+    Ranking(Vec<String>),
+    MultiLabel(Vec<String>),
+    Custom(serde_json::Value),
+}
+
+impl TryFrom<serde_json::Value> for GroundTruth {
+    type Error = serde_json::Error;
+
+    fn try_from(value: serde_json::Value) -> Result<Self, Self::Error> {
+        // `{"type": "custom", "field": ...}`, without a `value`.
+        if let serde_json::Value::Object(object) = &value {
+            let is_custom = object.get("type").and_then(|t| t.as_str()) == Some("custom");
+            if is_custom && !object.contains_key("value") {
+                let mut fields = object.clone();
+                fields.remove("type");
+                return Ok(GroundTruth::Custom(serde_json::Value::Object(fields)));
+            }
+        }
+
+        Ok(match serde_json::from_value(value)? {
+            TaggedGroundTruth::Label(label) => GroundTruth::Label(label),
+            TaggedGroundTruth::Value(value) => GroundTruth::Value(value),
+            TaggedGroundTruth::Binary(b) => GroundTruth::Binary(b),
+            TaggedGroundTruth::Ranking(items) => GroundTruth::Ranking(items),
+            TaggedGroundTruth::MultiLabel(labels) => GroundTruth::MultiLabel(labels),
+            TaggedGroundTruth::Custom(value) => GroundTruth::Custom(value),
+        })
+    }
+}
+
 impl GroundTruth {
     pub fn label(label: impl Into<String>) -> Self {
         GroundTruth::Label(label.into())
@@ -343,9 +392,10 @@ impl ImplicitLabelRule {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(tag = "strategy", rename_all = "snake_case")]
 pub enum SamplingConfig {
+    #[default]
     All,
     Random {
         rate: f64,
@@ -362,12 +412,6 @@ pub enum SamplingConfig {
     },
 }
 
-impl Default for SamplingConfig {
-    fn default() -> Self {
-        SamplingConfig::All
-    }
-}
-
 impl SamplingConfig {
     pub fn should_sample(&self, example: &LabeledExample) -> bool {
         match self {
@@ -407,3 +451,31 @@ fn rand_rate(rate: f64) -> bool {
     let hash = hasher.finish();
     (hash as f64 / u64::MAX as f64) < rate
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_ground_truth_round_trip() {
+        let truths = [
+            GroundTruth::label("fraud"),
+            GroundTruth::value(4.2),
+            GroundTruth::binary(true),
+            GroundTruth::ranking(vec!["a".into(), "b".into()]),
+            GroundTruth::multi_label(vec!["x".into()]),
+            GroundTruth::Custom(json!({"score": 3})),
+        ];
+        for truth in truths {
+            let value = serde_json::to_value(&truth).unwrap();
+            let back: GroundTruth = serde_json::from_value(value.clone()).unwrap();
+            assert_eq!(serde_json::to_value(&back).unwrap(), value);
+        }
+
+        assert_eq!(
+            serde_json::to_value(GroundTruth::label("fraud")).unwrap(),
+            json!({"type": "label", "value": "fraud"})
+        );
+    }
+}
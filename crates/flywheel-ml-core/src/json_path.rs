@@ -0,0 +1,133 @@
+use std::fmt;
+
+use crate::error::FeatureError;
+
+/// A parsed JSONPath expression restricted to the subset used by pipeline
+/// manifests: `$`, `.field`, `['field']` and `[index]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonPath {
+    segments: Vec<PathSegment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    Field(String),
+    Index(usize),
+}
+
+impl JsonPath {
+    pub fn parse(path: &str) -> Result<Self, FeatureError> {
+        let err = |reason: &str| FeatureError::JsonPath(format!("{}: {}", path, reason));
+
+        let trimmed = path.trim();
+        let mut rest = trimmed.strip_prefix('$').unwrap_or(trimmed);
+        let mut segments = Vec::new();
+
+        // Allow a bare leading field (`metrics.cpu`) as shorthand for `$.metrics.cpu`.
+        if !trimmed.starts_with('$') && !rest.starts_with('[') {
+            if rest.is_empty() {
+                return Err(err("empty path"));
+            }
+            rest = &rest[rest.find(['.', '[']).unwrap_or(rest.len())..];
+            let head = &trimmed[..trimmed.len() - rest.len()];
+            segments.push(PathSegment::Field(head.to_string()));
+        }
+
+        while !rest.is_empty() {
+            if let Some(after_dot) = rest.strip_prefix('.') {
+                let end = after_dot.find(['.', '[']).unwrap_or(after_dot.len());
+                let name = &after_dot[..end];
+                if name.is_empty() {
+                    return Err(err("empty field name"));
+                }
+                segments.push(PathSegment::Field(name.to_string()));
+                rest = &after_dot[end..];
+            } else if let Some(after_bracket) = rest.strip_prefix('[') {
+                let end = after_bracket
+                    .find(']')
+                    .ok_or_else(|| err("unterminated '['"))?;
+                let inner = after_bracket[..end].trim();
+                let quoted = inner
+                    .strip_prefix('\'')
+                    .and_then(|s| s.strip_suffix('\''))
+                    .or_else(|| inner.strip_prefix('"').and_then(|s| s.strip_suffix('"')));
+                let segment = match quoted {
+                    Some(name) => PathSegment::Field(name.to_string()),
+                    None => PathSegment::Index(
+                        inner
+                            .parse()
+                            .map_err(|_| err(&format!("invalid index '{}'", inner)))?,
+                    ),
+                };
+                segments.push(segment);
+                rest = &after_bracket[end + 1..];
+            } else {
+                return Err(err(&format!("unexpected token at '{}'", rest)));
+            }
+        }
+
+        Ok(Self { segments })
+    }
+
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    /// Resolves the path against `value`, returning `None` when any segment is absent.
+    pub fn select<'a>(&self, value: &'a serde_json::Value) -> Option<&'a serde_json::Value> {
+        self.segments
+            .iter()
+            .try_fold(value, |current, segment| match segment {
+                PathSegment::Field(name) => current.get(name.as_str()),
+                PathSegment::Index(index) => current.get(*index),
+            })
+    }
+}
+
+impl fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "$")?;
+        for segment in &self.segments {
+            match segment {
+                PathSegment::Field(name) if name.contains(['.', '[', ']', '\'']) => {
+                    write!(f, "[\"{}\"]", name)?
+                }
+                PathSegment::Field(name) => write!(f, ".{}", name)?,
+                PathSegment::Index(index) => write!(f, "[{}]", index)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for JsonPath {
+    type Err = FeatureError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_select_brackets_and_indices() {
+        let record = json!({"hosts": [{"name": "a"}, {"name": "b"}], "odd.key": 1});
+        assert_eq!(
+            JsonPath::parse("$.hosts[1].name").unwrap().select(&record),
+            Some(&json!("b"))
+        );
+        assert_eq!(
+            JsonPath::parse("$['odd.key']").unwrap().select(&record),
+            Some(&json!(1))
+        );
+        assert_eq!(
+            JsonPath::parse("hosts[0]['name']").unwrap().select(&record),
+            Some(&json!("a"))
+        );
+        assert_eq!(JsonPath::parse("$.hosts[5]").unwrap().select(&record), None);
+    }
+}
@@ -1,11 +1,13 @@
 pub mod error;
 pub mod feature;
 pub mod feedback;
+pub mod json_path;
 pub mod model;
 pub mod prediction;
 
 pub use error::*;
 pub use feature::*;
 pub use feedback::*;
+pub use json_path::*;
 pub use model::*;
 pub use prediction::*;
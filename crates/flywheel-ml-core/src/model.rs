@@ -88,6 +88,20 @@ impl ModelType {
             ModelType::Custom => "custom",
         }
     }
+
+    /// The type `as_str` names, if any.
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            ModelType::AnomalyDetection,
+            ModelType::Classification,
+            ModelType::Regression,
+            ModelType::Clustering,
+            ModelType::Embedding,
+            ModelType::Custom,
+        ]
+        .into_iter()
+        .find(|t| t.as_str() == name)
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -215,12 +229,44 @@ pub enum FallbackStrategy {
     Error,
 }
 
+impl FallbackStrategy {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            FallbackStrategy::ReturnNull => "return_null",
+            FallbackStrategy::UseDefault(_) => "use_default",
+            FallbackStrategy::UseLastKnown => "use_last_known",
+            FallbackStrategy::SendToDlq => "send_to_dlq",
+            FallbackStrategy::PassThrough => "pass_through",
+            FallbackStrategy::Error => "error",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CircuitBreakerConfig {
+    /// Consecutive failures that open the circuit.
     pub failure_threshold: u32,
+    /// Successful probes that close a half-open circuit.
     pub success_threshold: u32,
+    /// Probes allowed in flight while half-open.
     pub half_open_max_calls: u32,
     pub reset_timeout_secs: u64,
+    /// Failure rate over `window_secs` that opens the circuit, once the
+    /// window holds `min_calls` calls.
+    #[serde(default)]
+    pub failure_rate_threshold: Option<f64>,
+    #[serde(default = "default_circuit_window_secs")]
+    pub window_secs: u64,
+    #[serde(default = "default_circuit_min_calls")]
+    pub min_calls: u32,
+}
+
+fn default_circuit_window_secs() -> u64 {
+    60
+}
+
+fn default_circuit_min_calls() -> u32 {
+    20
 }
 
 impl Default for CircuitBreakerConfig {
@@ -230,6 +276,9 @@ impl Default for CircuitBreakerConfig {
             success_threshold: 3,
             half_open_max_calls: 3,
             reset_timeout_secs: 30,
+            failure_rate_threshold: None,
+            window_secs: default_circuit_window_secs(),
+            min_calls: default_circuit_min_calls(),
         }
     }
 }
@@ -252,3 +301,33 @@ impl Default for RetryConfig {
         }
     }
 }
+
+impl RetryConfig {
+    /// Delay before retry number `retry` (starting at 0), growing by
+    /// `multiplier` from `initial_delay_ms` and capped at `max_delay_ms`.
+    pub fn delay(&self, retry: u32) -> std::time::Duration {
+        let delay = self.initial_delay_ms as f64 * self.multiplier.max(1.0).powi(retry as i32);
+        std::time::Duration::from_millis(delay.min(self.max_delay_ms as f64) as u64)
+    }
+}
+
+/// How a bandit splits traffic between the models it compares.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BanditPolicy {
+    /// Serves the model with the best observed reward, and a model picked at
+    /// random for an `epsilon` share of records.
+    EpsilonGreedy { epsilon: f64 },
+    /// Serves each record with the model whose reward, drawn from its
+    /// posterior, is highest.
+    ThompsonSampling,
+}
+
+impl BanditPolicy {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            BanditPolicy::EpsilonGreedy { .. } => "epsilon_greedy",
+            BanditPolicy::ThompsonSampling => "thompson_sampling",
+        }
+    }
+}
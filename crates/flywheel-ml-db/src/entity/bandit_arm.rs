@@ -0,0 +1,64 @@
+use sea_orm::entity::prelude::*;
+use sea_orm::sea_query::{Index, IndexCreateStatement};
+use serde::{Deserialize, Serialize};
+
+/// What the bandit of an ml-inference stage has learned about one of the
+/// models it splits traffic between, saved so it resumes across restarts.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "bandit_arms")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub pipeline_id: Uuid,
+    #[sea_orm(column_type = "String(StringLen::N(255))")]
+    pub stage_id: String,
+    #[sea_orm(column_type = "String(StringLen::N(255))")]
+    pub model_id: String,
+    /// The version the model last reported, empty before its first prediction.
+    #[sea_orm(column_type = "String(StringLen::N(64))")]
+    pub model_version: String,
+    #[sea_orm(column_type = "String(StringLen::N(32))")]
+    pub policy: String,
+    /// Records the model was chosen to serve.
+    pub pulls: i64,
+    /// Served predictions that feedback found correct.
+    pub successes: i64,
+    /// Served predictions that feedback found wrong.
+    pub failures: i64,
+    /// Share of the stage's records, from 0 to 1, the model is expected to
+    /// serve.
+    pub traffic_share: f64,
+    pub updated_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::pipeline::Entity",
+        from = "Column::PipelineId",
+        to = "super::pipeline::Column::Id"
+    )]
+    Pipeline,
+}
+
+impl Related<super::pipeline::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Pipeline.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+/// The columns naming one arm of a stage's bandit, unique together. Saving
+/// an arm again replaces what was saved for it.
+pub const ARM_KEY: [Column; 3] = [Column::PipelineId, Column::StageId, Column::ModelId];
+
+/// The unique index over [`ARM_KEY`], as migration m20240107 creates it.
+pub fn arm_key_index() -> IndexCreateStatement {
+    let mut index = Index::create();
+    index.name("idx_bandit_arms_pipeline_stage_model").table(Entity).unique();
+    for column in ARM_KEY {
+        index.col(column);
+    }
+    index
+}
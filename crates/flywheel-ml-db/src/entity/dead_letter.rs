@@ -0,0 +1,56 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::N(32))")]
+pub enum DeadLetterStatus {
+    #[sea_orm(string_value = "pending")]
+    Pending,
+    /// Waiting for the pipeline runner to send it back through its stage.
+    #[sea_orm(string_value = "queued")]
+    Queued,
+    #[sea_orm(string_value = "replayed")]
+    Replayed,
+}
+
+/// A record an ml-inference stage could not score, parked by the
+/// `send_to_dlq` fallback with the features it was scored on, or one a
+/// blocking drift-detection stage held back while drift was open.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "dead_letters")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub pipeline_id: Uuid,
+    #[sea_orm(column_type = "String(StringLen::N(255))")]
+    pub stage_id: String,
+    #[sea_orm(column_type = "String(StringLen::N(255))")]
+    pub record_id: String,
+    #[sea_orm(column_type = "String(StringLen::N(255))")]
+    pub model_id: String,
+    pub payload_json: Json,
+    pub features_json: Option<Json>,
+    #[sea_orm(column_type = "Text")]
+    pub error_message: String,
+    pub status: DeadLetterStatus,
+    pub created_at: DateTimeUtc,
+    pub replayed_at: Option<DateTimeUtc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::pipeline::Entity",
+        from = "Column::PipelineId",
+        to = "super::pipeline::Column::Id"
+    )]
+    Pipeline,
+}
+
+impl Related<super::pipeline::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Pipeline.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
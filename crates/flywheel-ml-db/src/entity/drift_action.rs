@@ -0,0 +1,60 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::N(32))")]
+pub enum DriftActionKind {
+    #[sea_orm(string_value = "alert")]
+    Alert,
+    #[sea_orm(string_value = "retrain")]
+    Retrain,
+    #[sea_orm(string_value = "fallback")]
+    Fallback,
+    #[sea_orm(string_value = "restore")]
+    Restore,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::N(32))")]
+pub enum DriftActionStatus {
+    /// Recorded but not yet picked up, e.g. a retrain job awaiting a trainer.
+    #[sea_orm(string_value = "pending")]
+    Pending,
+    #[sea_orm(string_value = "succeeded")]
+    Succeeded,
+    #[sea_orm(string_value = "failed")]
+    Failed,
+}
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "drift_actions")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub drift_event_id: Uuid,
+    pub pipeline_id: Uuid,
+    pub action: DriftActionKind,
+    pub status: DriftActionStatus,
+    pub details_json: Json,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub error_message: Option<String>,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::drift_event::Entity",
+        from = "Column::DriftEventId",
+        to = "super::drift_event::Column::Id"
+    )]
+    DriftEvent,
+}
+
+impl Related<super::drift_event::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::DriftEvent.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -10,6 +10,12 @@ pub enum DriftType {
     Performance,
     #[sea_orm(string_value = "both")]
     Both,
+    #[sea_orm(string_value = "prediction")]
+    Prediction,
+    #[sea_orm(string_value = "label")]
+    Label,
+    #[sea_orm(string_value = "concept")]
+    Concept,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
@@ -38,6 +44,8 @@ pub struct Model {
     pub psi_score: Option<f64>,
     pub kl_divergence: Option<f64>,
     pub accuracy_delta: Option<f64>,
+    /// Per-feature drift results, most drifted first.
+    pub feature_drifts_json: Option<Json>,
     pub detected_at: DateTimeUtc,
     pub resolved_at: Option<DateTimeUtc>,
 }
@@ -20,12 +20,28 @@ pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
     pub id: Uuid,
     pub prediction_id: Uuid,
+    /// A readable summary of `ground_truth_json`, cut to fit.
     #[sea_orm(column_type = "String(StringLen::N(255))")]
     pub ground_truth: String,
     pub source: FeedbackSource,
     pub confidence: f64,
     pub received_at: DateTimeUtc,
     pub exported: bool,
+    /// The model that made the prediction; empty for rows stored before it
+    /// was recorded.
+    #[sea_orm(column_type = "String(StringLen::N(255))")]
+    pub model_id: String,
+    /// The `GroundTruth` as reported.
+    pub ground_truth_json: Option<Json>,
+    /// The `FeedbackSource` as reported.
+    pub source_json: Option<Json>,
+    pub metadata_json: Option<Json>,
+    /// When the feedback says the outcome was observed.
+    pub feedback_time: Option<DateTimeUtc>,
+    /// Time from the prediction to `feedback_time`.
+    pub delay_ms: Option<i64>,
+    /// Whether the prediction was right, when the ground truth can judge it.
+    pub is_correct: Option<bool>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -1,12 +1,20 @@
+pub mod bandit_arm;
+pub mod dead_letter;
+pub mod drift_action;
 pub mod drift_event;
 pub mod feedback;
+pub mod model_promotion;
 pub mod model_version;
 pub mod pipeline;
 pub mod pipeline_run;
 pub mod prediction;
 
+pub use bandit_arm::Entity as BanditArm;
+pub use dead_letter::Entity as DeadLetter;
+pub use drift_action::Entity as DriftAction;
 pub use drift_event::Entity as DriftEvent;
 pub use feedback::Entity as Feedback;
+pub use model_promotion::Entity as ModelPromotion;
 pub use model_version::Entity as ModelVersion;
 pub use pipeline::Entity as Pipeline;
 pub use pipeline_run::Entity as PipelineRun;
@@ -0,0 +1,55 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::N(32))")]
+pub enum PromotionAction {
+    /// The version became the active one.
+    #[sea_orm(string_value = "promote")]
+    Promote,
+    /// The version was taken out of service and marked failed.
+    #[sea_orm(string_value = "rollback")]
+    Rollback,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::N(32))")]
+pub enum PromotionTrigger {
+    /// Decided by the promotion controller from live performance.
+    #[sea_orm(string_value = "automatic")]
+    Automatic,
+    /// Requested by an operator.
+    #[sea_orm(string_value = "manual")]
+    Manual,
+}
+
+/// A decision to promote or roll back a model version, with why it was made.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "model_promotions")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    #[sea_orm(column_type = "String(StringLen::N(255))")]
+    pub model_id: String,
+    /// The version promoted or rolled back.
+    #[sea_orm(column_type = "String(StringLen::N(64))")]
+    pub version: String,
+    /// The version serving the model before the decision, if any.
+    #[sea_orm(column_type = "String(StringLen::N(64))", nullable)]
+    pub previous_version: Option<String>,
+    /// The version serving the model once the decision took effect.
+    #[sea_orm(column_type = "String(StringLen::N(64))", nullable)]
+    pub active_version: Option<String>,
+    pub action: PromotionAction,
+    pub trigger: PromotionTrigger,
+    /// A JSON array of human-readable reasons.
+    pub reasons_json: Json,
+    /// The measurements the decision was based on.
+    pub metrics_json: Json,
+    pub decided_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
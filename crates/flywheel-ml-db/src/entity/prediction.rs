@@ -15,6 +15,11 @@ pub struct Model {
     pub prediction_json: Json,
     pub created_at: DateTimeUtc,
     pub feedback_id: Option<Uuid>,
+    /// How the prediction was served: `primary`, `canary`, `bandit` or `shadow`.
+    #[sea_orm(column_type = "String(StringLen::N(16))")]
+    pub variant: String,
+    /// For a shadow prediction, the prediction served for the same record.
+    pub shadow_of: Option<Uuid>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -0,0 +1,88 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(DriftActions::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(DriftActions::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(DriftActions::DriftEventId).uuid().not_null())
+                    .col(ColumnDef::new(DriftActions::PipelineId).uuid().not_null())
+                    .col(
+                        ColumnDef::new(DriftActions::Action)
+                            .string_len(32)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(DriftActions::Status)
+                            .string_len(32)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(DriftActions::DetailsJson).json().not_null())
+                    .col(ColumnDef::new(DriftActions::ErrorMessage).text())
+                    .col(
+                        ColumnDef::new(DriftActions::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(DriftActions::Table, DriftActions::DriftEventId)
+                            .to(DriftEvents::Table, DriftEvents::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_drift_actions_drift_event_id")
+                    .table(DriftActions::Table)
+                    .col(DriftActions::DriftEventId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(DriftActions::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum DriftEvents {
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum DriftActions {
+    Table,
+    Id,
+    DriftEventId,
+    PipelineId,
+    Action,
+    Status,
+    DetailsJson,
+    ErrorMessage,
+    CreatedAt,
+}
@@ -0,0 +1,103 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(DeadLetters::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(DeadLetters::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(DeadLetters::PipelineId).uuid().not_null())
+                    .col(
+                        ColumnDef::new(DeadLetters::StageId)
+                            .string_len(255)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(DeadLetters::RecordId)
+                            .string_len(255)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(DeadLetters::ModelId)
+                            .string_len(255)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(DeadLetters::PayloadJson).json().not_null())
+                    .col(ColumnDef::new(DeadLetters::FeaturesJson).json())
+                    .col(ColumnDef::new(DeadLetters::ErrorMessage).text().not_null())
+                    .col(
+                        ColumnDef::new(DeadLetters::Status)
+                            .string_len(32)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(DeadLetters::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(DeadLetters::ReplayedAt).timestamp_with_time_zone())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(DeadLetters::Table, DeadLetters::PipelineId)
+                            .to(Pipelines::Table, Pipelines::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_dead_letters_pipeline_status")
+                    .table(DeadLetters::Table)
+                    .col(DeadLetters::PipelineId)
+                    .col(DeadLetters::Status)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(DeadLetters::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Pipelines {
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum DeadLetters {
+    Table,
+    Id,
+    PipelineId,
+    StageId,
+    RecordId,
+    ModelId,
+    PayloadJson,
+    FeaturesJson,
+    ErrorMessage,
+    Status,
+    CreatedAt,
+    ReplayedAt,
+}
@@ -0,0 +1,73 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let columns = [
+            ColumnDef::new(Predictions::Variant)
+                .string_len(16)
+                .not_null()
+                .default("primary")
+                .to_owned(),
+            ColumnDef::new(Predictions::ShadowOf).uuid().to_owned(),
+        ];
+        // One column per statement, as SQLite alters a table a change at a time.
+        for mut column in columns {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Predictions::Table)
+                        .add_column_if_not_exists(&mut column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_predictions_shadow_of")
+                    .table(Predictions::Table)
+                    .col(Predictions::ShadowOf)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_predictions_shadow_of")
+                    .table(Predictions::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        for column in [Predictions::Variant, Predictions::ShadowOf] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Predictions::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Predictions {
+    Table,
+    Variant,
+    ShadowOf,
+}
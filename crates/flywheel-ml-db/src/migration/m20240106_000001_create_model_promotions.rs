@@ -0,0 +1,89 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ModelPromotions::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ModelPromotions::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ModelPromotions::ModelId)
+                            .string_len(255)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ModelPromotions::Version)
+                            .string_len(64)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ModelPromotions::PreviousVersion).string_len(64))
+                    .col(ColumnDef::new(ModelPromotions::ActiveVersion).string_len(64))
+                    .col(
+                        ColumnDef::new(ModelPromotions::Action)
+                            .string_len(32)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ModelPromotions::Trigger)
+                            .string_len(32)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ModelPromotions::ReasonsJson).json().not_null())
+                    .col(ColumnDef::new(ModelPromotions::MetricsJson).json().not_null())
+                    .col(
+                        ColumnDef::new(ModelPromotions::DecidedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_model_promotions_model_decided")
+                    .table(ModelPromotions::Table)
+                    .col(ModelPromotions::ModelId)
+                    .col(ModelPromotions::DecidedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ModelPromotions::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum ModelPromotions {
+    Table,
+    Id,
+    ModelId,
+    Version,
+    PreviousVersion,
+    ActiveVersion,
+    Action,
+    Trigger,
+    ReasonsJson,
+    MetricsJson,
+    DecidedAt,
+}
@@ -0,0 +1,120 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(BanditArms::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(BanditArms::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(BanditArms::PipelineId).uuid().not_null())
+                    .col(
+                        ColumnDef::new(BanditArms::StageId)
+                            .string_len(255)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(BanditArms::ModelId)
+                            .string_len(255)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(BanditArms::ModelVersion)
+                            .string_len(64)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(BanditArms::Policy).string_len(32).not_null())
+                    .col(ColumnDef::new(BanditArms::Pulls).big_integer().not_null())
+                    .col(
+                        ColumnDef::new(BanditArms::Successes)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(BanditArms::Failures)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(BanditArms::TrafficShare).double().not_null())
+                    .col(
+                        ColumnDef::new(BanditArms::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(BanditArms::Table, BanditArms::PipelineId)
+                            .to(Pipelines::Table, Pipelines::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_bandit_arms_pipeline_stage_model")
+                    .table(BanditArms::Table)
+                    .col(BanditArms::PipelineId)
+                    .col(BanditArms::StageId)
+                    .col(BanditArms::ModelId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_bandit_arms_model")
+                    .table(BanditArms::Table)
+                    .col(BanditArms::ModelId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(BanditArms::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Pipelines {
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum BanditArms {
+    Table,
+    Id,
+    PipelineId,
+    StageId,
+    ModelId,
+    ModelVersion,
+    Policy,
+    Pulls,
+    Successes,
+    Failures,
+    TrafficShare,
+    UpdatedAt,
+}
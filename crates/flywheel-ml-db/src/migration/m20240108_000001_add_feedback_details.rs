@@ -0,0 +1,101 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let columns = [
+            ColumnDef::new(Feedback::ModelId)
+                .string_len(255)
+                .not_null()
+                .default("")
+                .to_owned(),
+            ColumnDef::new(Feedback::GroundTruthJson).json().to_owned(),
+            ColumnDef::new(Feedback::SourceJson).json().to_owned(),
+            ColumnDef::new(Feedback::MetadataJson).json().to_owned(),
+            ColumnDef::new(Feedback::FeedbackTime)
+                .timestamp_with_time_zone()
+                .to_owned(),
+            ColumnDef::new(Feedback::DelayMs).big_integer().to_owned(),
+            ColumnDef::new(Feedback::IsCorrect).boolean().to_owned(),
+        ];
+        // One column per statement, as SQLite alters a table a change at a time.
+        for mut column in columns {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Feedback::Table)
+                        .add_column_if_not_exists(&mut column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_feedback_prediction")
+                    .table(Feedback::Table)
+                    .col(Feedback::PredictionId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_feedback_model_received")
+                    .table(Feedback::Table)
+                    .col(Feedback::ModelId)
+                    .col(Feedback::ReceivedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for index in ["idx_feedback_prediction", "idx_feedback_model_received"] {
+            manager
+                .drop_index(Index::drop().name(index).table(Feedback::Table).to_owned())
+                .await?;
+        }
+
+        let columns = [
+            Feedback::ModelId,
+            Feedback::GroundTruthJson,
+            Feedback::SourceJson,
+            Feedback::MetadataJson,
+            Feedback::FeedbackTime,
+            Feedback::DelayMs,
+            Feedback::IsCorrect,
+        ];
+        for column in columns {
+            manager
+                .alter_table(Table::alter().table(Feedback::Table).drop_column(column).to_owned())
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+#[allow(clippy::enum_variant_names)]
+enum Feedback {
+    Table,
+    PredictionId,
+    ReceivedAt,
+    ModelId,
+    GroundTruthJson,
+    SourceJson,
+    MetadataJson,
+    FeedbackTime,
+    DelayMs,
+    IsCorrect,
+}
@@ -1,12 +1,28 @@
 use sea_orm_migration::prelude::*;
 
 mod m20240101_000001_create_tables;
+mod m20240102_000001_create_drift_actions;
+mod m20240103_000001_add_drift_event_feature_drifts;
+mod m20240104_000001_create_dead_letters;
+mod m20240105_000001_add_prediction_variants;
+mod m20240106_000001_create_model_promotions;
+mod m20240107_000001_create_bandit_arms;
+mod m20240108_000001_add_feedback_details;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20240101_000001_create_tables::Migration)]
+        vec![
+            Box::new(m20240101_000001_create_tables::Migration),
+            Box::new(m20240102_000001_create_drift_actions::Migration),
+            Box::new(m20240103_000001_add_drift_event_feature_drifts::Migration),
+            Box::new(m20240104_000001_create_dead_letters::Migration),
+            Box::new(m20240105_000001_add_prediction_variants::Migration),
+            Box::new(m20240106_000001_create_model_promotions::Migration),
+            Box::new(m20240107_000001_create_bandit_arms::Migration),
+            Box::new(m20240108_000001_add_feedback_details::Migration),
+        ]
     }
 }
@@ -1,7 +1,10 @@
 use sea_orm::*;
 use uuid::Uuid;
 
-use crate::entity::{pipeline, pipeline_run, model_version, drift_event, prediction, feedback};
+use crate::entity::{
+    bandit_arm, dead_letter, drift_action, drift_event, feedback, model_promotion, model_version,
+    pipeline, prediction,
+};
 
 pub struct PipelineRepo;
 
@@ -117,8 +120,8 @@ impl ModelVersionRepo {
         model.insert(db).await
     }
 
-    pub async fn find_by_model_id(
-        db: &DatabaseConnection,
+    pub async fn find_by_model_id<C: ConnectionTrait>(
+        db: &C,
         model_id: &str,
     ) -> Result<Option<model_version::Model>, DbErr> {
         model_version::Entity::find()
@@ -129,6 +132,19 @@ impl ModelVersionRepo {
             .await
     }
 
+    pub async fn find_by_version(
+        db: &DatabaseConnection,
+        model_id: &str,
+        version: &str,
+    ) -> Result<Option<model_version::Model>, DbErr> {
+        model_version::Entity::find()
+            .filter(model_version::Column::ModelId.eq(model_id))
+            .filter(model_version::Column::Version.eq(version))
+            .order_by_desc(model_version::Column::DeployedAt)
+            .one(db)
+            .await
+    }
+
     pub async fn list(db: &DatabaseConnection, limit: u64) -> Result<Vec<model_version::Model>, DbErr> {
         model_version::Entity::find()
             .order_by_desc(model_version::Column::DeployedAt)
@@ -151,11 +167,123 @@ impl ModelVersionRepo {
         };
         model.update(db).await
     }
+
+    pub async fn list_by_status(
+        db: &DatabaseConnection,
+        status: model_version::ModelStatus,
+    ) -> Result<Vec<model_version::Model>, DbErr> {
+        model_version::Entity::find()
+            .filter(model_version::Column::Status.eq(status))
+            .order_by_asc(model_version::Column::DeployedAt)
+            .all(db)
+            .await
+    }
+
+    /// The most recently deployed version that was replaced by another.
+    pub async fn find_previous(
+        db: &DatabaseConnection,
+        model_id: &str,
+    ) -> Result<Option<model_version::Model>, DbErr> {
+        model_version::Entity::find()
+            .filter(model_version::Column::ModelId.eq(model_id))
+            .filter(model_version::Column::Status.eq(model_version::ModelStatus::Deprecated))
+            .order_by_desc(model_version::Column::DeployedAt)
+            .one(db)
+            .await
+    }
+
+    /// Makes a version the active one of its model, deprecating the version
+    /// it replaces, and stamps it as deployed now. Inside a transaction, it
+    /// commits with it.
+    pub async fn activate<C: ConnectionTrait + TransactionTrait>(
+        db: &C,
+        version: &model_version::Model,
+    ) -> Result<model_version::Model, DbErr> {
+        let txn = db.begin().await?;
+        model_version::Entity::update_many()
+            .col_expr(
+                model_version::Column::Status,
+                sea_query::Expr::value(model_version::ModelStatus::Deprecated),
+            )
+            .filter(model_version::Column::ModelId.eq(version.model_id.as_str()))
+            .filter(model_version::Column::Status.eq(model_version::ModelStatus::Active))
+            .filter(model_version::Column::Id.ne(version.id))
+            .exec(&txn)
+            .await?;
+        let model = model_version::ActiveModel {
+            id: Set(version.id),
+            status: Set(model_version::ModelStatus::Active),
+            deployed_at: Set(chrono::Utc::now()),
+            ..Default::default()
+        };
+        let activated = model.update(&txn).await?;
+        txn.commit().await?;
+        Ok(activated)
+    }
+
+    pub async fn set_status<C: ConnectionTrait>(
+        db: &C,
+        id: Uuid,
+        status: model_version::ModelStatus,
+    ) -> Result<model_version::Model, DbErr> {
+        let model = model_version::ActiveModel {
+            id: Set(id),
+            status: Set(status),
+            ..Default::default()
+        };
+        model.update(db).await
+    }
+}
+
+pub struct ModelPromotionRepo;
+
+impl ModelPromotionRepo {
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create<C: ConnectionTrait>(
+        db: &C,
+        model_id: String,
+        version: String,
+        previous_version: Option<String>,
+        active_version: Option<String>,
+        action: model_promotion::PromotionAction,
+        trigger: model_promotion::PromotionTrigger,
+        reasons_json: serde_json::Value,
+        metrics_json: serde_json::Value,
+    ) -> Result<model_promotion::Model, DbErr> {
+        let model = model_promotion::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            model_id: Set(model_id),
+            version: Set(version),
+            previous_version: Set(previous_version),
+            active_version: Set(active_version),
+            action: Set(action),
+            trigger: Set(trigger),
+            reasons_json: Set(reasons_json),
+            metrics_json: Set(metrics_json),
+            decided_at: Set(chrono::Utc::now()),
+        };
+        model.insert(db).await
+    }
+
+    /// Decisions about a model, newest first.
+    pub async fn list_by_model(
+        db: &DatabaseConnection,
+        model_id: &str,
+        limit: u64,
+    ) -> Result<Vec<model_promotion::Model>, DbErr> {
+        model_promotion::Entity::find()
+            .filter(model_promotion::Column::ModelId.eq(model_id))
+            .order_by_desc(model_promotion::Column::DecidedAt)
+            .limit(limit)
+            .all(db)
+            .await
+    }
 }
 
 pub struct DriftEventRepo;
 
 impl DriftEventRepo {
+    #[allow(clippy::too_many_arguments)]
     pub async fn create(
         db: &DatabaseConnection,
         pipeline_id: Uuid,
@@ -165,6 +293,7 @@ impl DriftEventRepo {
         psi_score: Option<f64>,
         kl_divergence: Option<f64>,
         accuracy_delta: Option<f64>,
+        feature_drifts_json: Option<serde_json::Value>,
     ) -> Result<drift_event::Model, DbErr> {
         let model = drift_event::ActiveModel {
             id: Set(Uuid::new_v4()),
@@ -175,6 +304,7 @@ impl DriftEventRepo {
             psi_score: Set(psi_score),
             kl_divergence: Set(kl_divergence),
             accuracy_delta: Set(accuracy_delta),
+            feature_drifts_json: Set(feature_drifts_json),
             detected_at: Set(chrono::Utc::now()),
             resolved_at: Set(None),
         };
@@ -194,6 +324,21 @@ impl DriftEventRepo {
             .await
     }
 
+    /// The latest unresolved event for a model of a pipeline.
+    pub async fn find_open(
+        db: &DatabaseConnection,
+        pipeline_id: Uuid,
+        model_id: &str,
+    ) -> Result<Option<drift_event::Model>, DbErr> {
+        drift_event::Entity::find()
+            .filter(drift_event::Column::PipelineId.eq(pipeline_id))
+            .filter(drift_event::Column::ModelId.eq(model_id))
+            .filter(drift_event::Column::ResolvedAt.is_null())
+            .order_by_desc(drift_event::Column::DetectedAt)
+            .one(db)
+            .await
+    }
+
     pub async fn resolve(db: &DatabaseConnection, id: Uuid) -> Result<drift_event::Model, DbErr> {
         let model = drift_event::ActiveModel {
             id: Set(id),
@@ -204,6 +349,71 @@ impl DriftEventRepo {
     }
 }
 
+pub struct DriftActionRepo;
+
+impl DriftActionRepo {
+    pub async fn create(
+        db: &DatabaseConnection,
+        drift_event_id: Uuid,
+        pipeline_id: Uuid,
+        action: drift_action::DriftActionKind,
+        status: drift_action::DriftActionStatus,
+        details_json: serde_json::Value,
+        error_message: Option<String>,
+    ) -> Result<drift_action::Model, DbErr> {
+        let model = drift_action::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            drift_event_id: Set(drift_event_id),
+            pipeline_id: Set(pipeline_id),
+            action: Set(action),
+            status: Set(status),
+            details_json: Set(details_json),
+            error_message: Set(error_message),
+            created_at: Set(chrono::Utc::now()),
+        };
+        model.insert(db).await
+    }
+
+    pub async fn list_by_event(
+        db: &DatabaseConnection,
+        drift_event_id: Uuid,
+    ) -> Result<Vec<drift_action::Model>, DbErr> {
+        drift_action::Entity::find()
+            .filter(drift_action::Column::DriftEventId.eq(drift_event_id))
+            .order_by_asc(drift_action::Column::CreatedAt)
+            .all(db)
+            .await
+    }
+
+    pub async fn list_by_status(
+        db: &DatabaseConnection,
+        action: drift_action::DriftActionKind,
+        status: drift_action::DriftActionStatus,
+        limit: u64,
+    ) -> Result<Vec<drift_action::Model>, DbErr> {
+        drift_action::Entity::find()
+            .filter(drift_action::Column::Action.eq(action))
+            .filter(drift_action::Column::Status.eq(status))
+            .order_by_asc(drift_action::Column::CreatedAt)
+            .limit(limit)
+            .all(db)
+            .await
+    }
+
+    pub async fn update_status(
+        db: &DatabaseConnection,
+        id: Uuid,
+        status: drift_action::DriftActionStatus,
+    ) -> Result<drift_action::Model, DbErr> {
+        let model = drift_action::ActiveModel {
+            id: Set(id),
+            status: Set(status),
+            ..Default::default()
+        };
+        model.update(db).await
+    }
+}
+
 pub struct PredictionRepo;
 
 impl PredictionRepo {
@@ -214,6 +424,32 @@ impl PredictionRepo {
         model_version: String,
         features_json: serde_json::Value,
         prediction_json: serde_json::Value,
+    ) -> Result<prediction::Model, DbErr> {
+        Self::create_variant(
+            db,
+            pipeline_id,
+            model_id,
+            model_version,
+            features_json,
+            prediction_json,
+            "primary".to_string(),
+            None,
+        )
+        .await
+    }
+
+    /// Stores a prediction served by a canary or shadow model. A shadow
+    /// prediction names the prediction served for the same record.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_variant(
+        db: &DatabaseConnection,
+        pipeline_id: Uuid,
+        model_id: String,
+        model_version: String,
+        features_json: serde_json::Value,
+        prediction_json: serde_json::Value,
+        variant: String,
+        shadow_of: Option<Uuid>,
     ) -> Result<prediction::Model, DbErr> {
         let model = prediction::ActiveModel {
             id: Set(Uuid::new_v4()),
@@ -224,6 +460,8 @@ impl PredictionRepo {
             prediction_json: Set(prediction_json),
             created_at: Set(chrono::Utc::now()),
             feedback_id: Set(None),
+            variant: Set(variant),
+            shadow_of: Set(shadow_of),
         };
         model.insert(db).await
     }
@@ -244,10 +482,44 @@ impl PredictionRepo {
         };
         model.update(db).await
     }
+
+    /// The shadow predictions made for the same records as `prediction_id`.
+    pub async fn find_shadows(
+        db: &DatabaseConnection,
+        prediction_id: Uuid,
+    ) -> Result<Vec<prediction::Model>, DbErr> {
+        prediction::Entity::find()
+            .filter(prediction::Column::ShadowOf.eq(prediction_id))
+            .all(db)
+            .await
+    }
+
+    /// Predictions `model_id` served since `since`; shadow predictions
+    /// reach no one, so they are not counted.
+    pub async fn count_served_since(
+        db: &DatabaseConnection,
+        model_id: &str,
+        since: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, DbErr> {
+        prediction::Entity::find()
+            .filter(prediction::Column::ModelId.eq(model_id))
+            .filter(prediction::Column::CreatedAt.gte(since))
+            .filter(prediction::Column::Variant.ne("shadow"))
+            .count(db)
+            .await
+    }
+
 }
 
 pub struct FeedbackRepo;
 
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FeedbackCounts {
+    pub total: u64,
+    pub correct: u64,
+    pub incorrect: u64,
+}
+
 impl FeedbackRepo {
     pub async fn create(
         db: &DatabaseConnection,
@@ -264,10 +536,105 @@ impl FeedbackRepo {
             confidence: Set(confidence),
             received_at: Set(chrono::Utc::now()),
             exported: Set(false),
+            model_id: Set(String::new()),
+            ground_truth_json: Set(None),
+            source_json: Set(None),
+            metadata_json: Set(None),
+            feedback_time: Set(None),
+            delay_ms: Set(None),
+            is_correct: Set(None),
         };
         model.insert(db).await
     }
 
+    /// Stores feedback with its ground truth and source as reported, under
+    /// the id the caller assigned it, and links its prediction to it. Both
+    /// happen in one transaction, which fails with `RecordNotUpdated` when
+    /// the prediction is missing or already linked to other feedback.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_record(
+        db: &DatabaseConnection,
+        id: Uuid,
+        prediction_id: Uuid,
+        model_id: String,
+        ground_truth: String,
+        ground_truth_json: serde_json::Value,
+        source: feedback::FeedbackSource,
+        source_json: serde_json::Value,
+        confidence: f64,
+        metadata_json: serde_json::Value,
+        feedback_time: chrono::DateTime<chrono::Utc>,
+        delay_ms: i64,
+        is_correct: Option<bool>,
+    ) -> Result<feedback::Model, DbErr> {
+        let model = feedback::ActiveModel {
+            id: Set(id),
+            prediction_id: Set(prediction_id),
+            ground_truth: Set(ground_truth),
+            source: Set(source),
+            confidence: Set(confidence),
+            received_at: Set(chrono::Utc::now()),
+            exported: Set(false),
+            model_id: Set(model_id),
+            ground_truth_json: Set(Some(ground_truth_json)),
+            source_json: Set(Some(source_json)),
+            metadata_json: Set(Some(metadata_json)),
+            feedback_time: Set(Some(feedback_time)),
+            delay_ms: Set(Some(delay_ms)),
+            is_correct: Set(is_correct),
+        };
+        let txn = db.begin().await?;
+        let stored = model.insert(&txn).await?;
+        let linked = prediction::Entity::update_many()
+            .col_expr(prediction::Column::FeedbackId, sea_query::Expr::value(id))
+            .filter(prediction::Column::Id.eq(prediction_id))
+            .filter(prediction::Column::FeedbackId.is_null())
+            .exec(&txn)
+            .await?;
+        if linked.rows_affected == 0 {
+            return Err(DbErr::RecordNotUpdated);
+        }
+        txn.commit().await?;
+        Ok(stored)
+    }
+
+    /// Feedback on any of `prediction_ids`, oldest first.
+    pub async fn list_by_predictions(
+        db: &DatabaseConnection,
+        prediction_ids: Vec<Uuid>,
+    ) -> Result<Vec<feedback::Model>, DbErr> {
+        feedback::Entity::find()
+            .filter(feedback::Column::PredictionId.is_in(prediction_ids))
+            .order_by_asc(feedback::Column::ReceivedAt)
+            .all(db)
+            .await
+    }
+
+    /// How much feedback on `model_id` arrived since `since`, and how much
+    /// of it judged the prediction right and wrong.
+    pub async fn count_by_model_since(
+        db: &DatabaseConnection,
+        model_id: &str,
+        since: chrono::DateTime<chrono::Utc>,
+    ) -> Result<FeedbackCounts, DbErr> {
+        let in_window = || {
+            feedback::Entity::find()
+                .filter(feedback::Column::ModelId.eq(model_id))
+                .filter(feedback::Column::ReceivedAt.gte(since))
+        };
+        Ok(FeedbackCounts {
+            total: in_window().count(db).await?,
+            correct: in_window()
+                .filter(feedback::Column::IsCorrect.eq(true))
+                .count(db)
+                .await?,
+            incorrect: in_window()
+                .filter(feedback::Column::IsCorrect.eq(false))
+                .count(db)
+                .await?,
+        })
+    }
+
     pub async fn mark_exported(db: &DatabaseConnection, id: Uuid) -> Result<feedback::Model, DbErr> {
         let model = feedback::ActiveModel {
             id: Set(id),
@@ -289,3 +656,189 @@ impl FeedbackRepo {
             .await
     }
 }
+
+pub struct DeadLetterRepo;
+
+impl DeadLetterRepo {
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create(
+        db: &DatabaseConnection,
+        pipeline_id: Uuid,
+        stage_id: String,
+        record_id: String,
+        model_id: String,
+        payload_json: serde_json::Value,
+        features_json: Option<serde_json::Value>,
+        error_message: String,
+    ) -> Result<dead_letter::Model, DbErr> {
+        let model = dead_letter::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            pipeline_id: Set(pipeline_id),
+            stage_id: Set(stage_id),
+            record_id: Set(record_id),
+            model_id: Set(model_id),
+            payload_json: Set(payload_json),
+            features_json: Set(features_json),
+            error_message: Set(error_message),
+            status: Set(dead_letter::DeadLetterStatus::Pending),
+            created_at: Set(chrono::Utc::now()),
+            replayed_at: Set(None),
+        };
+        model.insert(db).await
+    }
+
+    /// Dead letters of a pipeline, oldest first, optionally in one status.
+    pub async fn list_by_pipeline(
+        db: &DatabaseConnection,
+        pipeline_id: Uuid,
+        status: Option<dead_letter::DeadLetterStatus>,
+        limit: u64,
+    ) -> Result<Vec<dead_letter::Model>, DbErr> {
+        let mut query =
+            dead_letter::Entity::find().filter(dead_letter::Column::PipelineId.eq(pipeline_id));
+        if let Some(status) = status {
+            query = query.filter(dead_letter::Column::Status.eq(status));
+        }
+        query
+            .order_by_asc(dead_letter::Column::CreatedAt)
+            .limit(limit)
+            .all(db)
+            .await
+    }
+
+    /// Queues up to `limit` of a pipeline's pending dead letters for replay,
+    /// oldest first. Returns how many were queued.
+    ///
+    /// One statement picks and queues them, so requests racing each other
+    /// never queue the same dead letter twice.
+    pub async fn queue_replay(
+        db: &DatabaseConnection,
+        pipeline_id: Uuid,
+        limit: u64,
+    ) -> Result<u64, DbErr> {
+        let pending = || dead_letter::Column::Status.eq(dead_letter::DeadLetterStatus::Pending);
+        let oldest = dead_letter::Entity::find()
+            .select_only()
+            .column(dead_letter::Column::Id)
+            .filter(dead_letter::Column::PipelineId.eq(pipeline_id))
+            .filter(pending())
+            .order_by_asc(dead_letter::Column::CreatedAt)
+            .limit(limit)
+            .into_query();
+
+        let result = dead_letter::Entity::update_many()
+            .col_expr(
+                dead_letter::Column::Status,
+                sea_query::Expr::value(dead_letter::DeadLetterStatus::Queued),
+            )
+            .filter(dead_letter::Column::Id.in_subquery(oldest))
+            .filter(pending())
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    /// Parks a replayed dead letter again after it failed with `error_message`.
+    pub async fn return_to_pending(
+        db: &DatabaseConnection,
+        id: Uuid,
+        error_message: String,
+    ) -> Result<dead_letter::Model, DbErr> {
+        let model = dead_letter::ActiveModel {
+            id: Set(id),
+            status: Set(dead_letter::DeadLetterStatus::Pending),
+            error_message: Set(error_message),
+            ..Default::default()
+        };
+        model.update(db).await
+    }
+
+    pub async fn mark_replayed(
+        db: &DatabaseConnection,
+        id: Uuid,
+    ) -> Result<dead_letter::Model, DbErr> {
+        let model = dead_letter::ActiveModel {
+            id: Set(id),
+            status: Set(dead_letter::DeadLetterStatus::Replayed),
+            replayed_at: Set(Some(chrono::Utc::now())),
+            ..Default::default()
+        };
+        model.update(db).await
+    }
+}
+
+pub struct BanditArmRepo;
+
+impl BanditArmRepo {
+    /// Saves an arm of a stage's bandit, replacing what was saved before.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn upsert(
+        db: &DatabaseConnection,
+        pipeline_id: Uuid,
+        stage_id: String,
+        model_id: String,
+        model_version: String,
+        policy: String,
+        pulls: i64,
+        successes: i64,
+        failures: i64,
+        traffic_share: f64,
+    ) -> Result<(), DbErr> {
+        let model = bandit_arm::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            pipeline_id: Set(pipeline_id),
+            stage_id: Set(stage_id),
+            model_id: Set(model_id),
+            model_version: Set(model_version),
+            policy: Set(policy),
+            pulls: Set(pulls),
+            successes: Set(successes),
+            failures: Set(failures),
+            traffic_share: Set(traffic_share),
+            updated_at: Set(chrono::Utc::now()),
+        };
+        bandit_arm::Entity::insert(model)
+            .on_conflict(
+                sea_query::OnConflict::columns(bandit_arm::ARM_KEY)
+                    .update_columns([
+                        bandit_arm::Column::ModelVersion,
+                        bandit_arm::Column::Policy,
+                        bandit_arm::Column::Pulls,
+                        bandit_arm::Column::Successes,
+                        bandit_arm::Column::Failures,
+                        bandit_arm::Column::TrafficShare,
+                        bandit_arm::Column::UpdatedAt,
+                    ])
+                    .to_owned(),
+            )
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn list_by_stage(
+        db: &DatabaseConnection,
+        pipeline_id: Uuid,
+        stage_id: &str,
+    ) -> Result<Vec<bandit_arm::Model>, DbErr> {
+        bandit_arm::Entity::find()
+            .filter(bandit_arm::Column::PipelineId.eq(pipeline_id))
+            .filter(bandit_arm::Column::StageId.eq(stage_id))
+            .order_by_asc(bandit_arm::Column::ModelId)
+            .all(db)
+            .await
+    }
+
+    /// Every bandit arm serving `model_id`, across pipelines and stages.
+    pub async fn list_by_model(
+        db: &DatabaseConnection,
+        model_id: &str,
+    ) -> Result<Vec<bandit_arm::Model>, DbErr> {
+        bandit_arm::Entity::find()
+            .filter(bandit_arm::Column::ModelId.eq(model_id))
+            .order_by_asc(bandit_arm::Column::PipelineId)
+            .order_by_asc(bandit_arm::Column::StageId)
+            .all(db)
+            .await
+    }
+}
@@ -0,0 +1,116 @@
+//! Per-record and per-check cost of `DriftDetector` and of the
+//! `FeatureSetDriftDetector` drift stages run, across window sizes. All
+//! should stay flat as the window grows.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use flywheel_ml_core::{FeatureValue, FeatureVector};
+use flywheel_ml_drift::{DriftConfig, DriftDetector, FeatureSetDriftDetector};
+
+const WINDOW_SIZES: [usize; 3] = [1_000, 10_000, 100_000];
+
+/// A detector with a full window, so every added value evicts one.
+fn full_detector(window_size: usize) -> DriftDetector {
+    let mut detector = DriftDetector::new(DriftConfig {
+        window_size,
+        ..DriftConfig::default()
+    });
+    detector.set_reference((0..10_000).map(|i| i as f64 / 10_000.0).collect());
+    for i in 0..window_size {
+        detector.add_value((i % 10_000) as f64 / 10_000.0);
+    }
+    detector
+}
+
+/// A record with one numeric and one categorical feature.
+fn vector(i: u64) -> FeatureVector {
+    let region = ["us", "eu", "ap"][(i % 3) as usize];
+    FeatureVector::new("bench")
+        .with_feature("cpu", FeatureValue::Float((i % 10_000) as f64 / 10_000.0))
+        .with_feature("region", FeatureValue::Categorical(region.to_string()))
+}
+
+/// A feature-set detector whose warm-up reference and current windows are full.
+fn full_feature_set(window_size: usize) -> FeatureSetDriftDetector {
+    let mut detector = FeatureSetDriftDetector::new(DriftConfig {
+        window_size,
+        ..DriftConfig::default()
+    });
+    for i in 0..2 * window_size as u64 {
+        detector.observe(&vector(i));
+    }
+    detector
+}
+
+fn add_value(c: &mut Criterion) {
+    let mut group = c.benchmark_group("add_value");
+    for window_size in WINDOW_SIZES {
+        let mut detector = full_detector(window_size);
+        let mut i = 0u64;
+        group.bench_with_input(
+            BenchmarkId::from_parameter(window_size),
+            &window_size,
+            |b, _| {
+                b.iter(|| {
+                    i = i.wrapping_add(1);
+                    detector.add_value(black_box((i % 10_000) as f64 / 10_000.0));
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn check_drift(c: &mut Criterion) {
+    let mut group = c.benchmark_group("check_drift");
+    for window_size in WINDOW_SIZES {
+        let detector = full_detector(window_size);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(window_size),
+            &window_size,
+            |b, _| b.iter(|| black_box(detector.check_drift())),
+        );
+    }
+    group.finish();
+}
+
+fn feature_set_observe(c: &mut Criterion) {
+    let mut group = c.benchmark_group("feature_set_observe");
+    for window_size in WINDOW_SIZES {
+        let mut detector = full_feature_set(window_size);
+        let vectors: Vec<FeatureVector> = (0..1_000).map(vector).collect();
+        let mut i = 0;
+        group.bench_with_input(
+            BenchmarkId::from_parameter(window_size),
+            &window_size,
+            |b, _| {
+                b.iter(|| {
+                    i = (i + 1) % vectors.len();
+                    detector.observe(black_box(&vectors[i]));
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn feature_set_check_drift(c: &mut Criterion) {
+    let mut group = c.benchmark_group("feature_set_check_drift");
+    for window_size in WINDOW_SIZES {
+        let detector = full_feature_set(window_size);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(window_size),
+            &window_size,
+            |b, _| b.iter(|| black_box(detector.check_drift())),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    add_value,
+    check_drift,
+    feature_set_observe,
+    feature_set_check_drift
+);
+criterion_main!(benches);
@@ -0,0 +1,376 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use flywheel_ml_core::{FeatureValue, FeatureVector};
+use object_store::path::Path;
+use object_store::{ObjectStore, PutPayload};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use url::Url;
+
+/// Current version of the baseline JSON format.
+pub const BASELINE_FORMAT_VERSION: u32 = 1;
+
+/// Number of quantiles kept per numeric feature.
+pub const BASELINE_QUANTILES: usize = 1000;
+
+/// Object name used when a baseline URI points at a directory-like prefix.
+const BASELINE_FILE_NAME: &str = "baseline.json";
+
+#[derive(Error, Debug)]
+pub enum BaselineError {
+    #[error("Invalid baseline URI '{0}': {1}")]
+    InvalidUri(String, String),
+    #[error("Object store error: {0}")]
+    Store(#[from] object_store::Error),
+    #[error("Invalid baseline JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Unsupported baseline format version {0}")]
+    UnsupportedVersion(u32),
+}
+
+/// The reference distribution of every monitored feature, as captured from a
+/// training dataset.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Baseline {
+    pub format_version: u32,
+    #[serde(default)]
+    pub model_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub features: BTreeMap<String, FeatureBaseline>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FeatureBaseline {
+    /// Evenly spaced quantiles of the feature. With fewer samples than
+    /// `BASELINE_QUANTILES` they are the sorted samples themselves.
+    Numeric {
+        count: u64,
+        min: f64,
+        max: f64,
+        mean: f64,
+        quantiles: Vec<f64>,
+    },
+    /// Occurrences of each category.
+    Categorical {
+        count: u64,
+        frequencies: BTreeMap<String, u64>,
+    },
+}
+
+impl FeatureBaseline {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            FeatureBaseline::Numeric { .. } => "numeric",
+            FeatureBaseline::Categorical { .. } => "categorical",
+        }
+    }
+
+    pub fn count(&self) -> u64 {
+        match self {
+            FeatureBaseline::Numeric { count, .. } | FeatureBaseline::Categorical { count, .. } => {
+                *count
+            }
+        }
+    }
+}
+
+impl Baseline {
+    pub fn to_json(&self) -> Result<Vec<u8>, BaselineError> {
+        Ok(serde_json::to_vec_pretty(self)?)
+    }
+
+    pub fn from_json(bytes: &[u8]) -> Result<Self, BaselineError> {
+        let baseline: Baseline = serde_json::from_slice(bytes)?;
+        if baseline.format_version != BASELINE_FORMAT_VERSION {
+            return Err(BaselineError::UnsupportedVersion(baseline.format_version));
+        }
+        Ok(baseline)
+    }
+}
+
+/// Accumulates feature vectors into a `Baseline`.
+#[derive(Default)]
+pub struct BaselineBuilder {
+    numeric: BTreeMap<String, Vec<f64>>,
+    categorical: BTreeMap<String, BTreeMap<String, u64>>,
+}
+
+impl BaselineBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds every numeric (float, int, boolean) and categorical (string)
+    /// feature of `vector`. A name seen as numeric stays numeric.
+    pub fn observe(&mut self, vector: &FeatureVector) {
+        for (name, value) in &vector.features {
+            let numeric = match value {
+                FeatureValue::Float(v) => Some(*v),
+                FeatureValue::Int(v) => Some(*v as f64),
+                FeatureValue::Boolean(b) => Some(f64::from(u8::from(*b))),
+                _ => None,
+            };
+
+            match (numeric, value) {
+                (Some(v), _) if !self.categorical.contains_key(name) => {
+                    self.numeric.entry(name.clone()).or_default().push(v);
+                }
+                (None, FeatureValue::String(s) | FeatureValue::Categorical(s))
+                    if !self.numeric.contains_key(name) =>
+                {
+                    *self
+                        .categorical
+                        .entry(name.clone())
+                        .or_default()
+                        .entry(s.clone())
+                        .or_default() += 1;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    pub fn build(self, model_id: Option<String>) -> Baseline {
+        let mut features = BTreeMap::new();
+
+        for (name, mut values) in self.numeric {
+            values.retain(|v| v.is_finite());
+            if values.is_empty() {
+                continue;
+            }
+            values.sort_by(f64::total_cmp);
+
+            let count = values.len();
+            let quantiles = if count <= BASELINE_QUANTILES {
+                values.clone()
+            } else {
+                (0..BASELINE_QUANTILES)
+                    .map(|i| {
+                        let q = (i as f64 + 0.5) / BASELINE_QUANTILES as f64;
+                        values[((q * count as f64) as usize).min(count - 1)]
+                    })
+                    .collect()
+            };
+
+            features.insert(
+                name,
+                FeatureBaseline::Numeric {
+                    count: count as u64,
+                    min: values[0],
+                    max: values[count - 1],
+                    mean: values.iter().sum::<f64>() / count as f64,
+                    quantiles,
+                },
+            );
+        }
+
+        for (name, frequencies) in self.categorical {
+            features.insert(
+                name,
+                FeatureBaseline::Categorical {
+                    count: frequencies.values().sum(),
+                    frequencies,
+                },
+            );
+        }
+
+        Baseline {
+            format_version: BASELINE_FORMAT_VERSION,
+            model_id,
+            created_at: Utc::now(),
+            features,
+        }
+    }
+}
+
+/// Where a baseline is stored: a `file://` URI or plain path, or an
+/// S3-compatible `s3://bucket/key` URI.
+///
+/// A URI that does not end in `.json` is treated as a prefix holding
+/// `baseline.json`. S3 settings (`AWS_ENDPOINT`, `AWS_REGION`,
+/// `AWS_ACCESS_KEY_ID`, `AWS_ALLOW_HTTP`, ...) are read from the environment.
+pub struct BaselineLocation {
+    store: Box<dyn ObjectStore>,
+    path: Path,
+}
+
+impl BaselineLocation {
+    pub fn parse(uri: &str) -> Result<Self, BaselineError> {
+        let options = std::env::vars()
+            .filter(|(key, _)| key.starts_with("AWS_"))
+            .map(|(key, value)| (key.to_ascii_lowercase(), value));
+        Self::parse_with_options(uri, options)
+    }
+
+    /// Like `parse`, with object store options (e.g. `aws_endpoint`) given
+    /// explicitly instead of taken from the environment.
+    pub fn parse_with_options<I, K, V>(uri: &str, options: I) -> Result<Self, BaselineError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: Into<String>,
+    {
+        let invalid = |msg: String| BaselineError::InvalidUri(uri.to_string(), msg);
+
+        let mut url = if uri.contains("://") {
+            Url::parse(uri).map_err(|e| invalid(e.to_string()))?
+        } else {
+            let path = std::env::current_dir()
+                .map_err(|e| invalid(e.to_string()))?
+                .join(uri);
+            Url::from_file_path(&path).map_err(|()| invalid("not a valid path".to_string()))?
+        };
+
+        if !matches!(url.scheme(), "file" | "s3") {
+            return Err(invalid(format!(
+                "unsupported scheme '{}', expected file:// or s3://",
+                url.scheme()
+            )));
+        }
+        if !url.path().ends_with(".json") {
+            let path = format!(
+                "{}/{}",
+                url.path().trim_end_matches('/'),
+                BASELINE_FILE_NAME
+            );
+            url.set_path(&path);
+        }
+
+        let (store, path) = object_store::parse_url_opts(&url, options)?;
+        Ok(Self { store, path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub async fn load(&self) -> Result<Baseline, BaselineError> {
+        let bytes = self.store.get(&self.path).await?.bytes().await?;
+        Baseline::from_json(&bytes)
+    }
+
+    pub async fn save(&self, baseline: &Baseline) -> Result<(), BaselineError> {
+        self.store
+            .put(&self.path, PutPayload::from(baseline.to_json()?))
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    use axum::body::Bytes;
+    use axum::extract::{Path as UrlPath, State};
+    use axum::http::{header, StatusCode};
+    use axum::response::{IntoResponse, Response};
+    use axum::routing::get;
+
+    fn baseline() -> Baseline {
+        let mut builder = BaselineBuilder::new();
+        for i in 0..5000 {
+            builder.observe(
+                &FeatureVector::new("test")
+                    .with_feature("cpu", FeatureValue::Float(i as f64 / 5000.0))
+                    .with_feature("errors", FeatureValue::Int(i % 3))
+                    .with_feature(
+                        "region",
+                        FeatureValue::Categorical(if i % 4 == 0 { "us" } else { "eu" }.to_string()),
+                    ),
+            );
+        }
+        builder.build(Some("isolation-forest-v3".to_string()))
+    }
+
+    #[tokio::test]
+    async fn test_file_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let uri = format!("file://{}/baselines/v3", dir.path().display());
+        let baseline = baseline();
+
+        let location = BaselineLocation::parse(&uri).unwrap();
+        location.save(&baseline).await.unwrap();
+        assert!(dir.path().join("baselines/v3/baseline.json").exists());
+
+        assert_same(&location.load().await.unwrap(), &baseline);
+    }
+
+    fn assert_same(loaded: &Baseline, expected: &Baseline) {
+        assert_eq!(loaded.model_id, expected.model_id);
+        assert_eq!(loaded.features.len(), expected.features.len());
+        assert_eq!(loaded.features["region"], expected.features["region"]);
+        assert_eq!(
+            loaded.features["cpu"].count(),
+            expected.features["cpu"].count()
+        );
+    }
+
+    type Objects = Arc<Mutex<HashMap<String, Bytes>>>;
+
+    /// A minimal path-style S3 endpoint that stores objects in memory.
+    async fn s3_stand_in() -> String {
+        async fn put_object(
+            State(objects): State<Objects>,
+            UrlPath(key): UrlPath<String>,
+            body: Bytes,
+        ) -> Response {
+            objects.lock().unwrap().insert(key, body);
+            ([(header::ETAG, "\"1\"")], "").into_response()
+        }
+
+        async fn get_object(
+            State(objects): State<Objects>,
+            UrlPath(key): UrlPath<String>,
+        ) -> Response {
+            match objects.lock().unwrap().get(&key) {
+                Some(body) => (
+                    [
+                        (header::ETAG, "\"1\""),
+                        (header::LAST_MODIFIED, "Tue, 01 Oct 2024 00:00:00 GMT"),
+                    ],
+                    body.clone(),
+                )
+                    .into_response(),
+                None => StatusCode::NOT_FOUND.into_response(),
+            }
+        }
+
+        let app = axum::Router::new()
+            .route("/*key", get(get_object).put(put_object))
+            .with_state(Objects::default());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", address)
+    }
+
+    #[tokio::test]
+    async fn test_s3_round_trip() {
+        let endpoint = s3_stand_in().await;
+        let options = [
+            ("aws_endpoint", endpoint.as_str()),
+            ("aws_allow_http", "true"),
+            ("aws_region", "us-east-1"),
+            ("aws_access_key_id", "test"),
+            ("aws_secret_access_key", "test"),
+        ];
+        let baseline = baseline();
+
+        let location =
+            BaselineLocation::parse_with_options("s3://ml-models/baselines/v3", options).unwrap();
+        assert_eq!(location.path().as_ref(), "baselines/v3/baseline.json");
+        location.save(&baseline).await.unwrap();
+
+        let location = BaselineLocation::parse_with_options(
+            "s3://ml-models/baselines/v3/baseline.json",
+            options,
+        )
+        .unwrap();
+        assert_same(&location.load().await.unwrap(), &baseline);
+    }
+}
@@ -1,6 +1,15 @@
 use crate::performance::PerformanceTracker;
-use crate::statistical::compute_psi;
+use crate::statistical::{compute_kl_divergence, psi_from_distributions, FeatureTest};
+use crate::window::{BinEdges, StreamingHistogram, StreamingWindow, WindowSpan};
+use flywheel_ml_core::LabeledExample;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Bins of the PSI histogram, fixed from the reference distribution.
+const PSI_BINS: usize = 10;
+/// Values needed in the current window before it is compared.
+const MIN_WINDOW: usize = 100;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DriftConfig {
@@ -8,7 +17,15 @@ pub struct DriftConfig {
     pub kl_threshold: f64,
     pub accuracy_threshold: f64,
     pub window_size: usize,
+    /// Keep only values added within this many seconds, up to `window_size`,
+    /// instead of the last `window_size` values.
+    #[serde(default)]
+    pub window_duration_secs: Option<u64>,
     pub check_interval_secs: u64,
+    /// Per-feature test overrides for `FeatureSetDriftDetector`. Features
+    /// without one drift when PSI or KL divergence exceeds its threshold.
+    #[serde(default)]
+    pub feature_tests: HashMap<String, FeatureTest>,
 }
 
 impl Default for DriftConfig {
@@ -18,7 +35,9 @@ impl Default for DriftConfig {
             kl_threshold: 0.1,
             accuracy_threshold: 0.85,
             window_size: 10000,
+            window_duration_secs: None,
             check_interval_secs: 300,
+            feature_tests: HashMap::new(),
         }
     }
 }
@@ -33,14 +52,64 @@ pub struct DriftResult {
     pub accuracy_delta: Option<f64>,
 }
 
+impl DriftResult {
+    /// The result while there is not enough data to compare.
+    fn unchecked() -> Self {
+        Self {
+            is_drifted: false,
+            drift_type: None,
+            severity: DriftSeverity::None,
+            psi_score: None,
+            kl_divergence: None,
+            accuracy_delta: None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum DriftType {
+    /// The input features moved.
     Statistical,
+    /// Accuracy dropped below the baseline.
     Performance,
+    /// Both of the above.
     Both,
+    /// The distribution of the model's outputs moved.
+    Prediction,
+    /// The distribution of the ground-truth labels moved.
+    Label,
+    /// Accuracy dropped while inputs and labels held steady.
+    Concept,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+impl DriftType {
+    /// The type of a check in which the given signals drifted. `labels` is
+    /// `None` when no labels were compared.
+    ///
+    /// An accuracy drop is concept drift when neither the inputs nor the
+    /// labels moved, since only the relationship between them is left to
+    /// explain it. Without an accuracy drop, input drift outranks label
+    /// shift, which outranks a shift in predictions.
+    pub fn from_signals(
+        inputs: bool,
+        predictions: bool,
+        labels: Option<bool>,
+        accuracy: bool,
+    ) -> Option<Self> {
+        match (inputs, predictions, labels, accuracy) {
+            (true, _, _, true) => Some(DriftType::Both),
+            (false, _, Some(true), true) => Some(DriftType::Label),
+            (false, _, Some(false), true) => Some(DriftType::Concept),
+            (false, _, None, true) => Some(DriftType::Performance),
+            (true, _, _, false) => Some(DriftType::Statistical),
+            (false, _, Some(true), false) => Some(DriftType::Label),
+            (false, true, _, false) => Some(DriftType::Prediction),
+            (false, false, _, false) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 pub enum DriftSeverity {
     None,
     Low,
@@ -50,6 +119,34 @@ pub enum DriftSeverity {
 }
 
 impl DriftSeverity {
+    /// Severity of an accuracy drop below the baseline, as a fraction.
+    pub fn from_accuracy_delta(delta: f64) -> Self {
+        if delta < 0.02 {
+            DriftSeverity::None
+        } else if delta < 0.05 {
+            DriftSeverity::Low
+        } else if delta < 0.1 {
+            DriftSeverity::Medium
+        } else if delta < 0.2 {
+            DriftSeverity::High
+        } else {
+            DriftSeverity::Critical
+        }
+    }
+
+    /// Severity of a confirmed change in mean, in standard deviations.
+    pub fn from_mean_shift(sigmas: f64) -> Self {
+        if sigmas < 1.0 {
+            DriftSeverity::Low
+        } else if sigmas < 2.0 {
+            DriftSeverity::Medium
+        } else if sigmas < 3.0 {
+            DriftSeverity::High
+        } else {
+            DriftSeverity::Critical
+        }
+    }
+
     pub fn from_psi(psi: f64) -> Self {
         if psi < 0.1 {
             DriftSeverity::None
@@ -65,10 +162,28 @@ impl DriftSeverity {
     }
 }
 
+impl DriftConfig {
+    pub fn window_span(&self) -> WindowSpan {
+        match self.window_duration_secs {
+            Some(secs) => WindowSpan::Time {
+                duration: Duration::from_secs(secs),
+                max_len: self.window_size,
+            },
+            None => WindowSpan::Count(self.window_size),
+        }
+    }
+}
+
+/// Compares a stream of values against a reference distribution.
+///
+/// The reference is reduced to a histogram when set, and the current window
+/// keeps its own histogram over the same bins up to date as values arrive,
+/// so `add_value` is O(1) and `check_drift` is O(bins) whatever the window
+/// size.
 pub struct DriftDetector {
     config: DriftConfig,
-    reference_values: Vec<f64>,
-    current_window: Vec<f64>,
+    reference: Option<StreamingHistogram>,
+    current_window: StreamingWindow,
     performance_tracker: PerformanceTracker,
     baseline_accuracy: f64,
 }
@@ -76,47 +191,82 @@ pub struct DriftDetector {
 impl DriftDetector {
     pub fn new(config: DriftConfig) -> Self {
         Self {
+            current_window: StreamingWindow::new(config.window_span()),
             config,
-            reference_values: Vec::new(),
-            current_window: Vec::new(),
+            reference: None,
             performance_tracker: PerformanceTracker::new(),
             baseline_accuracy: 0.9,
         }
     }
 
     pub fn set_reference(&mut self, values: Vec<f64>) {
-        self.reference_values = values;
+        if values.is_empty() {
+            self.reference = None;
+            return;
+        }
+        let edges = BinEdges::from_reference(&values, PSI_BINS);
+        self.reference = Some(StreamingHistogram::from_values(edges, values));
+        self.current_window.set_edges(edges);
     }
 
     pub fn set_baseline_accuracy(&mut self, accuracy: f64) {
         self.baseline_accuracy = accuracy;
     }
 
+    pub fn has_reference(&self) -> bool {
+        self.reference.is_some()
+    }
+
+    pub fn record_prediction(
+        &mut self,
+        predicted: bool,
+        actual: bool,
+        latency_ms: u64,
+        is_error: bool,
+    ) {
+        self.performance_tracker
+            .record_prediction(predicted, actual, latency_ms, is_error);
+    }
+
+    /// Records a prediction joined with its feedback.
+    pub fn record_example(&mut self, example: &LabeledExample) -> bool {
+        self.performance_tracker.record_example(example)
+    }
+
     pub fn add_value(&mut self, value: f64) {
         self.current_window.push(value);
-        if self.current_window.len() > self.config.window_size {
-            self.current_window.remove(0);
-        }
+    }
+
+    /// Adds a value observed at `at`, for time-based windows.
+    pub fn add_value_at(&mut self, value: f64, at: Instant) {
+        self.current_window.push_at(value, at);
+    }
+
+    pub fn window_len(&self) -> usize {
+        self.current_window.len()
     }
 
     pub fn check_drift(&self) -> DriftResult {
-        if self.reference_values.is_empty() || self.current_window.len() < 100 {
-            return DriftResult {
-                is_drifted: false,
-                drift_type: None,
-                severity: DriftSeverity::None,
-                psi_score: None,
-                kl_divergence: None,
-                accuracy_delta: None,
-            };
+        let (Some(reference), Some(current)) = (&self.reference, self.current_window.histogram())
+        else {
+            return DriftResult::unchecked();
+        };
+        if self.current_window.len() < MIN_WINDOW {
+            return DriftResult::unchecked();
         }
 
-        let psi = compute_psi(&self.reference_values, &self.current_window, 10);
-        let statistical_drifted = psi > self.config.psi_threshold;
+        let ref_dist = reference.distribution();
+        let cur_dist = current.distribution();
+        let psi = psi_from_distributions(&ref_dist, &cur_dist);
+        let kl = compute_kl_divergence(&cur_dist, &ref_dist);
+        let statistical_drifted = psi > self.config.psi_threshold || kl > self.config.kl_threshold;
 
-        let current_accuracy = self.performance_tracker.accuracy();
-        let accuracy_delta = self.baseline_accuracy - current_accuracy;
-        let performance_drifted = current_accuracy < self.config.accuracy_threshold;
+        // Without labeled predictions there is no accuracy to compare against.
+        let current_accuracy = (self.performance_tracker.labeled_count() > 0)
+            .then(|| self.performance_tracker.accuracy());
+        let accuracy_delta = current_accuracy.map(|accuracy| self.baseline_accuracy - accuracy);
+        let performance_drifted =
+            current_accuracy.is_some_and(|accuracy| accuracy < self.config.accuracy_threshold);
 
         let (is_drifted, drift_type) = match (statistical_drifted, performance_drifted) {
             (true, true) => (true, Some(DriftType::Both)),
@@ -132,8 +282,38 @@ impl DriftDetector {
             drift_type,
             severity,
             psi_score: Some(psi),
-            kl_divergence: None,
-            accuracy_delta: Some(accuracy_delta),
+            kl_divergence: Some(kl),
+            accuracy_delta,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detector_with_window(current: impl Iterator<Item = f64>) -> DriftDetector {
+        let mut detector = DriftDetector::new(DriftConfig::default());
+        detector.set_reference((0..1000).map(|i| i as f64 / 1000.0).collect());
+        for value in current {
+            detector.add_value(value);
+        }
+        detector
+    }
+
+    #[test]
+    fn test_statistical_and_performance_drift() {
+        let mut detector = detector_with_window((0..1000).map(|i| i as f64 / 1000.0 + 0.5));
+        assert_eq!(
+            detector.check_drift().drift_type,
+            Some(DriftType::Statistical)
+        );
+
+        for i in 0..10 {
+            detector.record_prediction(true, i < 5, 1, false);
         }
+        let result = detector.check_drift();
+        assert_eq!(result.drift_type, Some(DriftType::Both));
+        assert!((result.accuracy_delta.unwrap() - 0.4).abs() < 1e-9);
     }
 }
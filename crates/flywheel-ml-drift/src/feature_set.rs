@@ -0,0 +1,617 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::time::Instant;
+
+use flywheel_ml_core::{FeatureValue, FeatureVector};
+
+use crate::baseline::{Baseline, FeatureBaseline};
+use crate::detector::DriftConfig;
+use crate::statistical::{
+    categorical_distributions, compute_chi_squared, compute_js_distance, compute_kl_divergence,
+    compute_ks, compute_wasserstein, psi_from_distributions, DriftDirection, DriftTest,
+    FeatureDriftResult, FeatureTest, StatisticalDriftResult, TestResult,
+};
+use crate::window::{BinEdges, StreamingHistogram, StreamingWindow, WindowSpan};
+
+/// Smallest current window a feature is compared on, capped at `window_size`.
+const MIN_SAMPLES: usize = 100;
+const NUMERIC_BINS: usize = 10;
+
+/// Detects drift across every feature of a `FeatureVector` stream.
+///
+/// Each feature keeps its own reference and current window. Numeric features
+/// (floats, ints and booleans) are compared on histograms over bins fixed
+/// from the reference range, categorical features (strings) on category
+/// frequencies. A feature's kind is fixed by the first value seen; values of
+/// another kind are ignored.
+///
+/// Current windows span `DriftConfig::window_span` and keep their histogram
+/// or category counts up to date as values arrive, so `observe` is O(1) per
+/// feature and `check_drift` is O(bins) or O(categories) whatever the window
+/// size. Only features tested with KS or Wasserstein read the raw windows.
+///
+/// PSI and KL divergence are reported for every feature. Whether a feature
+/// drifted is decided by its `DriftConfig::feature_tests` entry, or by the PSI
+/// and KL thresholds when it has none or the test needs numeric samples.
+pub struct FeatureSetDriftDetector {
+    config: DriftConfig,
+    features: BTreeMap<String, FeatureWindow>,
+}
+
+enum FeatureWindow {
+    Numeric(NumericWindow),
+    Categorical(CategoricalWindow),
+}
+
+struct NumericWindow {
+    reference: Vec<f64>,
+    /// The reference binned over its own range; set once it has been loaded
+    /// or has filled up.
+    reference_histogram: Option<StreamingHistogram>,
+    reference_mean: f64,
+    /// Binned over the reference's edges once those are known.
+    current: StreamingWindow,
+}
+
+struct CategoricalWindow {
+    reference: CategoryCounts,
+    /// Set once the reference has been loaded or has filled up.
+    reference_ready: bool,
+    current: CategoryWindow,
+}
+
+#[derive(Default)]
+struct CategoryCounts {
+    counts: BTreeMap<String, u64>,
+    total: usize,
+}
+
+impl CategoryCounts {
+    fn push(&mut self, value: String) {
+        *self.counts.entry(value).or_default() += 1;
+        self.total += 1;
+    }
+
+    fn remove(&mut self, value: &str) {
+        if let Some(count) = self.counts.get_mut(value) {
+            *count -= 1;
+            self.total -= 1;
+            if *count == 0 {
+                self.counts.remove(value);
+            }
+        }
+    }
+}
+
+/// The most recent categories of a stream and their counts, the categorical
+/// counterpart of `StreamingWindow`.
+struct CategoryWindow {
+    span: WindowSpan,
+    values: VecDeque<(Instant, String)>,
+    counts: CategoryCounts,
+}
+
+enum Sample {
+    Numeric(f64),
+    Categorical(String),
+}
+
+impl FeatureSetDriftDetector {
+    pub fn new(config: DriftConfig) -> Self {
+        Self {
+            config,
+            features: BTreeMap::new(),
+        }
+    }
+
+    /// Replaces the reference of every feature present in `vectors`.
+    pub fn set_reference(&mut self, vectors: &[FeatureVector]) {
+        let span = self.config.window_span();
+        let mut references: BTreeMap<String, FeatureWindow> = BTreeMap::new();
+        for vector in vectors {
+            for (name, value) in &vector.features {
+                let Some(sample) = Sample::from_value(value) else {
+                    continue;
+                };
+                references
+                    .entry(name.clone())
+                    .or_insert_with(|| FeatureWindow::for_sample(&sample, span))
+                    .push_reference(sample);
+            }
+        }
+
+        for (name, mut window) in references {
+            window.mark_reference_ready();
+            self.features.insert(name, window);
+        }
+    }
+
+    /// Replaces the reference of every feature in `baseline`. Numeric features
+    /// are represented by their quantile sketch.
+    pub fn set_baseline(&mut self, baseline: &Baseline) {
+        let span = self.config.window_span();
+        for (name, feature) in &baseline.features {
+            let window = match feature {
+                FeatureBaseline::Numeric { quantiles, .. } => {
+                    FeatureWindow::Numeric(NumericWindow::with_reference(quantiles.clone(), span))
+                }
+                FeatureBaseline::Categorical { count, frequencies } => {
+                    let reference = CategoryCounts {
+                        counts: frequencies.clone(),
+                        total: *count as usize,
+                    };
+                    FeatureWindow::Categorical(CategoricalWindow::new(reference, true, span))
+                }
+            };
+            self.features.insert(name.clone(), window);
+        }
+    }
+
+    /// Adds a vector to the current windows. Features without a reference
+    /// use their first `window_size` values as one.
+    pub fn observe(&mut self, vector: &FeatureVector) {
+        self.observe_at(vector, Instant::now());
+    }
+
+    /// Adds a vector observed at `at`, for time-based windows.
+    pub fn observe_at(&mut self, vector: &FeatureVector, at: Instant) {
+        let window_size = self.config.window_size;
+        let span = self.config.window_span();
+        for (name, value) in &vector.features {
+            let Some(sample) = Sample::from_value(value) else {
+                continue;
+            };
+            self.features
+                .entry(name.clone())
+                .or_insert_with(|| FeatureWindow::for_sample(&sample, span))
+                .observe(sample, window_size, at);
+        }
+    }
+
+    pub fn feature_names(&self) -> impl Iterator<Item = &str> {
+        self.features.keys().map(String::as_str)
+    }
+
+    /// Compares every feature with enough data against its reference.
+    pub fn check_drift(&self) -> StatisticalDriftResult {
+        let min_samples = MIN_SAMPLES.min(self.config.window_size.max(1));
+
+        let feature_drifts: HashMap<String, FeatureDriftResult> = self
+            .features
+            .iter()
+            .filter_map(|(name, window)| {
+                let samples = self
+                    .config
+                    .feature_tests
+                    .get(name)
+                    .is_some_and(|t| t.test.numeric_only());
+                let comparison = window.compare(min_samples, samples)?;
+                Some((name.clone(), self.judge(name, comparison)))
+            })
+            .collect();
+
+        let max =
+            |f: fn(&FeatureDriftResult) -> f64| feature_drifts.values().map(f).fold(0.0, f64::max);
+
+        StatisticalDriftResult {
+            is_drifted: feature_drifts.values().any(|f| f.is_drifted),
+            psi_score: max(|f| f.psi_score),
+            kl_divergence: max(|f| f.kl_divergence),
+            feature_drifts,
+        }
+    }
+}
+
+impl FeatureSetDriftDetector {
+    fn judge(&self, name: &str, comparison: Comparison) -> FeatureDriftResult {
+        let test = self
+            .config
+            .feature_tests
+            .get(name)
+            .filter(|t| comparison.samples.is_some() || !t.test.numeric_only());
+
+        let (test, statistic, p_value, is_drifted) = match test {
+            Some(FeatureTest { test, threshold }) => {
+                let result = comparison.run(*test);
+                let is_drifted = if test.uses_p_value() {
+                    result.p_value < *threshold
+                } else {
+                    result.statistic > *threshold
+                };
+                let p_value = test.uses_p_value().then_some(result.p_value);
+                (*test, result.statistic, p_value, is_drifted)
+            }
+            None => (
+                DriftTest::Psi,
+                comparison.psi,
+                None,
+                comparison.psi > self.config.psi_threshold
+                    || comparison.kl > self.config.kl_threshold,
+            ),
+        };
+
+        FeatureDriftResult {
+            feature_name: name.to_string(),
+            psi_score: comparison.psi,
+            kl_divergence: comparison.kl,
+            is_drifted,
+            direction: comparison.direction,
+            test,
+            statistic,
+            p_value,
+        }
+    }
+}
+
+/// One feature's reference and current windows, reduced for testing.
+struct Comparison {
+    reference_dist: Vec<f64>,
+    current_dist: Vec<f64>,
+    reference_n: usize,
+    current_n: usize,
+    /// Raw reference and current values, for numeric features whose test
+    /// needs them.
+    samples: Option<(Vec<f64>, Vec<f64>)>,
+    psi: f64,
+    kl: f64,
+    direction: DriftDirection,
+}
+
+impl Comparison {
+    fn new(
+        reference_dist: Vec<f64>,
+        current_dist: Vec<f64>,
+        reference_n: usize,
+        current_n: usize,
+        samples: Option<(Vec<f64>, Vec<f64>)>,
+        direction: DriftDirection,
+    ) -> Self {
+        Self {
+            psi: psi_from_distributions(&reference_dist, &current_dist),
+            kl: compute_kl_divergence(&current_dist, &reference_dist),
+            reference_dist,
+            current_dist,
+            reference_n,
+            current_n,
+            samples,
+            direction,
+        }
+    }
+
+    fn run(&self, test: DriftTest) -> TestResult {
+        let distance = |statistic| TestResult {
+            statistic,
+            p_value: 1.0,
+        };
+        match (test, &self.samples) {
+            (DriftTest::Ks, Some((reference, current))) => compute_ks(reference, current),
+            (DriftTest::Wasserstein, Some((reference, current))) => {
+                distance(compute_wasserstein(reference, current))
+            }
+            (DriftTest::JensenShannon, _) => distance(compute_js_distance(
+                &self.reference_dist,
+                &self.current_dist,
+            )),
+            (DriftTest::ChiSquared, _) => compute_chi_squared(
+                &self.reference_dist,
+                self.reference_n,
+                &self.current_dist,
+                self.current_n,
+            ),
+            (DriftTest::KlDivergence, _) => distance(self.kl),
+            // Psi, and numeric-only tests on categorical features (filtered out by `judge`).
+            _ => distance(self.psi),
+        }
+    }
+}
+
+impl Sample {
+    fn from_value(value: &FeatureValue) -> Option<Self> {
+        match value {
+            FeatureValue::Float(v) => Some(Sample::Numeric(*v)),
+            FeatureValue::Int(v) => Some(Sample::Numeric(*v as f64)),
+            FeatureValue::Boolean(b) => Some(Sample::Numeric(f64::from(u8::from(*b)))),
+            FeatureValue::String(s) | FeatureValue::Categorical(s) => {
+                Some(Sample::Categorical(s.clone()))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl FeatureWindow {
+    fn for_sample(sample: &Sample, span: WindowSpan) -> Self {
+        match sample {
+            Sample::Numeric(_) => FeatureWindow::Numeric(NumericWindow::new(Vec::new(), span)),
+            Sample::Categorical(_) => FeatureWindow::Categorical(CategoricalWindow::new(
+                CategoryCounts::default(),
+                false,
+                span,
+            )),
+        }
+    }
+
+    fn push_reference(&mut self, sample: Sample) {
+        match (self, sample) {
+            (FeatureWindow::Numeric(w), Sample::Numeric(v)) => w.reference.push(v),
+            (FeatureWindow::Categorical(w), Sample::Categorical(v)) => w.reference.push(v),
+            _ => {}
+        }
+    }
+
+    fn mark_reference_ready(&mut self) {
+        match self {
+            FeatureWindow::Numeric(w) => w.fix_reference(),
+            FeatureWindow::Categorical(w) => w.reference_ready = true,
+        }
+    }
+
+    fn observe(&mut self, sample: Sample, window_size: usize, at: Instant) {
+        match (self, sample) {
+            (FeatureWindow::Numeric(w), Sample::Numeric(v)) => w.observe(v, window_size, at),
+            (FeatureWindow::Categorical(w), Sample::Categorical(v)) => {
+                w.observe(v, window_size, at)
+            }
+            _ => {}
+        }
+    }
+
+    /// `None` while either window is too small to compare. Raw values are
+    /// only copied out with `samples`.
+    fn compare(&self, min_samples: usize, samples: bool) -> Option<Comparison> {
+        match self {
+            FeatureWindow::Numeric(w) => {
+                let reference = w.reference_histogram.as_ref()?;
+                let current = w.current.histogram()?;
+                if reference.total() == 0 || w.current.len() < min_samples {
+                    return None;
+                }
+                let direction = match w.current.mean().total_cmp(&w.reference_mean) {
+                    std::cmp::Ordering::Greater => DriftDirection::Increased,
+                    std::cmp::Ordering::Less => DriftDirection::Decreased,
+                    std::cmp::Ordering::Equal => DriftDirection::Shifted,
+                };
+                Some(Comparison::new(
+                    reference.distribution(),
+                    current.distribution(),
+                    w.reference.len(),
+                    w.current.len(),
+                    samples.then(|| (w.reference.clone(), w.current.values().collect())),
+                    direction,
+                ))
+            }
+            FeatureWindow::Categorical(w) => {
+                let current = &w.current.counts;
+                if !w.reference_ready || w.reference.total == 0 || current.total < min_samples {
+                    return None;
+                }
+                let (ref_dist, cur_dist) =
+                    categorical_distributions(&w.reference.counts, &current.counts);
+                Some(Comparison::new(
+                    ref_dist,
+                    cur_dist,
+                    w.reference.total,
+                    current.total,
+                    None,
+                    DriftDirection::Shifted,
+                ))
+            }
+        }
+    }
+}
+
+impl NumericWindow {
+    fn new(reference: Vec<f64>, span: WindowSpan) -> Self {
+        Self {
+            reference,
+            reference_histogram: None,
+            reference_mean: 0.0,
+            current: StreamingWindow::new(span),
+        }
+    }
+
+    /// A window whose reference is complete.
+    fn with_reference(reference: Vec<f64>, span: WindowSpan) -> Self {
+        let mut window = Self::new(reference, span);
+        window.fix_reference();
+        window
+    }
+
+    /// Fixes the bins from the reference and bins the current window over them.
+    fn fix_reference(&mut self) {
+        let edges = BinEdges::from_reference(&self.reference, NUMERIC_BINS);
+        self.reference_histogram = Some(StreamingHistogram::from_values(
+            edges,
+            self.reference.iter().copied(),
+        ));
+        self.reference_mean = mean(&self.reference);
+        self.current.set_edges(edges);
+    }
+
+    fn observe(&mut self, value: f64, window_size: usize, at: Instant) {
+        if self.reference_histogram.is_none() {
+            self.reference.push(value);
+            if self.reference.len() >= window_size {
+                self.fix_reference();
+            }
+            return;
+        }
+        self.current.push_at(value, at);
+    }
+}
+
+impl CategoricalWindow {
+    fn new(reference: CategoryCounts, reference_ready: bool, span: WindowSpan) -> Self {
+        Self {
+            reference,
+            reference_ready,
+            current: CategoryWindow {
+                span,
+                values: VecDeque::new(),
+                counts: CategoryCounts::default(),
+            },
+        }
+    }
+
+    fn observe(&mut self, value: String, window_size: usize, at: Instant) {
+        if !self.reference_ready {
+            self.reference.push(value);
+            self.reference_ready = self.reference.total >= window_size;
+            return;
+        }
+        self.current.push_at(value, at);
+    }
+}
+
+impl CategoryWindow {
+    fn push_at(&mut self, value: String, at: Instant) {
+        self.counts.push(value.clone());
+        self.values.push_back((at, value));
+
+        let max_len = match self.span {
+            WindowSpan::Count(n) => n,
+            WindowSpan::Time { duration, max_len } => {
+                while self
+                    .values
+                    .front()
+                    .is_some_and(|(added, _)| at.saturating_duration_since(*added) > duration)
+                {
+                    self.pop_front();
+                }
+                max_len
+            }
+        };
+        while self.values.len() > max_len {
+            self.pop_front();
+        }
+    }
+
+    fn pop_front(&mut self) {
+        if let Some((_, value)) = self.values.pop_front() {
+            self.counts.remove(&value);
+        }
+    }
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len().max(1) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::baseline::BaselineBuilder;
+
+    fn vector(cpu: f64, region: &str) -> FeatureVector {
+        FeatureVector::new("test")
+            .with_feature("cpu", FeatureValue::Float(cpu))
+            .with_feature("region", FeatureValue::Categorical(region.to_string()))
+    }
+
+    fn detector() -> FeatureSetDriftDetector {
+        let mut detector = FeatureSetDriftDetector::new(DriftConfig {
+            window_size: 1000,
+            ..DriftConfig::default()
+        });
+        let reference: Vec<FeatureVector> = (0..1000)
+            .map(|i| vector(i as f64 / 1000.0, if i % 2 == 0 { "us" } else { "eu" }))
+            .collect();
+        detector.set_reference(&reference);
+        detector
+    }
+
+    #[test]
+    fn test_ranks_drifted_numeric_feature_first() {
+        let mut detector = detector();
+        for i in 0..1000 {
+            detector.observe(&vector(
+                i as f64 / 1000.0 + 0.5,
+                if i % 2 == 0 { "us" } else { "eu" },
+            ));
+        }
+
+        let result = detector.check_drift();
+        assert!(result.is_drifted);
+        let ranked = result.ranked_features();
+        assert_eq!(ranked[0].feature_name, "cpu");
+        assert!(ranked[0].is_drifted);
+        assert_eq!(ranked[0].direction, DriftDirection::Increased);
+        assert!(!ranked[1].is_drifted);
+        assert_eq!(result.psi_score, ranked[0].psi_score);
+    }
+
+    #[test]
+    fn test_detects_categorical_shift() {
+        let mut detector = detector();
+        for i in 0..1000 {
+            detector.observe(&vector(
+                i as f64 / 1000.0,
+                if i % 10 == 0 { "us" } else { "ap" },
+            ));
+        }
+
+        let result = detector.check_drift();
+        let region = &result.feature_drifts["region"];
+        assert!(region.is_drifted);
+        assert_eq!(region.direction, DriftDirection::Shifted);
+        assert!(!result.feature_drifts["cpu"].is_drifted);
+    }
+
+    #[test]
+    fn test_time_window_forgets_old_values() {
+        let mut detector = FeatureSetDriftDetector::new(DriftConfig {
+            window_size: 1000,
+            window_duration_secs: Some(60),
+            ..DriftConfig::default()
+        });
+        let reference: Vec<FeatureVector> = (0..1000)
+            .map(|i| vector(i as f64 / 1000.0, if i % 2 == 0 { "us" } else { "eu" }))
+            .collect();
+        detector.set_reference(&reference);
+
+        let start = Instant::now();
+        for i in 0..500 {
+            detector.observe_at(&vector(i as f64 / 500.0 + 0.5, "ap"), start);
+        }
+        assert!(detector.check_drift().is_drifted);
+
+        let later = start + std::time::Duration::from_secs(61);
+        for i in 0..500 {
+            let region = if i % 2 == 0 { "us" } else { "eu" };
+            detector.observe_at(&vector(i as f64 / 500.0, region), later);
+        }
+        let result = detector.check_drift();
+        assert!(!result.is_drifted);
+        assert!(result.feature_drifts["cpu"].psi_score < 0.1);
+    }
+
+    #[test]
+    fn test_baseline_serves_as_reference() {
+        let mut builder = BaselineBuilder::new();
+        for i in 0..1000 {
+            builder.observe(&vector(
+                i as f64 / 1000.0,
+                if i % 2 == 0 { "us" } else { "eu" },
+            ));
+        }
+        let mut detector = FeatureSetDriftDetector::new(DriftConfig {
+            window_size: 1000,
+            ..DriftConfig::default()
+        });
+        detector.set_baseline(&builder.build(None));
+
+        for i in 0..1000 {
+            detector.observe(&vector(
+                i as f64 / 1000.0,
+                if i % 2 == 0 { "us" } else { "eu" },
+            ));
+        }
+        assert!(!detector.check_drift().is_drifted);
+
+        for i in 0..1000 {
+            detector.observe(&vector(i as f64 / 1000.0 + 0.5, "ap"));
+        }
+        let result = detector.check_drift();
+        assert!(result.feature_drifts["cpu"].is_drifted);
+        assert!(result.feature_drifts["region"].is_drifted);
+    }
+}
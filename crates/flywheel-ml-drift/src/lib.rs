@@ -1,6 +1,21 @@
+pub mod baseline;
 pub mod detector;
+pub mod feature_set;
+pub mod model_performance;
+pub mod output;
 pub mod performance;
+pub mod promotion;
+pub mod sequential;
 pub mod statistical;
+pub mod window;
 
+pub use baseline::*;
 pub use detector::*;
+pub use feature_set::*;
+pub use model_performance::*;
+pub use output::*;
+pub use performance::*;
+pub use promotion::*;
+pub use sequential::*;
 pub use statistical::*;
+pub use window::*;
@@ -0,0 +1,298 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use flywheel_ml_core::LabeledExample;
+use parking_lot::Mutex;
+
+use crate::performance::PerformanceTracker;
+
+/// The window is tracked as this many consecutive slots, so old outcomes
+/// expire one slot at a time.
+const WINDOW_SLOTS: u32 = 12;
+
+/// Live performance of one model version over a sliding time window, built
+/// from predictions joined with their feedback.
+pub struct ModelPerformance {
+    baseline_accuracy: Option<f64>,
+    window: Duration,
+    slots: VecDeque<Slot>,
+}
+
+struct Slot {
+    start: Instant,
+    tracker: PerformanceTracker,
+    /// Examples with a known `is_correct`, and how many of them were correct.
+    judged: u64,
+    correct: u64,
+}
+
+/// Performance over the window at one point in time.
+pub struct PerformanceSnapshot {
+    /// Fraction of labeled examples that were correct, once there are any.
+    pub accuracy: Option<f64>,
+    /// Accuracy the model version was registered with.
+    pub baseline_accuracy: Option<f64>,
+    /// Labeled examples in the window.
+    pub examples: u64,
+    /// Class, score, regression and latency metrics over the window.
+    pub tracker: PerformanceTracker,
+}
+
+impl PerformanceSnapshot {
+    /// How far accuracy fell below the baseline; negative when it improved.
+    pub fn accuracy_delta(&self) -> Option<f64> {
+        Some(self.baseline_accuracy? - self.accuracy?)
+    }
+}
+
+impl ModelPerformance {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            baseline_accuracy: None,
+            window,
+            slots: VecDeque::new(),
+        }
+    }
+
+    pub fn with_baseline_accuracy(mut self, accuracy: Option<f64>) -> Self {
+        self.baseline_accuracy = accuracy;
+        self
+    }
+
+    pub fn baseline_accuracy(&self) -> Option<f64> {
+        self.baseline_accuracy
+    }
+
+    pub fn record(&mut self, example: &LabeledExample) {
+        self.record_at(example, Instant::now());
+    }
+
+    pub fn record_at(&mut self, example: &LabeledExample, at: Instant) {
+        let slot = self.slot_at(at);
+        slot.tracker.record_example(example);
+        if let Some(correct) = example.is_correct {
+            slot.judged += 1;
+            slot.correct += u64::from(correct);
+        }
+    }
+
+    /// Records a served prediction's latency.
+    pub fn record_latency(&mut self, latency_ms: u64) {
+        self.record_latency_at(latency_ms, Instant::now());
+    }
+
+    pub fn record_latency_at(&mut self, latency_ms: u64, at: Instant) {
+        self.slot_at(at).tracker.record_latency(latency_ms);
+    }
+
+    /// Records a prediction the model failed to make.
+    pub fn record_error(&mut self, latency_ms: u64) {
+        self.record_error_at(latency_ms, Instant::now());
+    }
+
+    pub fn record_error_at(&mut self, latency_ms: u64, at: Instant) {
+        self.slot_at(at).tracker.record_error(latency_ms);
+    }
+
+    pub fn snapshot(&self) -> PerformanceSnapshot {
+        self.snapshot_at(Instant::now())
+    }
+
+    pub fn snapshot_at(&self, now: Instant) -> PerformanceSnapshot {
+        let mut tracker = PerformanceTracker::new();
+        let (mut judged, mut correct) = (0, 0);
+        for slot in self.slots.iter().filter(|s| !self.expired(s, now)) {
+            tracker.merge(&slot.tracker);
+            judged += slot.judged;
+            correct += slot.correct;
+        }
+
+        PerformanceSnapshot {
+            accuracy: (judged > 0).then(|| correct as f64 / judged as f64),
+            baseline_accuracy: self.baseline_accuracy,
+            examples: judged,
+            tracker,
+        }
+    }
+
+    fn slot_duration(&self) -> Duration {
+        (self.window / WINDOW_SLOTS).max(Duration::from_millis(1))
+    }
+
+    fn expired(&self, slot: &Slot, now: Instant) -> bool {
+        now.saturating_duration_since(slot.start) >= self.window
+    }
+
+    fn slot_at(&mut self, at: Instant) -> &mut Slot {
+        while self.slots.front().is_some_and(|s| self.expired(s, at)) {
+            self.slots.pop_front();
+        }
+
+        let slot_duration = self.slot_duration();
+        let current = self
+            .slots
+            .back()
+            .is_some_and(|s| at.saturating_duration_since(s.start) < slot_duration);
+        if !current {
+            self.slots.push_back(Slot {
+                start: at,
+                tracker: PerformanceTracker::new(),
+                judged: 0,
+                correct: 0,
+            });
+        }
+        self.slots.back_mut().expect("slot was just ensured")
+    }
+}
+
+/// `ModelPerformance` for every model version, shared between the stages
+/// that record outcomes and the services that report them.
+pub struct PerformanceRegistry {
+    window: Duration,
+    models: Mutex<HashMap<(String, String), ModelPerformance>>,
+}
+
+impl PerformanceRegistry {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            models: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// How far back snapshots look.
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    pub fn contains(&self, model_id: &str, version: &str) -> bool {
+        self.models
+            .lock()
+            .contains_key(&(model_id.to_string(), version.to_string()))
+    }
+
+    /// Starts tracking a model version against `baseline_accuracy`. Keeps
+    /// what has been recorded if it is already tracked.
+    pub fn seed(&self, model_id: &str, version: &str, baseline_accuracy: Option<f64>) {
+        let mut models = self.models.lock();
+        let performance = models
+            .entry((model_id.to_string(), version.to_string()))
+            .or_insert_with(|| ModelPerformance::new(self.window));
+        performance.baseline_accuracy = baseline_accuracy;
+    }
+
+    pub fn record(&self, example: &LabeledExample) {
+        self.with_model(&example.model_id, &example.model_version, |m| {
+            m.record(example)
+        });
+    }
+
+    pub fn record_latency(&self, model_id: &str, version: &str, latency_ms: u64) {
+        self.with_model(model_id, version, |m| m.record_latency(latency_ms));
+    }
+
+    pub fn record_error(&self, model_id: &str, version: &str, latency_ms: u64) {
+        self.with_model(model_id, version, |m| m.record_error(latency_ms));
+    }
+
+    pub fn snapshot(&self, model_id: &str, version: &str) -> Option<PerformanceSnapshot> {
+        self.models
+            .lock()
+            .get(&(model_id.to_string(), version.to_string()))
+            .map(ModelPerformance::snapshot)
+    }
+
+    /// Performance across every tracked version of the given models. The
+    /// baseline is taken from the version with the most examples.
+    pub fn snapshot_models(&self, model_ids: &[&str]) -> Option<PerformanceSnapshot> {
+        let models = self.models.lock();
+        let mut combined: Option<PerformanceSnapshot> = None;
+        let mut baseline_examples = 0;
+        let mut correct = 0.0;
+
+        for ((model_id, _), performance) in models.iter() {
+            if !model_ids.contains(&model_id.as_str()) {
+                continue;
+            }
+            let snapshot = performance.snapshot();
+            correct += snapshot.accuracy.unwrap_or(0.0) * snapshot.examples as f64;
+
+            let combined = combined.get_or_insert_with(|| PerformanceSnapshot {
+                accuracy: None,
+                baseline_accuracy: None,
+                examples: 0,
+                tracker: PerformanceTracker::new(),
+            });
+            if combined.baseline_accuracy.is_none() || snapshot.examples > baseline_examples {
+                combined.baseline_accuracy = snapshot.baseline_accuracy;
+                baseline_examples = snapshot.examples;
+            }
+            combined.examples += snapshot.examples;
+            combined.tracker.merge(&snapshot.tracker);
+        }
+
+        let mut combined = combined?;
+        combined.accuracy = (combined.examples > 0).then(|| correct / combined.examples as f64);
+        Some(combined)
+    }
+
+    fn with_model(&self, model_id: &str, version: &str, f: impl FnOnce(&mut ModelPerformance)) {
+        let mut models = self.models.lock();
+        let performance = models
+            .entry((model_id.to_string(), version.to_string()))
+            .or_insert_with(|| ModelPerformance::new(self.window));
+        f(performance);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use flywheel_ml_core::{GroundTruth, PredictionResult};
+
+    fn example(is_anomaly: bool, actual: bool) -> LabeledExample {
+        LabeledExample {
+            example_id: "ex-1".to_string(),
+            prediction_id: "pred-1".to_string(),
+            model_id: "model-1".to_string(),
+            model_version: "v1".to_string(),
+            features: serde_json::json!({}),
+            prediction: serde_json::to_value(PredictionResult::anomaly(
+                if is_anomaly { 0.9 } else { 0.1 },
+                0.5,
+            ))
+            .unwrap(),
+            ground_truth: GroundTruth::Binary(actual),
+            prediction_timestamp: Utc::now(),
+            feedback_timestamp: Utc::now(),
+            delay_ms: 0,
+            feedback_confidence: 1.0,
+            is_correct: Some(is_anomaly == actual),
+            metadata: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_window_forgets_old_outcomes() {
+        let mut performance =
+            ModelPerformance::new(Duration::from_secs(60)).with_baseline_accuracy(Some(0.9));
+        let start = Instant::now();
+        for _ in 0..10 {
+            performance.record_at(&example(true, false), start);
+        }
+        let snapshot = performance.snapshot_at(start);
+        assert_eq!(snapshot.accuracy, Some(0.0));
+        assert_eq!(snapshot.accuracy_delta(), Some(0.9));
+        assert_eq!(snapshot.tracker.labeled_count(), 10);
+
+        let later = start + Duration::from_secs(90);
+        for i in 0..4 {
+            performance.record_at(&example(true, i < 3), later);
+        }
+        let snapshot = performance.snapshot_at(later);
+        assert_eq!(snapshot.examples, 4);
+        assert_eq!(snapshot.accuracy, Some(0.75));
+        assert!(performance.slots.len() <= WINDOW_SLOTS as usize);
+    }
+}
@@ -0,0 +1,171 @@
+use flywheel_ml_core::{FeatureValue, FeatureVector, GroundTruth, PredictionResult};
+
+use crate::detector::DriftConfig;
+use crate::feature_set::FeatureSetDriftDetector;
+use crate::statistical::{FeatureDriftResult, StatisticalDriftResult};
+
+/// Detects drift in what a model predicts and, once feedback arrives, in the
+/// ground truth its predictions are judged against.
+///
+/// Each prediction is reduced to a few named outputs (`prediction.score` and
+/// `prediction.is_anomaly`, `prediction.class` and its
+/// `prediction.class_probability`, `prediction.value`, `prediction.cluster`
+/// and `prediction.distance`) and each label to `label.class` or
+/// `label.value`. Outputs and labels are then compared like input features,
+/// against their first `window_size` observations.
+pub struct OutputDriftDetector {
+    predictions: FeatureSetDriftDetector,
+    labels: FeatureSetDriftDetector,
+}
+
+#[derive(Debug, Clone)]
+pub struct OutputDriftResult {
+    pub prediction: StatisticalDriftResult,
+    pub label: StatisticalDriftResult,
+}
+
+impl OutputDriftResult {
+    /// Whether any label has been compared against its reference.
+    pub fn labels_checked(&self) -> bool {
+        !self.label.feature_drifts.is_empty()
+    }
+
+    /// Per-output results of predictions and labels, most drifted first.
+    pub fn ranked_outputs(&self) -> Vec<&FeatureDriftResult> {
+        let mut outputs = self.prediction.ranked_features();
+        outputs.extend(self.label.ranked_features());
+        outputs.sort_by(|a, b| {
+            b.psi_score
+                .total_cmp(&a.psi_score)
+                .then_with(|| a.feature_name.cmp(&b.feature_name))
+        });
+        outputs
+    }
+}
+
+impl OutputDriftDetector {
+    pub fn new(config: DriftConfig) -> Self {
+        Self {
+            predictions: FeatureSetDriftDetector::new(config.clone()),
+            labels: FeatureSetDriftDetector::new(config),
+        }
+    }
+
+    pub fn observe_prediction(&mut self, result: &PredictionResult) {
+        let outputs = prediction_outputs(result);
+        if !outputs.features.is_empty() {
+            self.predictions.observe(&outputs);
+        }
+    }
+
+    pub fn observe_label(&mut self, truth: &GroundTruth) {
+        let labels = label_values(truth);
+        if !labels.features.is_empty() {
+            self.labels.observe(&labels);
+        }
+    }
+
+    pub fn check_drift(&self) -> OutputDriftResult {
+        OutputDriftResult {
+            prediction: self.predictions.check_drift(),
+            label: self.labels.check_drift(),
+        }
+    }
+}
+
+fn prediction_outputs(result: &PredictionResult) -> FeatureVector {
+    let mut outputs = FeatureVector::new("");
+    let mut add = |name: &str, value: FeatureValue| {
+        outputs
+            .features
+            .insert(format!("prediction.{}", name), value);
+    };
+
+    match result {
+        PredictionResult::Anomaly {
+            score, is_anomaly, ..
+        } => {
+            add("score", FeatureValue::Float(*score));
+            add("is_anomaly", FeatureValue::Boolean(*is_anomaly));
+        }
+        PredictionResult::Classification {
+            class,
+            probabilities,
+        } => {
+            add("class", FeatureValue::Categorical(class.clone()));
+            if let Some(probability) = probabilities.get(class) {
+                add("class_probability", FeatureValue::Float(*probability));
+            }
+        }
+        PredictionResult::Regression { value, .. } => {
+            add("value", FeatureValue::Float(*value));
+        }
+        PredictionResult::Clustering {
+            cluster_id,
+            distance,
+        } => {
+            add("cluster", FeatureValue::Categorical(cluster_id.to_string()));
+            add("distance", FeatureValue::Float(*distance));
+        }
+        PredictionResult::Embedding { .. } | PredictionResult::Custom(_) => {}
+    }
+    outputs
+}
+
+fn label_values(truth: &GroundTruth) -> FeatureVector {
+    let mut labels = FeatureVector::new("");
+    let label = match truth {
+        GroundTruth::Label(label) => Some(("class", FeatureValue::Categorical(label.clone()))),
+        GroundTruth::Binary(b) => Some(("class", FeatureValue::Categorical(b.to_string()))),
+        GroundTruth::Value(v) => Some(("value", FeatureValue::Float(*v))),
+        GroundTruth::Ranking(_) | GroundTruth::MultiLabel(_) | GroundTruth::Custom(_) => None,
+    };
+    if let Some((name, value)) = label {
+        labels.features.insert(format!("label.{}", name), value);
+    }
+    labels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> DriftConfig {
+        DriftConfig {
+            window_size: 200,
+            ..DriftConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_detects_score_shift() {
+        let mut detector = OutputDriftDetector::new(config());
+        for i in 0..400 {
+            let score = if i < 200 { 0.1 } else { 0.8 } + (i % 10) as f64 * 0.01;
+            detector.observe_prediction(&PredictionResult::anomaly(score, 0.5));
+        }
+
+        let result = detector.check_drift();
+        assert!(result.prediction.is_drifted);
+        assert!(!result.labels_checked());
+        let top = result.ranked_outputs()[0];
+        assert!(top.is_drifted);
+        assert!(top.feature_name.starts_with("prediction."));
+    }
+
+    #[test]
+    fn test_detects_label_shift() {
+        let mut detector = OutputDriftDetector::new(config());
+        for i in 0..400 {
+            // The positive rate rises from 10% to 60%.
+            let positive = if i < 200 { i % 10 == 0 } else { i % 10 < 6 };
+            detector.observe_label(&GroundTruth::Binary(positive));
+        }
+
+        let result = detector.check_drift();
+        assert!(result.labels_checked());
+        assert!(result.label.is_drifted);
+        assert!(result.label.feature_drifts.contains_key("label.class"));
+        assert!(!result.prediction.is_drifted);
+    }
+}
@@ -1,7 +1,17 @@
+use flywheel_ml_core::{GroundTruth, LabeledExample, ModelType, PredictionResult};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+/// Most recent scored outcomes kept per class for ROC-AUC and PR-AUC.
+const MAX_SCORED_OUTCOMES: usize = 10_000;
+
+/// Class names of boolean predictions and labels.
+const POSITIVE: &str = "true";
+const NEGATIVE: &str = "false";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PerformanceDriftResult {
+    pub model_type: ModelType,
     pub is_degraded: bool,
     pub current_accuracy: f64,
     pub baseline_accuracy: f64,
@@ -10,14 +20,422 @@ pub struct PerformanceDriftResult {
     pub current_recall: f64,
     pub current_latency_p99_ms: u64,
     pub current_error_rate: f64,
+    pub latency: LatencyPercentiles,
+    /// Set once labeled class or anomaly outcomes have been recorded.
+    pub classification: Option<ClassificationMetrics>,
+    /// Set once scored outcomes include both positives and negatives.
+    pub scores: Option<ScoreMetrics>,
+    /// Set once regression outcomes have been recorded.
+    pub regression: Option<RegressionMetrics>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LatencyPercentiles {
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+    pub p99_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ClassificationMetrics {
+    pub accuracy: f64,
+    pub macro_f1: f64,
+    pub micro_f1: f64,
+    pub classes: BTreeMap<String, ClassMetrics>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct ClassMetrics {
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+    /// Outcomes whose actual class is this one.
+    pub support: u64,
+}
+
+/// Ranking quality of scored outputs, macro-averaged one-vs-rest over classes.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct ScoreMetrics {
+    pub roc_auc: f64,
+    /// Average precision.
+    pub pr_auc: f64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct RegressionMetrics {
+    pub count: u64,
+    pub mae: f64,
+    pub rmse: f64,
+    /// Mean absolute percentage error as a fraction, over outcomes with a
+    /// non-zero actual value. `None` when there are none.
+    pub mape: Option<f64>,
+}
+
+/// Counts of (actual, predicted) class pairs.
+#[derive(Debug, Clone, Default)]
+pub struct ConfusionMatrix {
+    counts: BTreeMap<(String, String), u64>,
+    total: u64,
+}
+
+impl ConfusionMatrix {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, actual: &str, predicted: &str) {
+        *self
+            .counts
+            .entry((actual.to_string(), predicted.to_string()))
+            .or_default() += 1;
+        self.total += 1;
+    }
+
+    pub fn merge(&mut self, other: &ConfusionMatrix) {
+        for (key, n) in &other.counts {
+            *self.counts.entry(key.clone()).or_default() += n;
+        }
+        self.total += other.total;
+    }
+
+    pub fn count(&self, actual: &str, predicted: &str) -> u64 {
+        self.counts
+            .get(&(actual.to_string(), predicted.to_string()))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Every class seen as either an actual or a predicted value.
+    pub fn classes(&self) -> BTreeSet<&str> {
+        self.counts
+            .keys()
+            .flat_map(|(a, p)| [a.as_str(), p.as_str()])
+            .collect()
+    }
+
+    pub fn accuracy(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        let correct: u64 = self
+            .counts
+            .iter()
+            .filter(|((a, p), _)| a == p)
+            .map(|(_, n)| n)
+            .sum();
+        correct as f64 / self.total as f64
+    }
+
+    pub fn class_metrics(&self, class: &str) -> ClassMetrics {
+        let (tp, fp, fn_) = self.class_counts(class);
+        let precision = ratio(tp, tp + fp);
+        let recall = ratio(tp, tp + fn_);
+        ClassMetrics {
+            precision,
+            recall,
+            f1: ratio(2 * tp, 2 * tp + fp + fn_),
+            support: tp + fn_,
+        }
+    }
+
+    /// Unweighted mean of the per-class F1 scores.
+    pub fn macro_f1(&self) -> f64 {
+        let classes = self.classes();
+        if classes.is_empty() {
+            return 0.0;
+        }
+        classes
+            .iter()
+            .map(|c| self.class_metrics(c).f1)
+            .sum::<f64>()
+            / classes.len() as f64
+    }
+
+    /// F1 over the true/false positives and negatives pooled across classes.
+    pub fn micro_f1(&self) -> f64 {
+        let (tp, fp, fn_) = self
+            .classes()
+            .iter()
+            .map(|c| self.class_counts(c))
+            .fold((0, 0, 0), |acc, c| (acc.0 + c.0, acc.1 + c.1, acc.2 + c.2));
+        ratio(2 * tp, 2 * tp + fp + fn_)
+    }
+
+    pub fn metrics(&self) -> ClassificationMetrics {
+        ClassificationMetrics {
+            accuracy: self.accuracy(),
+            macro_f1: self.macro_f1(),
+            micro_f1: self.micro_f1(),
+            classes: self
+                .classes()
+                .into_iter()
+                .map(|c| (c.to_string(), self.class_metrics(c)))
+                .collect(),
+        }
+    }
+
+    /// Whether only boolean classes have been recorded.
+    fn is_binary(&self) -> bool {
+        self.classes()
+            .iter()
+            .all(|c| *c == POSITIVE || *c == NEGATIVE)
+    }
+
+    /// True positives, false positives and false negatives of `class`.
+    fn class_counts(&self, class: &str) -> (u64, u64, u64) {
+        let (mut tp, mut fp, mut fn_) = (0, 0, 0);
+        for ((actual, predicted), &n) in &self.counts {
+            match (actual == class, predicted == class) {
+                (true, true) => tp += n,
+                (false, true) => fp += n,
+                (true, false) => fn_ += n,
+                (false, false) => {}
+            }
+        }
+        (tp, fp, fn_)
+    }
+}
+
+/// Latency counts in log-scaled buckets, in the style of an HDR histogram:
+/// exact below 64 ms and within about 3% above, in fixed memory.
+#[derive(Debug, Clone)]
+pub struct LatencyHistogram {
+    counts: Vec<u64>,
+    total: u64,
+    sum: u64,
+}
+
+impl LatencyHistogram {
+    /// Sub-buckets per power of two, as a number of bits.
+    const SUB_BUCKET_BITS: u32 = 5;
+    const SUB_BUCKETS: u64 = 1 << Self::SUB_BUCKET_BITS;
+    /// Values below this get a bucket each.
+    const EXACT: u64 = Self::SUB_BUCKETS * 2;
+    const BUCKETS: usize =
+        (Self::EXACT + (64 - Self::SUB_BUCKET_BITS as u64 - 1) * Self::SUB_BUCKETS) as usize;
+
+    pub fn new() -> Self {
+        Self {
+            counts: vec![0; Self::BUCKETS],
+            total: 0,
+            sum: 0,
+        }
+    }
+
+    pub fn record(&mut self, value: u64) {
+        self.counts[Self::index(value)] += 1;
+        self.total += 1;
+        self.sum = self.sum.saturating_add(value);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.total
+    }
+
+    pub fn merge(&mut self, other: &LatencyHistogram) {
+        for (count, other) in self.counts.iter_mut().zip(&other.counts) {
+            *count += other;
+        }
+        self.total += other.total;
+        self.sum = self.sum.saturating_add(other.sum);
+    }
+
+    /// The mean of the recorded values, exact rather than bucketed. Zero
+    /// when empty.
+    pub fn mean(&self) -> u64 {
+        self.sum.checked_div(self.total).unwrap_or(0)
+    }
+
+    /// The value at quantile `q` (0 to 1), reported as the highest value of
+    /// its bucket. Zero when empty.
+    pub fn quantile(&self, q: f64) -> u64 {
+        if self.total == 0 {
+            return 0;
+        }
+        let rank = ((q.clamp(0.0, 1.0) * self.total as f64) as u64 + 1).min(self.total);
+        let mut seen = 0;
+        for (index, &count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return Self::highest_value(index);
+            }
+        }
+        Self::highest_value(self.counts.len() - 1)
+    }
+
+    pub fn clear(&mut self) {
+        self.counts.iter_mut().for_each(|c| *c = 0);
+        self.total = 0;
+        self.sum = 0;
+    }
+
+    fn index(value: u64) -> usize {
+        if value < Self::EXACT {
+            return value as usize;
+        }
+        let exponent = 63 - value.leading_zeros();
+        let shift = exponent - Self::SUB_BUCKET_BITS;
+        let mantissa = (value >> shift) - Self::SUB_BUCKETS;
+        let row = (exponent - Self::SUB_BUCKET_BITS - 1) as u64;
+        (Self::EXACT + row * Self::SUB_BUCKETS + mantissa) as usize
+    }
+
+    fn highest_value(index: usize) -> u64 {
+        let index = index as u64;
+        if index < Self::EXACT {
+            return index;
+        }
+        let row = (index - Self::EXACT) / Self::SUB_BUCKETS;
+        let mantissa = (index - Self::EXACT) % Self::SUB_BUCKETS + Self::SUB_BUCKETS;
+        let shift = row as u32 + 1;
+        (mantissa << shift) + ((1u64 << shift) - 1)
+    }
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct RegressionErrors {
+    count: u64,
+    abs_sum: f64,
+    squared_sum: f64,
+    pct_sum: f64,
+    pct_count: u64,
+}
+
+impl RegressionErrors {
+    fn record(&mut self, predicted: f64, actual: f64) {
+        let error = (predicted - actual).abs();
+        self.count += 1;
+        self.abs_sum += error;
+        self.squared_sum += error * error;
+        if actual != 0.0 {
+            self.pct_sum += error / actual.abs();
+            self.pct_count += 1;
+        }
+    }
+
+    fn merge(&mut self, other: &RegressionErrors) {
+        self.count += other.count;
+        self.abs_sum += other.abs_sum;
+        self.squared_sum += other.squared_sum;
+        self.pct_sum += other.pct_sum;
+        self.pct_count += other.pct_count;
+    }
+
+    fn metrics(&self) -> Option<RegressionMetrics> {
+        if self.count == 0 {
+            return None;
+        }
+        let n = self.count as f64;
+        Some(RegressionMetrics {
+            count: self.count,
+            mae: self.abs_sum / n,
+            rmse: (self.squared_sum / n).sqrt(),
+            mape: (self.pct_count > 0).then(|| self.pct_sum / self.pct_count as f64),
+        })
+    }
+}
+
+/// Area under the ROC curve of `(score, is_positive)` outcomes, with tied
+/// scores counted as half. `None` unless both classes are present.
+pub fn roc_auc(outcomes: &[(f64, bool)]) -> Option<f64> {
+    let positives = outcomes.iter().filter(|(_, p)| *p).count();
+    let negatives = outcomes.len() - positives;
+    if positives == 0 || negatives == 0 {
+        return None;
+    }
+
+    let mut sorted = outcomes.to_vec();
+    sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    // Mann–Whitney U with average ranks over ties.
+    let mut positive_rank_sum = 0.0;
+    let mut start = 0;
+    while start < sorted.len() {
+        let end = tie_end(&sorted, start);
+        let average_rank = (start + end + 1) as f64 / 2.0;
+        let tied_positives = sorted[start..end].iter().filter(|(_, p)| *p).count();
+        positive_rank_sum += average_rank * tied_positives as f64;
+        start = end;
+    }
+
+    let (p, n) = (positives as f64, negatives as f64);
+    Some((positive_rank_sum - p * (p + 1.0) / 2.0) / (p * n))
+}
+
+/// Area under the precision-recall curve of `(score, is_positive)` outcomes,
+/// as average precision. `None` without positives.
+pub fn pr_auc(outcomes: &[(f64, bool)]) -> Option<f64> {
+    let positives = outcomes.iter().filter(|(_, p)| *p).count();
+    if positives == 0 {
+        return None;
+    }
+
+    let mut sorted = outcomes.to_vec();
+    sorted.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let (mut tp, mut fp) = (0usize, 0usize);
+    let mut previous_recall = 0.0;
+    let mut area = 0.0;
+    let mut start = 0;
+    while start < sorted.len() {
+        let end = tie_end(&sorted, start);
+        let tied_positives = sorted[start..end].iter().filter(|(_, p)| *p).count();
+        tp += tied_positives;
+        fp += end - start - tied_positives;
+
+        let recall = tp as f64 / positives as f64;
+        let precision = tp as f64 / (tp + fp) as f64;
+        area += (recall - previous_recall) * precision;
+        previous_recall = recall;
+        start = end;
+    }
+    Some(area)
+}
+
+/// End of the run of outcomes sharing the score at `start`.
+fn tie_end(sorted: &[(f64, bool)], start: usize) -> usize {
+    let mut end = start + 1;
+    while end < sorted.len() && sorted[end].0 == sorted[start].0 {
+        end += 1;
+    }
+    end
+}
+
+fn ratio(numerator: u64, denominator: u64) -> f64 {
+    if denominator == 0 {
+        return 0.0;
+    }
+    numerator as f64 / denominator as f64
+}
+
+fn bool_class(value: bool) -> &'static str {
+    if value {
+        POSITIVE
+    } else {
+        NEGATIVE
+    }
 }
 
+/// Tracks served predictions and, as ground truth arrives, their quality.
+///
+/// Memory is bounded: class outcomes are counted, regression errors summed,
+/// latencies bucketed, and only the latest scored outcomes per class are
+/// kept for ROC-AUC and PR-AUC.
 pub struct PerformanceTracker {
-    true_positives: u64,
-    true_negatives: u64,
-    false_positives: u64,
-    false_negatives: u64,
-    latencies: Vec<u64>,
+    confusion: ConfusionMatrix,
+    scores: BTreeMap<String, VecDeque<(f64, bool)>>,
+    regression: RegressionErrors,
+    latencies: LatencyHistogram,
     errors: u64,
     total: u64,
 }
@@ -25,11 +443,10 @@ pub struct PerformanceTracker {
 impl PerformanceTracker {
     pub fn new() -> Self {
         Self {
-            true_positives: 0,
-            true_negatives: 0,
-            false_positives: 0,
-            false_negatives: 0,
-            latencies: Vec::new(),
+            confusion: ConfusionMatrix::new(),
+            scores: BTreeMap::new(),
+            regression: RegressionErrors::default(),
+            latencies: LatencyHistogram::new(),
             errors: 0,
             total: 0,
         }
@@ -42,81 +459,237 @@ impl PerformanceTracker {
         latency_ms: u64,
         is_error: bool,
     ) {
-        self.total += 1;
-
         if is_error {
-            self.errors += 1;
+            self.record_error(latency_ms);
         } else {
-            match (predicted, actual) {
-                (true, true) => self.true_positives += 1,
-                (true, false) => self.false_positives += 1,
-                (false, true) => self.false_negatives += 1,
-                (false, false) => self.true_negatives += 1,
+            self.record_latency(latency_ms);
+            self.confusion
+                .record(bool_class(actual), bool_class(predicted));
+        }
+    }
+
+    /// Records a served prediction whose outcome is not known yet.
+    pub fn record_latency(&mut self, latency_ms: u64) {
+        self.total += 1;
+        self.latencies.record(latency_ms);
+    }
+
+    /// Records a failed prediction.
+    pub fn record_error(&mut self, latency_ms: u64) {
+        self.record_latency(latency_ms);
+        self.errors += 1;
+    }
+
+    /// Records how a prediction compared with its ground truth. Returns
+    /// `false`, recording nothing, when the two cannot be compared: anomaly
+    /// predictions need a boolean label, classifications a class label (or a
+    /// boolean, compared as `"true"`/`"false"`), regressions a value.
+    pub fn record_outcome(
+        &mut self,
+        prediction: &PredictionResult,
+        ground_truth: &GroundTruth,
+    ) -> bool {
+        match prediction {
+            PredictionResult::Anomaly {
+                score, is_anomaly, ..
+            } => {
+                let Some(actual) = ground_truth.as_binary() else {
+                    return false;
+                };
+                self.confusion
+                    .record(bool_class(actual), bool_class(*is_anomaly));
+                self.record_score(POSITIVE, *score, actual);
             }
+            PredictionResult::Classification {
+                class,
+                probabilities,
+            } => {
+                let actual = match ground_truth {
+                    GroundTruth::Label(label) => label.as_str(),
+                    GroundTruth::Binary(b) => bool_class(*b),
+                    _ => return false,
+                };
+                self.confusion.record(actual, class);
+                for (candidate, probability) in probabilities {
+                    self.record_score(candidate, *probability, candidate == actual);
+                }
+            }
+            PredictionResult::Regression { value, .. } => {
+                let Some(actual) = ground_truth.as_value() else {
+                    return false;
+                };
+                self.regression.record(*value, actual);
+            }
+            PredictionResult::Clustering { .. }
+            | PredictionResult::Embedding { .. }
+            | PredictionResult::Custom(_) => return false,
         }
+        true
+    }
 
-        self.latencies.push(latency_ms);
+    /// Records a prediction joined with its feedback. Returns `false` when the
+    /// stored prediction cannot be read or compared with the ground truth.
+    pub fn record_example(&mut self, example: &LabeledExample) -> bool {
+        match serde_json::from_value::<PredictionResult>(example.prediction.clone()) {
+            Ok(prediction) => self.record_outcome(&prediction, &example.ground_truth),
+            Err(_) => false,
+        }
     }
 
-    pub fn accuracy(&self) -> f64 {
-        let correct = self.true_positives + self.true_negatives;
-        let total = self.true_positives + self.true_negatives + self.false_positives + self.false_negatives;
-        if total == 0 {
-            return 0.0;
+    /// Adds everything recorded by `other`. Scored outcomes beyond the
+    /// per-class limit are dropped, oldest first.
+    pub fn merge(&mut self, other: &PerformanceTracker) {
+        self.confusion.merge(&other.confusion);
+        for (class, outcomes) in &other.scores {
+            for &(score, is_positive) in outcomes {
+                self.record_score(class, score, is_positive);
+            }
         }
-        correct as f64 / total as f64
+        self.regression.merge(&other.regression);
+        self.latencies.merge(&other.latencies);
+        self.errors += other.errors;
+        self.total += other.total;
     }
 
-    pub fn precision(&self) -> f64 {
-        let denominator = self.true_positives + self.false_positives;
-        if denominator == 0 {
-            return 0.0;
+    fn record_score(&mut self, class: &str, score: f64, is_positive: bool) {
+        let outcomes = self.scores.entry(class.to_string()).or_default();
+        if outcomes.len() == MAX_SCORED_OUTCOMES {
+            outcomes.pop_front();
         }
-        self.true_positives as f64 / denominator as f64
+        outcomes.push_back((score, is_positive));
+    }
+
+    /// Number of predictions recorded with a ground-truth label.
+    pub fn labeled_count(&self) -> u64 {
+        self.confusion.total()
+    }
+
+    pub fn confusion_matrix(&self) -> &ConfusionMatrix {
+        &self.confusion
     }
 
+    pub fn accuracy(&self) -> f64 {
+        self.confusion.accuracy()
+    }
+
+    /// Precision of the positive class for boolean outcomes, otherwise the
+    /// macro average over classes.
+    pub fn precision(&self) -> f64 {
+        self.headline(|m| m.precision)
+    }
+
+    /// Recall of the positive class for boolean outcomes, otherwise the
+    /// macro average over classes.
     pub fn recall(&self) -> f64 {
-        let denominator = self.true_positives + self.false_negatives;
-        if denominator == 0 {
-            return 0.0;
-        }
-        self.true_positives as f64 / denominator as f64
+        self.headline(|m| m.recall)
     }
 
     pub fn f1_score(&self) -> f64 {
-        let p = self.precision();
-        let r = self.recall();
-        if p + r == 0.0 {
+        self.headline(|m| m.f1)
+    }
+
+    fn headline(&self, metric: fn(&ClassMetrics) -> f64) -> f64 {
+        if self.confusion.is_binary() {
+            return metric(&self.confusion.class_metrics(POSITIVE));
+        }
+        let classes = self.confusion.classes();
+        if classes.is_empty() {
             return 0.0;
         }
-        2.0 * p * r / (p + r)
+        classes
+            .iter()
+            .map(|c| metric(&self.confusion.class_metrics(c)))
+            .sum::<f64>()
+            / classes.len() as f64
+    }
+
+    pub fn classification_metrics(&self) -> Option<ClassificationMetrics> {
+        (self.confusion.total() > 0).then(|| self.confusion.metrics())
+    }
+
+    /// ROC-AUC and PR-AUC averaged over the classes that have both positive
+    /// and negative scored outcomes.
+    pub fn score_metrics(&self) -> Option<ScoreMetrics> {
+        let per_class: Vec<(f64, f64)> = self
+            .scores
+            .values()
+            .filter_map(|outcomes| {
+                let outcomes = outcomes.iter().copied().collect::<Vec<_>>();
+                Some((roc_auc(&outcomes)?, pr_auc(&outcomes)?))
+            })
+            .collect();
+        if per_class.is_empty() {
+            return None;
+        }
+        let n = per_class.len() as f64;
+        Some(ScoreMetrics {
+            roc_auc: per_class.iter().map(|(roc, _)| roc).sum::<f64>() / n,
+            pr_auc: per_class.iter().map(|(_, pr)| pr).sum::<f64>() / n,
+        })
+    }
+
+    pub fn regression_metrics(&self) -> Option<RegressionMetrics> {
+        self.regression.metrics()
+    }
+
+    /// Predictions recorded, failed ones included.
+    pub fn predictions(&self) -> u64 {
+        self.total
     }
 
     pub fn error_rate(&self) -> f64 {
-        if self.total == 0 {
-            return 0.0;
+        ratio(self.errors, self.total)
+    }
+
+    pub fn latency_percentiles(&self) -> LatencyPercentiles {
+        LatencyPercentiles {
+            p50_ms: self.latencies.quantile(0.50),
+            p95_ms: self.latencies.quantile(0.95),
+            p99_ms: self.latencies.quantile(0.99),
         }
-        self.errors as f64 / self.total as f64
     }
 
     pub fn latency_p99(&self) -> u64 {
-        if self.latencies.is_empty() {
-            return 0;
+        self.latencies.quantile(0.99)
+    }
+
+    pub fn latency_mean(&self) -> u64 {
+        self.latencies.mean()
+    }
+
+    /// Everything tracked so far, compared against `baseline_accuracy`.
+    /// Degraded when labeled accuracy is below `accuracy_threshold`.
+    pub fn performance_drift(
+        &self,
+        model_type: ModelType,
+        baseline_accuracy: f64,
+        accuracy_threshold: f64,
+    ) -> PerformanceDriftResult {
+        let accuracy = self.accuracy();
+        let labeled = self.labeled_count() > 0;
+        PerformanceDriftResult {
+            model_type,
+            is_degraded: labeled && accuracy < accuracy_threshold,
+            current_accuracy: accuracy,
+            baseline_accuracy,
+            accuracy_delta: if labeled {
+                baseline_accuracy - accuracy
+            } else {
+                0.0
+            },
+            current_precision: self.precision(),
+            current_recall: self.recall(),
+            current_latency_p99_ms: self.latency_p99(),
+            current_error_rate: self.error_rate(),
+            latency: self.latency_percentiles(),
+            classification: self.classification_metrics(),
+            scores: self.score_metrics(),
+            regression: self.regression_metrics(),
         }
-        let mut sorted = self.latencies.clone();
-        sorted.sort();
-        let idx = (sorted.len() * 99 / 100).min(sorted.len() - 1);
-        sorted[idx]
     }
 
     pub fn reset(&mut self) {
-        self.true_positives = 0;
-        self.true_negatives = 0;
-        self.false_positives = 0;
-        self.false_negatives = 0;
-        self.latencies.clear();
-        self.errors = 0;
-        self.total = 0;
+        *self = Self::new();
     }
 }
 
@@ -125,3 +698,93 @@ impl Default for PerformanceTracker {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_multi_class_f1() {
+        let mut tracker = PerformanceTracker::new();
+        let class = |c: &str| PredictionResult::classification(c, HashMap::new());
+        for (actual, predicted) in [("a", "a"), ("a", "a"), ("a", "b"), ("b", "b"), ("c", "a")] {
+            assert!(tracker.record_outcome(&class(predicted), &GroundTruth::label(actual)));
+        }
+
+        let metrics = tracker.classification_metrics().unwrap();
+        assert!(close(metrics.accuracy, 0.6));
+        assert!(close(metrics.micro_f1, 0.6));
+        assert!(close(metrics.macro_f1, 4.0 / 9.0));
+        assert!(close(metrics.classes["a"].precision, 2.0 / 3.0));
+        assert!(close(metrics.classes["b"].recall, 1.0));
+        assert_eq!(metrics.classes["c"].support, 1);
+        assert_eq!(metrics.classes["c"].f1, 0.0);
+    }
+
+    #[test]
+    fn test_roc_and_pr_auc() {
+        let outcomes = [
+            (0.9, true),
+            (0.8, true),
+            (0.7, false),
+            (0.6, true),
+            (0.2, false),
+        ];
+        assert!(close(roc_auc(&outcomes).unwrap(), 5.0 / 6.0));
+        assert!(close(pr_auc(&outcomes).unwrap(), 2.0 / 3.0 + 0.25));
+        assert_eq!(roc_auc(&[(0.5, true)]), None);
+        // A constant score ranks nothing.
+        assert!(close(roc_auc(&[(0.5, true), (0.5, false)]).unwrap(), 0.5));
+    }
+
+    #[test]
+    fn test_regression_metrics() {
+        let mut tracker = PerformanceTracker::new();
+        for (predicted, actual) in [(2.0, 1.0), (3.0, 4.0), (5.0, 5.0), (1.0, 0.0)] {
+            tracker.record_outcome(
+                &PredictionResult::regression(predicted),
+                &GroundTruth::value(actual),
+            );
+        }
+
+        let result = tracker.performance_drift(ModelType::Regression, 0.9, 0.85);
+        let regression = result.regression.unwrap();
+        assert!(close(regression.mae, 0.75));
+        assert!(close(regression.rmse, 0.75f64.sqrt()));
+        assert!(close(regression.mape.unwrap(), 1.25 / 3.0));
+        assert!(!result.is_degraded);
+        assert!(result.classification.is_none());
+    }
+
+    #[test]
+    fn test_latency_histogram_is_accurate() {
+        let mut histogram = LatencyHistogram::new();
+        for _ in 0..100 {
+            histogram.record(5);
+        }
+        assert_eq!(histogram.quantile(0.99), 5);
+
+        let mut histogram = LatencyHistogram::new();
+        for ms in 1..=100_000 {
+            histogram.record(ms);
+        }
+        for (q, expected) in [(0.5, 50_000.0), (0.95, 95_000.0), (0.99, 99_000.0)] {
+            let actual = histogram.quantile(q) as f64;
+            assert!(
+                (actual - expected).abs() / expected < 0.035,
+                "{} {}",
+                q,
+                actual
+            );
+        }
+        assert_eq!(histogram.mean(), 50_000);
+
+        histogram.record(u64::MAX);
+        assert_eq!(histogram.quantile(1.0), u64::MAX);
+    }
+}
@@ -0,0 +1,188 @@
+use serde::{Deserialize, Serialize};
+
+use crate::model_performance::PerformanceSnapshot;
+
+/// What a candidate model version must show on the shadow or canary traffic
+/// it served before it replaces the active version.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromotionGates {
+    /// Labeled examples the candidate needs before it is judged at all.
+    pub min_feedback: u64,
+    /// How far the candidate's accuracy may trail the active version's.
+    pub max_accuracy_drop: f64,
+    pub max_latency_p99_ms: u64,
+    /// Highest share of the candidate's predictions that may fail.
+    pub max_error_rate: f64,
+}
+
+impl Default for PromotionGates {
+    fn default() -> Self {
+        Self {
+            min_feedback: 100,
+            max_accuracy_drop: 0.0,
+            max_latency_p99_ms: 500,
+            max_error_rate: 0.05,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromotionVerdict {
+    /// Too little feedback to judge the candidate yet.
+    Wait,
+    /// Every gate passed; the candidate should become the active version.
+    Promote,
+    /// A gate failed; the candidate should be taken out of service.
+    Rollback,
+}
+
+/// The measurements a verdict was based on.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PromotionMetrics {
+    pub feedback: u64,
+    pub accuracy: Option<f64>,
+    /// The active version's accuracy over the same window, or the accuracy
+    /// it was registered with when it has no feedback in the window.
+    pub active_accuracy: Option<f64>,
+    pub latency_p99_ms: u64,
+    pub error_rate: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromotionDecision {
+    pub verdict: PromotionVerdict,
+    /// Why, one entry per gate, e.g. `error rate 0.120 exceeds 0.050`.
+    pub reasons: Vec<String>,
+    pub metrics: PromotionMetrics,
+}
+
+impl PromotionGates {
+    /// Judges `candidate` against the version it would replace, if any.
+    /// Without an accuracy to compare with, the accuracy gate passes.
+    pub fn evaluate(
+        &self,
+        candidate: &PerformanceSnapshot,
+        active: Option<&PerformanceSnapshot>,
+    ) -> PromotionDecision {
+        let metrics = PromotionMetrics {
+            feedback: candidate.examples,
+            accuracy: candidate.accuracy,
+            active_accuracy: active.and_then(|a| a.accuracy.or(a.baseline_accuracy)),
+            latency_p99_ms: candidate.tracker.latency_p99(),
+            error_rate: candidate.tracker.error_rate(),
+        };
+
+        if metrics.feedback < self.min_feedback {
+            return PromotionDecision {
+                verdict: PromotionVerdict::Wait,
+                reasons: vec![format!(
+                    "{} of {} feedback events needed",
+                    metrics.feedback, self.min_feedback
+                )],
+                metrics,
+            };
+        }
+
+        let mut passed = Vec::new();
+        let mut failed = Vec::new();
+
+        match (metrics.accuracy, metrics.active_accuracy) {
+            (Some(accuracy), Some(active)) if accuracy < active - self.max_accuracy_drop => {
+                failed.push(format!(
+                    "accuracy {:.3} trails the active version's {:.3} by more than {:.3}",
+                    accuracy, active, self.max_accuracy_drop
+                ));
+            }
+            (Some(accuracy), Some(active)) => passed.push(format!(
+                "accuracy {:.3} against the active version's {:.3}",
+                accuracy, active
+            )),
+            _ => passed.push("no accuracy to compare with the active version".to_string()),
+        }
+
+        let latency = format!(
+            "latency p99 {}ms, limit {}ms",
+            metrics.latency_p99_ms, self.max_latency_p99_ms
+        );
+        if metrics.latency_p99_ms > self.max_latency_p99_ms {
+            failed.push(latency);
+        } else {
+            passed.push(latency);
+        }
+
+        let errors = format!(
+            "error rate {:.3}, limit {:.3}",
+            metrics.error_rate, self.max_error_rate
+        );
+        if metrics.error_rate > self.max_error_rate {
+            failed.push(errors);
+        } else {
+            passed.push(errors);
+        }
+
+        let (verdict, reasons) = if failed.is_empty() {
+            (PromotionVerdict::Promote, passed)
+        } else {
+            (PromotionVerdict::Rollback, failed)
+        };
+        PromotionDecision {
+            verdict,
+            reasons,
+            metrics,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::performance::PerformanceTracker;
+
+    fn snapshot(accuracy: f64, examples: u64, latency_ms: u64, errors: u64) -> PerformanceSnapshot {
+        let mut tracker = PerformanceTracker::new();
+        for _ in 0..100 - errors {
+            tracker.record_latency(latency_ms);
+        }
+        for _ in 0..errors {
+            tracker.record_error(latency_ms);
+        }
+        PerformanceSnapshot {
+            accuracy: Some(accuracy),
+            baseline_accuracy: None,
+            examples,
+            tracker,
+        }
+    }
+
+    #[test]
+    fn test_promotes_candidate_that_passes_every_gate() {
+        let gates = PromotionGates::default();
+        let active = snapshot(0.90, 500, 20, 0);
+        let decision = gates.evaluate(&snapshot(0.92, 150, 20, 1), Some(&active));
+        assert_eq!(decision.verdict, PromotionVerdict::Promote);
+        assert_eq!(decision.reasons.len(), 3);
+        assert_eq!(decision.metrics.active_accuracy, Some(0.90));
+    }
+
+    #[test]
+    fn test_rolls_back_on_any_failed_gate() {
+        let gates = PromotionGates {
+            max_accuracy_drop: 0.01,
+            ..PromotionGates::default()
+        };
+        let active = snapshot(0.90, 500, 20, 0);
+
+        let decision = gates.evaluate(&snapshot(0.85, 150, 20, 0), Some(&active));
+        assert_eq!(decision.verdict, PromotionVerdict::Rollback);
+        assert!(decision.reasons[0].starts_with("accuracy 0.850 trails"));
+
+        let decision = gates.evaluate(&snapshot(0.95, 150, 20, 12), Some(&active));
+        assert_eq!(decision.verdict, PromotionVerdict::Rollback);
+        assert_eq!(decision.reasons, vec!["error rate 0.120, limit 0.050"]);
+
+        let decision = gates.evaluate(&snapshot(0.95, 150, 2000, 0), None);
+        assert_eq!(decision.verdict, PromotionVerdict::Rollback);
+        assert_eq!(decision.reasons.len(), 1);
+        assert!(decision.reasons[0].starts_with("latency p99"));
+    }
+}
@@ -0,0 +1,572 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use flywheel_ml_core::{FeatureValue, FeatureVector};
+use serde::{Deserialize, Serialize};
+
+use crate::detector::{DriftResult, DriftSeverity, DriftType};
+use crate::statistical::{DriftDirection, DriftTest, FeatureDriftResult};
+
+/// Buckets ADWIN keeps per size before merging the oldest two.
+const ADWIN_MAX_BUCKETS: usize = 5;
+
+/// A sequential change-point test and how sensitive it is.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeTest {
+    /// A change once the cumulative deviation from the running mean, less
+    /// `delta` per value, exceeds `threshold` (in the value's units).
+    PageHinkley { delta: f64, threshold: f64 },
+    /// A change once the cumulative deviation from the warm-up mean, beyond
+    /// `slack` standard deviations per value, exceeds `threshold` standard
+    /// deviations.
+    Cusum { slack: f64, threshold: f64 },
+    /// A change once two halves of an adaptively sized window differ in mean
+    /// with confidence `1 - delta`.
+    Adwin { delta: f64 },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct ChangeDetection {
+    pub test: ChangeTest,
+    /// Values seen after starting, or after a confirmed change, before a
+    /// change can be confirmed.
+    pub warm_up: usize,
+}
+
+impl ChangeDetection {
+    pub fn detector(&self) -> Box<dyn ChangeDetector> {
+        match self.test {
+            ChangeTest::PageHinkley { delta, threshold } => {
+                Box::new(PageHinkley::new(delta, threshold, self.warm_up))
+            }
+            ChangeTest::Cusum { slack, threshold } => {
+                Box::new(Cusum::new(slack, threshold, self.warm_up))
+            }
+            ChangeTest::Adwin { delta } => Box::new(Adwin::new(delta, self.warm_up)),
+        }
+    }
+
+    fn drift_test(&self) -> DriftTest {
+        match self.test {
+            ChangeTest::PageHinkley { .. } => DriftTest::PageHinkley,
+            ChangeTest::Cusum { .. } => DriftTest::Cusum,
+            ChangeTest::Adwin { .. } => DriftTest::Adwin,
+        }
+    }
+}
+
+/// A confirmed change in a stream's mean.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChangePoint {
+    pub mean_before: f64,
+    pub mean_after: f64,
+    pub std_dev: f64,
+}
+
+impl ChangePoint {
+    /// How far the mean moved, in standard deviations.
+    pub fn shift(&self) -> f64 {
+        let delta = self.mean_after - self.mean_before;
+        if self.std_dev > 0.0 {
+            delta / self.std_dev
+        } else if delta == 0.0 {
+            0.0
+        } else {
+            delta.signum() * f64::INFINITY
+        }
+    }
+
+    pub fn direction(&self) -> DriftDirection {
+        if self.mean_after > self.mean_before {
+            DriftDirection::Increased
+        } else {
+            DriftDirection::Decreased
+        }
+    }
+
+    /// The change as a drift verdict.
+    pub fn result(&self) -> DriftResult {
+        DriftResult {
+            is_drifted: true,
+            drift_type: Some(DriftType::Statistical),
+            severity: DriftSeverity::from_mean_shift(self.shift().abs()),
+            psi_score: None,
+            kl_divergence: None,
+            accuracy_delta: None,
+        }
+    }
+}
+
+/// A streaming test for a change in the mean of a stream of values.
+pub trait ChangeDetector: Send + Sync {
+    /// Adds a value and returns the change it confirms, if any. After a
+    /// change the detector only considers the values that follow it.
+    fn update(&mut self, value: f64) -> Option<ChangePoint>;
+}
+
+/// Running mean and variance (Welford).
+#[derive(Debug, Clone, Copy, Default)]
+struct Moments {
+    n: u64,
+    mean: f64,
+    m2: f64,
+}
+
+impl Moments {
+    fn add(&mut self, value: f64) {
+        self.n += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.n as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    fn std_dev(&self) -> f64 {
+        if self.n < 2 {
+            return 0.0;
+        }
+        (self.m2 / (self.n - 1) as f64).sqrt()
+    }
+}
+
+/// Two-sided Page-Hinkley test.
+pub struct PageHinkley {
+    delta: f64,
+    threshold: f64,
+    warm_up: usize,
+    moments: Moments,
+    total: f64,
+    increase: Extreme,
+    decrease: Extreme,
+}
+
+/// A cumulative sum and where it was at its extreme, so the mean on either
+/// side of a change can be recovered.
+#[derive(Debug, Clone, Copy)]
+struct Extreme {
+    sum: f64,
+    extreme: f64,
+    count: u64,
+    total: f64,
+}
+
+impl Extreme {
+    fn new(extreme: f64) -> Self {
+        Self {
+            sum: 0.0,
+            extreme,
+            count: 0,
+            total: 0.0,
+        }
+    }
+
+    fn change(&self, moments: &Moments, total: f64) -> ChangePoint {
+        let before = self.count.max(1);
+        let after = (moments.n - self.count).max(1);
+        ChangePoint {
+            mean_before: self.total / before as f64,
+            mean_after: (total - self.total) / after as f64,
+            std_dev: moments.std_dev(),
+        }
+    }
+}
+
+impl PageHinkley {
+    pub fn new(delta: f64, threshold: f64, warm_up: usize) -> Self {
+        Self {
+            delta,
+            threshold,
+            warm_up,
+            moments: Moments::default(),
+            total: 0.0,
+            increase: Extreme::new(f64::INFINITY),
+            decrease: Extreme::new(f64::NEG_INFINITY),
+        }
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(self.delta, self.threshold, self.warm_up);
+    }
+}
+
+impl ChangeDetector for PageHinkley {
+    fn update(&mut self, value: f64) -> Option<ChangePoint> {
+        self.moments.add(value);
+        self.total += value;
+        let (n, total) = (self.moments.n, self.total);
+
+        self.increase.sum += value - self.moments.mean - self.delta;
+        if self.increase.sum < self.increase.extreme {
+            self.increase = Extreme {
+                extreme: self.increase.sum,
+                count: n,
+                total,
+                ..self.increase
+            };
+        }
+        self.decrease.sum += value - self.moments.mean + self.delta;
+        if self.decrease.sum > self.decrease.extreme {
+            self.decrease = Extreme {
+                extreme: self.decrease.sum,
+                count: n,
+                total,
+                ..self.decrease
+            };
+        }
+
+        if (n as usize) < self.warm_up {
+            return None;
+        }
+        let change = if self.increase.sum - self.increase.extreme > self.threshold {
+            self.increase.change(&self.moments, total)
+        } else if self.decrease.extreme - self.decrease.sum > self.threshold {
+            self.decrease.change(&self.moments, total)
+        } else {
+            return None;
+        };
+        self.reset();
+        Some(change)
+    }
+}
+
+/// Two-sided CUSUM on values standardized by the warm-up's mean and
+/// standard deviation.
+pub struct Cusum {
+    slack: f64,
+    threshold: f64,
+    warm_up: usize,
+    reference: Moments,
+    upper: Run,
+    lower: Run,
+}
+
+/// A one-sided cumulative sum and the values it has accumulated since it
+/// last sat at zero.
+#[derive(Debug, Clone, Copy, Default)]
+struct Run {
+    sum: f64,
+    count: u64,
+    total: f64,
+}
+
+impl Run {
+    fn add(&mut self, step: f64, value: f64) {
+        self.sum = (self.sum + step).max(0.0);
+        if self.sum > 0.0 {
+            self.count += 1;
+            self.total += value;
+        } else {
+            *self = Run::default();
+        }
+    }
+}
+
+impl Cusum {
+    pub fn new(slack: f64, threshold: f64, warm_up: usize) -> Self {
+        Self {
+            slack,
+            threshold,
+            // The standard deviation needs two values.
+            warm_up: warm_up.max(2),
+            reference: Moments::default(),
+            upper: Run::default(),
+            lower: Run::default(),
+        }
+    }
+}
+
+impl ChangeDetector for Cusum {
+    fn update(&mut self, value: f64) -> Option<ChangePoint> {
+        if (self.reference.n as usize) < self.warm_up {
+            self.reference.add(value);
+            return None;
+        }
+
+        let std_dev = self.reference.std_dev();
+        let deviation = value - self.reference.mean;
+        let z = if std_dev > 0.0 {
+            deviation / std_dev
+        } else if deviation == 0.0 {
+            0.0
+        } else {
+            deviation.signum() * f64::INFINITY
+        };
+        self.upper.add(z - self.slack, value);
+        self.lower.add(-z - self.slack, value);
+
+        let run = if self.upper.sum > self.threshold {
+            self.upper
+        } else if self.lower.sum > self.threshold {
+            self.lower
+        } else {
+            return None;
+        };
+        let change = ChangePoint {
+            mean_before: self.reference.mean,
+            mean_after: run.total / run.count as f64,
+            std_dev,
+        };
+        *self = Self::new(self.slack, self.threshold, self.warm_up);
+        Some(change)
+    }
+}
+
+/// ADWIN2: keeps an adaptively sized window as exponential histogram buckets
+/// and drops its oldest part whenever the two parts' means differ by more
+/// than chance allows.
+pub struct Adwin {
+    delta: f64,
+    warm_up: usize,
+    /// Buckets of 2^i values in row i, newest first.
+    rows: Vec<VecDeque<Bucket>>,
+    width: u64,
+    total: f64,
+    /// Sum of squared deviations from the window mean.
+    variance: f64,
+    since_change: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    total: f64,
+    variance: f64,
+    count: u64,
+}
+
+impl Bucket {
+    fn mean(&self) -> f64 {
+        self.total / self.count as f64
+    }
+
+    fn merge(&self, other: &Bucket) -> Bucket {
+        let (n1, n2) = (self.count as f64, other.count as f64);
+        Bucket {
+            total: self.total + other.total,
+            variance: self.variance
+                + other.variance
+                + n1 * n2 / (n1 + n2) * (self.mean() - other.mean()).powi(2),
+            count: self.count + other.count,
+        }
+    }
+}
+
+impl Adwin {
+    pub fn new(delta: f64, warm_up: usize) -> Self {
+        Self {
+            delta,
+            warm_up,
+            rows: vec![VecDeque::new()],
+            width: 0,
+            total: 0.0,
+            variance: 0.0,
+            since_change: 0,
+        }
+    }
+
+    pub fn width(&self) -> u64 {
+        self.width
+    }
+
+    fn insert(&mut self, value: f64) {
+        if self.width > 0 {
+            let mean = self.total / self.width as f64;
+            let width = self.width as f64;
+            self.variance += width * (value - mean).powi(2) / (width + 1.0);
+        }
+        self.width += 1;
+        self.total += value;
+        self.rows[0].push_front(Bucket {
+            total: value,
+            variance: 0.0,
+            count: 1,
+        });
+
+        let mut row = 0;
+        while self.rows[row].len() > ADWIN_MAX_BUCKETS {
+            let older = self.rows[row].pop_back().expect("row is over capacity");
+            let newer = self.rows[row].pop_back().expect("row is over capacity");
+            if self.rows.len() == row + 1 {
+                self.rows.push(VecDeque::new());
+            }
+            self.rows[row + 1].push_front(newer.merge(&older));
+            row += 1;
+        }
+    }
+
+    fn drop_oldest(&mut self) {
+        let Some(row) = self.rows.iter_mut().rev().find(|r| !r.is_empty()) else {
+            return;
+        };
+        let bucket = row.pop_back().expect("row is not empty");
+
+        let rest = (self.width - bucket.count) as f64;
+        if rest > 0.0 {
+            let rest_mean = (self.total - bucket.total) / rest;
+            let n = bucket.count as f64;
+            self.variance -=
+                bucket.variance + n * rest / (n + rest) * (bucket.mean() - rest_mean).powi(2);
+            self.variance = self.variance.max(0.0);
+        } else {
+            self.variance = 0.0;
+        }
+        self.width -= bucket.count;
+        self.total -= bucket.total;
+    }
+
+    /// The first split, oldest part first, whose means differ significantly.
+    fn cut(&self) -> Option<ChangePoint> {
+        let width = self.width as f64;
+        let window_variance = self.variance / width;
+        let confidence = (2.0 * width.ln() / self.delta).ln();
+
+        let mut n0 = 0u64;
+        let mut total0 = 0.0;
+        let buckets = self.rows.iter().rev().flat_map(|row| row.iter().rev());
+        for bucket in buckets {
+            n0 += bucket.count;
+            total0 += bucket.total;
+            let n1 = self.width - n0;
+            if n1 == 0 {
+                break;
+            }
+
+            let (mean0, mean1) = (total0 / n0 as f64, (self.total - total0) / n1 as f64);
+            let m = 1.0 / n0 as f64 + 1.0 / n1 as f64;
+            let epsilon =
+                (2.0 * m * window_variance * confidence).sqrt() + 2.0 / 3.0 * m * confidence;
+            if (mean0 - mean1).abs() > epsilon {
+                return Some(ChangePoint {
+                    mean_before: mean0,
+                    mean_after: mean1,
+                    std_dev: window_variance.sqrt(),
+                });
+            }
+        }
+        None
+    }
+}
+
+impl ChangeDetector for Adwin {
+    fn update(&mut self, value: f64) -> Option<ChangePoint> {
+        self.insert(value);
+        self.since_change += 1;
+        if self.since_change < self.warm_up || self.width < 2 {
+            return None;
+        }
+
+        let change = self.cut()?;
+        self.drop_oldest();
+        while self.width >= 2 && self.cut().is_some() {
+            self.drop_oldest();
+        }
+        self.since_change = 0;
+        Some(change)
+    }
+}
+
+/// A change confirmed in one feature.
+#[derive(Debug, Clone)]
+pub struct FeatureChange {
+    pub feature_name: String,
+    pub change: ChangePoint,
+    pub result: DriftResult,
+    pub test: DriftTest,
+}
+
+impl FeatureChange {
+    pub fn feature_drift(&self) -> FeatureDriftResult {
+        FeatureDriftResult {
+            feature_name: self.feature_name.clone(),
+            psi_score: 0.0,
+            kl_divergence: 0.0,
+            is_drifted: true,
+            direction: self.change.direction(),
+            test: self.test,
+            statistic: self.change.shift(),
+            p_value: None,
+        }
+    }
+}
+
+/// Runs a sequential change-point test on every numeric feature of a
+/// `FeatureVector` stream, reporting changes as soon as they are confirmed
+/// rather than at a check interval. Categorical features are ignored.
+pub struct SequentialDriftDetector {
+    detection: ChangeDetection,
+    features: BTreeMap<String, Box<dyn ChangeDetector>>,
+}
+
+impl SequentialDriftDetector {
+    pub fn new(detection: ChangeDetection) -> Self {
+        Self {
+            detection,
+            features: BTreeMap::new(),
+        }
+    }
+
+    /// Adds a vector and returns the changes it confirms.
+    pub fn observe(&mut self, vector: &FeatureVector) -> Vec<FeatureChange> {
+        let mut changes = Vec::new();
+        for (name, value) in &vector.features {
+            let value = match value {
+                FeatureValue::Float(v) => *v,
+                FeatureValue::Int(v) => *v as f64,
+                FeatureValue::Boolean(b) => f64::from(u8::from(*b)),
+                _ => continue,
+            };
+            let detector = self
+                .features
+                .entry(name.clone())
+                .or_insert_with(|| self.detection.detector());
+            if let Some(change) = detector.update(value) {
+                changes.push(FeatureChange {
+                    feature_name: name.clone(),
+                    change,
+                    result: change.result(),
+                    test: self.detection.drift_test(),
+                });
+            }
+        }
+        changes.sort_by(|a, b| a.feature_name.cmp(&b.feature_name));
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A deterministic stream alternating around `mean`.
+    fn stream(mean: f64, n: usize) -> impl Iterator<Item = f64> {
+        (0..n).map(move |i| mean + [-1.0, 0.5, 1.0, -0.5][i % 4])
+    }
+
+    /// Index of the first value, counted from the shift, that confirms a change.
+    fn detection_delay(detector: &mut dyn ChangeDetector) -> Option<usize> {
+        for value in stream(10.0, 500) {
+            assert_eq!(detector.update(value), None);
+        }
+        stream(15.0, 500).position(|value| detector.update(value).is_some())
+    }
+
+    #[test]
+    fn test_page_hinkley_detects_shift() {
+        let mut detector = PageHinkley::new(0.005, 50.0, 30);
+        let delay = detection_delay(&mut detector).unwrap();
+        assert!(delay < 30, "detected after {}", delay);
+    }
+
+    #[test]
+    fn test_cusum_detects_shift() {
+        let mut detector = Cusum::new(0.5, 5.0, 30);
+        let delay = detection_delay(&mut detector).unwrap();
+        assert!(delay < 5, "detected after {}", delay);
+    }
+
+    #[test]
+    fn test_adwin_detects_shift_and_shrinks() {
+        let mut detector = Adwin::new(0.002, 30);
+        let delay = detection_delay(&mut detector).unwrap();
+        assert!(delay < 30, "detected after {}", delay);
+        assert!(detector.width() < 500);
+    }
+}
@@ -1,5 +1,6 @@
+use crate::window::{BinEdges, StreamingHistogram};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StatisticalDriftResult {
@@ -9,20 +10,125 @@ pub struct StatisticalDriftResult {
     pub feature_drifts: HashMap<String, FeatureDriftResult>,
 }
 
+impl StatisticalDriftResult {
+    /// Per-feature results, most drifted (highest PSI) first.
+    pub fn ranked_features(&self) -> Vec<&FeatureDriftResult> {
+        let mut features: Vec<&FeatureDriftResult> = self.feature_drifts.values().collect();
+        features.sort_by(|a, b| {
+            b.psi_score
+                .total_cmp(&a.psi_score)
+                .then_with(|| a.feature_name.cmp(&b.feature_name))
+        });
+        features
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FeatureDriftResult {
     pub feature_name: String,
     pub psi_score: f64,
     pub kl_divergence: f64,
     pub is_drifted: bool,
+    pub direction: DriftDirection,
+    /// The test that decided `is_drifted`.
+    #[serde(default)]
+    pub test: DriftTest,
+    /// The deciding test's statistic, e.g. the KS `D` or the Wasserstein distance.
+    #[serde(default)]
+    pub statistic: f64,
+    #[serde(default)]
+    pub p_value: Option<f64>,
 }
 
+/// A two-sample test used to decide whether a feature drifted.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftTest {
+    /// Population stability index above the threshold.
+    #[default]
+    Psi,
+    /// KL divergence of current from reference above the threshold.
+    KlDivergence,
+    /// Kolmogorov–Smirnov p-value below the threshold. Numeric only.
+    Ks,
+    /// Wasserstein-1 distance above the threshold, in the feature's units. Numeric only.
+    Wasserstein,
+    /// Jensen–Shannon distance (0 to 1) above the threshold.
+    JensenShannon,
+    /// Chi-squared homogeneity p-value below the threshold.
+    ChiSquared,
+    /// Sequential change-point tests run by `SequentialDriftDetector`. The
+    /// statistic is the mean shift in standard deviations.
+    PageHinkley,
+    Cusum,
+    Adwin,
+}
+
+impl DriftTest {
+    /// Whether the threshold is a significance level rather than a distance.
+    pub fn uses_p_value(&self) -> bool {
+        matches!(self, DriftTest::Ks | DriftTest::ChiSquared)
+    }
+
+    /// Whether the test needs raw numeric samples rather than a distribution.
+    pub fn numeric_only(&self) -> bool {
+        matches!(self, DriftTest::Ks | DriftTest::Wasserstein)
+    }
+}
+
+/// The test and threshold a feature is judged by instead of the PSI and KL defaults.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct FeatureTest {
+    pub test: DriftTest,
+    /// A p-value for `ks` and `chi_squared`, otherwise a distance.
+    pub threshold: f64,
+}
+
+/// A test statistic and the probability of seeing it if nothing drifted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TestResult {
+    pub statistic: f64,
+    pub p_value: f64,
+}
+
+/// How the current window moved relative to the reference.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftDirection {
+    /// The mean of a numeric feature went up.
+    Increased,
+    /// The mean of a numeric feature went down.
+    Decreased,
+    /// The distribution changed shape, or the feature is categorical.
+    Shifted,
+}
+
+impl DriftDirection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DriftDirection::Increased => "increased",
+            DriftDirection::Decreased => "decreased",
+            DriftDirection::Shifted => "shifted",
+        }
+    }
+}
+
+/// Population stability index of `current` against `reference`, over `bins`
+/// equal-width bins spanning the reference's range.
+///
+/// Both samples are binned on the reference's edges, so a shift moves
+/// current values into other bins, or into the outermost bins when they fall
+/// outside the reference's range. Binning each sample over its own range
+/// would hide a shift that keeps the distribution's shape.
 pub fn compute_psi(reference: &[f64], current: &[f64], bins: usize) -> f64 {
-    let ref_hist = histogram(reference, bins);
-    let cur_hist = histogram(current, bins);
+    let (ref_hist, cur_hist) = binned_distributions(reference, current, bins);
+    psi_from_distributions(&ref_hist, &cur_hist)
+}
 
+/// PSI between two distributions over the same bins or categories.
+pub fn psi_from_distributions(reference: &[f64], current: &[f64]) -> f64 {
     let mut psi = 0.0;
-    for (ref_pct, cur_pct) in ref_hist.iter().zip(cur_hist.iter()) {
+    for (ref_pct, cur_pct) in reference.iter().zip(current.iter()) {
         let ref_pct = ref_pct.max(0.0001);
         let cur_pct = cur_pct.max(0.0001);
         psi += (cur_pct - ref_pct) * (cur_pct / ref_pct).ln();
@@ -30,6 +136,36 @@ pub fn compute_psi(reference: &[f64], current: &[f64], bins: usize) -> f64 {
     psi
 }
 
+/// Histograms of both windows over the reference's bin edges.
+pub fn binned_distributions(
+    reference: &[f64],
+    current: &[f64],
+    bins: usize,
+) -> (Vec<f64>, Vec<f64>) {
+    // Both histograms share the reference bin edges, otherwise a pure shift is invisible.
+    let edges = BinEdges::from_reference(reference, bins);
+    (histogram(reference, edges), histogram(current, edges))
+}
+
+/// Category frequencies of both windows, given as occurrence counts, over
+/// every category seen in either.
+pub fn categorical_distributions(
+    reference: &BTreeMap<String, u64>,
+    current: &BTreeMap<String, u64>,
+) -> (Vec<f64>, Vec<f64>) {
+    let categories: BTreeSet<&String> = reference.keys().chain(current.keys()).collect();
+
+    let frequencies = |counts: &BTreeMap<String, u64>| -> Vec<f64> {
+        let total = counts.values().sum::<u64>().max(1) as f64;
+        categories
+            .iter()
+            .map(|c| counts.get(*c).copied().unwrap_or(0) as f64 / total)
+            .collect()
+    };
+
+    (frequencies(reference), frequencies(current))
+}
+
 pub fn compute_kl_divergence(p: &[f64], q: &[f64]) -> f64 {
     p.iter()
         .zip(q.iter())
@@ -41,31 +177,217 @@ pub fn compute_kl_divergence(p: &[f64], q: &[f64]) -> f64 {
         .sum()
 }
 
-fn histogram(values: &[f64], bins: usize) -> Vec<f64> {
-    if values.is_empty() {
-        return vec![0.0; bins];
+/// Two-sample Kolmogorov–Smirnov test. The p-value uses the asymptotic
+/// Kolmogorov distribution with the effective sample size.
+pub fn compute_ks(reference: &[f64], current: &[f64]) -> TestResult {
+    if reference.is_empty() || current.is_empty() {
+        return TestResult {
+            statistic: 0.0,
+            p_value: 1.0,
+        };
+    }
+
+    let reference = sorted(reference);
+    let current = sorted(current);
+    let (n, m) = (reference.len() as f64, current.len() as f64);
+
+    let (mut i, mut j, mut d) = (0, 0, 0.0f64);
+    while i < reference.len() && j < current.len() {
+        let x = reference[i].min(current[j]);
+        while i < reference.len() && reference[i] <= x {
+            i += 1;
+        }
+        while j < current.len() && current[j] <= x {
+            j += 1;
+        }
+        d = d.max((i as f64 / n - j as f64 / m).abs());
+    }
+
+    let en = (n * m / (n + m)).sqrt();
+    TestResult {
+        statistic: d,
+        p_value: kolmogorov_q((en + 0.12 + 0.11 / en) * d),
+    }
+}
+
+/// Wasserstein-1 (earth mover's) distance between two samples.
+pub fn compute_wasserstein(reference: &[f64], current: &[f64]) -> f64 {
+    if reference.is_empty() || current.is_empty() {
+        return 0.0;
+    }
+
+    let reference = sorted(reference);
+    let current = sorted(current);
+    let (n, m) = (reference.len() as f64, current.len() as f64);
+
+    // Integrate |F_ref - F_cur| between consecutive points of the merged sample.
+    let mut points: Vec<f64> = reference.iter().chain(&current).copied().collect();
+    points.sort_by(f64::total_cmp);
+
+    let (mut i, mut j, mut distance) = (0, 0, 0.0);
+    for pair in points.windows(2) {
+        while i < reference.len() && reference[i] <= pair[0] {
+            i += 1;
+        }
+        while j < current.len() && current[j] <= pair[0] {
+            j += 1;
+        }
+        distance += (i as f64 / n - j as f64 / m).abs() * (pair[1] - pair[0]);
+    }
+    distance
+}
+
+/// Jensen–Shannon distance (the square root of the base-2 divergence) between
+/// two distributions over the same bins or categories. Ranges from 0 to 1.
+pub fn compute_js_distance(p: &[f64], q: &[f64]) -> f64 {
+    let kl = |a: f64, m: f64| if a > 0.0 { a * (a / m).log2() } else { 0.0 };
+    let divergence: f64 = p
+        .iter()
+        .zip(q)
+        .map(|(&p_i, &q_i)| {
+            let m = (p_i + q_i) / 2.0;
+            0.5 * kl(p_i, m) + 0.5 * kl(q_i, m)
+        })
+        .sum();
+    divergence.max(0.0).sqrt().min(1.0)
+}
+
+/// Chi-squared test of homogeneity between two samples given as
+/// distributions over the same bins or categories and their sizes.
+pub fn compute_chi_squared(
+    reference: &[f64],
+    reference_n: usize,
+    current: &[f64],
+    current_n: usize,
+) -> TestResult {
+    let (n, m) = (reference_n as f64, current_n as f64);
+    let total = n + m;
+
+    let mut statistic = 0.0;
+    let mut categories = 0;
+    for (&ref_pct, &cur_pct) in reference.iter().zip(current) {
+        let (observed_ref, observed_cur) = (ref_pct * n, cur_pct * m);
+        let column = observed_ref + observed_cur;
+        if column <= 0.0 {
+            continue;
+        }
+        categories += 1;
+
+        let expected_ref = column * n / total;
+        let expected_cur = column * m / total;
+        statistic += (observed_ref - expected_ref).powi(2) / expected_ref
+            + (observed_cur - expected_cur).powi(2) / expected_cur;
     }
 
-    let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
-    let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    if categories < 2 || n == 0.0 || m == 0.0 {
+        return TestResult {
+            statistic: 0.0,
+            p_value: 1.0,
+        };
+    }
 
-    if (max - min).abs() < f64::EPSILON {
-        let mut hist = vec![0.0; bins];
-        hist[0] = 1.0;
-        return hist;
+    let dof = (categories - 1) as f64;
+    TestResult {
+        statistic,
+        p_value: gamma_q(dof / 2.0, statistic / 2.0),
     }
+}
 
-    let bin_width = (max - min) / bins as f64;
-    let mut counts = vec![0usize; bins];
+fn sorted(values: &[f64]) -> Vec<f64> {
+    let mut values = values.to_vec();
+    values.sort_by(f64::total_cmp);
+    values
+}
 
-    for &v in values {
-        let bin = ((v - min) / bin_width).floor() as usize;
-        let bin = bin.min(bins - 1);
-        counts[bin] += 1;
+/// Survival function of the Kolmogorov distribution.
+fn kolmogorov_q(lambda: f64) -> f64 {
+    if lambda < 0.2 {
+        return 1.0;
     }
 
-    let total = values.len() as f64;
-    counts.iter().map(|&c| c as f64 / total).collect()
+    let mut sum = 0.0;
+    let mut sign = 1.0;
+    for j in 1..=100 {
+        let term = sign * (-2.0 * (j * j) as f64 * lambda * lambda).exp();
+        sum += term;
+        if term.abs() < 1e-12 {
+            break;
+        }
+        sign = -sign;
+    }
+    (2.0 * sum).clamp(0.0, 1.0)
+}
+
+/// Regularized upper incomplete gamma function `Q(a, x)`.
+fn gamma_q(a: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 1.0;
+    }
+
+    let prefix = (-x + a * x.ln() - ln_gamma(a)).exp();
+    if x < a + 1.0 {
+        // Series for P(a, x).
+        let (mut term, mut sum, mut ap) = (1.0 / a, 1.0 / a, a);
+        for _ in 0..500 {
+            ap += 1.0;
+            term *= x / ap;
+            sum += term;
+            if term.abs() < sum.abs() * 1e-14 {
+                break;
+            }
+        }
+        (1.0 - sum * prefix).clamp(0.0, 1.0)
+    } else {
+        // Continued fraction for Q(a, x) (modified Lentz).
+        let tiny = 1e-300;
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / tiny;
+        let mut d = 1.0 / b;
+        let mut h = d;
+        for i in 1..500 {
+            let an = -(i as f64) * (i as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            if d.abs() < tiny {
+                d = tiny;
+            }
+            c = b + an / c;
+            if c.abs() < tiny {
+                c = tiny;
+            }
+            d = 1.0 / d;
+            let delta = d * c;
+            h *= delta;
+            if (delta - 1.0).abs() < 1e-14 {
+                break;
+            }
+        }
+        (prefix * h).clamp(0.0, 1.0)
+    }
+}
+
+/// Lanczos approximation of `ln Γ(x)` for `x > 0`.
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.180_091_729_471_46,
+        -86.505_320_329_416_77,
+        24.014_098_240_830_91,
+        -1.231_739_572_450_155,
+        0.001_208_650_973_866_179,
+        -0.000_005_395_239_384_953,
+    ];
+
+    let tmp = x + 5.5;
+    let tmp = tmp - (x + 0.5) * tmp.ln();
+    let mut series = 1.000_000_000_190_015;
+    for (i, c) in COEFFICIENTS.iter().enumerate() {
+        series += c / (x + 1.0 + i as f64);
+    }
+    -tmp + (2.506_628_274_631_000_5 * series / x).ln()
+}
+
+fn histogram(values: &[f64], edges: BinEdges) -> Vec<f64> {
+    StreamingHistogram::from_values(edges, values.iter().copied()).distribution()
 }
 
 #[cfg(test)]
@@ -89,4 +411,62 @@ mod tests {
         let psi = compute_psi(&reference, &current, 10);
         assert!(psi > 0.1, "PSI should be high for shifted distributions");
     }
+
+    #[test]
+    fn test_psi_uses_reference_bins() {
+        let reference: Vec<f64> = (0..1000).map(|i| i as f64 / 1000.0).collect();
+        // Same shape, entirely above the reference: every value lands in the last bin.
+        let current: Vec<f64> = reference.iter().map(|v| v + 10.0).collect();
+
+        let (ref_dist, cur_dist) = binned_distributions(&reference, &current, 10);
+        assert!((ref_dist[0] - 0.1).abs() < 1e-9);
+        assert_eq!(cur_dist[9], 1.0);
+        assert!(compute_psi(&reference, &current, 10) > 1.0);
+
+        // A constant reference has one bin to compare against.
+        let psi = compute_psi(&[1.0; 100], &[1.0; 100], 10);
+        assert!(psi.abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_ks() {
+        let reference: Vec<f64> = (0..1000).map(|i| i as f64 / 1000.0).collect();
+        let same: Vec<f64> = (0..500).map(|i| i as f64 / 500.0).collect();
+        let shifted: Vec<f64> = (0..500).map(|i| i as f64 / 500.0 + 0.2).collect();
+
+        let result = compute_ks(&reference, &same);
+        assert!(result.statistic < 0.01);
+        assert!(result.p_value > 0.99);
+
+        let result = compute_ks(&reference, &shifted);
+        assert!((result.statistic - 0.2).abs() < 0.01);
+        assert!(result.p_value < 1e-6);
+    }
+
+    #[test]
+    fn test_wasserstein_is_mean_shift_for_translated_sample() {
+        let reference: Vec<f64> = (0..100).map(|i| i as f64).collect();
+        let current: Vec<f64> = (0..100).map(|i| i as f64 + 3.0).collect();
+
+        assert!(compute_wasserstein(&reference, &reference).abs() < 1e-9);
+        assert!((compute_wasserstein(&reference, &current) - 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_js_distance_bounds() {
+        assert!(compute_js_distance(&[0.5, 0.5], &[0.5, 0.5]).abs() < 1e-9);
+        assert!((compute_js_distance(&[1.0, 0.0], &[0.0, 1.0]) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_chi_squared() {
+        let result = compute_chi_squared(&[0.5, 0.5], 1000, &[0.5, 0.5], 1000);
+        assert!(result.statistic.abs() < 1e-9);
+        assert!((result.p_value - 1.0).abs() < 1e-9);
+
+        // 2x2 table [[60, 40], [40, 60]]: chi-squared 8.0 with one degree of freedom.
+        let result = compute_chi_squared(&[0.6, 0.4], 100, &[0.4, 0.6], 100);
+        assert!((result.statistic - 8.0).abs() < 1e-9);
+        assert!((result.p_value - 0.004678).abs() < 1e-5);
+    }
 }
@@ -0,0 +1,268 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Equal-width bins over a reference sample's range. Values outside the range
+/// land in the outermost bins.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BinEdges {
+    min: f64,
+    max: f64,
+    bins: usize,
+}
+
+impl BinEdges {
+    pub fn new(min: f64, max: f64, bins: usize) -> Self {
+        Self { min, max, bins }
+    }
+
+    pub fn from_reference(reference: &[f64], bins: usize) -> Self {
+        let min = reference.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max = reference.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        Self::new(min, max, bins)
+    }
+
+    pub fn len(&self) -> usize {
+        self.bins
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bins == 0
+    }
+
+    /// Index of the bin holding `value`. Must not be called with zero bins.
+    pub fn bin(&self, value: f64) -> usize {
+        let width = (self.max - self.min) / self.bins as f64;
+        let bin = if width.is_finite() && width > f64::EPSILON {
+            ((value - self.min) / width).floor().max(0.0) as usize
+        } else if value > self.max {
+            self.bins - 1
+        } else {
+            0
+        };
+        bin.min(self.bins - 1)
+    }
+}
+
+/// Counts per fixed bin, updated one value at a time.
+#[derive(Debug, Clone)]
+pub struct StreamingHistogram {
+    edges: BinEdges,
+    counts: Vec<u64>,
+    total: u64,
+}
+
+impl StreamingHistogram {
+    pub fn new(edges: BinEdges) -> Self {
+        Self {
+            edges,
+            counts: vec![0; edges.len()],
+            total: 0,
+        }
+    }
+
+    pub fn from_values(edges: BinEdges, values: impl IntoIterator<Item = f64>) -> Self {
+        let mut histogram = Self::new(edges);
+        for value in values {
+            histogram.add(value);
+        }
+        histogram
+    }
+
+    pub fn edges(&self) -> BinEdges {
+        self.edges
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    pub fn add(&mut self, value: f64) {
+        if self.edges.is_empty() {
+            return;
+        }
+        self.counts[self.edges.bin(value)] += 1;
+        self.total += 1;
+    }
+
+    /// Removes a value previously added.
+    pub fn remove(&mut self, value: f64) {
+        if self.edges.is_empty() {
+            return;
+        }
+        let count = &mut self.counts[self.edges.bin(value)];
+        if *count > 0 {
+            *count -= 1;
+            self.total -= 1;
+        }
+    }
+
+    /// Fraction of values in each bin.
+    pub fn distribution(&self) -> Vec<f64> {
+        if self.total == 0 {
+            return vec![0.0; self.counts.len()];
+        }
+        let total = self.total as f64;
+        self.counts.iter().map(|&c| c as f64 / total).collect()
+    }
+}
+
+/// Which values a `StreamingWindow` keeps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowSpan {
+    /// The last `n` values.
+    Count(usize),
+    /// Values added within the duration, but never more than `max_len`.
+    Time { duration: Duration, max_len: usize },
+}
+
+/// The most recent values of a stream, kept in a ring buffer alongside a
+/// histogram over fixed bins and their sum. Adding a value costs O(1)
+/// regardless of the window size, and reading the distribution costs O(bins).
+#[derive(Debug, Clone)]
+pub struct StreamingWindow {
+    span: WindowSpan,
+    values: VecDeque<(Instant, f64)>,
+    histogram: Option<StreamingHistogram>,
+    sum: f64,
+}
+
+impl StreamingWindow {
+    pub fn new(span: WindowSpan) -> Self {
+        let capacity = match span {
+            WindowSpan::Count(n) => n,
+            WindowSpan::Time { max_len, .. } => max_len,
+        };
+        Self {
+            span,
+            values: VecDeque::with_capacity(capacity.min(1 << 16)),
+            histogram: None,
+            sum: 0.0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Mean of the values in the window, 0 when it is empty.
+    pub fn mean(&self) -> f64 {
+        self.sum / self.values.len().max(1) as f64
+    }
+
+    /// The values in the window, oldest first.
+    pub fn values(&self) -> impl ExactSizeIterator<Item = f64> + '_ {
+        self.values.iter().map(|&(_, v)| v)
+    }
+
+    /// Bins the window over `edges` from now on. Rebuilds the histogram from
+    /// the buffered values, so this is the only O(n) operation.
+    pub fn set_edges(&mut self, edges: BinEdges) {
+        self.histogram = Some(StreamingHistogram::from_values(
+            edges,
+            self.values.iter().map(|&(_, v)| v),
+        ));
+    }
+
+    /// `None` until `set_edges` has been called.
+    pub fn histogram(&self) -> Option<&StreamingHistogram> {
+        self.histogram.as_ref()
+    }
+
+    pub fn push(&mut self, value: f64) {
+        self.push_at(value, Instant::now());
+    }
+
+    pub fn push_at(&mut self, value: f64, at: Instant) {
+        self.values.push_back((at, value));
+        self.sum += value;
+        if let Some(histogram) = &mut self.histogram {
+            histogram.add(value);
+        }
+        self.evict(at);
+    }
+
+    fn evict(&mut self, now: Instant) {
+        let max_len = match self.span {
+            WindowSpan::Count(n) => n,
+            WindowSpan::Time { duration, max_len } => {
+                while self
+                    .values
+                    .front()
+                    .is_some_and(|&(at, _)| now.saturating_duration_since(at) > duration)
+                {
+                    self.pop_front();
+                }
+                max_len
+            }
+        };
+        while self.values.len() > max_len {
+            self.pop_front();
+        }
+    }
+
+    fn pop_front(&mut self) {
+        if let Some((_, value)) = self.values.pop_front() {
+            self.sum -= value;
+            if let Some(histogram) = &mut self.histogram {
+                histogram.remove(value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_add_and_remove() {
+        let edges = BinEdges::new(0.0, 10.0, 5);
+        let mut histogram = StreamingHistogram::from_values(edges, [1.0, 3.0, 3.5, 9.0, 20.0]);
+        assert_eq!(histogram.distribution(), vec![0.2, 0.4, 0.0, 0.0, 0.4]);
+
+        histogram.remove(20.0);
+        assert_eq!(histogram.total(), 4);
+        assert_eq!(histogram.distribution()[4], 0.25);
+    }
+
+    #[test]
+    fn test_count_window_evicts_oldest() {
+        let mut window = StreamingWindow::new(WindowSpan::Count(3));
+        window.set_edges(BinEdges::new(0.0, 4.0, 4));
+        for value in [0.5, 1.5, 2.5, 3.5] {
+            window.push(value);
+        }
+
+        assert_eq!(window.len(), 3);
+        assert_eq!(
+            window.histogram().unwrap().distribution(),
+            vec![0.0, 1.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0]
+        );
+        assert_eq!(window.mean(), 2.5);
+        assert_eq!(window.values().collect::<Vec<_>>(), vec![1.5, 2.5, 3.5]);
+    }
+
+    #[test]
+    fn test_time_window_evicts_expired() {
+        let start = Instant::now();
+        let mut window = StreamingWindow::new(WindowSpan::Time {
+            duration: Duration::from_secs(60),
+            max_len: 100,
+        });
+        window.push_at(1.0, start);
+        window.push_at(2.0, start + Duration::from_secs(30));
+        assert_eq!(window.len(), 2);
+
+        window.set_edges(BinEdges::new(0.0, 4.0, 4));
+        window.push_at(3.0, start + Duration::from_secs(61));
+        assert_eq!(window.len(), 2);
+        assert_eq!(
+            window.histogram().unwrap().distribution(),
+            vec![0.0, 0.0, 0.5, 0.5]
+        );
+    }
+}
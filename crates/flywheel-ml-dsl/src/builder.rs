@@ -12,7 +12,7 @@ pub struct HasSinks;
 pub struct FlywheelPipelineBuilder<Source, Stages, Sinks> {
     name: String,
     namespace: String,
-    source: Option<String>,
+    source: Option<SourceSpec>,
     stages: Vec<FlywheelStage>,
     feedback: Option<FeedbackSpec>,
     training_export: Option<TrainingExportSpec>,
@@ -38,7 +38,7 @@ impl FlywheelPipelineBuilder<NoSource, NoStages, NoSinks> {
 }
 
 impl<Stages, Sinks> FlywheelPipelineBuilder<NoSource, Stages, Sinks> {
-    pub fn source(self, source: impl Into<String>) -> FlywheelPipelineBuilder<HasSource, Stages, Sinks> {
+    pub fn source(self, source: impl Into<SourceSpec>) -> FlywheelPipelineBuilder<HasSource, Stages, Sinks> {
         FlywheelPipelineBuilder {
             name: self.name,
             namespace: self.namespace,
@@ -0,0 +1,505 @@
+//! Sink condition expressions.
+//!
+//! A condition is a boolean expression evaluated against each enriched record,
+//! e.g. `anomaly_prediction.is_anomaly && anomaly_prediction.score > 0.8`.
+//! The language is intentionally small: field paths, numeric/string/boolean/null
+//! literals, comparisons (`== != < <= > >=`), `&&`, `||`, `!` and parentheses.
+
+use std::fmt;
+use std::ops::Range;
+
+use flywheel_ml_core::JsonPath;
+use serde_json::Value;
+use thiserror::Error;
+
+/// A parse error with the byte range of the offending input.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{message} at column {}", span.start + 1)]
+pub struct ConditionError {
+    pub message: String,
+    pub span: Range<usize>,
+}
+
+impl ConditionError {
+    fn new(message: impl Into<String>, span: Range<usize>) -> Self {
+        Self {
+            message: message.into(),
+            span,
+        }
+    }
+
+    /// Renders the error under the expression with a caret marking the span.
+    pub fn render(&self, source: &str) -> String {
+        let start = source[..self.span.start.min(source.len())].chars().count();
+        let width = source
+            .get(self.span.clone())
+            .map(|s| s.chars().count())
+            .unwrap_or(0)
+            .max(1);
+        format!(
+            "{}\n  {}\n  {}{}",
+            self,
+            source,
+            " ".repeat(start),
+            "^".repeat(width)
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    expr: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Literal(Value),
+    Path(JsonPath),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Compare(Box<Expr>, CompareOp, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Condition {
+    pub fn parse(source: &str) -> Result<Self, ConditionError> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            end: source.len(),
+        };
+
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            return Err(ConditionError::new(
+                format!("unexpected {}", token.kind),
+                token.span.clone(),
+            ));
+        }
+
+        Ok(Self { expr })
+    }
+
+    /// Evaluates the condition against a record. Missing fields read as `null`.
+    pub fn evaluate(&self, record: &Value) -> bool {
+        truthy(&self.expr.eval(record))
+    }
+}
+
+impl std::str::FromStr for Condition {
+    type Err = ConditionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl Expr {
+    fn eval(&self, record: &Value) -> Value {
+        match self {
+            Expr::Literal(value) => value.clone(),
+            Expr::Path(path) => path.select(record).cloned().unwrap_or(Value::Null),
+            Expr::Not(inner) => Value::Bool(!truthy(&inner.eval(record))),
+            Expr::And(lhs, rhs) => {
+                Value::Bool(truthy(&lhs.eval(record)) && truthy(&rhs.eval(record)))
+            }
+            Expr::Or(lhs, rhs) => {
+                Value::Bool(truthy(&lhs.eval(record)) || truthy(&rhs.eval(record)))
+            }
+            Expr::Compare(lhs, op, rhs) => {
+                Value::Bool(compare(&lhs.eval(record), *op, &rhs.eval(record)))
+            }
+        }
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(fields) => !fields.is_empty(),
+    }
+}
+
+/// Numbers compare numerically and strings lexicographically; ordering
+/// between any other pair of values is false.
+fn compare(lhs: &Value, op: CompareOp, rhs: &Value) -> bool {
+    let ordering = match (lhs, rhs) {
+        (Value::Number(a), Value::Number(b)) => a
+            .as_f64()
+            .zip(b.as_f64())
+            .and_then(|(a, b)| a.partial_cmp(&b)),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        (Value::Null, Value::Null) => Some(std::cmp::Ordering::Equal),
+        _ => None,
+    };
+
+    match op {
+        CompareOp::Eq => ordering.map_or(lhs == rhs, |o| o.is_eq()),
+        CompareOp::Ne => ordering.map_or(lhs != rhs, |o| o.is_ne()),
+        CompareOp::Lt => ordering.is_some_and(|o| o.is_lt()),
+        CompareOp::Le => ordering.is_some_and(|o| o.is_le()),
+        CompareOp::Gt => ordering.is_some_and(|o| o.is_gt()),
+        CompareOp::Ge => ordering.is_some_and(|o| o.is_ge()),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Path(String),
+    Number(f64),
+    String(String),
+    True,
+    False,
+    Null,
+    And,
+    Or,
+    Not,
+    Compare(CompareOp),
+    LParen,
+    RParen,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenKind::Path(path) => write!(f, "field '{}'", path),
+            TokenKind::Number(n) => write!(f, "number {}", n),
+            TokenKind::String(s) => write!(f, "string '{}'", s),
+            TokenKind::True => write!(f, "'true'"),
+            TokenKind::False => write!(f, "'false'"),
+            TokenKind::Null => write!(f, "'null'"),
+            TokenKind::And => write!(f, "'&&'"),
+            TokenKind::Or => write!(f, "'||'"),
+            TokenKind::Not => write!(f, "'!'"),
+            TokenKind::Compare(op) => write!(f, "'{}'", op),
+            TokenKind::LParen => write!(f, "'('"),
+            TokenKind::RParen => write!(f, "')'"),
+        }
+    }
+}
+
+impl fmt::Display for CompareOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            CompareOp::Eq => "==",
+            CompareOp::Ne => "!=",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+        };
+        f.write_str(op)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    span: Range<usize>,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, ConditionError> {
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let start = i;
+        let c = bytes[i];
+
+        if c.is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let two = source.get(i..i + 2).unwrap_or("");
+        let kind = match (c, two) {
+            (_, "&&") => TokenKind::And,
+            (_, "||") => TokenKind::Or,
+            (_, "==") => TokenKind::Compare(CompareOp::Eq),
+            (_, "!=") => TokenKind::Compare(CompareOp::Ne),
+            (_, "<=") => TokenKind::Compare(CompareOp::Le),
+            (_, ">=") => TokenKind::Compare(CompareOp::Ge),
+            (b'<', _) => TokenKind::Compare(CompareOp::Lt),
+            (b'>', _) => TokenKind::Compare(CompareOp::Gt),
+            (b'!', _) => TokenKind::Not,
+            (b'(', _) => TokenKind::LParen,
+            (b')', _) => TokenKind::RParen,
+            (b'\'' | b'"', _) => {
+                let end = source[i + 1..]
+                    .find(c as char)
+                    .map(|offset| i + 1 + offset)
+                    .ok_or_else(|| ConditionError::new("unterminated string", i..source.len()))?;
+                tokens.push(Token {
+                    kind: TokenKind::String(source[i + 1..end].to_string()),
+                    span: i..end + 1,
+                });
+                i = end + 1;
+                continue;
+            }
+            (b'0'..=b'9' | b'-' | b'.', _) => {
+                i += 1;
+                while i < bytes.len() {
+                    // An exponent may be signed, as in `1e-5`.
+                    let exponent_sign = matches!(bytes[i], b'+' | b'-')
+                        && matches!(bytes[i - 1], b'e' | b'E')
+                        && bytes[start..i - 1].iter().all(|b| !b.is_ascii_alphabetic());
+                    if !(bytes[i].is_ascii_alphanumeric() || bytes[i] == b'.' || exponent_sign) {
+                        break;
+                    }
+                    i += 1;
+                }
+                let text = &source[start..i];
+                let value = text.parse().map_err(|_| {
+                    ConditionError::new(format!("invalid number '{}'", text), start..i)
+                })?;
+                tokens.push(Token {
+                    kind: TokenKind::Number(value),
+                    span: start..i,
+                });
+                continue;
+            }
+            (b'$' | b'_' | b'a'..=b'z' | b'A'..=b'Z', _) => {
+                i = scan_path(source, i)?;
+                let text = &source[start..i];
+                let kind = match text {
+                    "true" => TokenKind::True,
+                    "false" => TokenKind::False,
+                    "null" => TokenKind::Null,
+                    _ => TokenKind::Path(text.to_string()),
+                };
+                tokens.push(Token {
+                    kind,
+                    span: start..i,
+                });
+                continue;
+            }
+            _ => {
+                let len = source[i..].chars().next().map_or(1, char::len_utf8);
+                return Err(ConditionError::new(
+                    format!("unexpected character '{}'", &source[i..i + len]),
+                    i..i + len,
+                ));
+            }
+        };
+
+        i += match kind {
+            TokenKind::And
+            | TokenKind::Or
+            | TokenKind::Compare(CompareOp::Eq | CompareOp::Ne | CompareOp::Le | CompareOp::Ge) => {
+                2
+            }
+            _ => 1,
+        };
+        tokens.push(Token {
+            kind,
+            span: start..i,
+        });
+    }
+
+    Ok(tokens)
+}
+
+/// Scans a field path (`a.b[0]['c d']`) starting at `start`, returning its end.
+fn scan_path(source: &str, start: usize) -> Result<usize, ConditionError> {
+    let bytes = source.as_bytes();
+    let mut i = start;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'$' | b'_' | b'.' => i += 1,
+            c if c.is_ascii_alphanumeric() => i += 1,
+            b'[' => {
+                let end = source[i..]
+                    .find(']')
+                    .ok_or_else(|| ConditionError::new("unterminated '['", i..source.len()))?;
+                i += end + 1;
+            }
+            _ => break,
+        }
+    }
+
+    Ok(i)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn eat(&mut self, kind: &TokenKind) -> bool {
+        if self.peek().is_some_and(|t| &t.kind == kind) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, ConditionError> {
+        let mut lhs = self.parse_and()?;
+        while self.eat(&TokenKind::Or) {
+            let rhs = self.parse_and()?;
+            lhs = Expr::Or(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, ConditionError> {
+        let mut lhs = self.parse_not()?;
+        while self.eat(&TokenKind::And) {
+            let rhs = self.parse_not()?;
+            lhs = Expr::And(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, ConditionError> {
+        if self.eat(&TokenKind::Not) {
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, ConditionError> {
+        let lhs = self.parse_primary()?;
+        let Some(Token {
+            kind: TokenKind::Compare(op),
+            ..
+        }) = self.peek().cloned()
+        else {
+            return Ok(lhs);
+        };
+        self.pos += 1;
+
+        let rhs = self.parse_primary()?;
+        if let Some(token) = self
+            .peek()
+            .filter(|t| matches!(t.kind, TokenKind::Compare(_)))
+        {
+            return Err(ConditionError::new(
+                "comparisons cannot be chained; use '&&'",
+                token.span.clone(),
+            ));
+        }
+
+        Ok(Expr::Compare(Box::new(lhs), op, Box::new(rhs)))
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, ConditionError> {
+        let Some(token) = self.peek().cloned() else {
+            return Err(ConditionError::new(
+                "unexpected end of expression",
+                self.end..self.end,
+            ));
+        };
+        self.pos += 1;
+
+        let expr = match token.kind {
+            TokenKind::Number(n) => Expr::Literal(Value::from(n)),
+            TokenKind::String(s) => Expr::Literal(Value::String(s)),
+            TokenKind::True => Expr::Literal(Value::Bool(true)),
+            TokenKind::False => Expr::Literal(Value::Bool(false)),
+            TokenKind::Null => Expr::Literal(Value::Null),
+            TokenKind::Path(path) => Expr::Path(
+                JsonPath::parse(&path)
+                    .map_err(|e| ConditionError::new(e.to_string(), token.span))?,
+            ),
+            TokenKind::LParen => {
+                let inner = self.parse_or()?;
+                if !self.eat(&TokenKind::RParen) {
+                    let span = self.peek().map_or(self.end..self.end, |t| t.span.clone());
+                    return Err(ConditionError::new("expected ')'", span));
+                }
+                inner
+            }
+            kind => {
+                return Err(ConditionError::new(
+                    format!("expected a value, found {}", kind),
+                    token.span,
+                ))
+            }
+        };
+
+        Ok(expr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn eval(condition: &str, record: &Value) -> bool {
+        Condition::parse(condition).unwrap().evaluate(record)
+    }
+
+    #[test]
+    fn test_evaluate_readme_condition() {
+        let condition = "anomaly_prediction.is_anomaly && anomaly_prediction.score > 0.8";
+        let hit = json!({"anomaly_prediction": {"is_anomaly": true, "score": 0.93}});
+        let low = json!({"anomaly_prediction": {"is_anomaly": true, "score": 0.5}});
+        let missing = json!({"cpu": 0.9});
+
+        assert!(eval(condition, &hit));
+        assert!(!eval(condition, &low));
+        assert!(!eval(condition, &missing));
+    }
+
+    #[test]
+    fn test_operators_and_literals() {
+        let record = json!({"host": "web-1", "cpu": 42, "tags": ["a"], "region": null});
+
+        assert!(eval("host == 'web-1'", &record));
+        assert!(eval("host != \"web-2\"", &record));
+        assert!(eval("cpu >= 42 && cpu <= 42.0", &record));
+        assert!(eval("cpu < -1 || !(cpu > 100)", &record));
+        assert!(eval("cpu > 1e-5 && cpu < 2.5E3 && cpu == 4.2e+1", &record));
+        assert!(eval("region == null && tags", &record));
+        assert!(eval("tags[0] == 'a'", &record));
+        assert!(!eval("host > 5", &record));
+        assert!(!eval("missing.field", &record));
+    }
+
+    #[test]
+    fn test_parse_errors_have_spans() {
+        let cases = [
+            ("score >", 7..7),
+            ("score > 0.8 &&", 14..14),
+            ("score >> 1", 7..8),
+            ("(a && b", 7..7),
+            ("a = 1", 2..3),
+            ("name == 'abc", 8..12),
+            ("1 < x < 2", 6..7),
+            ("score > 1.2.3", 8..13),
+            ("a b", 2..3),
+        ];
+
+        for (source, span) in cases {
+            let err = Condition::parse(source).unwrap_err();
+            assert_eq!(err.span, span, "{}: {}", source, err);
+        }
+    }
+}
@@ -1,4 +1,5 @@
 pub mod builder;
+pub mod condition;
 pub mod convert;
 pub mod parser;
 pub mod types;
@@ -66,4 +66,114 @@ spec:
         assert_eq!(manifest.metadata.name, "test-pipeline");
         assert_eq!(manifest.spec.stages.len(), 1);
     }
+
+    #[test]
+    fn test_example_manifest_validates() {
+        let yaml = include_str!("../../../examples/anomaly-detection.yaml");
+        let manifest = parse_manifest(yaml).unwrap();
+        crate::validation::validate_manifest(&manifest).unwrap();
+    }
+
+    fn manifest_with_source(source: &str) -> String {
+        format!(
+            r#"
+apiVersion: flywheel-ml.io/v1
+kind: FlywheelPipeline
+metadata:
+  name: test-pipeline
+spec:
+  source:
+{source}
+  stages:
+    - id: features
+      type: feature-extraction
+      config:
+        features:
+          - name: cpu
+            source_field: $.cpu
+  sinks:
+    - name: output
+      all: true
+"#
+        )
+    }
+
+    #[test]
+    fn test_parse_typed_source() {
+        let yaml = manifest_with_source("    type: directory\n    path: /data/in\n    format: csv");
+        let manifest = parse_manifest(&yaml).unwrap();
+        crate::validation::validate_manifest(&manifest).unwrap();
+
+        match &manifest.spec.source {
+            crate::types::SourceSpec::Connector(crate::types::SourceConfig::Directory(c)) => {
+                assert_eq!(c.path, "/data/in");
+                assert_eq!(c.format, crate::types::RecordFormat::Csv);
+                assert!(!c.watch);
+            }
+            other => panic!("unexpected source: {:?}", other),
+        }
+        assert_eq!(manifest.spec.source.name(), "directory");
+    }
+
+    #[test]
+    fn test_source_errors_name_the_problem() {
+        let yaml = manifest_with_source("    type: directroy\n    path: /data/in");
+        let error = parse_manifest(&yaml).unwrap_err().to_string();
+        assert!(error.contains("unknown variant `directroy`"), "{}", error);
+
+        let yaml = manifest_with_source("    type: unix-socket");
+        let error = parse_manifest(&yaml).unwrap_err().to_string();
+        assert!(error.contains("missing field `path`"), "{}", error);
+
+        let yaml = manifest_with_source("    kafka-topic");
+        let manifest = parse_manifest(&yaml).unwrap();
+        assert_eq!(manifest.spec.source.name(), "kafka-topic");
+    }
+
+    #[test]
+    fn test_shadow_and_canary() {
+        let manifest_with_canary = |percent: f64| {
+            format!(
+                r#"
+apiVersion: flywheel-ml.io/v1
+kind: FlywheelPipeline
+metadata:
+  name: test-pipeline
+spec:
+  source: kafka-topic
+  stages:
+    - id: inference
+      type: ml-inference
+      config:
+        model_endpoint: model-server:50051
+        model_id: isolation-forest-v3
+        input_features: [cpu]
+        output_field: prediction
+        shadow:
+          model_id: isolation-forest-v4
+        canary:
+          model_id: isolation-forest-v4
+          model_endpoint: builtin://isolation-forest
+          percent: {percent}
+          sticky_key: $.user_id
+  sinks:
+    - name: output
+      all: true
+"#
+            )
+        };
+
+        let manifest = parse_manifest(&manifest_with_canary(10.0)).unwrap();
+        crate::validation::validate_manifest(&manifest).unwrap();
+        let config: crate::types::MlInferenceConfig =
+            serde_json::from_value(manifest.spec.stages[0].config.clone()).unwrap();
+        assert_eq!(config.shadow.unwrap().model_id, "isolation-forest-v4");
+        let canary = config.canary.unwrap();
+        assert_eq!(canary.percent, 10.0);
+        assert_eq!(canary.sticky_key.as_deref(), Some("$.user_id"));
+
+        let manifest = parse_manifest(&manifest_with_canary(150.0)).unwrap();
+        let err = crate::validation::validate_manifest(&manifest).unwrap_err();
+        assert!(err.to_string().contains("canary percent must be between 0 and 100"));
+    }
 }
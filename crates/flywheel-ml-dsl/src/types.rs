@@ -23,7 +23,7 @@ pub struct ObjectMeta {
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct FlywheelPipelineSpec {
-    pub source: String,
+    pub source: SourceSpec,
     pub stages: Vec<FlywheelStage>,
     #[serde(default)]
     pub feedback: Option<FeedbackSpec>,
@@ -38,6 +38,153 @@ fn default_enabled() -> bool {
     true
 }
 
+/// Where a pipeline reads its records from.
+///
+/// A bare string names an external source (e.g. a Kafka topic) that is fed
+/// outside the server; a typed config selects one of the built-in connectors.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(untagged)]
+pub enum SourceSpec {
+    Named(String),
+    Connector(SourceConfig),
+}
+
+impl<'de> Deserialize<'de> for SourceSpec {
+    // Not derived: an untagged enum reports any bad connector config as
+    // "did not match any variant", hiding an unknown `type` or a missing field.
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        match serde_json::Value::deserialize(deserializer)? {
+            serde_json::Value::String(name) => Ok(SourceSpec::Named(name)),
+            value => SourceConfig::deserialize(value)
+                .map(SourceSpec::Connector)
+                .map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+impl SourceSpec {
+    pub fn name(&self) -> &str {
+        match self {
+            SourceSpec::Named(name) => name,
+            SourceSpec::Connector(config) => config.type_name(),
+        }
+    }
+}
+
+impl From<&str> for SourceSpec {
+    fn from(name: &str) -> Self {
+        SourceSpec::Named(name.to_string())
+    }
+}
+
+impl From<String> for SourceSpec {
+    fn from(name: String) -> Self {
+        SourceSpec::Named(name)
+    }
+}
+
+impl From<SourceConfig> for SourceSpec {
+    fn from(config: SourceConfig) -> Self {
+        SourceSpec::Connector(config)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum SourceConfig {
+    /// Tails a JSON Lines file, following appends.
+    File(FileSourceConfig),
+    /// Reads every NDJSON or CSV file in a directory.
+    Directory(DirectorySourceConfig),
+    /// Reads JSON Lines from the server's standard input.
+    Stdin,
+    /// Accepts JSON Lines connections on a Unix domain socket.
+    UnixSocket(UnixSocketSourceConfig),
+    /// Accepts records POSTed as JSON or NDJSON.
+    Http(HttpSourceConfig),
+    /// Accepts records pushed through the `IngestService` gRPC API.
+    Grpc(GrpcSourceConfig),
+}
+
+impl SourceConfig {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            SourceConfig::File(_) => "file",
+            SourceConfig::Directory(_) => "directory",
+            SourceConfig::Stdin => "stdin",
+            SourceConfig::UnixSocket(_) => "unix-socket",
+            SourceConfig::Http(_) => "http",
+            SourceConfig::Grpc(_) => "grpc",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FileSourceConfig {
+    pub path: String,
+    #[serde(default)]
+    pub from_beginning: bool,
+    #[serde(default = "default_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DirectorySourceConfig {
+    pub path: String,
+    #[serde(default)]
+    pub format: RecordFormat,
+    /// Keep polling for new files instead of stopping after one pass.
+    #[serde(default)]
+    pub watch: bool,
+    #[serde(default = "default_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordFormat {
+    #[default]
+    Ndjson,
+    Csv,
+}
+
+impl RecordFormat {
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            RecordFormat::Ndjson => &["ndjson", "jsonl", "json"],
+            RecordFormat::Csv => &["csv"],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct UnixSocketSourceConfig {
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct HttpSourceConfig {
+    pub bind_address: String,
+    #[serde(default = "default_http_path")]
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GrpcSourceConfig {
+    pub bind_address: String,
+}
+
+fn default_poll_interval_ms() -> u64 {
+    500
+}
+
+fn default_http_path() -> String {
+    "/records".to_string()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct FlywheelStage {
     pub id: String,
@@ -89,6 +236,9 @@ pub struct FeatureDef {
     pub source_field: String,
     #[serde(default)]
     pub transform: Option<FeatureTransformSpec>,
+    /// Emit a null feature instead of failing when the field is missing.
+    #[serde(default)]
+    pub nullable: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -101,18 +251,140 @@ pub enum FeatureTransformSpec {
     OneHot { categories: Vec<String> },
 }
 
+impl From<&FeatureTransformSpec> for flywheel_ml_core::FeatureTransform {
+    fn from(spec: &FeatureTransformSpec) -> Self {
+        use flywheel_ml_core::FeatureTransform;
+
+        match spec {
+            FeatureTransformSpec::Normalize { min, max } => FeatureTransform::Normalize {
+                min: *min,
+                max: *max,
+            },
+            FeatureTransformSpec::Log1p {} => FeatureTransform::Log1p,
+            FeatureTransformSpec::Clip { min, max } => FeatureTransform::Clip {
+                min: *min,
+                max: *max,
+            },
+            FeatureTransformSpec::Bucketize { boundaries } => FeatureTransform::Bucketize {
+                boundaries: boundaries.clone(),
+            },
+            FeatureTransformSpec::OneHot { categories } => FeatureTransform::OneHot {
+                categories: categories.clone(),
+            },
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct MlInferenceConfig {
+    /// A model server address, or an `onnx://`, `trees://` or `builtin://`
+    /// endpoint for a model run in-process.
     pub model_endpoint: String,
     pub model_id: String,
     pub input_features: Vec<String>,
     pub output_field: String,
     #[serde(default = "default_timeout_ms")]
     pub timeout_ms: u64,
+    /// Records sent to the model in one call.
     #[serde(default = "default_batch_size")]
     pub batch_size: usize,
+    /// How long a record waits for others to fill its batch before the
+    /// batch is sent anyway.
+    #[serde(default = "default_batch_linger_ms")]
+    pub batch_linger_ms: u64,
+    /// Batches the stage may have waiting on the model at once.
+    #[serde(default = "default_max_concurrent_batches")]
+    pub max_concurrent_batches: usize,
     #[serde(default)]
     pub fallback: FallbackStrategy,
+    /// Resends calls that time out or find the model server unavailable.
+    #[serde(default)]
+    pub retry: Option<RetrySpec>,
+    /// Stops calling a failing model; defaults apply when unset.
+    #[serde(default)]
+    pub circuit_breaker: Option<CircuitBreakerSpec>,
+    /// A model scored on a copy of every record, for evaluation only.
+    #[serde(default)]
+    pub shadow: Option<ShadowSpec>,
+    /// A model that serves a share of the records in place of `model_id`.
+    #[serde(default)]
+    pub canary: Option<CanarySpec>,
+    /// Models that compete with `model_id` for the records, with traffic
+    /// shifting toward whichever earns the most correct feedback.
+    #[serde(default)]
+    pub bandit: Option<BanditSpec>,
+}
+
+/// A model that sees the same features as the stage's model. Its
+/// predictions are stored, tagged `shadow`, but never reach sinks.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ShadowSpec {
+    pub model_id: String,
+    /// Defaults to the stage's `model_endpoint`.
+    #[serde(default)]
+    pub model_endpoint: Option<String>,
+}
+
+/// A model that serves `percent` of the records. Its predictions are
+/// stored tagged `canary` and continue downstream like any other.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CanarySpec {
+    pub model_id: String,
+    /// Defaults to the stage's `model_endpoint`.
+    #[serde(default)]
+    pub model_endpoint: Option<String>,
+    /// Share of records, from 0 to 100, the canary serves.
+    pub percent: f64,
+    /// JSONPath into the record whose value picks the model, so records that
+    /// share it, such as one user's, always get the same model. The record
+    /// id is used when unset or absent.
+    #[serde(default)]
+    pub sticky_key: Option<String>,
+}
+
+/// Splits records between the stage's model and `arms` by the reward each
+/// earns, 1 for a prediction that joined feedback found correct and 0
+/// otherwise. Predictions served by an arm are stored tagged `bandit`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BanditSpec {
+    #[serde(default)]
+    pub policy: BanditPolicySpec,
+    /// Share of records, from 0 to 1, `epsilon_greedy` serves with a model
+    /// picked at random.
+    #[serde(default = "default_epsilon")]
+    pub epsilon: f64,
+    pub arms: Vec<BanditArmSpec>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BanditPolicySpec {
+    EpsilonGreedy,
+    #[default]
+    ThompsonSampling,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BanditArmSpec {
+    pub model_id: String,
+    /// Defaults to the stage's `model_endpoint`.
+    #[serde(default)]
+    pub model_endpoint: Option<String>,
+}
+
+fn default_epsilon() -> f64 {
+    0.1
+}
+
+impl From<&BanditSpec> for flywheel_ml_core::BanditPolicy {
+    fn from(spec: &BanditSpec) -> Self {
+        match spec.policy {
+            BanditPolicySpec::EpsilonGreedy => Self::EpsilonGreedy {
+                epsilon: spec.epsilon,
+            },
+            BanditPolicySpec::ThompsonSampling => Self::ThompsonSampling,
+        }
+    }
 }
 
 fn default_timeout_ms() -> u64 {
@@ -123,16 +395,162 @@ fn default_batch_size() -> usize {
     32
 }
 
+fn default_batch_linger_ms() -> u64 {
+    10
+}
+
+fn default_max_concurrent_batches() -> usize {
+    4
+}
+
+/// What an ml-inference stage does with records it could not get a
+/// prediction for.
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
 #[serde(rename_all = "snake_case")]
 pub enum FallbackStrategy {
+    /// Forward the record without a prediction.
     #[default]
     Passthrough,
+    /// Write `null` under the output field.
     ReturnNull,
+    /// Write this value under the output field.
     UseDefault(serde_json::Value),
+    /// Reuse the last prediction made for the same features or source record.
+    UseLastKnown,
+    /// Park the record in the pipeline's dead-letter queue for replay.
+    SendToDlq,
+    /// Drop the record and count it as failed.
     Error,
 }
 
+impl FallbackStrategy {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            FallbackStrategy::Passthrough => "passthrough",
+            FallbackStrategy::ReturnNull => "return_null",
+            FallbackStrategy::UseDefault(_) => "use_default",
+            FallbackStrategy::UseLastKnown => "use_last_known",
+            FallbackStrategy::SendToDlq => "send_to_dlq",
+            FallbackStrategy::Error => "error",
+        }
+    }
+}
+
+impl From<&FallbackStrategy> for flywheel_ml_core::FallbackStrategy {
+    fn from(spec: &FallbackStrategy) -> Self {
+        use flywheel_ml_core::FallbackStrategy as Strategy;
+
+        match spec {
+            FallbackStrategy::Passthrough => Strategy::PassThrough,
+            FallbackStrategy::ReturnNull => Strategy::ReturnNull,
+            FallbackStrategy::UseDefault(value) => Strategy::UseDefault(value.clone()),
+            FallbackStrategy::UseLastKnown => Strategy::UseLastKnown,
+            FallbackStrategy::SendToDlq => Strategy::SendToDlq,
+            FallbackStrategy::Error => Strategy::Error,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RetrySpec {
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    #[serde(default = "default_initial_delay_ms")]
+    pub initial_delay_ms: u64,
+    #[serde(default = "default_max_delay_ms")]
+    pub max_delay_ms: u64,
+    #[serde(default = "default_multiplier")]
+    pub multiplier: f64,
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_initial_delay_ms() -> u64 {
+    100
+}
+
+fn default_max_delay_ms() -> u64 {
+    5000
+}
+
+fn default_multiplier() -> f64 {
+    2.0
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CircuitBreakerSpec {
+    /// Consecutive failures that open the circuit.
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+    /// Successful probes that close a half-open circuit.
+    #[serde(default = "default_success_threshold")]
+    pub success_threshold: u32,
+    /// Probes allowed in flight while half-open.
+    #[serde(default = "default_half_open_max_calls")]
+    pub half_open_max_calls: u32,
+    #[serde(default = "default_reset_timeout_secs")]
+    pub reset_timeout_secs: u64,
+    /// Failure rate over `window_secs` that opens the circuit.
+    #[serde(default)]
+    pub failure_rate_threshold: Option<f64>,
+    #[serde(default = "default_circuit_window_secs")]
+    pub window_secs: u64,
+    /// Calls the window must hold before its failure rate counts.
+    #[serde(default = "default_min_calls")]
+    pub min_calls: u32,
+}
+
+fn default_failure_threshold() -> u32 {
+    5
+}
+
+fn default_success_threshold() -> u32 {
+    3
+}
+
+fn default_half_open_max_calls() -> u32 {
+    3
+}
+
+fn default_reset_timeout_secs() -> u64 {
+    30
+}
+
+fn default_circuit_window_secs() -> u64 {
+    60
+}
+
+fn default_min_calls() -> u32 {
+    20
+}
+
+impl From<&CircuitBreakerSpec> for flywheel_ml_core::CircuitBreakerConfig {
+    fn from(spec: &CircuitBreakerSpec) -> Self {
+        Self {
+            failure_threshold: spec.failure_threshold,
+            success_threshold: spec.success_threshold,
+            half_open_max_calls: spec.half_open_max_calls,
+            reset_timeout_secs: spec.reset_timeout_secs,
+            failure_rate_threshold: spec.failure_rate_threshold,
+            window_secs: spec.window_secs,
+            min_calls: spec.min_calls,
+        }
+    }
+}
+
+impl From<&RetrySpec> for flywheel_ml_core::RetryConfig {
+    fn from(spec: &RetrySpec) -> Self {
+        Self {
+            max_retries: spec.max_retries,
+            initial_delay_ms: spec.initial_delay_ms,
+            max_delay_ms: spec.max_delay_ms,
+            multiplier: spec.multiplier,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DriftDetectionConfig {
     #[serde(default)]
@@ -140,11 +558,35 @@ pub struct DriftDetectionConfig {
     pub baseline_uri: String,
     #[serde(default = "default_window_size")]
     pub window_size: usize,
+    /// Compare only the values seen in the last this many seconds, up to
+    /// `window_size`, instead of the last `window_size` values.
+    #[serde(default)]
+    pub window_duration_secs: Option<u64>,
     #[serde(default = "default_check_interval")]
     pub check_interval_secs: u64,
+    /// How monitored values are judged. Sequential strategies replace the
+    /// window comparison for numeric values and report a change as soon as
+    /// it is confirmed.
+    #[serde(default)]
+    pub strategy: DriftStrategy,
     pub thresholds: DriftThresholds,
     #[serde(default)]
     pub on_drift: DriftAction,
+    /// Record fields to monitor, e.g. `cpu_usage` or `anomaly_prediction.score`.
+    /// Defaults to every numeric feature extracted upstream.
+    #[serde(default)]
+    pub features: Vec<String>,
+    /// Model the drift events are attributed to. Defaults to the model of the
+    /// closest upstream ml-inference stage.
+    #[serde(default)]
+    pub model_id: Option<String>,
+    /// Where `alert` notifications are sent. Without it alerts are only logged.
+    #[serde(default)]
+    pub notify: Option<SinkConfig>,
+    /// Where `retrain` requests are sent (e.g. a webhook or another server's
+    /// gRPC ingest), in addition to the job record written to the database.
+    #[serde(default)]
+    pub retrain_target: Option<SinkConfig>,
 }
 
 fn default_window_size() -> usize {
@@ -163,12 +605,144 @@ pub enum DriftMode {
     Blocking,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftStrategy {
+    /// Compare a window of values with the reference every `check_interval_secs`.
+    #[default]
+    Window,
+    /// Page-Hinkley: a change once values drift from their running mean by
+    /// more than `threshold` in total, ignoring `delta` per value. Both are in
+    /// the value's units.
+    PageHinkley {
+        #[serde(default = "default_page_hinkley_delta")]
+        delta: f64,
+        #[serde(default = "default_page_hinkley_threshold")]
+        threshold: f64,
+        #[serde(default = "default_warm_up")]
+        warm_up: usize,
+    },
+    /// CUSUM: a change once values drift from the warm-up mean by more than
+    /// `threshold` standard deviations in total, ignoring `slack` per value.
+    Cusum {
+        #[serde(default = "default_cusum_slack")]
+        slack: f64,
+        #[serde(default = "default_cusum_threshold")]
+        threshold: f64,
+        #[serde(default = "default_warm_up")]
+        warm_up: usize,
+    },
+    /// ADWIN: a change once the older and newer parts of an adaptive window
+    /// differ in mean with confidence `1 - delta`.
+    Adwin {
+        #[serde(default = "default_adwin_delta")]
+        delta: f64,
+        #[serde(default = "default_warm_up")]
+        warm_up: usize,
+    },
+}
+
+impl DriftStrategy {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            DriftStrategy::Window => "window",
+            DriftStrategy::PageHinkley { .. } => "page_hinkley",
+            DriftStrategy::Cusum { .. } => "cusum",
+            DriftStrategy::Adwin { .. } => "adwin",
+        }
+    }
+}
+
+fn default_page_hinkley_delta() -> f64 {
+    0.005
+}
+
+fn default_page_hinkley_threshold() -> f64 {
+    50.0
+}
+
+fn default_cusum_slack() -> f64 {
+    0.5
+}
+
+fn default_cusum_threshold() -> f64 {
+    5.0
+}
+
+fn default_adwin_delta() -> f64 {
+    0.002
+}
+
+fn default_warm_up() -> usize {
+    30
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DriftThresholds {
     #[serde(default = "default_psi")]
     pub psi: f64,
     #[serde(default = "default_kl")]
     pub kl_divergence: f64,
+    /// Drop in live accuracy below the model version's registered accuracy
+    /// that counts as performance drift. Needs joined feedback.
+    #[serde(default)]
+    pub accuracy_drop: Option<f64>,
+    /// Per-feature tests, keyed by monitored feature name. Other features
+    /// drift when PSI or KL divergence exceeds the thresholds above.
+    #[serde(default)]
+    pub features: HashMap<String, FeatureDriftThreshold>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FeatureDriftThreshold {
+    pub test: DriftTest,
+    /// A p-value for `ks` and `chi_squared`, otherwise a distance. Required
+    /// for `wasserstein`, which is measured in the feature's units.
+    #[serde(default)]
+    pub threshold: Option<f64>,
+}
+
+impl FeatureDriftThreshold {
+    /// The configured threshold, or the test's default.
+    pub fn threshold_or_default(&self, thresholds: &DriftThresholds) -> Option<f64> {
+        self.threshold.or(match self.test {
+            DriftTest::Psi => Some(thresholds.psi),
+            DriftTest::KlDivergence => Some(thresholds.kl_divergence),
+            DriftTest::Ks | DriftTest::ChiSquared => Some(0.05),
+            DriftTest::JensenShannon => Some(0.1),
+            DriftTest::Wasserstein => None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftTest {
+    Psi,
+    KlDivergence,
+    /// Kolmogorov–Smirnov; numeric features only.
+    Ks,
+    /// Wasserstein-1 distance; numeric features only.
+    Wasserstein,
+    JensenShannon,
+    ChiSquared,
+}
+
+impl DriftTest {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            DriftTest::Psi => "psi",
+            DriftTest::KlDivergence => "kl_divergence",
+            DriftTest::Ks => "ks",
+            DriftTest::Wasserstein => "wasserstein",
+            DriftTest::JensenShannon => "jensen_shannon",
+            DriftTest::ChiSquared => "chi_squared",
+        }
+    }
+
+    pub fn uses_p_value(&self) -> bool {
+        matches!(self, DriftTest::Ks | DriftTest::ChiSquared)
+    }
 }
 
 fn default_psi() -> f64 {
@@ -184,6 +758,8 @@ impl Default for DriftThresholds {
         Self {
             psi: default_psi(),
             kl_divergence: default_kl(),
+            accuracy_drop: None,
+            features: HashMap::new(),
         }
     }
 }
@@ -194,7 +770,16 @@ pub enum DriftAction {
     #[default]
     Alert,
     Retrain,
-    Fallback { to_model: String },
+    Fallback {
+        to_model: String,
+        /// Switch back to the stage's configured model once drift resolves.
+        #[serde(default = "default_restore_on_resolve")]
+        restore_on_resolve: bool,
+    },
+}
+
+fn default_restore_on_resolve() -> bool {
+    true
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -244,19 +829,15 @@ pub enum ExportFormat {
     JsonLines,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
 #[serde(tag = "strategy", rename_all = "snake_case")]
 pub enum SamplingSpec {
+    #[default]
     All,
     Random { rate: f64 },
     Stratified { positive_rate: f64, negative_rate: f64 },
 }
 
-impl Default for SamplingSpec {
-    fn default() -> Self {
-        SamplingSpec::All
-    }
-}
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SinkSpec {
@@ -265,4 +846,73 @@ pub struct SinkSpec {
     pub condition: Option<String>,
     #[serde(default)]
     pub all: bool,
+    /// Where matching records are delivered. Sinks without an output are
+    /// consumed outside the server.
+    #[serde(default)]
+    pub output: Option<SinkConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum SinkConfig {
+    /// Appends records to a JSON Lines file.
+    File(FileSinkConfig),
+    /// Writes records to the server's standard output as JSON Lines.
+    Stdout,
+    /// POSTs each batch of records as a JSON array.
+    Webhook(WebhookSinkConfig),
+    /// Pushes records to another server's `IngestService`.
+    Grpc(GrpcSinkConfig),
+}
+
+impl SinkConfig {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            SinkConfig::File(_) => "file",
+            SinkConfig::Stdout => "stdout",
+            SinkConfig::Webhook(_) => "webhook",
+            SinkConfig::Grpc(_) => "grpc",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FileSinkConfig {
+    pub path: String,
+    /// Rotate the file once a delivery would take it past this size.
+    #[serde(default)]
+    pub max_bytes: Option<u64>,
+    /// Rotated files kept as `<path>.1` (newest) to `<path>.<max_files>`.
+    #[serde(default = "default_max_files")]
+    pub max_files: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WebhookSinkConfig {
+    pub url: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    /// Retries after a failed POST: connection errors, timeouts, 429 and 5xx.
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// Wait before the first retry, doubled for each one after.
+    #[serde(default = "default_retry_backoff_ms")]
+    pub retry_backoff_ms: u64,
+}
+
+fn default_max_files() -> u32 {
+    5
+}
+
+fn default_retry_backoff_ms() -> u64 {
+    100
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GrpcSinkConfig {
+    pub endpoint: String,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
 }
@@ -1,3 +1,4 @@
+use crate::condition::Condition;
 use crate::types::*;
 use thiserror::Error;
 
@@ -19,6 +20,12 @@ pub enum ValidationError {
     InvalidMlInference(String),
     #[error("Invalid drift detection config: {0}")]
     InvalidDriftDetection(String),
+    #[error("Invalid source config: {0}")]
+    InvalidSource(String),
+    #[error("Invalid sink '{0}': {1}")]
+    InvalidSink(String, String),
+    #[error("Duplicate sink name: {0}")]
+    DuplicateSinkName(String),
 }
 
 pub fn validate_manifest(manifest: &FlywheelPipelineManifest) -> Result<(), ValidationError> {
@@ -32,6 +39,8 @@ pub fn validate_manifest(manifest: &FlywheelPipelineManifest) -> Result<(), Vali
 }
 
 pub fn validate_spec(spec: &FlywheelPipelineSpec) -> Result<(), ValidationError> {
+    validate_source(&spec.source)?;
+
     if spec.stages.is_empty() {
         return Err(ValidationError::NoStages);
     }
@@ -53,34 +62,448 @@ pub fn validate_spec(spec: &FlywheelPipelineSpec) -> Result<(), ValidationError>
         validate_stage(stage)?;
     }
 
+    validate_drift_fallbacks(&spec.stages)?;
+
+    let mut sink_names = std::collections::HashSet::new();
+    for sink in &spec.sinks {
+        if !sink_names.insert(&sink.name) {
+            return Err(ValidationError::DuplicateSinkName(sink.name.clone()));
+        }
+
+        validate_sink(sink)?;
+    }
+
+    Ok(())
+}
+
+fn validate_source(source: &SourceSpec) -> Result<(), ValidationError> {
+    let invalid = |msg: String| Err(ValidationError::InvalidSource(msg));
+
+    let config = match source {
+        SourceSpec::Named(name) if name.is_empty() => {
+            return invalid("source name cannot be empty".to_string())
+        }
+        SourceSpec::Named(_) => return Ok(()),
+        SourceSpec::Connector(config) => config,
+    };
+
+    let (path, poll_interval_ms, bind_address) = match config {
+        SourceConfig::File(c) => (Some(&c.path), Some(c.poll_interval_ms), None),
+        SourceConfig::Directory(c) => (Some(&c.path), Some(c.poll_interval_ms), None),
+        SourceConfig::Stdin => (None, None, None),
+        SourceConfig::UnixSocket(c) => (Some(&c.path), None, None),
+        SourceConfig::Http(c) => {
+            if !c.path.starts_with('/') {
+                return invalid(format!("http path must start with '/': {}", c.path));
+            }
+            (None, None, Some(&c.bind_address))
+        }
+        SourceConfig::Grpc(c) => (None, None, Some(&c.bind_address)),
+    };
+
+    if path.is_some_and(|p| p.is_empty()) {
+        return invalid(format!("{} source requires a path", config.type_name()));
+    }
+    if poll_interval_ms == Some(0) {
+        return invalid("poll_interval_ms must be greater than 0".to_string());
+    }
+    if let Some(address) = bind_address {
+        if address.parse::<std::net::SocketAddr>().is_err() {
+            return invalid(format!("invalid bind_address: {}", address));
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_sink(sink: &SinkSpec) -> Result<(), ValidationError> {
+    let invalid = |msg: String| Err(ValidationError::InvalidSink(sink.name.clone(), msg));
+
+    if sink.name.is_empty() {
+        return invalid("sink name cannot be empty".to_string());
+    }
+
+    match (&sink.condition, sink.all) {
+        (Some(_), true) => return invalid("'condition' and 'all' are mutually exclusive".to_string()),
+        (None, false) => return invalid("either 'condition' or 'all: true' is required".to_string()),
+        (Some(condition), false) => {
+            if let Err(e) = Condition::parse(condition) {
+                return invalid(e.render(condition));
+            }
+        }
+        (None, true) => {}
+    }
+
+    if let Some(output) = &sink.output {
+        validate_sink_output(output).or_else(invalid)?;
+    }
+
+    Ok(())
+}
+
+fn validate_sink_output(output: &SinkConfig) -> Result<(), String> {
+    let timeout_ms = match output {
+        SinkConfig::Stdout => None,
+        SinkConfig::File(c) => {
+            if c.path.is_empty() {
+                return Err("file output requires a path".to_string());
+            }
+            if c.max_bytes == Some(0) {
+                return Err("max_bytes must be greater than 0".to_string());
+            }
+            if c.max_files == 0 {
+                return Err("max_files must be at least 1".to_string());
+            }
+            None
+        }
+        SinkConfig::Webhook(c) => {
+            if !(c.url.starts_with("http://") || c.url.starts_with("https://")) {
+                return Err(format!("webhook url must be http(s): {}", c.url));
+            }
+            if c.max_retries > 0 && c.retry_backoff_ms == 0 {
+                return Err("retry_backoff_ms must be greater than 0".to_string());
+            }
+            Some(c.timeout_ms)
+        }
+        SinkConfig::Grpc(c) => {
+            if c.endpoint.is_empty() {
+                return Err("grpc output requires an endpoint".to_string());
+            }
+            Some(c.timeout_ms)
+        }
+    };
+
+    if timeout_ms == Some(0) {
+        return Err("timeout_ms must be greater than 0".to_string());
+    }
+
     Ok(())
 }
 
 fn validate_stage(stage: &FlywheelStage) -> Result<(), ValidationError> {
     match stage.stage_type {
         FlywheelStageType::FeatureExtraction => {
-            let config: Result<FeatureExtractionConfig, _> =
-                serde_json::from_value(stage.config.clone());
-            if let Err(e) = config {
-                return Err(ValidationError::InvalidFeatureExtraction(e.to_string()));
-            }
+            let config: FeatureExtractionConfig = serde_json::from_value(stage.config.clone())
+                .map_err(|e| ValidationError::InvalidFeatureExtraction(e.to_string()))?;
+            validate_feature_extraction(&config)?;
         }
         FlywheelStageType::MlInference => {
-            let config: Result<MlInferenceConfig, _> =
-                serde_json::from_value(stage.config.clone());
-            if let Err(e) = config {
-                return Err(ValidationError::InvalidMlInference(e.to_string()));
-            }
+            let config: MlInferenceConfig = serde_json::from_value(stage.config.clone())
+                .map_err(|e| ValidationError::InvalidMlInference(e.to_string()))?;
+            validate_ml_inference(&config)?;
         }
         FlywheelStageType::DriftDetection => {
-            let config: Result<DriftDetectionConfig, _> =
-                serde_json::from_value(stage.config.clone());
-            if let Err(e) = config {
-                return Err(ValidationError::InvalidDriftDetection(e.to_string()));
-            }
+            let config: DriftDetectionConfig = serde_json::from_value(stage.config.clone())
+                .map_err(|e| ValidationError::InvalidDriftDetection(e.to_string()))?;
+            validate_drift_detection(&config)?;
         }
         _ => {}
     }
 
     Ok(())
 }
+
+fn validate_ml_inference(config: &MlInferenceConfig) -> Result<(), ValidationError> {
+    if config.timeout_ms == 0 {
+        return Err(ValidationError::InvalidMlInference(
+            "timeout_ms must be greater than 0".to_string(),
+        ));
+    }
+
+    if config.batch_size == 0 || config.max_concurrent_batches == 0 {
+        return Err(ValidationError::InvalidMlInference(
+            "batch_size and max_concurrent_batches must be greater than 0".to_string(),
+        ));
+    }
+
+    if let Some(retry) = &config.retry {
+        if retry.multiplier.is_nan() || retry.multiplier < 1.0 {
+            return Err(ValidationError::InvalidMlInference(format!(
+                "retry multiplier must be at least 1, got {}",
+                retry.multiplier
+            )));
+        }
+        if retry.initial_delay_ms > retry.max_delay_ms {
+            return Err(ValidationError::InvalidMlInference(format!(
+                "retry initial_delay_ms ({}) exceeds max_delay_ms ({})",
+                retry.initial_delay_ms, retry.max_delay_ms
+            )));
+        }
+    }
+
+    if let Some(breaker) = &config.circuit_breaker {
+        if breaker.failure_threshold == 0
+            || breaker.success_threshold == 0
+            || breaker.half_open_max_calls == 0
+        {
+            return Err(ValidationError::InvalidMlInference(
+                "circuit_breaker thresholds and half_open_max_calls must be greater than 0"
+                    .to_string(),
+            ));
+        }
+        if let Some(rate) = breaker.failure_rate_threshold {
+            if rate.is_nan() || rate <= 0.0 || rate > 1.0 {
+                return Err(ValidationError::InvalidMlInference(format!(
+                    "circuit_breaker failure_rate_threshold must be in (0, 1], got {}",
+                    rate
+                )));
+            }
+            if breaker.window_secs == 0 {
+                return Err(ValidationError::InvalidMlInference(
+                    "circuit_breaker window_secs must be greater than 0".to_string(),
+                ));
+            }
+        }
+    }
+
+    if let Some(shadow) = &config.shadow {
+        if shadow.model_id.is_empty() {
+            return Err(ValidationError::InvalidMlInference(
+                "shadow model_id must not be empty".to_string(),
+            ));
+        }
+    }
+
+    if let Some(canary) = &config.canary {
+        if canary.model_id.is_empty() {
+            return Err(ValidationError::InvalidMlInference(
+                "canary model_id must not be empty".to_string(),
+            ));
+        }
+        if !(0.0..=100.0).contains(&canary.percent) {
+            return Err(ValidationError::InvalidMlInference(format!(
+                "canary percent must be between 0 and 100, got {}",
+                canary.percent
+            )));
+        }
+        if let Some(key) = &canary.sticky_key {
+            flywheel_ml_core::JsonPath::parse(key).map_err(|e| {
+                ValidationError::InvalidMlInference(format!("canary sticky_key: {}", e))
+            })?;
+        }
+    }
+
+    if let Some(bandit) = &config.bandit {
+        if config.canary.is_some() {
+            return Err(ValidationError::InvalidMlInference(
+                "a stage cannot have both a canary and a bandit".to_string(),
+            ));
+        }
+        if bandit.arms.is_empty() {
+            return Err(ValidationError::InvalidMlInference(
+                "bandit needs at least one arm".to_string(),
+            ));
+        }
+        let mut model_ids = std::collections::HashSet::from([config.model_id.as_str()]);
+        for arm in &bandit.arms {
+            if arm.model_id.is_empty() {
+                return Err(ValidationError::InvalidMlInference(
+                    "bandit arm model_id must not be empty".to_string(),
+                ));
+            }
+            if !model_ids.insert(&arm.model_id) {
+                return Err(ValidationError::InvalidMlInference(format!(
+                    "bandit arm '{}' is already served by the stage",
+                    arm.model_id
+                )));
+            }
+        }
+        if !(0.0..=1.0).contains(&bandit.epsilon) {
+            return Err(ValidationError::InvalidMlInference(format!(
+                "bandit epsilon must be between 0 and 1, got {}",
+                bandit.epsilon
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_feature_extraction(config: &FeatureExtractionConfig) -> Result<(), ValidationError> {
+    if config.features.is_empty() {
+        return Err(ValidationError::InvalidFeatureExtraction(
+            "at least one feature is required".to_string(),
+        ));
+    }
+
+    let mut names = std::collections::HashSet::new();
+    for feature in &config.features {
+        if feature.name.is_empty() {
+            return Err(ValidationError::InvalidFeatureExtraction(
+                "feature name cannot be empty".to_string(),
+            ));
+        }
+        if !names.insert(&feature.name) {
+            return Err(ValidationError::InvalidFeatureExtraction(format!(
+                "duplicate feature: {}",
+                feature.name
+            )));
+        }
+        flywheel_ml_core::JsonPath::parse(&feature.source_field)
+            .map_err(|e| ValidationError::InvalidFeatureExtraction(e.to_string()))?;
+
+        if let Some(
+            FeatureTransformSpec::Normalize { min, max } | FeatureTransformSpec::Clip { min, max },
+        ) = &feature.transform
+        {
+            if min >= max {
+                return Err(ValidationError::InvalidFeatureExtraction(format!(
+                    "feature '{}': min must be less than max",
+                    feature.name
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_drift_fallbacks(stages: &[FlywheelStage]) -> Result<(), ValidationError> {
+    for (i, stage) in stages.iter().enumerate() {
+        if stage.stage_type != FlywheelStageType::DriftDetection {
+            continue;
+        }
+
+        let Ok(config) = serde_json::from_value::<DriftDetectionConfig>(stage.config.clone()) else {
+            continue;
+        };
+        let DriftAction::Fallback { to_model, .. } = &config.on_drift else {
+            continue;
+        };
+
+        if to_model.is_empty() {
+            return Err(ValidationError::InvalidDriftDetection(format!(
+                "stage '{}': fallback to_model cannot be empty",
+                stage.id
+            )));
+        }
+        if !stages[..i]
+            .iter()
+            .any(|s| s.stage_type == FlywheelStageType::MlInference)
+        {
+            return Err(ValidationError::InvalidDriftDetection(format!(
+                "stage '{}': fallback requires an ml-inference stage before it",
+                stage.id
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_drift_strategy(strategy: &DriftStrategy) -> Result<(), ValidationError> {
+    let invalid = |msg: String| {
+        Err(ValidationError::InvalidDriftDetection(format!(
+            "strategy {}: {}",
+            strategy.type_name(),
+            msg
+        )))
+    };
+
+    match *strategy {
+        DriftStrategy::Window => {}
+        DriftStrategy::PageHinkley {
+            delta, threshold, ..
+        } => {
+            if delta.is_nan() || delta < 0.0 {
+                return invalid(format!("delta must not be negative, got {}", delta));
+            }
+            if threshold.is_nan() || threshold <= 0.0 {
+                return invalid(format!("threshold must be greater than 0, got {}", threshold));
+            }
+        }
+        DriftStrategy::Cusum {
+            slack,
+            threshold,
+            warm_up,
+        } => {
+            if slack.is_nan() || slack < 0.0 {
+                return invalid(format!("slack must not be negative, got {}", slack));
+            }
+            if threshold.is_nan() || threshold <= 0.0 {
+                return invalid(format!("threshold must be greater than 0, got {}", threshold));
+            }
+            if warm_up < 2 {
+                return invalid(format!("warm_up must be at least 2, got {}", warm_up));
+            }
+        }
+        DriftStrategy::Adwin { delta, .. } => {
+            if delta.is_nan() || delta <= 0.0 || delta >= 1.0 {
+                return invalid(format!("delta must be between 0 and 1, got {}", delta));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn validate_drift_detection(config: &DriftDetectionConfig) -> Result<(), ValidationError> {
+    if config.window_size == 0 {
+        return Err(ValidationError::InvalidDriftDetection(
+            "window_size must be greater than 0".to_string(),
+        ));
+    }
+    if config.window_duration_secs == Some(0) {
+        return Err(ValidationError::InvalidDriftDetection(
+            "window_duration_secs must be greater than 0".to_string(),
+        ));
+    }
+
+    if let Some((scheme, _)) = config.baseline_uri.split_once("://") {
+        if !matches!(scheme, "file" | "s3") {
+            return Err(ValidationError::InvalidDriftDetection(format!(
+                "baseline_uri scheme '{}' is not supported, expected file:// or s3://",
+                scheme
+            )));
+        }
+    }
+
+    validate_drift_strategy(&config.strategy)?;
+
+    if let Some(drop) = config.thresholds.accuracy_drop {
+        if drop.is_nan() || drop <= 0.0 || drop >= 1.0 {
+            return Err(ValidationError::InvalidDriftDetection(format!(
+                "accuracy_drop must be between 0 and 1, got {}",
+                drop
+            )));
+        }
+    }
+
+    for feature in &config.features {
+        flywheel_ml_core::JsonPath::parse(feature)
+            .map_err(|e| ValidationError::InvalidDriftDetection(e.to_string()))?;
+    }
+
+    for (name, feature) in &config.thresholds.features {
+        let invalid = |msg: String| {
+            Err(ValidationError::InvalidDriftDetection(format!(
+                "thresholds for feature '{}': {}",
+                name, msg
+            )))
+        };
+
+        if !config.features.is_empty() && !config.features.contains(name) {
+            return invalid("feature is not monitored".to_string());
+        }
+        let Some(threshold) = feature.threshold_or_default(&config.thresholds) else {
+            return invalid(format!("{} requires a threshold", feature.test.type_name()));
+        };
+        if threshold.is_nan() || threshold <= 0.0 {
+            return invalid(format!("threshold must be greater than 0, got {}", threshold));
+        }
+        if feature.test.uses_p_value() && threshold >= 1.0 {
+            return invalid(format!("p-value threshold must be below 1, got {}", threshold));
+        }
+    }
+
+    if let Some(notify) = &config.notify {
+        validate_sink_output(notify)
+            .map_err(|e| ValidationError::InvalidDriftDetection(format!("notify: {}", e)))?;
+    }
+    if let Some(target) = &config.retrain_target {
+        validate_sink_output(target).map_err(|e| {
+            ValidationError::InvalidDriftDetection(format!("retrain_target: {}", e))
+        })?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,201 @@
+use parking_lot::Mutex;
+use rand::Rng;
+use rand_distr::{Beta, Distribution};
+
+pub use flywheel_ml_core::BanditPolicy;
+
+/// Draws used to estimate each arm's share of traffic under Thompson
+/// sampling.
+const SHARE_DRAWS: usize = 1000;
+
+/// What a bandit knows about one of its arms.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ArmStats {
+    pub arm: String,
+    /// Records the arm was chosen to serve.
+    pub pulls: u64,
+    /// Served predictions that feedback found correct.
+    pub successes: u64,
+    /// Served predictions that feedback found wrong.
+    pub failures: u64,
+}
+
+impl ArmStats {
+    pub fn new(arm: impl Into<String>) -> Self {
+        Self {
+            arm: arm.into(),
+            ..Default::default()
+        }
+    }
+
+    /// The expected reward under a uniform prior, 0.5 before any feedback.
+    pub fn mean_reward(&self) -> f64 {
+        (self.successes as f64 + 1.0) / ((self.successes + self.failures) as f64 + 2.0)
+    }
+
+    /// A reward drawn from the arm's Beta posterior.
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> f64 {
+        Beta::new(self.successes as f64 + 1.0, self.failures as f64 + 1.0)
+            .map(|beta| beta.sample(rng))
+            .unwrap_or(0.5)
+    }
+}
+
+/// Splits records between competing models by the reward each earns, 1 for
+/// a prediction feedback found correct and 0 otherwise, so traffic shifts
+/// toward the better model as feedback arrives.
+///
+/// Arms are named, usually by model id. `choose` picks the arm for each
+/// record and `reward` credits it once feedback joins the prediction.
+pub struct BanditAllocator {
+    policy: BanditPolicy,
+    arms: Mutex<Vec<ArmStats>>,
+}
+
+impl BanditAllocator {
+    pub fn new<I, S>(policy: BanditPolicy, arms: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            policy,
+            arms: Mutex::new(arms.into_iter().map(ArmStats::new).collect()),
+        }
+    }
+
+    pub fn policy(&self) -> BanditPolicy {
+        self.policy
+    }
+
+    /// Resumes from counts saved by an earlier run. Returns false when
+    /// `saved` names an arm the bandit does not have.
+    pub fn restore(&self, saved: &ArmStats) -> bool {
+        let mut arms = self.arms.lock();
+        match arms.iter_mut().find(|arm| arm.arm == saved.arm) {
+            Some(arm) => {
+                *arm = saved.clone();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Picks the arm that serves the next record and counts the pull.
+    pub fn choose(&self) -> usize {
+        self.choose_with(&mut rand::thread_rng())
+    }
+
+    pub fn choose_with<R: Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        let mut arms = self.arms.lock();
+        let chosen = match self.policy {
+            BanditPolicy::EpsilonGreedy { epsilon } if rng.gen_bool(epsilon.clamp(0.0, 1.0)) => {
+                rng.gen_range(0..arms.len())
+            }
+            BanditPolicy::EpsilonGreedy { .. } => best(arms.iter().map(ArmStats::mean_reward)),
+            BanditPolicy::ThompsonSampling => best(arms.iter().map(|arm| arm.sample(rng))),
+        };
+        arms[chosen].pulls += 1;
+        chosen
+    }
+
+    /// Credits `arm` with the outcome of a prediction it served. Returns
+    /// false when `arm` is not one of the bandit's.
+    pub fn reward(&self, arm: &str, correct: bool) -> bool {
+        let mut arms = self.arms.lock();
+        let Some(arm) = arms.iter_mut().find(|a| a.arm == arm) else {
+            return false;
+        };
+        if correct {
+            arm.successes += 1;
+        } else {
+            arm.failures += 1;
+        }
+        true
+    }
+
+    pub fn arm(&self, index: usize) -> Option<String> {
+        self.arms.lock().get(index).map(|arm| arm.arm.clone())
+    }
+
+    pub fn stats(&self) -> Vec<ArmStats> {
+        self.arms.lock().clone()
+    }
+
+    /// The share of records, from 0 to 1, each arm is expected to serve
+    /// given the feedback so far, in arm order.
+    pub fn shares(&self) -> Vec<f64> {
+        let arms = self.stats();
+        let mut shares = vec![0.0; arms.len()];
+        if arms.is_empty() {
+            return shares;
+        }
+
+        match self.policy {
+            BanditPolicy::EpsilonGreedy { epsilon } => {
+                let epsilon = epsilon.clamp(0.0, 1.0);
+                shares.fill(epsilon / arms.len() as f64);
+                shares[best(arms.iter().map(ArmStats::mean_reward))] += 1.0 - epsilon;
+            }
+            BanditPolicy::ThompsonSampling => {
+                let mut rng = rand::thread_rng();
+                for _ in 0..SHARE_DRAWS {
+                    shares[best(arms.iter().map(|arm| arm.sample(&mut rng)))] += 1.0;
+                }
+                shares.iter_mut().for_each(|s| *s /= SHARE_DRAWS as f64);
+            }
+        }
+        shares
+    }
+}
+
+/// The index of the highest reward, the first on ties.
+fn best(rewards: impl Iterator<Item = f64>) -> usize {
+    rewards
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(&b.1).then(b.0.cmp(&a.0)))
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_thompson_shifts_to_better_arm() {
+        let bandit = BanditAllocator::new(BanditPolicy::ThompsonSampling, ["a", "b"]);
+        let mut rng = StdRng::seed_from_u64(7);
+
+        // Arm b is right 80% of the time, arm a 40%.
+        for i in 0..2000 {
+            let chosen = bandit.choose_with(&mut rng);
+            let correct = match chosen {
+                0 => i % 5 < 2,
+                _ => i % 5 < 4,
+            };
+            bandit.reward(&bandit.arm(chosen).unwrap(), correct);
+        }
+
+        let stats = bandit.stats();
+        assert!(stats[1].pulls > stats[0].pulls * 4, "{:?}", stats);
+        assert!(bandit.shares()[1] > 0.9);
+    }
+
+    #[test]
+    fn test_restore() {
+        let bandit = BanditAllocator::new(BanditPolicy::ThompsonSampling, ["a", "b"]);
+        let saved = ArmStats {
+            arm: "b".to_string(),
+            pulls: 50,
+            successes: 40,
+            failures: 10,
+        };
+        assert!(bandit.restore(&saved));
+        assert!(!bandit.restore(&ArmStats::new("c")));
+        assert_eq!(bandit.stats()[1], saved);
+        assert!((bandit.stats()[1].mean_reward() - 41.0 / 52.0).abs() < 1e-12);
+    }
+}
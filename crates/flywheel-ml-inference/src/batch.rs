@@ -1,36 +1,267 @@
-use flywheel_ml_core::FeatureVector;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
 
-pub struct BatchProcessor {
-    batch_size: usize,
-    buffer: Vec<FeatureVector>,
+use flywheel_ml_core::{FeatureVector, ModelError, Prediction};
+use tokio::sync::{mpsc, oneshot, Semaphore};
+use tokio::time::Instant;
+
+#[derive(Debug, Clone)]
+pub struct BatchConfig {
+    /// A batch is sent as soon as it holds this many records.
+    pub max_batch_size: usize,
+    /// How long the first record of a batch waits for more before the batch
+    /// is sent anyway.
+    pub max_linger: Duration,
+    /// Batches sent and not yet answered. Further batches wait for one to
+    /// finish.
+    pub max_in_flight: usize,
 }
 
-impl BatchProcessor {
-    pub fn new(batch_size: usize) -> Self {
+impl Default for BatchConfig {
+    fn default() -> Self {
         Self {
-            batch_size,
-            buffer: Vec::with_capacity(batch_size),
+            max_batch_size: 32,
+            max_linger: Duration::from_millis(10),
+            max_in_flight: 4,
+        }
+    }
+}
+
+/// Groups features submitted one at a time into batches for a model.
+///
+/// A batch is sent once it is full or its first record has waited
+/// `max_linger`, whichever comes first, so a slow stream never holds records
+/// back. Each caller gets the prediction for its own features; when a batch
+/// fails, every caller in it gets the error.
+///
+/// Batching runs on a task spawned by `spawn`, which stops once the batcher
+/// is dropped and its in-flight batches are answered.
+pub struct MicroBatcher {
+    queue: mpsc::Sender<Pending>,
+}
+
+struct Pending {
+    features: FeatureVector,
+    reply: oneshot::Sender<Result<Prediction, ModelError>>,
+}
+
+impl MicroBatcher {
+    /// Starts batching, sending each batch to `infer`. Must be called from
+    /// within a Tokio runtime.
+    pub fn spawn<F, Fut>(config: BatchConfig, infer: F) -> Self
+    where
+        F: Fn(Vec<FeatureVector>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Vec<Prediction>, ModelError>> + Send + 'static,
+    {
+        let config = BatchConfig {
+            max_batch_size: config.max_batch_size.max(1),
+            max_in_flight: config.max_in_flight.max(1),
+            ..config
+        };
+        let (queue, pending) = mpsc::channel(config.max_batch_size * (config.max_in_flight + 1));
+        tokio::spawn(run(config, pending, Arc::new(infer)));
+        Self { queue }
+    }
+
+    pub async fn submit(&self, features: FeatureVector) -> Result<Prediction, ModelError> {
+        match self.enqueue(features).await {
+            Some(reply) => reply.await.unwrap_or_else(|_| Err(stopped())),
+            None => Err(stopped()),
+        }
+    }
+
+    /// Submits every vector before waiting on any, so they can share batches.
+    /// Results are in the order of `features`.
+    pub async fn submit_all(
+        &self,
+        features: Vec<FeatureVector>,
+    ) -> Vec<Result<Prediction, ModelError>> {
+        let mut replies = Vec::with_capacity(features.len());
+        for features in features {
+            replies.push(self.enqueue(features).await);
+        }
+
+        let mut results = Vec::with_capacity(replies.len());
+        for reply in replies {
+            results.push(match reply {
+                Some(reply) => reply.await.unwrap_or_else(|_| Err(stopped())),
+                None => Err(stopped()),
+            });
+        }
+        results
+    }
+
+    async fn enqueue(
+        &self,
+        features: FeatureVector,
+    ) -> Option<oneshot::Receiver<Result<Prediction, ModelError>>> {
+        let (reply, receiver) = oneshot::channel();
+        self.queue.send(Pending { features, reply }).await.ok()?;
+        Some(receiver)
+    }
+}
+
+fn stopped() -> ModelError {
+    ModelError::Unavailable("inference batcher stopped".to_string())
+}
+
+async fn run<F, Fut>(config: BatchConfig, mut pending: mpsc::Receiver<Pending>, infer: Arc<F>)
+where
+    F: Fn(Vec<FeatureVector>) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<Vec<Prediction>, ModelError>> + Send + 'static,
+{
+    let in_flight = Arc::new(Semaphore::new(config.max_in_flight));
+
+    while let Some(first) = pending.recv().await {
+        let deadline = Instant::now() + config.max_linger;
+        let mut batch = Vec::with_capacity(config.max_batch_size);
+        batch.push(first);
+        while batch.len() < config.max_batch_size {
+            match tokio::time::timeout_at(deadline, pending.recv()).await {
+                Ok(Some(next)) => batch.push(next),
+                Ok(None) | Err(_) => break,
+            }
         }
+
+        let Ok(permit) = in_flight.clone().acquire_owned().await else {
+            break;
+        };
+        let infer = infer.clone();
+        tokio::spawn(async move {
+            let (features, replies): (Vec<_>, Vec<_>) =
+                batch.into_iter().map(|p| (p.features, p.reply)).unzip();
+            let count = features.len();
+            let results = infer(features).await;
+            drop(permit);
+            scatter(results, replies, count);
+        });
     }
+}
 
-    pub fn add(&mut self, features: FeatureVector) -> Option<Vec<FeatureVector>> {
-        self.buffer.push(features);
-        if self.buffer.len() >= self.batch_size {
-            Some(std::mem::take(&mut self.buffer))
-        } else {
-            None
+fn scatter(
+    results: Result<Vec<Prediction>, ModelError>,
+    replies: Vec<oneshot::Sender<Result<Prediction, ModelError>>>,
+    count: usize,
+) {
+    let error = match results {
+        Ok(predictions) if predictions.len() == count => {
+            for (reply, prediction) in replies.into_iter().zip(predictions) {
+                // The caller may have given up waiting.
+                let _ = reply.send(Ok(prediction));
+            }
+            return;
         }
+        Ok(predictions) => ModelError::InferenceFailed(format!(
+            "model returned {} predictions for a batch of {}",
+            predictions.len(),
+            count
+        )),
+        Err(e) => e,
+    };
+    for reply in replies {
+        let _ = reply.send(Err(error.clone()));
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flywheel_ml_core::PredictionResult;
+    use parking_lot::Mutex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
-    pub fn flush(&mut self) -> Vec<FeatureVector> {
-        std::mem::take(&mut self.buffer)
+    type Ready = std::future::Ready<Result<Vec<Prediction>, ModelError>>;
+
+    /// Records the size of every batch and tags each prediction with the
+    /// record it was made for.
+    fn recording(sizes: Arc<Mutex<Vec<usize>>>) -> impl Fn(Vec<FeatureVector>) -> Ready {
+        move |features| {
+            sizes.lock().push(features.len());
+            std::future::ready(Ok(features
+                .iter()
+                .map(|f| {
+                    Prediction::new("m", PredictionResult::anomaly(0.1, 0.5))
+                        .with_metadata("record_id", f.source_record_id.clone())
+                })
+                .collect()))
+        }
     }
 
-    pub fn len(&self) -> usize {
-        self.buffer.len()
+    fn features(n: usize) -> Vec<FeatureVector> {
+        (0..n)
+            .map(|i| FeatureVector::new(format!("rec-{}", i)))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_flushes_full_batches_and_scatters_results() {
+        let sizes = Arc::new(Mutex::new(Vec::new()));
+        let config = BatchConfig {
+            max_batch_size: 4,
+            max_linger: Duration::from_secs(60),
+            max_in_flight: 2,
+        };
+        let batcher = MicroBatcher::spawn(config, recording(sizes.clone()));
+
+        let results = batcher.submit_all(features(8)).await;
+        let ids: Vec<_> = results
+            .into_iter()
+            .map(|r| r.unwrap().metadata["record_id"].clone())
+            .collect();
+        assert_eq!(
+            ids,
+            (0..8).map(|i| format!("rec-{}", i)).collect::<Vec<_>>()
+        );
+        assert_eq!(*sizes.lock(), vec![4, 4]);
     }
 
-    pub fn is_empty(&self) -> bool {
-        self.buffer.is_empty()
+    #[tokio::test]
+    async fn test_flushes_partial_batch_after_linger() {
+        let sizes = Arc::new(Mutex::new(Vec::new()));
+        let config = BatchConfig {
+            max_batch_size: 100,
+            max_linger: Duration::from_millis(20),
+            max_in_flight: 1,
+        };
+        let batcher = MicroBatcher::spawn(config, recording(sizes.clone()));
+
+        let result = tokio::time::timeout(
+            Duration::from_secs(5),
+            batcher.submit(FeatureVector::new("rec-1")),
+        )
+        .await
+        .expect("a partial batch is flushed after max_linger");
+        assert!(result.is_ok());
+        assert_eq!(*sizes.lock(), vec![1]);
+    }
+
+    #[tokio::test]
+    async fn test_limits_batches_in_flight() {
+        let active = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let config = BatchConfig {
+            max_batch_size: 1,
+            max_linger: Duration::ZERO,
+            max_in_flight: 2,
+        };
+        let (a, p) = (active.clone(), peak.clone());
+        let batcher = MicroBatcher::spawn(config, move |features: Vec<FeatureVector>| {
+            let (active, peak) = (a.clone(), p.clone());
+            async move {
+                let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                active.fetch_sub(1, Ordering::SeqCst);
+                Ok(features
+                    .iter()
+                    .map(|_| Prediction::new("m", PredictionResult::anomaly(0.1, 0.5)))
+                    .collect())
+            }
+        });
+
+        let results = batcher.submit_all(features(8)).await;
+        assert!(results.iter().all(|r| r.is_ok()));
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
     }
 }
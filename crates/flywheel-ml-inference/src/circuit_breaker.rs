@@ -1,6 +1,12 @@
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::collections::VecDeque;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
-use parking_lot::RwLock;
+
+use parking_lot::Mutex;
+
+/// The failure-rate window is tracked as this many consecutive buckets, so
+/// old calls expire one bucket at a time.
+const WINDOW_BUCKETS: u32 = 10;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CircuitState {
@@ -9,19 +15,37 @@ pub enum CircuitState {
     HalfOpen,
 }
 
+/// Called with the previous and the new state whenever the circuit changes
+/// state, after the breaker's lock is released.
+pub type StateListener = Arc<dyn Fn(CircuitState, CircuitState) + Send + Sync>;
+
+/// Stops calls to a failing model and lets a few probes through once
+/// `reset_timeout` has passed.
+///
+/// Every call takes a `CallPermit` from `try_acquire` and reports its
+/// outcome on it. The circuit opens after `failure_threshold` consecutive
+/// failures, or when the failure rate over `window` reaches
+/// `failure_rate_threshold`. While half-open at most `half_open_max_calls`
+/// probes are in flight; `success_threshold` successful probes close the
+/// circuit and any failed probe opens it again.
 pub struct CircuitBreaker {
-    state: RwLock<CircuitState>,
-    failure_count: AtomicU32,
-    success_count: AtomicU32,
-    last_failure_time: RwLock<Option<Instant>>,
+    inner: Mutex<Inner>,
     config: CircuitBreakerConfig,
+    listener: Option<StateListener>,
 }
 
+#[derive(Debug, Clone)]
 pub struct CircuitBreakerConfig {
     pub failure_threshold: u32,
     pub success_threshold: u32,
     pub half_open_max_calls: u32,
     pub reset_timeout: Duration,
+    /// Failure rate (0 to 1) that opens the circuit; `None` trips on
+    /// consecutive failures only.
+    pub failure_rate_threshold: Option<f64>,
+    pub window: Duration,
+    /// Calls the window must hold before its failure rate counts.
+    pub min_calls: u32,
 }
 
 impl Default for CircuitBreakerConfig {
@@ -31,6 +55,70 @@ impl Default for CircuitBreakerConfig {
             success_threshold: 3,
             half_open_max_calls: 3,
             reset_timeout: Duration::from_secs(30),
+            failure_rate_threshold: None,
+            window: Duration::from_secs(60),
+            min_calls: 20,
+        }
+    }
+}
+
+impl From<&flywheel_ml_core::CircuitBreakerConfig> for CircuitBreakerConfig {
+    fn from(config: &flywheel_ml_core::CircuitBreakerConfig) -> Self {
+        Self {
+            failure_threshold: config.failure_threshold,
+            success_threshold: config.success_threshold,
+            half_open_max_calls: config.half_open_max_calls,
+            reset_timeout: Duration::from_secs(config.reset_timeout_secs),
+            failure_rate_threshold: config.failure_rate_threshold,
+            window: Duration::from_secs(config.window_secs),
+            min_calls: config.min_calls,
+        }
+    }
+}
+
+struct Inner {
+    state: CircuitState,
+    /// Bumped on every transition, so outcomes of calls admitted in an
+    /// earlier state are not counted against the current one.
+    generation: u64,
+    opened_at: Option<Instant>,
+    consecutive_failures: u32,
+    probes_in_flight: u32,
+    probe_successes: u32,
+    window: VecDeque<Bucket>,
+}
+
+struct Bucket {
+    start: Instant,
+    calls: u32,
+    failures: u32,
+}
+
+/// Admission for one call. Report its outcome with `succeed` or `fail`;
+/// dropping it without one frees its probe slot and counts nothing.
+pub struct CallPermit<'a> {
+    breaker: &'a CircuitBreaker,
+    generation: u64,
+    probe: bool,
+    done: bool,
+}
+
+impl CallPermit<'_> {
+    pub fn succeed(mut self) {
+        self.done = true;
+        self.breaker.record(self.generation, self.probe, true);
+    }
+
+    pub fn fail(mut self) {
+        self.done = true;
+        self.breaker.record(self.generation, self.probe, false);
+    }
+}
+
+impl Drop for CallPermit<'_> {
+    fn drop(&mut self) {
+        if !self.done && self.probe {
+            self.breaker.release_probe(self.generation);
         }
     }
 }
@@ -38,68 +126,274 @@ impl Default for CircuitBreakerConfig {
 impl CircuitBreaker {
     pub fn new(config: CircuitBreakerConfig) -> Self {
         Self {
-            state: RwLock::new(CircuitState::Closed),
-            failure_count: AtomicU32::new(0),
-            success_count: AtomicU32::new(0),
-            last_failure_time: RwLock::new(None),
+            inner: Mutex::new(Inner {
+                state: CircuitState::Closed,
+                generation: 0,
+                opened_at: None,
+                consecutive_failures: 0,
+                probes_in_flight: 0,
+                probe_successes: 0,
+                window: VecDeque::new(),
+            }),
             config,
+            listener: None,
         }
     }
 
+    pub fn with_listener(mut self, listener: StateListener) -> Self {
+        self.listener = Some(listener);
+        self
+    }
+
+    pub fn config(&self) -> &CircuitBreakerConfig {
+        &self.config
+    }
+
     pub fn state(&self) -> CircuitState {
-        *self.state.read()
-    }
-
-    pub fn can_execute(&self) -> bool {
-        let state = *self.state.read();
-        match state {
-            CircuitState::Closed => true,
-            CircuitState::Open => {
-                if let Some(last_failure) = *self.last_failure_time.read() {
-                    if last_failure.elapsed() > self.config.reset_timeout {
-                        *self.state.write() = CircuitState::HalfOpen;
-                        self.success_count.store(0, Ordering::SeqCst);
-                        return true;
-                    }
+        self.inner.lock().state
+    }
+
+    /// Admits a call, or returns `None` while the circuit is open or every
+    /// half-open probe slot is taken.
+    pub fn try_acquire(&self) -> Option<CallPermit<'_>> {
+        let mut transition = None;
+        let permit = {
+            let mut inner = self.inner.lock();
+            if inner.state == CircuitState::Open
+                && inner
+                    .opened_at
+                    .is_some_and(|at| at.elapsed() >= self.config.reset_timeout)
+            {
+                transition = self.transition(&mut inner, CircuitState::HalfOpen);
+            }
+
+            match inner.state {
+                CircuitState::Closed => Some((inner.generation, false)),
+                CircuitState::HalfOpen
+                    if inner.probes_in_flight < self.config.half_open_max_calls.max(1) =>
+                {
+                    inner.probes_in_flight += 1;
+                    Some((inner.generation, true))
                 }
-                false
+                CircuitState::HalfOpen | CircuitState::Open => None,
             }
-            CircuitState::HalfOpen => true,
-        }
+        };
+        self.notify(transition);
+
+        permit.map(|(generation, probe)| CallPermit {
+            breaker: self,
+            generation,
+            probe,
+            done: false,
+        })
     }
 
-    pub fn record_success(&self) {
-        let state = *self.state.read();
-        match state {
-            CircuitState::Closed => {
-                self.failure_count.store(0, Ordering::SeqCst);
+    fn record(&self, generation: u64, probe: bool, success: bool) {
+        let transition = {
+            let mut inner = self.inner.lock();
+            if inner.generation != generation {
+                return;
             }
-            CircuitState::HalfOpen => {
-                let count = self.success_count.fetch_add(1, Ordering::SeqCst) + 1;
-                if count >= self.config.success_threshold {
-                    *self.state.write() = CircuitState::Closed;
-                    self.failure_count.store(0, Ordering::SeqCst);
+
+            match inner.state {
+                CircuitState::Closed => {
+                    self.observe(&mut inner, success);
+                    if success {
+                        inner.consecutive_failures = 0;
+                        None
+                    } else {
+                        inner.consecutive_failures += 1;
+                        if self.should_trip(&inner) {
+                            self.transition(&mut inner, CircuitState::Open)
+                        } else {
+                            None
+                        }
+                    }
                 }
+                CircuitState::HalfOpen if probe => {
+                    inner.probes_in_flight = inner.probes_in_flight.saturating_sub(1);
+                    if !success {
+                        self.transition(&mut inner, CircuitState::Open)
+                    } else {
+                        inner.probe_successes += 1;
+                        if inner.probe_successes >= self.config.success_threshold {
+                            self.transition(&mut inner, CircuitState::Closed)
+                        } else {
+                            None
+                        }
+                    }
+                }
+                CircuitState::HalfOpen | CircuitState::Open => None,
             }
-            CircuitState::Open => {}
+        };
+        self.notify(transition);
+    }
+
+    fn release_probe(&self, generation: u64) {
+        let mut inner = self.inner.lock();
+        if inner.generation == generation && inner.state == CircuitState::HalfOpen {
+            inner.probes_in_flight = inner.probes_in_flight.saturating_sub(1);
+        }
+    }
+
+    fn should_trip(&self, inner: &Inner) -> bool {
+        if inner.consecutive_failures >= self.config.failure_threshold {
+            return true;
+        }
+        let Some(threshold) = self.config.failure_rate_threshold else {
+            return false;
+        };
+
+        let now = Instant::now();
+        let (calls, failures) = inner
+            .window
+            .iter()
+            .filter(|b| now.saturating_duration_since(b.start) < self.config.window)
+            .fold((0, 0), |(c, f), b| (c + b.calls, f + b.failures));
+        calls >= self.config.min_calls.max(1) && failures as f64 / calls as f64 >= threshold
+    }
+
+    fn observe(&self, inner: &mut Inner, success: bool) {
+        let now = Instant::now();
+        let window = self.config.window;
+        while inner
+            .window
+            .front()
+            .is_some_and(|b| now.saturating_duration_since(b.start) >= window)
+        {
+            inner.window.pop_front();
+        }
+
+        let bucket_duration = (window / WINDOW_BUCKETS).max(Duration::from_millis(1));
+        let current = inner
+            .window
+            .back()
+            .is_some_and(|b| now.saturating_duration_since(b.start) < bucket_duration);
+        if !current {
+            inner.window.push_back(Bucket {
+                start: now,
+                calls: 0,
+                failures: 0,
+            });
         }
+
+        let bucket = inner.window.back_mut().expect("bucket was just ensured");
+        bucket.calls += 1;
+        bucket.failures += u32::from(!success);
     }
 
-    pub fn record_failure(&self) {
-        let state = *self.state.read();
-        match state {
+    fn transition(
+        &self,
+        inner: &mut Inner,
+        to: CircuitState,
+    ) -> Option<(CircuitState, CircuitState)> {
+        let from = inner.state;
+        if from == to {
+            return None;
+        }
+
+        inner.state = to;
+        inner.generation += 1;
+        inner.probes_in_flight = 0;
+        inner.probe_successes = 0;
+        match to {
+            CircuitState::Open => inner.opened_at = Some(Instant::now()),
             CircuitState::Closed => {
-                let count = self.failure_count.fetch_add(1, Ordering::SeqCst) + 1;
-                if count >= self.config.failure_threshold {
-                    *self.state.write() = CircuitState::Open;
-                    *self.last_failure_time.write() = Some(Instant::now());
-                }
+                inner.opened_at = None;
+                inner.consecutive_failures = 0;
+                inner.window.clear();
             }
-            CircuitState::HalfOpen => {
-                *self.state.write() = CircuitState::Open;
-                *self.last_failure_time.write() = Some(Instant::now());
+            CircuitState::HalfOpen => {}
+        }
+        Some((from, to))
+    }
+
+    fn notify(&self, transition: Option<(CircuitState, CircuitState)>) {
+        if let (Some((from, to)), Some(listener)) = (transition, &self.listener) {
+            listener(from, to);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> CircuitBreakerConfig {
+        CircuitBreakerConfig {
+            failure_threshold: 3,
+            success_threshold: 2,
+            half_open_max_calls: 2,
+            reset_timeout: Duration::from_millis(20),
+            ..CircuitBreakerConfig::default()
+        }
+    }
+
+    fn fail_times(breaker: &CircuitBreaker, n: usize) {
+        for _ in 0..n {
+            breaker.try_acquire().unwrap().fail();
+        }
+    }
+
+    #[test]
+    fn test_half_open_limits_probes() {
+        let breaker = CircuitBreaker::new(config());
+        fail_times(&breaker, 3);
+        std::thread::sleep(Duration::from_millis(30));
+
+        let first = breaker.try_acquire().unwrap();
+        let second = breaker.try_acquire().unwrap();
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert!(breaker.try_acquire().is_none());
+
+        // A dropped probe frees its slot without counting.
+        drop(second);
+        let second = breaker.try_acquire().unwrap();
+
+        first.succeed();
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        second.succeed();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_failed_probe_reopens() {
+        let breaker = CircuitBreaker::new(config());
+        fail_times(&breaker, 3);
+        std::thread::sleep(Duration::from_millis(30));
+
+        let probe = breaker.try_acquire().unwrap();
+        let other = breaker.try_acquire().unwrap();
+        probe.fail();
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        // The outcome of a probe from before the reopen is ignored.
+        other.succeed();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(breaker.try_acquire().is_none());
+    }
+
+    #[test]
+    fn test_opens_on_failure_rate() {
+        let breaker = CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 100,
+            failure_rate_threshold: Some(0.5),
+            min_calls: 10,
+            ..config()
+        });
+
+        // Alternating outcomes never reach three failures in a row.
+        for i in 0..9 {
+            let permit = breaker.try_acquire().unwrap();
+            if i % 2 == 0 {
+                permit.fail();
+            } else {
+                permit.succeed();
             }
-            CircuitState::Open => {}
         }
+        assert_eq!(breaker.state(), CircuitState::Closed);
+
+        breaker.try_acquire().unwrap().fail();
+        assert_eq!(breaker.state(), CircuitState::Open);
     }
 }
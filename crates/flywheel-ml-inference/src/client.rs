@@ -1,16 +1,86 @@
+use std::time::{Duration, Instant};
+
 use async_trait::async_trait;
-use flywheel_ml_core::{FeatureVector, Model, ModelError, ModelHealth, ModelMetadata, Prediction};
+use chrono::Utc;
+use flywheel_ml_core::{
+    FeatureVector, HealthStatus, Model, ModelConfig, ModelError, ModelHealth, ModelMetadata,
+    Prediction,
+};
+use flywheel_ml_proto::inference_service_client::InferenceServiceClient;
+use flywheel_ml_proto::{HealthCheckRequest, PredictBatchRequest};
+use tonic::transport::{Channel, Endpoint};
+
+use crate::convert;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_millis(1000);
 
+/// `Model` implementation that calls a remote model server speaking the
+/// `flywheel_ml.inference.InferenceService` gRPC contract.
+///
+/// The channel is created lazily, so constructing a client never blocks and a
+/// model server that is down surfaces as `ModelError::Unavailable` on the
+/// first call rather than at pipeline start.
 pub struct InferenceClient {
     endpoint: String,
     metadata: ModelMetadata,
+    timeout: Duration,
+    channel: Option<Channel>,
 }
 
 impl InferenceClient {
     pub fn new(endpoint: impl Into<String>, metadata: ModelMetadata) -> Self {
+        let endpoint = normalize_endpoint(&endpoint.into());
+        let channel = build_channel(&endpoint, DEFAULT_TIMEOUT);
         Self {
-            endpoint: endpoint.into(),
+            endpoint,
             metadata,
+            timeout: DEFAULT_TIMEOUT,
+            channel,
+        }
+    }
+
+    pub fn from_config(config: &ModelConfig) -> Self {
+        let metadata = ModelMetadata::new(config.model_id.clone(), config.model_type)
+            .with_endpoint(config.endpoint.clone());
+        Self::new(config.endpoint.clone(), metadata)
+            .with_timeout(Duration::from_millis(config.timeout_ms))
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self.channel = build_channel(&self.endpoint, timeout);
+        self
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    fn client(&self) -> Result<InferenceServiceClient<Channel>, ModelError> {
+        self.channel
+            .clone()
+            .map(InferenceServiceClient::new)
+            .ok_or_else(|| ModelError::Connection(format!("Invalid endpoint: {}", self.endpoint)))
+    }
+
+    fn timeout_ms(&self) -> u64 {
+        self.timeout.as_millis() as u64
+    }
+
+    fn request<T>(&self, message: T) -> tonic::Request<T> {
+        let mut request = tonic::Request::new(message);
+        request.set_timeout(self.timeout);
+        request
+    }
+
+    async fn call<F, T>(&self, fut: F) -> Result<T, ModelError>
+    where
+        F: std::future::Future<Output = Result<tonic::Response<T>, tonic::Status>>,
+    {
+        match tokio::time::timeout(self.timeout, fut).await {
+            Ok(Ok(response)) => Ok(response.into_inner()),
+            Ok(Err(status)) => Err(convert::model_error_from_status(status, self.timeout_ms())),
+            Err(_) => Err(ModelError::Timeout(self.timeout_ms())),
         }
     }
 }
@@ -21,12 +91,264 @@ impl Model for InferenceClient {
         &self.metadata
     }
 
-    async fn predict(&self, _features: FeatureVector) -> Result<Prediction, ModelError> {
-        // TODO: Implement gRPC call to Python model service
-        Err(ModelError::Unavailable("Not implemented".to_string()))
+    async fn predict(&self, features: FeatureVector) -> Result<Prediction, ModelError> {
+        let mut client = self.client()?;
+        let request = self.request(convert::predict_request(&self.metadata.model_id, &features));
+
+        let start = Instant::now();
+        let response = self.call(client.predict(request)).await?;
+        let elapsed_us = start.elapsed().as_micros() as u64;
+
+        let mut prediction = convert::prediction_from_response(response, features.hash())?;
+        // Report what the pipeline actually waited, including the network hop.
+        prediction.latency_us = prediction.latency_us.max(elapsed_us);
+        Ok(prediction)
+    }
+
+    async fn predict_batch(
+        &self,
+        features: Vec<FeatureVector>,
+    ) -> Result<Vec<Prediction>, ModelError> {
+        if features.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut client = self.client()?;
+        let model_id = &self.metadata.model_id;
+        let request = self.request(PredictBatchRequest {
+            batch_id: uuid::Uuid::new_v4().to_string(),
+            model_id: model_id.clone(),
+            requests: features
+                .iter()
+                .map(|f| convert::predict_request(model_id, f))
+                .collect(),
+        });
+
+        let response = self.call(client.predict_batch(request)).await?;
+
+        if let Some(error) = response.errors.first() {
+            return Err(ModelError::InferenceFailed(format!(
+                "Batch request {} failed: {}",
+                error.index, error.message
+            )));
+        }
+        if response.responses.len() != features.len() {
+            return Err(ModelError::InferenceFailed(format!(
+                "Batch returned {} predictions for {} inputs",
+                response.responses.len(),
+                features.len()
+            )));
+        }
+
+        response
+            .responses
+            .into_iter()
+            .zip(features.iter())
+            .map(|(r, f)| convert::prediction_from_response(r, f.hash()))
+            .collect()
     }
 
     async fn health_check(&self) -> ModelHealth {
-        ModelHealth::default()
+        let client = self.client();
+        let start = Instant::now();
+
+        let result = match client {
+            Ok(mut client) => {
+                let request = self.request(HealthCheckRequest {
+                    model_id: self.metadata.model_id.clone(),
+                });
+                self.call(client.health_check(request)).await
+            }
+            Err(e) => Err(e),
+        };
+        let latency_ms = start.elapsed().as_millis() as u64;
+
+        match result {
+            Ok(response) => ModelHealth {
+                status: health_status_from_str(&response.status),
+                last_check: Utc::now(),
+                latency_ms: Some(latency_ms),
+                error_rate: Some(response.error_rate),
+                message: (!response.message.is_empty()).then_some(response.message),
+            },
+            Err(e) => ModelHealth {
+                status: HealthStatus::Unhealthy,
+                last_check: Utc::now(),
+                latency_ms: Some(latency_ms),
+                error_rate: None,
+                message: Some(e.to_string()),
+            },
+        }
+    }
+}
+
+fn normalize_endpoint(endpoint: &str) -> String {
+    if endpoint.contains("://") {
+        endpoint.to_string()
+    } else {
+        format!("http://{}", endpoint)
+    }
+}
+
+fn build_channel(endpoint: &str, timeout: Duration) -> Option<Channel> {
+    Endpoint::from_shared(endpoint.to_string())
+        .ok()
+        .map(|e| e.connect_timeout(timeout).connect_lazy())
+}
+
+fn health_status_from_str(status: &str) -> HealthStatus {
+    match status.to_lowercase().as_str() {
+        "healthy" | "serving" | "ok" => HealthStatus::Healthy,
+        "degraded" => HealthStatus::Degraded,
+        "" | "unknown" => HealthStatus::Unknown,
+        _ => HealthStatus::Unhealthy,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flywheel_ml_core::{FeatureValue, ModelType, PredictionResult};
+    use flywheel_ml_proto::inference_service_server::{InferenceService, InferenceServiceServer};
+    use flywheel_ml_proto::{
+        HealthCheckResponse, ModelInfoRequest, ModelInfoResponse, PredictBatchResponse,
+        PredictRequest, PredictResponse,
+    };
+    use std::pin::Pin;
+    use tokio_stream::Stream;
+    use tonic::{Request, Response, Status, Streaming};
+
+    /// Stand-in model server: scores `cpu` as the anomaly score.
+    struct StandInModel {
+        delay: Duration,
+    }
+
+    impl StandInModel {
+        #[allow(clippy::result_large_err)]
+        fn score(req: &PredictRequest) -> Result<PredictResponse, Status> {
+            let features = convert::feature_vector_from_request(req);
+            let cpu = features
+                .get_float("cpu")
+                .ok_or_else(|| Status::invalid_argument("missing feature: cpu"))?;
+            let prediction =
+                Prediction::new(req.model_id.clone(), PredictionResult::anomaly(cpu, 0.8))
+                    .with_version("v3")
+                    .with_confidence(0.9)
+                    .with_latency(250);
+            Ok(convert::prediction_to_response(&prediction))
+        }
+    }
+
+    #[tonic::async_trait]
+    impl InferenceService for StandInModel {
+        async fn predict(
+            &self,
+            request: Request<PredictRequest>,
+        ) -> Result<Response<PredictResponse>, Status> {
+            tokio::time::sleep(self.delay).await;
+            Ok(Response::new(Self::score(request.get_ref())?))
+        }
+
+        async fn predict_batch(
+            &self,
+            request: Request<PredictBatchRequest>,
+        ) -> Result<Response<PredictBatchResponse>, Status> {
+            let req = request.into_inner();
+            let responses = req
+                .requests
+                .iter()
+                .map(Self::score)
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(Response::new(PredictBatchResponse {
+                batch_id: req.batch_id,
+                responses,
+                stats: None,
+                errors: Vec::new(),
+            }))
+        }
+
+        type PredictStreamStream =
+            Pin<Box<dyn Stream<Item = Result<PredictResponse, Status>> + Send + 'static>>;
+
+        async fn predict_stream(
+            &self,
+            _request: Request<Streaming<PredictRequest>>,
+        ) -> Result<Response<Self::PredictStreamStream>, Status> {
+            Err(Status::unimplemented("streaming not supported"))
+        }
+
+        async fn get_model_info(
+            &self,
+            _request: Request<ModelInfoRequest>,
+        ) -> Result<Response<ModelInfoResponse>, Status> {
+            Err(Status::unimplemented("model info not supported"))
+        }
+
+        async fn health_check(
+            &self,
+            _request: Request<HealthCheckRequest>,
+        ) -> Result<Response<HealthCheckResponse>, Status> {
+            Ok(Response::new(HealthCheckResponse {
+                status: "degraded".to_string(),
+                latency_ms: 3,
+                error_rate: 0.05,
+                message: "warming up".to_string(),
+            }))
+        }
+    }
+
+    async fn spawn_stand_in(delay: Duration) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            tonic::transport::Server::builder()
+                .add_service(InferenceServiceServer::new(StandInModel { delay }))
+                .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener))
+                .await
+                .unwrap();
+        });
+        addr.to_string()
+    }
+
+    fn client(endpoint: &str, timeout_ms: u64) -> InferenceClient {
+        let config = ModelConfig::new("isolation-forest")
+            .with_endpoint(endpoint)
+            .with_model_type(ModelType::AnomalyDetection)
+            .with_timeout_ms(timeout_ms);
+        InferenceClient::from_config(&config)
+    }
+
+    #[tokio::test]
+    async fn test_predict_roundtrip() {
+        let endpoint = spawn_stand_in(Duration::ZERO).await;
+        let client = client(&endpoint, 1000);
+
+        let features = FeatureVector::new("rec-1").with_feature("cpu", FeatureValue::Float(0.95));
+        let prediction = client.predict(features.clone()).await.unwrap();
+
+        assert_eq!(prediction.model_id, "isolation-forest");
+        assert_eq!(prediction.model_version, "v3");
+        assert_eq!(prediction.anomaly_score(), Some(0.95));
+        assert!(prediction.is_anomaly());
+        assert_eq!(prediction.confidence, Some(0.9));
+        assert_eq!(prediction.features_hash, features.hash());
+        assert!(prediction.latency_us >= 250);
+    }
+
+    #[tokio::test]
+    async fn test_predict_honors_timeout() {
+        let endpoint = spawn_stand_in(Duration::from_millis(500)).await;
+        let client = client(&endpoint, 50);
+
+        let features = FeatureVector::new("rec-1").with_feature("cpu", FeatureValue::Float(0.5));
+        let result = client.predict(features).await;
+        assert!(matches!(result, Err(ModelError::Timeout(50))));
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_server_is_unhealthy() {
+        let health = client("127.0.0.1:1", 200).health_check().await;
+        assert_eq!(health.status, HealthStatus::Unhealthy);
+        assert!(health.message.is_some());
     }
 }
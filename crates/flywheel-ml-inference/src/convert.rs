@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use flywheel_ml_core::{FeatureValue, FeatureVector, ModelError, Prediction, PredictionResult};
+use flywheel_ml_proto as proto;
+use prost_types::Timestamp;
+
+pub fn feature_value_to_proto(value: &FeatureValue) -> proto::FeatureValue {
+    use proto::feature_value::Value;
+
+    let value = match value {
+        FeatureValue::Float(v) => Some(Value::FloatValue(*v)),
+        FeatureValue::Int(v) => Some(Value::IntValue(*v)),
+        FeatureValue::String(s) | FeatureValue::Categorical(s) => {
+            Some(Value::StringValue(s.clone()))
+        }
+        FeatureValue::FloatArray(values) => Some(Value::FloatArray(proto::FloatArray {
+            values: values.clone(),
+        })),
+        FeatureValue::IntArray(values) => Some(Value::FloatArray(proto::FloatArray {
+            values: values.iter().map(|v| *v as f64).collect(),
+        })),
+        FeatureValue::Embedding(values) => Some(Value::FloatArray(proto::FloatArray {
+            values: values.iter().map(|v| *v as f64).collect(),
+        })),
+        FeatureValue::Boolean(b) => Some(Value::BoolValue(*b)),
+        FeatureValue::Null => None,
+    };
+
+    proto::FeatureValue { value }
+}
+
+pub fn feature_value_from_proto(value: &proto::FeatureValue) -> FeatureValue {
+    use proto::feature_value::Value;
+
+    match &value.value {
+        Some(Value::FloatValue(v)) => FeatureValue::Float(*v),
+        Some(Value::IntValue(v)) => FeatureValue::Int(*v),
+        Some(Value::StringValue(s)) => FeatureValue::String(s.clone()),
+        Some(Value::FloatArray(array)) => FeatureValue::FloatArray(array.values.clone()),
+        Some(Value::BoolValue(b)) => FeatureValue::Boolean(*b),
+        None => FeatureValue::Null,
+    }
+}
+
+pub fn features_to_proto(features: &FeatureVector) -> HashMap<String, proto::FeatureValue> {
+    features
+        .features
+        .iter()
+        .map(|(name, value)| (name.clone(), feature_value_to_proto(value)))
+        .collect()
+}
+
+pub fn predict_request(model_id: &str, features: &FeatureVector) -> proto::PredictRequest {
+    proto::PredictRequest {
+        request_id: features.source_record_id.clone(),
+        model_id: model_id.to_string(),
+        features: features_to_proto(features),
+        timestamp: Some(datetime_to_timestamp(features.timestamp)),
+        metadata: features.metadata.clone(),
+    }
+}
+
+pub fn feature_vector_from_request(request: &proto::PredictRequest) -> FeatureVector {
+    let mut vector = FeatureVector::new(request.request_id.clone());
+    vector.features = request
+        .features
+        .iter()
+        .map(|(name, value)| (name.clone(), feature_value_from_proto(value)))
+        .collect();
+    vector.metadata = request.metadata.clone();
+    if let Some(timestamp) = request.timestamp.as_ref().and_then(timestamp_to_datetime) {
+        vector.timestamp = timestamp;
+    }
+    vector
+}
+
+pub fn prediction_result_to_proto(result: &PredictionResult) -> proto::PredictionResult {
+    use proto::prediction_result::Result;
+
+    let result = match result {
+        PredictionResult::Anomaly {
+            score,
+            is_anomaly,
+            threshold,
+            contributing_features,
+        } => Result::Anomaly(proto::AnomalyResult {
+            score: *score,
+            is_anomaly: *is_anomaly,
+            threshold: *threshold,
+            contributing_features: contributing_features.clone(),
+        }),
+        PredictionResult::Classification {
+            class,
+            probabilities,
+        } => Result::Classification(proto::ClassificationResult {
+            predicted_class: class.clone(),
+            class_probabilities: probabilities.clone(),
+        }),
+        PredictionResult::Regression {
+            value,
+            confidence_interval,
+        } => {
+            let (lower_bound, upper_bound) = confidence_interval.unwrap_or((*value, *value));
+            Result::Regression(proto::RegressionResult {
+                value: *value,
+                lower_bound,
+                upper_bound,
+            })
+        }
+        PredictionResult::Embedding { vector } => Result::Embedding(proto::EmbeddingResult {
+            vector: vector.clone(),
+        }),
+        // The wire format has no clustering variant; ship it as tagged JSON.
+        PredictionResult::Clustering { .. } => {
+            Result::CustomJson(serde_json::to_vec(result).unwrap_or_default())
+        }
+        PredictionResult::Custom(value) => {
+            Result::CustomJson(serde_json::to_vec(value).unwrap_or_default())
+        }
+    };
+
+    proto::PredictionResult {
+        result: Some(result),
+    }
+}
+
+pub fn prediction_result_from_proto(
+    result: &proto::PredictionResult,
+) -> Result<PredictionResult, ModelError> {
+    use proto::prediction_result::Result;
+
+    match &result.result {
+        Some(Result::Anomaly(anomaly)) => Ok(PredictionResult::Anomaly {
+            score: anomaly.score,
+            is_anomaly: anomaly.is_anomaly,
+            threshold: anomaly.threshold,
+            contributing_features: anomaly.contributing_features.clone(),
+        }),
+        Some(Result::Classification(classification)) => Ok(PredictionResult::Classification {
+            class: classification.predicted_class.clone(),
+            probabilities: classification.class_probabilities.clone(),
+        }),
+        Some(Result::Regression(regression)) => {
+            let confidence_interval = if regression.lower_bound == regression.upper_bound {
+                None
+            } else {
+                Some((regression.lower_bound, regression.upper_bound))
+            };
+            Ok(PredictionResult::Regression {
+                value: regression.value,
+                confidence_interval,
+            })
+        }
+        Some(Result::Embedding(embedding)) => Ok(PredictionResult::Embedding {
+            vector: embedding.vector.clone(),
+        }),
+        Some(Result::CustomJson(bytes)) => {
+            let value: serde_json::Value = serde_json::from_slice(bytes).map_err(|e| {
+                ModelError::InferenceFailed(format!("Invalid custom result JSON: {}", e))
+            })?;
+            // Round-trip results we encoded ourselves (e.g. clustering) back to their variant.
+            Ok(serde_json::from_value(value.clone()).unwrap_or(PredictionResult::Custom(value)))
+        }
+        None => Err(ModelError::InferenceFailed(
+            "Model returned an empty prediction result".to_string(),
+        )),
+    }
+}
+
+pub fn prediction_from_response(
+    response: proto::PredictResponse,
+    features_hash: impl Into<String>,
+) -> Result<Prediction, ModelError> {
+    let result = response
+        .result
+        .as_ref()
+        .ok_or_else(|| {
+            ModelError::InferenceFailed("Model returned an empty prediction result".to_string())
+        })
+        .and_then(prediction_result_from_proto)?;
+
+    let mut prediction = Prediction::new(response.model_id, result)
+        .with_version(response.model_version)
+        .with_latency(response.latency_us)
+        .with_features_hash(features_hash);
+
+    if !response.prediction_id.is_empty() {
+        prediction.prediction_id = response.prediction_id;
+    }
+    if response.confidence > 0.0 {
+        prediction.confidence = Some(response.confidence);
+    }
+    if let Some(timestamp) = response.timestamp.as_ref().and_then(timestamp_to_datetime) {
+        prediction.timestamp = timestamp;
+    }
+
+    Ok(prediction)
+}
+
+pub fn prediction_to_response(prediction: &Prediction) -> proto::PredictResponse {
+    proto::PredictResponse {
+        prediction_id: prediction.prediction_id.clone(),
+        model_id: prediction.model_id.clone(),
+        model_version: prediction.model_version.clone(),
+        result: Some(prediction_result_to_proto(&prediction.result)),
+        confidence: prediction.confidence.unwrap_or(0.0),
+        latency_us: prediction.latency_us,
+        timestamp: Some(datetime_to_timestamp(prediction.timestamp)),
+        request_id: String::new(),
+        error: None,
+    }
+}
+
+pub fn model_error_from_status(status: tonic::Status, timeout_ms: u64) -> ModelError {
+    match status.code() {
+        // tonic servers answer an expired `grpc-timeout` with `Cancelled`.
+        tonic::Code::DeadlineExceeded | tonic::Code::Cancelled => ModelError::Timeout(timeout_ms),
+        tonic::Code::Unavailable => ModelError::Unavailable(status.message().to_string()),
+        tonic::Code::InvalidArgument => ModelError::InvalidInput(status.message().to_string()),
+        tonic::Code::NotFound => ModelError::NotFound(status.message().to_string()),
+        _ => ModelError::InferenceFailed(format!("{:?}: {}", status.code(), status.message())),
+    }
+}
+
+/// The status a model server answers a failed prediction with; the inverse
+/// of `model_error_from_status`.
+pub fn model_error_to_status(error: &ModelError) -> tonic::Status {
+    let message = error.to_string();
+    match error {
+        ModelError::Timeout(_) => tonic::Status::deadline_exceeded(message),
+        ModelError::Unavailable(_)
+        | ModelError::Connection(_)
+        | ModelError::CircuitBreakerOpen(_) => tonic::Status::unavailable(message),
+        ModelError::InvalidInput(_) => tonic::Status::invalid_argument(message),
+        ModelError::NotFound(_) => tonic::Status::not_found(message),
+        ModelError::InvalidModel(_) => tonic::Status::failed_precondition(message),
+        ModelError::InferenceFailed(_) => tonic::Status::internal(message),
+    }
+}
+
+fn datetime_to_timestamp(dt: DateTime<Utc>) -> Timestamp {
+    Timestamp {
+        seconds: dt.timestamp(),
+        nanos: dt.timestamp_subsec_nanos() as i32,
+    }
+}
+
+fn timestamp_to_datetime(ts: &Timestamp) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp(ts.seconds, ts.nanos.max(0) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prediction_result_roundtrip() {
+        let results = vec![
+            PredictionResult::anomaly(0.9, 0.5),
+            PredictionResult::binary_classification("spam", 0.8),
+            PredictionResult::regression_with_interval(1.5, 1.0, 2.0),
+            PredictionResult::regression(3.0),
+            PredictionResult::clustering(3, 0.25),
+        ];
+
+        for result in results {
+            let proto = prediction_result_to_proto(&result);
+            let back = prediction_result_from_proto(&proto).unwrap();
+            assert_eq!(
+                serde_json::to_value(&back).unwrap(),
+                serde_json::to_value(&result).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_model_error_status_roundtrip() {
+        let errors = vec![
+            ModelError::Timeout(100),
+            ModelError::Unavailable("down".to_string()),
+            ModelError::InvalidInput("missing feature: cpu".to_string()),
+            ModelError::NotFound("model-1".to_string()),
+        ];
+
+        for error in errors {
+            let back = model_error_from_status(model_error_to_status(&error), 100);
+            assert_eq!(
+                std::mem::discriminant(&back),
+                std::mem::discriminant(&error)
+            );
+        }
+    }
+}
@@ -1,5 +1,9 @@
+pub mod bandit;
 pub mod batch;
 pub mod circuit_breaker;
 pub mod client;
+pub mod convert;
+pub mod local;
 
 pub use client::InferenceClient;
+pub use local::load_model;
@@ -0,0 +1,454 @@
+use std::collections::HashMap;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use flywheel_ml_core::{
+    FeatureVector, Model, ModelError, ModelMetadata, Prediction, PredictionResult,
+};
+use parking_lot::Mutex;
+use rand::rngs::StdRng;
+use rand::seq::index;
+use rand::{Rng, SeedableRng};
+
+use super::LocalEndpoint;
+
+/// Marks predictions made before a model has seen enough records to score.
+const WARMING_UP: &str = "warming_up";
+
+/// Scores each record by how many standard deviations its most unusual
+/// feature lies from that feature's running mean.
+///
+/// Every record updates the running statistics after it is scored. A feature
+/// is only scored once it has `min_samples` observations (default 30); until
+/// then predictions score 0 and carry `warming_up` metadata. Records whose
+/// score exceeds `threshold` (default 3) are anomalies, with the features
+/// beyond it listed as contributing.
+pub struct ZScoreModel {
+    metadata: ModelMetadata,
+    threshold: f64,
+    min_samples: u64,
+    stats: Mutex<HashMap<String, RunningStats>>,
+}
+
+/// Welford's online mean and variance.
+#[derive(Debug, Default, Clone)]
+struct RunningStats {
+    count: u64,
+    mean: f64,
+    m2: f64,
+}
+
+impl RunningStats {
+    fn push(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    fn std_dev(&self) -> f64 {
+        if self.count < 2 {
+            return 0.0;
+        }
+        (self.m2 / (self.count - 1) as f64).sqrt()
+    }
+}
+
+impl ZScoreModel {
+    pub(crate) fn new(
+        metadata: ModelMetadata,
+        endpoint: &LocalEndpoint,
+    ) -> Result<Self, ModelError> {
+        Ok(Self {
+            threshold: endpoint.param("threshold", 3.0)?,
+            min_samples: endpoint.param("min_samples", 30)?,
+            stats: Mutex::new(HashMap::new()),
+            metadata,
+        })
+    }
+}
+
+#[async_trait]
+impl Model for ZScoreModel {
+    fn metadata(&self) -> &ModelMetadata {
+        &self.metadata
+    }
+
+    async fn predict(&self, features: FeatureVector) -> Result<Prediction, ModelError> {
+        let started = Instant::now();
+        let names = super::input_names(&self.metadata, &features);
+        let values = super::numeric_inputs(&features, &names)?;
+
+        let mut scores = Vec::new();
+        let mut warming_up = true;
+        let mut stats = self.stats.lock();
+        for (name, value) in names.iter().zip(values) {
+            if value.is_nan() {
+                continue;
+            }
+            let feature = stats.entry(name.clone()).or_default();
+            if feature.count >= self.min_samples {
+                warming_up = false;
+                let std_dev = feature.std_dev();
+                if std_dev > 0.0 {
+                    scores.push((name, (value - feature.mean).abs() / std_dev));
+                }
+            }
+            feature.push(value);
+        }
+        drop(stats);
+
+        let score = scores.iter().map(|(_, z)| *z).fold(0.0, f64::max);
+        scores.retain(|(_, z)| *z > self.threshold);
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let contributing = scores.into_iter().map(|(name, _)| name.clone()).collect();
+
+        let result = PredictionResult::anomaly_with_features(score, self.threshold, contributing);
+        let prediction = super::prediction(&self.metadata, &features, result, started);
+        Ok(if warming_up {
+            prediction.with_metadata(WARMING_UP, "true")
+        } else {
+            prediction
+        })
+    }
+}
+
+/// An Isolation Forest fitted on the first records it sees.
+///
+/// The first `training_size` records (default 1024) are kept and score 0
+/// with `warming_up` metadata; the forest of `trees` trees (default 100),
+/// each grown on `sample_size` of them (default 256), is then built and used
+/// for every later record. Scores run from 0 to 1, and records above
+/// `threshold` (default 0.6) are anomalies. `seed` makes fitting
+/// reproducible. A missing feature takes the right branch of every split.
+pub struct IsolationForestModel {
+    metadata: ModelMetadata,
+    config: ForestConfig,
+    state: Mutex<ForestState>,
+}
+
+#[derive(Debug, Clone)]
+struct ForestConfig {
+    trees: usize,
+    sample_size: usize,
+    training_size: usize,
+    threshold: f64,
+    seed: Option<u64>,
+}
+
+enum ForestState {
+    Training {
+        /// Feature order, fixed by the first record unless configured.
+        names: Option<Vec<String>>,
+        samples: Vec<Vec<f64>>,
+    },
+    Fitted {
+        names: Vec<String>,
+        forest: IsolationForest,
+    },
+}
+
+impl IsolationForestModel {
+    pub(crate) fn new(
+        metadata: ModelMetadata,
+        endpoint: &LocalEndpoint,
+    ) -> Result<Self, ModelError> {
+        let config = ForestConfig {
+            trees: endpoint.param("trees", 100)?,
+            sample_size: endpoint.param("sample_size", 256)?,
+            training_size: endpoint.param("training_size", 1024)?,
+            threshold: endpoint.param("threshold", 0.6)?,
+            seed: endpoint
+                .params
+                .get("seed")
+                .map(|_| endpoint.param("seed", 0))
+                .transpose()?,
+        };
+        if config.trees == 0 || config.sample_size < 2 || config.training_size < 2 {
+            return Err(ModelError::InvalidModel(
+                "isolation forest needs trees > 0, and sample_size and training_size of at least 2"
+                    .to_string(),
+            ));
+        }
+        let names = (!metadata.input_features.is_empty()).then(|| metadata.input_features.clone());
+        Ok(Self {
+            metadata,
+            config,
+            state: Mutex::new(ForestState::Training {
+                names,
+                samples: Vec::new(),
+            }),
+        })
+    }
+}
+
+#[async_trait]
+impl Model for IsolationForestModel {
+    fn metadata(&self) -> &ModelMetadata {
+        &self.metadata
+    }
+
+    async fn predict(&self, features: FeatureVector) -> Result<Prediction, ModelError> {
+        let started = Instant::now();
+        let mut state = self.state.lock();
+
+        let score = match &mut *state {
+            ForestState::Fitted { names, forest } => {
+                Some(forest.score(&super::numeric_inputs(&features, names)?))
+            }
+            ForestState::Training { names, samples } => {
+                let names =
+                    names.get_or_insert_with(|| super::input_names(&self.metadata, &features));
+                samples.push(super::numeric_inputs(&features, names)?);
+                if samples.len() >= self.config.training_size {
+                    *state = ForestState::Fitted {
+                        forest: IsolationForest::fit(samples, &self.config),
+                        names: std::mem::take(names),
+                    };
+                }
+                None
+            }
+        };
+        drop(state);
+
+        let result = PredictionResult::anomaly(score.unwrap_or(0.0), self.config.threshold);
+        let prediction = super::prediction(&self.metadata, &features, result, started);
+        Ok(match score {
+            Some(_) => prediction,
+            None => prediction.with_metadata(WARMING_UP, "true"),
+        })
+    }
+}
+
+struct IsolationForest {
+    trees: Vec<IsolationTree>,
+    /// Average path length of an unsuccessful search among `sample_size`
+    /// points, which normalizes scores.
+    normalizer: f64,
+}
+
+struct IsolationTree {
+    /// Nodes with the root first.
+    nodes: Vec<IsolationNode>,
+}
+
+enum IsolationNode {
+    Leaf {
+        size: usize,
+    },
+    Split {
+        feature: usize,
+        value: f64,
+        left: usize,
+        right: usize,
+    },
+}
+
+impl IsolationForest {
+    fn fit(samples: &[Vec<f64>], config: &ForestConfig) -> Self {
+        let mut rng = match config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let sample_size = config.sample_size.min(samples.len());
+        let max_depth = (sample_size as f64).log2().ceil() as usize;
+
+        let trees = (0..config.trees)
+            .map(|_| {
+                let sample: Vec<&[f64]> = index::sample(&mut rng, samples.len(), sample_size)
+                    .into_iter()
+                    .map(|i| samples[i].as_slice())
+                    .collect();
+                let mut nodes = Vec::new();
+                grow(&sample, 0, max_depth, &mut nodes, &mut rng);
+                IsolationTree { nodes }
+            })
+            .collect();
+
+        Self {
+            trees,
+            normalizer: average_path_length(sample_size),
+        }
+    }
+
+    fn score(&self, inputs: &[f64]) -> f64 {
+        let mean_depth = self
+            .trees
+            .iter()
+            .map(|tree| tree.path_length(inputs))
+            .sum::<f64>()
+            / self.trees.len() as f64;
+        2f64.powf(-mean_depth / self.normalizer)
+    }
+}
+
+impl IsolationTree {
+    fn path_length(&self, inputs: &[f64]) -> f64 {
+        let mut index = 0;
+        let mut depth = 0.0;
+        loop {
+            match &self.nodes[index] {
+                IsolationNode::Leaf { size } => return depth + average_path_length(*size),
+                IsolationNode::Split {
+                    feature,
+                    value,
+                    left,
+                    right,
+                } => {
+                    index = if inputs[*feature] < *value {
+                        *left
+                    } else {
+                        *right
+                    };
+                    depth += 1.0;
+                }
+            }
+        }
+    }
+}
+
+/// Appends a subtree isolating `points` to `nodes`, returning its index.
+fn grow(
+    points: &[&[f64]],
+    depth: usize,
+    max_depth: usize,
+    nodes: &mut Vec<IsolationNode>,
+    rng: &mut StdRng,
+) -> usize {
+    let index = nodes.len();
+    nodes.push(IsolationNode::Leaf { size: points.len() });
+    if depth >= max_depth || points.len() <= 1 {
+        return index;
+    }
+
+    // Only features that still vary among the points can split them.
+    let dimensions = points[0].len();
+    let ranges: Vec<(usize, f64, f64)> = (0..dimensions)
+        .filter_map(|feature| {
+            let values = points.iter().map(|p| p[feature]).filter(|v| !v.is_nan());
+            let (min, max) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| {
+                (lo.min(v), hi.max(v))
+            });
+            (min < max).then_some((feature, min, max))
+        })
+        .collect();
+    if ranges.is_empty() {
+        return index;
+    }
+
+    let (feature, min, max) = ranges[rng.gen_range(0..ranges.len())];
+    let value = rng.gen_range(min..max);
+    let (below, above): (Vec<&[f64]>, Vec<&[f64]>) =
+        points.iter().partition(|p| p[feature] < value);
+
+    let left = grow(&below, depth + 1, max_depth, nodes, rng);
+    let right = grow(&above, depth + 1, max_depth, nodes, rng);
+    nodes[index] = IsolationNode::Split {
+        feature,
+        value,
+        left,
+        right,
+    };
+    index
+}
+
+/// The average path length of an unsuccessful binary search tree lookup
+/// among `n` points, `c(n)` in the Isolation Forest paper.
+fn average_path_length(n: usize) -> f64 {
+    match n {
+        0 | 1 => 0.0,
+        2 => 1.0,
+        n => {
+            let n = n as f64;
+            let harmonic = (n - 1.0).ln() + 0.577_215_664_901_532_9;
+            2.0 * harmonic - 2.0 * (n - 1.0) / n
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flywheel_ml_core::{FeatureValue, ModelType};
+
+    fn metadata() -> ModelMetadata {
+        ModelMetadata::new("anomaly", ModelType::AnomalyDetection)
+            .with_input_features(vec!["cpu".to_string(), "latency".to_string()])
+    }
+
+    fn features(cpu: f64, latency: f64) -> FeatureVector {
+        FeatureVector::new("rec")
+            .with_feature("cpu", FeatureValue::Float(cpu))
+            .with_feature("latency", FeatureValue::Float(latency))
+    }
+
+    fn score(prediction: &Prediction) -> f64 {
+        prediction.anomaly_score().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_zscore_flags_outlying_feature() {
+        let endpoint = LocalEndpoint::parse("builtin://zscore?min_samples=50").unwrap();
+        let model = ZScoreModel::new(metadata(), &endpoint).unwrap();
+
+        let first = model.predict(features(0.5, 100.0)).await.unwrap();
+        assert_eq!(
+            first.metadata.get(WARMING_UP).map(String::as_str),
+            Some("true")
+        );
+        for i in 0..100 {
+            let jitter = (i % 10) as f64;
+            model
+                .predict(features(0.5 + jitter * 0.01, 100.0 + jitter))
+                .await
+                .unwrap();
+        }
+
+        let normal = model.predict(features(0.52, 103.0)).await.unwrap();
+        assert!(score(&normal) < 3.0);
+        assert!(!normal.metadata.contains_key(WARMING_UP));
+
+        let spike = model.predict(features(0.52, 400.0)).await.unwrap();
+        match spike.result {
+            PredictionResult::Anomaly {
+                is_anomaly,
+                contributing_features,
+                ..
+            } => {
+                assert!(is_anomaly);
+                assert_eq!(contributing_features, vec!["latency".to_string()]);
+            }
+            other => panic!("expected anomaly, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_isolation_forest_fits_then_scores() {
+        let endpoint = LocalEndpoint::parse(
+            "builtin://isolation-forest?training_size=500&sample_size=128&seed=7",
+        )
+        .unwrap();
+        let model = IsolationForestModel::new(metadata(), &endpoint).unwrap();
+
+        for i in 0..500 {
+            let jitter = ((i * 37) % 100) as f64 / 100.0;
+            let prediction = model
+                .predict(features(0.4 + jitter * 0.2, 90.0 + jitter * 20.0))
+                .await
+                .unwrap();
+            assert!(prediction.metadata.contains_key(WARMING_UP));
+        }
+
+        let inlier = model.predict(features(0.5, 100.0)).await.unwrap();
+        let outlier = model.predict(features(0.95, 900.0)).await.unwrap();
+        assert!(!inlier.metadata.contains_key(WARMING_UP));
+        assert!(score(&outlier) > score(&inlier));
+        assert!(matches!(
+            outlier.result,
+            PredictionResult::Anomaly {
+                is_anomaly: true,
+                ..
+            }
+        ));
+    }
+}
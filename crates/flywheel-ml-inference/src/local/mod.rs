@@ -0,0 +1,240 @@
+//! Models that run in-process on the CPU, chosen by the scheme of the model
+//! endpoint:
+//!
+//! - `onnx://<path>` runs an ONNX model.
+//! - `trees://<path>` evaluates an XGBoost or LightGBM JSON model dump.
+//! - `builtin://isolation-forest` and `builtin://zscore` score anomalies.
+//!
+//! Query parameters tune a runtime, e.g. `builtin://zscore?threshold=4`. Any
+//! other endpoint is a model server reached through `InferenceClient`.
+
+mod anomaly;
+mod onnx;
+mod trees;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use flywheel_ml_core::{
+    FeatureValue, FeatureVector, Model, ModelConfig, ModelError, ModelMetadata, ModelType,
+    Prediction, PredictionResult,
+};
+
+use crate::InferenceClient;
+
+pub use anomaly::{IsolationForestModel, ZScoreModel};
+pub use onnx::OnnxModel;
+pub use trees::TreeEnsembleModel;
+
+/// Loads the model `metadata.endpoint` points at. Local models are read and
+/// checked here, so a missing or malformed model file fails at load rather
+/// than on the first prediction. `timeout` only applies to model servers.
+pub fn load_model(
+    metadata: ModelMetadata,
+    timeout: Duration,
+) -> Result<Arc<dyn Model>, ModelError> {
+    let Some(endpoint) = LocalEndpoint::parse(&metadata.endpoint) else {
+        let endpoint = metadata.endpoint.clone();
+        return Ok(Arc::new(
+            InferenceClient::new(endpoint, metadata).with_timeout(timeout),
+        ));
+    };
+
+    let model: Arc<dyn Model> = match endpoint.scheme.as_str() {
+        "onnx" => Arc::new(OnnxModel::load(metadata, &endpoint)?),
+        "trees" => Arc::new(TreeEnsembleModel::load(metadata, &endpoint)?),
+        "builtin" => match endpoint.location.as_str() {
+            "isolation-forest" => Arc::new(IsolationForestModel::new(metadata, &endpoint)?),
+            "zscore" => Arc::new(ZScoreModel::new(metadata, &endpoint)?),
+            other => {
+                return Err(ModelError::NotFound(format!(
+                    "no builtin model named '{}'",
+                    other
+                )))
+            }
+        },
+        _ => unreachable!("LocalEndpoint::parse only accepts local schemes"),
+    };
+    Ok(model)
+}
+
+pub fn model_from_config(config: &ModelConfig) -> Result<Arc<dyn Model>, ModelError> {
+    let metadata = ModelMetadata::new(config.model_id.clone(), config.model_type)
+        .with_endpoint(config.endpoint.clone());
+    load_model(metadata, Duration::from_millis(config.timeout_ms))
+}
+
+/// Whether `endpoint` names an in-process model rather than a model server.
+pub fn is_local(endpoint: &str) -> bool {
+    LocalEndpoint::parse(endpoint).is_some()
+}
+
+/// A parsed `scheme://location?key=value&...` endpoint.
+#[derive(Debug, Clone)]
+pub(crate) struct LocalEndpoint {
+    pub scheme: String,
+    pub location: String,
+    pub params: HashMap<String, String>,
+}
+
+impl LocalEndpoint {
+    const SCHEMES: [&'static str; 3] = ["onnx", "trees", "builtin"];
+
+    pub fn parse(endpoint: &str) -> Option<Self> {
+        let (scheme, rest) = endpoint.split_once("://")?;
+        if !Self::SCHEMES.contains(&scheme) {
+            return None;
+        }
+        let (location, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let params = query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                (key.to_string(), value.to_string())
+            })
+            .collect();
+        Some(Self {
+            scheme: scheme.to_string(),
+            location: location.to_string(),
+            params,
+        })
+    }
+
+    pub fn path(&self) -> PathBuf {
+        PathBuf::from(&self.location)
+    }
+
+    /// The value of `key`, or `default` when it is not set.
+    pub fn param<T: std::str::FromStr>(&self, key: &str, default: T) -> Result<T, ModelError> {
+        match self.params.get(key) {
+            Some(value) => value.parse().map_err(|_| {
+                ModelError::InvalidModel(format!(
+                    "invalid value '{}' for '{}' in {}://{}",
+                    value, key, self.scheme, self.location
+                ))
+            }),
+            None => Ok(default),
+        }
+    }
+
+    /// How outputs are read: `type=` on the endpoint, else the model's type.
+    pub fn model_type(&self, metadata: &ModelMetadata) -> Result<ModelType, ModelError> {
+        let Some(name) = self.params.get("type") else {
+            return Ok(metadata.model_type);
+        };
+        ModelType::from_name(name).ok_or_else(|| ModelError::InvalidModel(format!("unsupported model type '{}'", name)))
+    }
+}
+
+/// The model's inputs in `names` order, as numbers. Booleans count as 0 or 1
+/// and a missing or null feature as NaN.
+pub(crate) fn numeric_inputs(
+    features: &FeatureVector,
+    names: &[String],
+) -> Result<Vec<f64>, ModelError> {
+    names
+        .iter()
+        .map(|name| match features.get(name) {
+            Some(FeatureValue::Float(v)) => Ok(*v),
+            Some(FeatureValue::Int(v)) => Ok(*v as f64),
+            Some(FeatureValue::Boolean(b)) => Ok(if *b { 1.0 } else { 0.0 }),
+            Some(FeatureValue::Null) | None => Ok(f64::NAN),
+            Some(other) => Err(ModelError::InvalidInput(format!(
+                "feature '{}' is {}, expected a number",
+                name,
+                other.type_name()
+            ))),
+        })
+        .collect()
+}
+
+/// The features a model reads: those configured on it, else every feature
+/// of the record in name order.
+pub(crate) fn input_names(metadata: &ModelMetadata, features: &FeatureVector) -> Vec<String> {
+    if !metadata.input_features.is_empty() {
+        return metadata.input_features.clone();
+    }
+    let mut names: Vec<String> = features.features.keys().cloned().collect();
+    names.sort();
+    names
+}
+
+/// Reads raw model outputs as a prediction of `model_type`.
+///
+/// Anomaly models output a score; classifiers output one probability per
+/// class, or a single positive-class probability, with classes named by
+/// `labels` or by index; regressors output a value. Anything else is kept as
+/// a JSON array.
+pub(crate) fn result_from_outputs(
+    model_type: ModelType,
+    outputs: &[f64],
+    threshold: f64,
+    labels: &[String],
+) -> Result<PredictionResult, ModelError> {
+    let first = || {
+        outputs
+            .first()
+            .copied()
+            .ok_or_else(|| ModelError::InferenceFailed("model produced no output".to_string()))
+    };
+    let label = |i: usize| labels.get(i).cloned().unwrap_or_else(|| i.to_string());
+
+    Ok(match model_type {
+        ModelType::AnomalyDetection => PredictionResult::anomaly(first()?, threshold),
+        ModelType::Regression => PredictionResult::regression(first()?),
+        ModelType::Classification if outputs.len() == 1 => {
+            let p = first()?;
+            let class = if p > threshold { label(1) } else { label(0) };
+            let probabilities = HashMap::from([(label(1), p), (label(0), 1.0 - p)]);
+            PredictionResult::classification(class, probabilities)
+        }
+        ModelType::Classification => {
+            let best = outputs
+                .iter()
+                .enumerate()
+                .max_by(|a, b| a.1.total_cmp(b.1))
+                .map(|(i, _)| i)
+                .ok_or_else(|| ModelError::InferenceFailed("model produced no output".into()))?;
+            let probabilities = outputs.iter().enumerate().map(|(i, p)| (label(i), *p));
+            PredictionResult::classification(label(best), probabilities.collect())
+        }
+        _ => PredictionResult::Custom(serde_json::json!(outputs)),
+    })
+}
+
+/// Wraps a result with the model's identity, the features it scored and how
+/// long scoring took.
+pub(crate) fn prediction(
+    metadata: &ModelMetadata,
+    features: &FeatureVector,
+    result: PredictionResult,
+    started: Instant,
+) -> Prediction {
+    Prediction::new(metadata.model_id.clone(), result)
+        .with_version(metadata.version.clone())
+        .with_features_hash(features.hash())
+        .with_latency(started.elapsed().as_micros() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_endpoints() {
+        let endpoint = LocalEndpoint::parse("builtin://zscore?threshold=4&min_samples=10").unwrap();
+        assert_eq!(endpoint.location, "zscore");
+        assert_eq!(endpoint.param("threshold", 3.0).unwrap(), 4.0);
+        assert_eq!(endpoint.param("window", 100usize).unwrap(), 100);
+        assert!(endpoint.param("min_samples", 0.5f32).is_ok());
+
+        let endpoint = LocalEndpoint::parse("trees:///models/churn.json").unwrap();
+        assert_eq!(endpoint.path(), PathBuf::from("/models/churn.json"));
+
+        assert!(LocalEndpoint::parse("http://model-server:50051").is_none());
+        assert!(LocalEndpoint::parse("model-server:50051").is_none());
+    }
+}
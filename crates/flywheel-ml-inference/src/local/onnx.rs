@@ -0,0 +1,225 @@
+use std::time::Instant;
+
+use async_trait::async_trait;
+use flywheel_ml_core::{FeatureVector, Model, ModelError, ModelMetadata, ModelType, Prediction};
+use tract_onnx::prelude::*;
+
+use super::LocalEndpoint;
+
+type Plan = TypedRunnableModel<TypedModel>;
+
+/// Runs an ONNX model with tract, a pure-Rust CPU runtime.
+///
+/// The model takes one `[1, n]` float tensor holding the configured input
+/// features in order; missing features are NaN. Output `output` (default 0)
+/// is read as a prediction of the model's type, see `result_from_outputs`.
+///
+/// Endpoint parameters: `type`, `threshold` (default 0.5), `output`, and
+/// `labels`, a comma-separated list of class names.
+pub struct OnnxModel {
+    metadata: ModelMetadata,
+    plan: Plan,
+    model_type: ModelType,
+    output: usize,
+    threshold: f64,
+    labels: Vec<String>,
+}
+
+impl OnnxModel {
+    pub(crate) fn load(
+        metadata: ModelMetadata,
+        endpoint: &LocalEndpoint,
+    ) -> Result<Self, ModelError> {
+        let path = endpoint.path();
+        if !path.is_file() {
+            return Err(ModelError::NotFound(format!(
+                "ONNX model file {}",
+                path.display()
+            )));
+        }
+        let model = tract_onnx::onnx()
+            .model_for_path(&path)
+            .map_err(|e| invalid(&format!("cannot read {}", path.display()), e))?;
+        Self::from_model(metadata, endpoint, model)
+    }
+
+    fn from_model(
+        metadata: ModelMetadata,
+        endpoint: &LocalEndpoint,
+        model: InferenceModel,
+    ) -> Result<Self, ModelError> {
+        if metadata.input_features.is_empty() {
+            return Err(ModelError::InvalidModel(
+                "ONNX models need input_features to order their input tensor".to_string(),
+            ));
+        }
+        let inputs = metadata.input_features.len();
+        let plan = model
+            .with_input_fact(0, f32::fact([1, inputs]).into())
+            .and_then(|model| model.into_optimized())
+            .and_then(|model| model.into_runnable())
+            .map_err(|e| invalid("cannot prepare ONNX model", e))?;
+
+        Ok(Self {
+            model_type: endpoint.model_type(&metadata)?,
+            output: endpoint.param("output", 0)?,
+            threshold: endpoint.param("threshold", 0.5)?,
+            labels: endpoint
+                .params
+                .get("labels")
+                .map(|labels| labels.split(',').map(str::to_string).collect())
+                .unwrap_or_default(),
+            metadata,
+            plan,
+        })
+    }
+
+    fn score(&self, features: &FeatureVector) -> Result<Vec<f64>, ModelError> {
+        let inputs: Vec<f32> = super::numeric_inputs(features, &self.metadata.input_features)?
+            .into_iter()
+            .map(|v| v as f32)
+            .collect();
+        let input = Tensor::from_shape(&[1, inputs.len()], &inputs)
+            .map_err(|e| ModelError::InvalidInput(e.to_string()))?;
+
+        let outputs = self
+            .plan
+            .run(tvec!(input.into()))
+            .map_err(|e| ModelError::InferenceFailed(e.to_string()))?;
+        let output = outputs.get(self.output).ok_or_else(|| {
+            ModelError::InferenceFailed(format!(
+                "model has {} outputs, no output {}",
+                outputs.len(),
+                self.output
+            ))
+        })?;
+        let values = output
+            .cast_to::<f64>()
+            .map_err(|e| ModelError::InferenceFailed(e.to_string()))?;
+        let values = values
+            .as_slice::<f64>()
+            .map_err(|e| ModelError::InferenceFailed(e.to_string()))?;
+        Ok(values.to_vec())
+    }
+}
+
+fn invalid(context: &str, error: TractError) -> ModelError {
+    ModelError::InvalidModel(format!("{}: {}", context, error))
+}
+
+#[async_trait]
+impl Model for OnnxModel {
+    fn metadata(&self) -> &ModelMetadata {
+        &self.metadata
+    }
+
+    async fn predict(&self, features: FeatureVector) -> Result<Prediction, ModelError> {
+        let started = Instant::now();
+        let outputs = self.score(&features)?;
+        let result =
+            super::result_from_outputs(self.model_type, &outputs, self.threshold, &self.labels)?;
+        Ok(super::prediction(
+            &self.metadata,
+            &features,
+            result,
+            started,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flywheel_ml_core::{FeatureValue, PredictionResult};
+    use tract_onnx::pb;
+
+    fn float_tensor(name: &str, dims: &[i64], values: &[f32]) -> pb::TensorProto {
+        pb::TensorProto {
+            name: name.to_string(),
+            dims: dims.to_vec(),
+            data_type: pb::tensor_proto::DataType::Float as i32,
+            float_data: values.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    fn float_input(name: &str, dims: &[i64]) -> pb::ValueInfoProto {
+        use pb::tensor_shape_proto::dimension::Value;
+        use pb::type_proto::{Tensor, Value as TypeValue};
+
+        let dim = dims
+            .iter()
+            .map(|d| pb::tensor_shape_proto::Dimension {
+                value: Some(Value::DimValue(*d)),
+                ..Default::default()
+            })
+            .collect();
+        pb::ValueInfoProto {
+            name: name.to_string(),
+            r#type: Some(pb::TypeProto {
+                value: Some(TypeValue::TensorType(Tensor {
+                    elem_type: pb::tensor_proto::DataType::Float as i32,
+                    shape: Some(pb::TensorShapeProto { dim }),
+                })),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn node(op: &str, inputs: &[&str], output: &str) -> pb::NodeProto {
+        pb::NodeProto {
+            op_type: op.to_string(),
+            input: inputs.iter().map(|s| s.to_string()).collect(),
+            output: vec![output.to_string()],
+            ..Default::default()
+        }
+    }
+
+    /// `y = x · [2, -1] + 0.5`, a linear regressor over two features.
+    fn linear_model() -> InferenceModel {
+        let graph = pb::GraphProto {
+            node: vec![
+                node("MatMul", &["x", "w"], "xw"),
+                node("Add", &["xw", "b"], "y"),
+            ],
+            initializer: vec![
+                float_tensor("w", &[2, 1], &[2.0, -1.0]),
+                float_tensor("b", &[1], &[0.5]),
+            ],
+            input: vec![float_input("x", &[1, 2])],
+            output: vec![pb::ValueInfoProto {
+                name: "y".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let proto = pb::ModelProto {
+            ir_version: 7,
+            opset_import: vec![pb::OperatorSetIdProto {
+                domain: String::new(),
+                version: 13,
+            }],
+            graph: Some(graph),
+            ..Default::default()
+        };
+        tract_onnx::onnx().model_for_proto_model(&proto).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_runs_onnx_graph() {
+        let metadata = ModelMetadata::new("linear", ModelType::Regression)
+            .with_input_features(vec!["cpu".to_string(), "memory".to_string()]);
+        let endpoint = LocalEndpoint::parse("onnx://linear.onnx").unwrap();
+        let model = OnnxModel::from_model(metadata, &endpoint, linear_model()).unwrap();
+
+        let features = FeatureVector::new("rec-1")
+            .with_feature("cpu", FeatureValue::Float(1.5))
+            .with_feature("memory", FeatureValue::Int(1));
+        let prediction = model.predict(features).await.unwrap();
+        match prediction.result {
+            PredictionResult::Regression { value, .. } => assert!((value - 2.5).abs() < 1e-6),
+            other => panic!("expected regression, got {:?}", other),
+        }
+    }
+}
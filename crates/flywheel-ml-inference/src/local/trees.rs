@@ -0,0 +1,495 @@
+use std::collections::HashMap;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use flywheel_ml_core::{FeatureVector, Model, ModelError, ModelMetadata, ModelType, Prediction};
+use serde_json::Value;
+
+use super::LocalEndpoint;
+
+/// Evaluates a gradient-boosted tree ensemble from an XGBoost
+/// (`dump_model(..., dump_format="json")`) or LightGBM (`dump_model()`) JSON
+/// dump.
+///
+/// Splits on an indexed feature (`f3` in XGBoost, `split_feature` in LightGBM)
+/// read the configured input feature at that index, or the dump's own feature
+/// name when none are configured. Missing features follow each split's
+/// default branch.
+///
+/// The summed leaves, plus `base_margin`, go through the objective's link:
+/// a sigmoid for binary objectives, a softmax across classes for multiclass
+/// ones, and nothing for regression. LightGBM dumps name their objective;
+/// for XGBoost pass e.g. `objective=binary:logistic`, or
+/// `objective=multi:softprob&num_class=3`. Unless `type` says otherwise,
+/// binary and multiclass models are classifiers and the rest regressors.
+pub struct TreeEnsembleModel {
+    metadata: ModelMetadata,
+    ensemble: TreeEnsemble,
+    model_type: ModelType,
+    threshold: f64,
+    labels: Vec<String>,
+}
+
+impl TreeEnsembleModel {
+    pub(crate) fn load(
+        metadata: ModelMetadata,
+        endpoint: &LocalEndpoint,
+    ) -> Result<Self, ModelError> {
+        let path = endpoint.path();
+        let contents = std::fs::read_to_string(&path).map_err(|e| {
+            ModelError::NotFound(format!("tree model file {}: {}", path.display(), e))
+        })?;
+        let dump: Value = serde_json::from_str(&contents).map_err(|e| {
+            ModelError::InvalidModel(format!("{} is not JSON: {}", path.display(), e))
+        })?;
+        Self::from_dump(metadata, endpoint, &dump)
+    }
+
+    fn from_dump(
+        metadata: ModelMetadata,
+        endpoint: &LocalEndpoint,
+        dump: &Value,
+    ) -> Result<Self, ModelError> {
+        let mut ensemble = match dump {
+            Value::Array(trees) => TreeEnsemble::from_xgboost(trees, &metadata, endpoint)?,
+            Value::Object(_) if dump.get("tree_info").is_some() => {
+                TreeEnsemble::from_lightgbm(dump, &metadata, endpoint)?
+            }
+            _ => {
+                return Err(ModelError::InvalidModel(
+                    "expected an XGBoost or LightGBM JSON dump".to_string(),
+                ))
+            }
+        };
+        ensemble.base_margin = endpoint.param("base_margin", 0.0)?;
+
+        let model_type = match endpoint.model_type(&metadata)? {
+            ModelType::Custom => match ensemble.link {
+                Link::Identity => ModelType::Regression,
+                Link::Sigmoid(_) | Link::Softmax(_) => ModelType::Classification,
+            },
+            model_type => model_type,
+        };
+
+        Ok(Self {
+            model_type,
+            threshold: endpoint.param("threshold", 0.5)?,
+            labels: endpoint
+                .params
+                .get("labels")
+                .map(|labels| labels.split(',').map(str::to_string).collect())
+                .unwrap_or_default(),
+            ensemble,
+            metadata,
+        })
+    }
+}
+
+#[async_trait]
+impl Model for TreeEnsembleModel {
+    fn metadata(&self) -> &ModelMetadata {
+        &self.metadata
+    }
+
+    async fn predict(&self, features: FeatureVector) -> Result<Prediction, ModelError> {
+        let started = Instant::now();
+        let inputs = super::numeric_inputs(&features, &self.ensemble.features)?;
+        let outputs = self.ensemble.predict(&inputs);
+        let result =
+            super::result_from_outputs(self.model_type, &outputs, self.threshold, &self.labels)?;
+        Ok(super::prediction(
+            &self.metadata,
+            &features,
+            result,
+            started,
+        ))
+    }
+}
+
+/// How summed leaf values become outputs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Link {
+    Identity,
+    /// A logistic function with this slope.
+    Sigmoid(f64),
+    /// A softmax over this many classes; tree `i` scores class `i % n`.
+    Softmax(usize),
+}
+
+impl Link {
+    fn from_objective(objective: &str, num_class: usize) -> Result<Self, ModelError> {
+        let mut parts = objective.split_whitespace();
+        let name = parts.next().unwrap_or_default();
+        Ok(match name {
+            "binary" | "binary:logistic" | "reg:logistic" | "cross_entropy" | "xentropy" => {
+                let slope = parts
+                    .find_map(|part| part.strip_prefix("sigmoid:"))
+                    .and_then(|slope| slope.parse().ok())
+                    .unwrap_or(1.0);
+                Link::Sigmoid(slope)
+            }
+            "multiclass" | "softmax" | "multi:softprob" | "multi:softmax" => {
+                if num_class < 2 {
+                    return Err(ModelError::InvalidModel(format!(
+                        "objective '{}' needs num_class of at least 2",
+                        name
+                    )));
+                }
+                Link::Softmax(num_class)
+            }
+            _ => Link::Identity,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct TreeEnsemble {
+    /// Features the splits read, in the order `predict` expects them.
+    features: Vec<String>,
+    trees: Vec<Tree>,
+    link: Link,
+    base_margin: f64,
+}
+
+#[derive(Debug)]
+struct Tree {
+    /// Nodes with the root first.
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug)]
+enum Node {
+    Leaf(f64),
+    Split {
+        feature: usize,
+        threshold: f64,
+        /// Values equal to the threshold go left (LightGBM) rather than
+        /// right (XGBoost).
+        inclusive: bool,
+        left: usize,
+        right: usize,
+        missing_left: bool,
+    },
+}
+
+impl Tree {
+    fn eval(&self, inputs: &[f64]) -> f64 {
+        let mut index = 0;
+        loop {
+            match &self.nodes[index] {
+                Node::Leaf(value) => return *value,
+                Node::Split {
+                    feature,
+                    threshold,
+                    inclusive,
+                    left,
+                    right,
+                    missing_left,
+                } => {
+                    let value = inputs[*feature];
+                    let goes_left = if value.is_nan() {
+                        *missing_left
+                    } else if *inclusive {
+                        value <= *threshold
+                    } else {
+                        value < *threshold
+                    };
+                    index = if goes_left { *left } else { *right };
+                }
+            }
+        }
+    }
+}
+
+impl TreeEnsemble {
+    fn predict(&self, inputs: &[f64]) -> Vec<f64> {
+        let classes = match self.link {
+            Link::Softmax(n) => n,
+            _ => 1,
+        };
+        let mut margins = vec![self.base_margin; classes];
+        for (i, tree) in self.trees.iter().enumerate() {
+            margins[i % classes] += tree.eval(inputs);
+        }
+
+        match self.link {
+            Link::Identity => margins,
+            Link::Sigmoid(slope) => vec![1.0 / (1.0 + (-slope * margins[0]).exp())],
+            Link::Softmax(_) => {
+                let max = margins.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+                let exps: Vec<f64> = margins.iter().map(|m| (m - max).exp()).collect();
+                let sum: f64 = exps.iter().sum();
+                exps.into_iter().map(|e| e / sum).collect()
+            }
+        }
+    }
+
+    fn from_xgboost(
+        trees: &[Value],
+        metadata: &ModelMetadata,
+        endpoint: &LocalEndpoint,
+    ) -> Result<Self, ModelError> {
+        let objective: String = endpoint.param("objective", "reg:squarederror".to_string())?;
+        let mut features = FeatureIndex::new(&metadata.input_features);
+        let trees = trees
+            .iter()
+            .map(|root| {
+                let mut nodes = Vec::new();
+                xgboost_node(root, &mut nodes, &mut features)?;
+                Ok(Tree { nodes })
+            })
+            .collect::<Result<_, ModelError>>()?;
+
+        Ok(Self {
+            features: features.names,
+            trees,
+            link: Link::from_objective(&objective, endpoint.param("num_class", 0)?)?,
+            base_margin: 0.0,
+        })
+    }
+
+    fn from_lightgbm(
+        dump: &Value,
+        metadata: &ModelMetadata,
+        endpoint: &LocalEndpoint,
+    ) -> Result<Self, ModelError> {
+        let dump_names: Vec<String> = dump["feature_names"]
+            .as_array()
+            .map(|names| {
+                names
+                    .iter()
+                    .filter_map(|n| n.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let names = if metadata.input_features.is_empty() {
+            &dump_names
+        } else {
+            &metadata.input_features
+        };
+        let mut features = FeatureIndex::new(names);
+
+        let trees = dump["tree_info"]
+            .as_array()
+            .ok_or_else(|| malformed("tree_info is not a list"))?
+            .iter()
+            .map(|info| {
+                let mut nodes = Vec::new();
+                lightgbm_node(&info["tree_structure"], names, &mut nodes, &mut features)?;
+                Ok(Tree { nodes })
+            })
+            .collect::<Result<_, ModelError>>()?;
+
+        let objective = match endpoint.params.get("objective") {
+            Some(objective) => objective.clone(),
+            None => dump["objective"]
+                .as_str()
+                .unwrap_or("regression")
+                .to_string(),
+        };
+        let num_class = dump["num_class"].as_u64().unwrap_or(1) as usize;
+
+        Ok(Self {
+            features: features.names,
+            trees,
+            link: Link::from_objective(&objective, endpoint.param("num_class", num_class)?)?,
+            base_margin: 0.0,
+        })
+    }
+}
+
+/// Assigns each feature a split reads a position in the model's inputs.
+struct FeatureIndex {
+    configured: Vec<String>,
+    names: Vec<String>,
+    positions: HashMap<String, usize>,
+}
+
+impl FeatureIndex {
+    fn new(configured: &[String]) -> Self {
+        Self {
+            configured: configured.to_vec(),
+            names: Vec::new(),
+            positions: HashMap::new(),
+        }
+    }
+
+    fn position(&mut self, name: &str) -> usize {
+        if let Some(&position) = self.positions.get(name) {
+            return position;
+        }
+        self.names.push(name.to_string());
+        self.positions
+            .insert(name.to_string(), self.names.len() - 1);
+        self.names.len() - 1
+    }
+
+    /// XGBoost names features `f<index>` unless it was given names.
+    fn xgboost(&mut self, split: &str) -> usize {
+        let configured = split
+            .strip_prefix('f')
+            .and_then(|i| i.parse::<usize>().ok())
+            .and_then(|i| self.configured.get(i).cloned());
+        match configured {
+            Some(name) => self.position(&name),
+            None => self.position(split),
+        }
+    }
+}
+
+fn malformed(reason: &str) -> ModelError {
+    ModelError::InvalidModel(format!("malformed tree dump: {}", reason))
+}
+
+fn number(node: &Value, key: &str) -> Result<f64, ModelError> {
+    node[key]
+        .as_f64()
+        .ok_or_else(|| malformed(&format!("node without numeric '{}': {}", key, node)))
+}
+
+fn node_id(node: &Value, key: &str) -> Result<u64, ModelError> {
+    node[key]
+        .as_u64()
+        .ok_or_else(|| malformed(&format!("node without '{}': {}", key, node)))
+}
+
+/// Appends `node` and its subtree to `nodes`, returning its index.
+fn xgboost_node(
+    node: &Value,
+    nodes: &mut Vec<Node>,
+    features: &mut FeatureIndex,
+) -> Result<usize, ModelError> {
+    let index = nodes.len();
+    if node.get("leaf").is_some() {
+        nodes.push(Node::Leaf(number(node, "leaf")?));
+        return Ok(index);
+    }
+    nodes.push(Node::Leaf(0.0));
+
+    let split = node["split"]
+        .as_str()
+        .ok_or_else(|| malformed(&format!("split node without 'split': {}", node)))?;
+    let yes = node_id(node, "yes")?;
+    let no = node_id(node, "no")?;
+    let missing = node["missing"].as_u64().unwrap_or(yes);
+
+    let mut children = HashMap::new();
+    for child in node["children"].as_array().into_iter().flatten() {
+        children.insert(
+            node_id(child, "nodeid")?,
+            xgboost_node(child, nodes, features)?,
+        );
+    }
+    let child = |id: u64| {
+        children
+            .get(&id)
+            .copied()
+            .ok_or_else(|| malformed(&format!("node {} has no child {}", node["nodeid"], id)))
+    };
+
+    nodes[index] = Node::Split {
+        feature: features.xgboost(split),
+        threshold: number(node, "split_condition")?,
+        inclusive: false,
+        left: child(yes)?,
+        right: child(no)?,
+        missing_left: missing == yes,
+    };
+    Ok(index)
+}
+
+/// Appends `node` and its subtree to `nodes`, returning its index.
+fn lightgbm_node(
+    node: &Value,
+    names: &[String],
+    nodes: &mut Vec<Node>,
+    features: &mut FeatureIndex,
+) -> Result<usize, ModelError> {
+    let index = nodes.len();
+    if node.get("leaf_value").is_some() {
+        nodes.push(Node::Leaf(number(node, "leaf_value")?));
+        return Ok(index);
+    }
+    nodes.push(Node::Leaf(0.0));
+
+    if node["decision_type"].as_str().unwrap_or("<=") != "<=" {
+        return Err(ModelError::InvalidModel(format!(
+            "unsupported LightGBM decision type {}",
+            node["decision_type"]
+        )));
+    }
+    let split = node_id(node, "split_feature")? as usize;
+    let name = names
+        .get(split)
+        .cloned()
+        .unwrap_or_else(|| format!("Column_{}", split));
+    let left = lightgbm_node(&node["left_child"], names, nodes, features)?;
+    let right = lightgbm_node(&node["right_child"], names, nodes, features)?;
+
+    nodes[index] = Node::Split {
+        feature: features.position(&name),
+        threshold: number(node, "threshold")?,
+        inclusive: true,
+        left,
+        right,
+        missing_left: node["default_left"].as_bool().unwrap_or(true),
+    };
+    Ok(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flywheel_ml_core::{FeatureValue, PredictionResult};
+    use serde_json::json;
+
+    fn model(endpoint: &str, dump: Value) -> TreeEnsembleModel {
+        let metadata = ModelMetadata::new("trees", ModelType::Custom)
+            .with_input_features(vec!["cpu".to_string(), "errors".to_string()]);
+        let endpoint = LocalEndpoint::parse(endpoint).unwrap();
+        TreeEnsembleModel::from_dump(metadata, &endpoint, &dump).unwrap()
+    }
+
+    fn features(cpu: Option<f64>, errors: f64) -> FeatureVector {
+        let features =
+            FeatureVector::new("rec-1").with_feature("errors", FeatureValue::Float(errors));
+        match cpu {
+            Some(cpu) => features.with_feature("cpu", FeatureValue::Float(cpu)),
+            None => features,
+        }
+    }
+
+    fn probability(prediction: &Prediction, class: &str) -> f64 {
+        match &prediction.result {
+            PredictionResult::Classification { probabilities, .. } => probabilities[class],
+            other => panic!("expected classification, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_xgboost_binary_dump() {
+        let dump = json!([
+            {"nodeid": 0, "split": "f0", "split_condition": 0.8, "yes": 1, "no": 2, "missing": 2,
+             "children": [
+                {"nodeid": 1, "leaf": -1.0},
+                {"nodeid": 2, "split": "f1", "split_condition": 5.0, "yes": 3, "no": 4,
+                 "missing": 3, "children": [
+                    {"nodeid": 3, "leaf": 0.5},
+                    {"nodeid": 4, "leaf": 2.0}
+                 ]}
+             ]},
+            {"nodeid": 0, "leaf": 0.25}
+        ]);
+        let model = model("trees://m.json?objective=binary:logistic", dump);
+
+        let low = model.predict(features(Some(0.2), 9.0)).await.unwrap();
+        let high = model.predict(features(Some(0.9), 9.0)).await.unwrap();
+        let missing = model.predict(features(None, 1.0)).await.unwrap();
+
+        let sigmoid = |x: f64| 1.0 / (1.0 + (-x).exp());
+        assert!((probability(&low, "1") - sigmoid(-0.75)).abs() < 1e-9);
+        assert!((probability(&high, "1") - sigmoid(2.25)).abs() < 1e-9);
+        assert!((probability(&missing, "1") - sigmoid(0.75)).abs() < 1e-9);
+        assert_eq!(high.predicted_class(), Some("1"));
+    }
+}
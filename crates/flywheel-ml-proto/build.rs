@@ -3,6 +3,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         "proto/inference.proto",
         "proto/control.proto",
         "proto/health.proto",
+        "proto/ingest.proto",
+        "proto/feedback.proto",
     ];
 
     for proto in &proto_files {
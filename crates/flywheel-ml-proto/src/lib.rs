@@ -1,3 +1,5 @@
 tonic::include_proto!("flywheel_ml.inference");
 tonic::include_proto!("flywheel_ml.control");
 tonic::include_proto!("flywheel_ml.health");
+tonic::include_proto!("flywheel_ml.ingest");
+tonic::include_proto!("flywheel_ml.feedback");
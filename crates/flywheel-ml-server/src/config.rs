@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Config {
     #[serde(default)]
     pub server: ServerConfig,
@@ -11,17 +11,12 @@ pub struct Config {
     pub conveyor: ConveyorConfig,
     #[serde(default)]
     pub storage: StorageConfig,
-}
-
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            server: ServerConfig::default(),
-            database: DatabaseConfig::default(),
-            conveyor: ConveyorConfig::default(),
-            storage: StorageConfig::default(),
-        }
-    }
+    #[serde(default)]
+    pub performance: PerformanceConfig,
+    #[serde(default)]
+    pub inference: InferenceConfig,
+    #[serde(default)]
+    pub promotion: PromotionConfig,
 }
 
 impl Config {
@@ -82,28 +77,115 @@ impl Default for DatabaseConfig {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ConveyorConfig {
     pub router_endpoint: Option<String>,
 }
 
-impl Default for ConveyorConfig {
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StorageConfig {
+    pub training_data_bucket: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PerformanceConfig {
+    /// Live accuracy is computed over the feedback joined in this window.
+    #[serde(default = "default_performance_window_secs")]
+    pub window_secs: u64,
+}
+
+fn default_performance_window_secs() -> u64 {
+    3600
+}
+
+impl Default for PerformanceConfig {
     fn default() -> Self {
         Self {
-            router_endpoint: None,
+            window_secs: default_performance_window_secs(),
         }
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct StorageConfig {
-    pub training_data_bucket: Option<String>,
+pub struct InferenceConfig {
+    /// Timeout of each call the InferenceService makes to a registered model.
+    #[serde(default = "default_inference_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+fn default_inference_timeout_ms() -> u64 {
+    1000
+}
+
+impl Default for InferenceConfig {
+    fn default() -> Self {
+        Self {
+            timeout_ms: default_inference_timeout_ms(),
+        }
+    }
+}
+
+/// When the promotion controller promotes a pending model version, or rolls
+/// it back, based on the shadow or canary traffic it served.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromotionConfig {
+    #[serde(default = "default_promotion_enabled")]
+    pub enabled: bool,
+    /// How often pending versions are evaluated.
+    #[serde(default = "default_promotion_interval_secs")]
+    pub interval_secs: u64,
+    /// Labeled examples a version needs before it is judged.
+    #[serde(default = "default_promotion_min_feedback")]
+    pub min_feedback: u64,
+    /// How far a version's accuracy may trail the active version's.
+    #[serde(default)]
+    pub max_accuracy_drop: f64,
+    #[serde(default = "default_promotion_max_latency_p99_ms")]
+    pub max_latency_p99_ms: u64,
+    #[serde(default = "default_promotion_max_error_rate")]
+    pub max_error_rate: f64,
+}
+
+fn default_promotion_enabled() -> bool {
+    true
+}
+
+fn default_promotion_interval_secs() -> u64 {
+    60
+}
+
+fn default_promotion_min_feedback() -> u64 {
+    100
+}
+
+fn default_promotion_max_latency_p99_ms() -> u64 {
+    500
+}
+
+fn default_promotion_max_error_rate() -> f64 {
+    0.05
+}
+
+impl PromotionConfig {
+    pub fn gates(&self) -> flywheel_ml_drift::PromotionGates {
+        flywheel_ml_drift::PromotionGates {
+            min_feedback: self.min_feedback,
+            max_accuracy_drop: self.max_accuracy_drop,
+            max_latency_p99_ms: self.max_latency_p99_ms,
+            max_error_rate: self.max_error_rate,
+        }
+    }
 }
 
-impl Default for StorageConfig {
+impl Default for PromotionConfig {
     fn default() -> Self {
         Self {
-            training_data_bucket: None,
+            enabled: default_promotion_enabled(),
+            interval_secs: default_promotion_interval_secs(),
+            min_feedback: default_promotion_min_feedback(),
+            max_accuracy_drop: 0.0,
+            max_latency_p99_ms: default_promotion_max_latency_p99_ms(),
+            max_error_rate: default_promotion_max_error_rate(),
         }
     }
 }
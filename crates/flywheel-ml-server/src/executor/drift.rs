@@ -0,0 +1,939 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use flywheel_ml_core::{FeatureValue, FeatureVector, JsonPath, LabeledExample, PredictionResult};
+use flywheel_ml_db::entity::{drift_action, drift_event};
+use flywheel_ml_db::{Database, DriftActionRepo, DriftEventRepo};
+use flywheel_ml_drift::{
+    BaselineLocation, ChangeDetection, ChangeTest, DriftConfig, DriftSeverity, DriftTest,
+    DriftType, FeatureChange, FeatureDriftResult, FeatureSetDriftDetector, FeatureTest,
+    OutputDriftDetector, PerformanceRegistry, PerformanceSnapshot, SequentialDriftDetector,
+};
+use flywheel_ml_dsl::{
+    DriftAction, DriftDetectionConfig, DriftMode, DriftStrategy, FlywheelStage, FlywheelStageType,
+    MlInferenceConfig,
+};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use super::model::ActiveModel;
+use super::record::PipelineRecord;
+use super::sink::{build_sink, Sink};
+use super::stage::StageContext;
+
+/// Joined feedback a model needs before its live accuracy can signal drift.
+const MIN_ACCURACY_EXAMPLES: u64 = 50;
+
+/// Tracks the monitored values with a `FeatureSetDriftDetector` and turns its
+/// verdicts into `drift_event` rows, running the stage's `on_drift` action
+/// when an event opens.
+///
+/// The reference is loaded from `baseline_uri` on the first batch. Without a
+/// baseline, or for values it does not cover, the first `window_size`
+/// observations of each value become its reference window.
+///
+/// With a sequential `strategy`, numeric values are instead fed one by one to
+/// a change-point test, and a confirmed change is checked right away. It
+/// counts as input drift until `check_interval_secs` pass without another.
+///
+/// The model's outputs are watched too: the predictions of records coming
+/// from inference, and both prediction and ground truth of the labeled
+/// examples a feedback-join stage emits for this model. With an
+/// `accuracy_drop` threshold, the model's live accuracy from joined feedback
+/// is checked against its registered accuracy as well.
+///
+/// An event left open by a previous run is picked up with the first batch,
+/// and a `fallback` model is served again until it resolves. It does not
+/// resolve before the values have been compared again.
+pub struct DriftMonitor {
+    stage_id: String,
+    pipeline_id: Uuid,
+    model_id: String,
+    baseline_uri: String,
+    mode: DriftMode,
+    check_interval: Duration,
+    accuracy_drop: Option<f64>,
+    performance: Arc<PerformanceRegistry>,
+    /// Monitored fields; empty means every feature on the record.
+    fields: Vec<(String, JsonPath)>,
+    actions: DriftActions,
+    state: Mutex<DriftState>,
+}
+
+/// Everything needed to carry out `on_drift`.
+struct DriftActions {
+    action: DriftAction,
+    notifier: Option<Box<dyn Sink>>,
+    retrain_target: Option<Box<dyn Sink>>,
+    /// The upstream inference stage's model, swapped by `fallback`.
+    model: Option<Arc<ActiveModel>>,
+    training_data_uri: Option<String>,
+}
+
+struct DriftState {
+    detector: FeatureSetDriftDetector,
+    sequential: Option<SequentialDriftDetector>,
+    /// The latest change confirmed in each value by `sequential`, and when
+    /// the last one was.
+    changes: Vec<FeatureChange>,
+    changed_at: Instant,
+    outputs: OutputDriftDetector,
+    last_check: Instant,
+    /// The unresolved event raised by this stage, if any.
+    open_event: Option<Uuid>,
+    initialized: bool,
+}
+
+impl DriftState {
+    /// Adds the monitored values of one record. Returns whether they confirmed
+    /// a change.
+    fn observe(&mut self, mut vector: FeatureVector) -> bool {
+        let Some(sequential) = &mut self.sequential else {
+            self.detector.observe(&vector);
+            return false;
+        };
+
+        let changes = sequential.observe(&vector);
+        // Categorical values have no mean to follow and stay with the window.
+        vector.features.retain(|_, value| {
+            matches!(value, FeatureValue::String(_) | FeatureValue::Categorical(_))
+        });
+        self.detector.observe(&vector);
+
+        if changes.is_empty() {
+            return false;
+        }
+        for change in changes {
+            self.changes.retain(|c| c.feature_name != change.feature_name);
+            self.changes.push(change);
+        }
+        self.changed_at = Instant::now();
+        true
+    }
+}
+
+/// What a drift stage does with a batch.
+pub struct DriftOutcome {
+    /// Records to forward downstream.
+    pub records: Vec<PipelineRecord>,
+    /// Records held back in blocking mode.
+    pub held: Vec<PipelineRecord>,
+    /// The open event the held records were held back for.
+    pub blocked_by: Option<Uuid>,
+}
+
+/// The outcome of a drift check.
+enum Verdict {
+    Drifted(DriftCheck),
+    Stable,
+    /// Nothing has had enough data to compare yet, as after a restart.
+    Unknown,
+}
+
+/// The combined verdict across all monitored values at one check.
+struct DriftCheck {
+    drift_type: DriftType,
+    severity: DriftSeverity,
+    psi_score: Option<f64>,
+    kl_divergence: Option<f64>,
+    accuracy_delta: Option<f64>,
+    drifted: Vec<String>,
+    /// Every changed value, then every compared feature, output and label,
+    /// each group most drifted first.
+    features: Vec<FeatureDriftResult>,
+}
+
+impl DriftMonitor {
+    pub fn new(
+        stage: &FlywheelStage,
+        config: &DriftDetectionConfig,
+        ctx: &StageContext,
+    ) -> anyhow::Result<Self> {
+        let fields = config
+            .features
+            .iter()
+            .map(|name| Ok((name.clone(), JsonPath::parse(name)?)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let upstream = upstream_inference(&ctx.spec.stages, &stage.id);
+        let model_id = config
+            .model_id
+            .clone()
+            .or_else(|| upstream.as_ref().map(|(_, c)| c.model_id.clone()))
+            .unwrap_or_else(|| stage.id.clone());
+
+        let actions = DriftActions {
+            action: config.on_drift.clone(),
+            notifier: config
+                .notify
+                .as_ref()
+                .map(build_sink)
+                .transpose()
+                .context("Invalid drift notify output")?,
+            retrain_target: config
+                .retrain_target
+                .as_ref()
+                .map(build_sink)
+                .transpose()
+                .context("Invalid drift retrain_target output")?,
+            model: upstream.and_then(|(id, _)| ctx.models.get(&id).cloned()),
+            training_data_uri: ctx
+                .spec
+                .training_export
+                .as_ref()
+                .map(|export| export.destination_uri.clone()),
+        };
+
+        let detector_config = DriftConfig {
+            psi_threshold: config.thresholds.psi,
+            kl_threshold: config.thresholds.kl_divergence,
+            window_size: config.window_size,
+            window_duration_secs: config.window_duration_secs,
+            check_interval_secs: config.check_interval_secs,
+            ..DriftConfig::default()
+        };
+
+        Ok(Self {
+            stage_id: stage.id.clone(),
+            pipeline_id: ctx.pipeline_id,
+            model_id,
+            baseline_uri: config.baseline_uri.clone(),
+            mode: config.mode.clone(),
+            check_interval: Duration::from_secs(config.check_interval_secs),
+            accuracy_drop: config.thresholds.accuracy_drop,
+            performance: ctx.performance.clone(),
+            fields,
+            actions,
+            state: Mutex::new(DriftState {
+                detector: FeatureSetDriftDetector::new(DriftConfig {
+                    feature_tests: feature_tests(config)?,
+                    ..detector_config.clone()
+                }),
+                sequential: change_detection(&config.strategy).map(SequentialDriftDetector::new),
+                changes: Vec::new(),
+                changed_at: Instant::now(),
+                outputs: OutputDriftDetector::new(detector_config),
+                last_check: Instant::now(),
+                open_event: None,
+                initialized: false,
+            }),
+        })
+    }
+
+    /// The model whose inputs and outputs this stage watches.
+    pub fn model_id(&self) -> &str {
+        &self.model_id
+    }
+
+    /// Feeds a batch through the detectors, runs a drift check when one is due
+    /// and splits the records into those to forward and those held back.
+    ///
+    /// In shadow mode every record is forwarded. In blocking mode records are
+    /// held back while a drift event is open; the stage parks them in the
+    /// dead-letter queue to be replayed once it resolves. Replayed records are
+    /// not observed again.
+    pub async fn process(&self, db: &Database, records: Vec<PipelineRecord>) -> DriftOutcome {
+        let mut state = self.state.lock().await;
+        let mut changed = false;
+
+        if !state.initialized {
+            self.load_baseline(&mut state.detector).await;
+            state.open_event = self.restore_open_event(db).await;
+            if state.open_event.is_some() {
+                self.resume_fallback();
+            }
+            state.initialized = true;
+        }
+
+        // Records replayed from this stage's dead letters were observed when parked.
+        for record in records.iter().filter(|r| r.replay_of.is_none()) {
+            if let Some(vector) = self.observed_vector(record) {
+                changed |= state.observe(vector);
+            }
+            if let Some(prediction) = &record.prediction {
+                state.outputs.observe_prediction(&prediction.result);
+            } else if let Some(example) = self.labeled_example(record) {
+                if let Ok(result) = serde_json::from_value::<PredictionResult>(example.prediction) {
+                    state.outputs.observe_prediction(&result);
+                }
+                state.outputs.observe_label(&example.ground_truth);
+            }
+        }
+
+        if !changed && state.changed_at.elapsed() >= self.check_interval {
+            state.changes.clear();
+        }
+
+        if changed || state.last_check.elapsed() >= self.check_interval {
+            state.last_check = Instant::now();
+            let performance = self
+                .accuracy_drop
+                .and_then(|_| self.performance.snapshot_models(&[&self.model_id]));
+            let verdict = check(&state, self.accuracy_drop, performance);
+            state.open_event = self.apply_check(db, state.open_event, verdict).await;
+        }
+
+        match (&self.mode, state.open_event) {
+            (DriftMode::Blocking, Some(event_id)) => {
+                tracing::debug!(
+                    stage_id = %self.stage_id,
+                    records = records.len(),
+                    "Drift detected, blocking records"
+                );
+                DriftOutcome {
+                    records: Vec::new(),
+                    held: records,
+                    blocked_by: Some(event_id),
+                }
+            }
+            _ => DriftOutcome {
+                records,
+                held: Vec::new(),
+                blocked_by: None,
+            },
+        }
+    }
+
+    /// The record as a labeled example of this stage's model, if it is one.
+    fn labeled_example(&self, record: &PipelineRecord) -> Option<LabeledExample> {
+        record.payload.get("ground_truth")?;
+        let example: LabeledExample = serde_json::from_value(record.payload.clone()).ok()?;
+        (example.model_id == self.model_id).then_some(example)
+    }
+
+    fn observed_vector(&self, record: &PipelineRecord) -> Option<FeatureVector> {
+        if self.fields.is_empty() {
+            return record.features.clone();
+        }
+
+        let mut vector = FeatureVector::new(record.id.to_string());
+        for (name, path) in &self.fields {
+            let value = match path.select(&record.payload) {
+                Some(serde_json::Value::Number(n)) => match n.as_f64() {
+                    Some(v) => FeatureValue::Float(v),
+                    None => continue,
+                },
+                Some(serde_json::Value::Bool(b)) => FeatureValue::Boolean(*b),
+                Some(serde_json::Value::String(s)) => FeatureValue::Categorical(s.clone()),
+                _ => continue,
+            };
+            vector.features.insert(name.clone(), value);
+        }
+        Some(vector)
+    }
+
+    /// Opens an event when drift starts and resolves it once drift subsides.
+    /// Returns the event that is open afterwards.
+    async fn apply_check(
+        &self,
+        db: &Database,
+        open_event: Option<Uuid>,
+        verdict: Verdict,
+    ) -> Option<Uuid> {
+        match (open_event, verdict) {
+            (None, Verdict::Drifted(check)) => {
+                tracing::warn!(
+                    pipeline_id = %self.pipeline_id,
+                    stage_id = %self.stage_id,
+                    model_id = %self.model_id,
+                    features = ?check.drifted,
+                    psi = ?check.psi_score,
+                    accuracy_delta = ?check.accuracy_delta,
+                    "Drift detected"
+                );
+
+                match DriftEventRepo::create(
+                    db.conn(),
+                    self.pipeline_id,
+                    self.model_id.clone(),
+                    db_drift_type(check.drift_type),
+                    db_severity(check.severity),
+                    check.psi_score,
+                    check.kl_divergence,
+                    check.accuracy_delta,
+                    serde_json::to_value(&check.features).ok(),
+                )
+                .await
+                {
+                    Ok(event) => {
+                        self.on_drift(db, event.id, &check).await;
+                        Some(event.id)
+                    }
+                    Err(e) => {
+                        tracing::error!(stage_id = %self.stage_id, error = %e, "Failed to record drift event");
+                        None
+                    }
+                }
+            }
+            (Some(id), Verdict::Stable) => {
+                tracing::info!(
+                    pipeline_id = %self.pipeline_id,
+                    stage_id = %self.stage_id,
+                    event_id = %id,
+                    "Drift resolved"
+                );
+
+                match DriftEventRepo::resolve(db.conn(), id).await {
+                    Ok(_) => {
+                        self.on_resolve(db, id).await;
+                        None
+                    }
+                    Err(e) => {
+                        tracing::error!(stage_id = %self.stage_id, error = %e, "Failed to resolve drift event");
+                        Some(id)
+                    }
+                }
+            }
+            (open_event, _) => open_event,
+        }
+    }
+
+    /// Runs the configured `on_drift` action for a newly opened event.
+    async fn on_drift(&self, db: &Database, event_id: Uuid, check: &DriftCheck) {
+        let event = serde_json::json!({
+            "event_id": event_id,
+            "pipeline_id": self.pipeline_id,
+            "stage_id": self.stage_id,
+            "model_id": self.model_id,
+            "drift_type": format!("{:?}", check.drift_type).to_lowercase(),
+            "severity": format!("{:?}", check.severity).to_lowercase(),
+            "psi_score": check.psi_score,
+            "kl_divergence": check.kl_divergence,
+            "accuracy_delta": check.accuracy_delta,
+            "features": check.drifted,
+        });
+
+        match &self.actions.action {
+            DriftAction::Alert => {
+                let outcome = match &self.actions.notifier {
+                    Some(notifier) => notifier
+                        .deliver(&[PipelineRecord::new(event.clone())])
+                        .await
+                        .map(|()| drift_action::DriftActionStatus::Succeeded),
+                    None => Ok(drift_action::DriftActionStatus::Succeeded),
+                };
+                self.record_action(
+                    db,
+                    event_id,
+                    drift_action::DriftActionKind::Alert,
+                    event,
+                    outcome,
+                )
+                .await;
+            }
+            DriftAction::Retrain => {
+                let mut request = event;
+                request["training_data_uri"] = self.actions.training_data_uri.clone().into();
+
+                // Without a target the pending row is the request; a trainer
+                // polls for it.
+                let outcome = match &self.actions.retrain_target {
+                    Some(target) => target
+                        .deliver(&[PipelineRecord::new(request.clone())])
+                        .await
+                        .map(|()| drift_action::DriftActionStatus::Succeeded),
+                    None => Ok(drift_action::DriftActionStatus::Pending),
+                };
+                self.record_action(
+                    db,
+                    event_id,
+                    drift_action::DriftActionKind::Retrain,
+                    request,
+                    outcome,
+                )
+                .await;
+            }
+            DriftAction::Fallback { to_model, .. } => {
+                let outcome = match &self.actions.model {
+                    Some(model) => model.swap_to(to_model).map(|previous| {
+                        tracing::warn!(
+                            stage_id = %self.stage_id,
+                            from_model = %previous,
+                            to_model = %to_model,
+                            "Falling back to another model"
+                        );
+                        serde_json::json!({ "from_model": previous, "to_model": to_model })
+                    }),
+                    None => Err(anyhow::anyhow!(
+                        "No upstream ml-inference stage to fall back"
+                    )),
+                };
+                let (details, outcome) = match outcome {
+                    Ok(details) => (details, Ok(drift_action::DriftActionStatus::Succeeded)),
+                    Err(e) => (serde_json::json!({ "to_model": to_model }), Err(e)),
+                };
+                self.record_action(
+                    db,
+                    event_id,
+                    drift_action::DriftActionKind::Fallback,
+                    details,
+                    outcome,
+                )
+                .await;
+            }
+        }
+    }
+
+    /// Serves the fallback model again for an event a previous run left open.
+    /// The swap was recorded as an action when the event opened.
+    fn resume_fallback(&self) {
+        let DriftAction::Fallback { to_model, .. } = &self.actions.action else {
+            return;
+        };
+        let Some(model) = &self.actions.model else {
+            return;
+        };
+        match model.swap_to(to_model) {
+            Ok(_) => tracing::warn!(
+                stage_id = %self.stage_id,
+                to_model = %to_model,
+                "Drift event still open, serving fallback model"
+            ),
+            Err(e) => tracing::error!(
+                stage_id = %self.stage_id,
+                to_model = %to_model,
+                error = %e,
+                "Failed to resume fallback model"
+            ),
+        }
+    }
+
+    /// Switches a fallen-back inference stage to its configured model once the
+    /// event resolves, unless `restore_on_resolve` is off.
+    async fn on_resolve(&self, db: &Database, event_id: Uuid) {
+        let DriftAction::Fallback {
+            restore_on_resolve: true,
+            ..
+        } = &self.actions.action
+        else {
+            return;
+        };
+        let Some(model) = &self.actions.model else {
+            return;
+        };
+        if !model.is_swapped() {
+            return;
+        }
+
+        let to_model = &model.config().model_id;
+        let previous = model.restore();
+        tracing::info!(
+            stage_id = %self.stage_id,
+            from_model = %previous,
+            to_model = %to_model,
+            "Restoring model after drift resolved"
+        );
+        self.record_action(
+            db,
+            event_id,
+            drift_action::DriftActionKind::Restore,
+            serde_json::json!({ "from_model": previous, "to_model": to_model }),
+            Ok(drift_action::DriftActionStatus::Succeeded),
+        )
+        .await;
+    }
+
+    async fn record_action(
+        &self,
+        db: &Database,
+        event_id: Uuid,
+        action: drift_action::DriftActionKind,
+        details: serde_json::Value,
+        outcome: anyhow::Result<drift_action::DriftActionStatus>,
+    ) {
+        let (status, error) = match outcome {
+            Ok(status) => (status, None),
+            Err(e) => {
+                tracing::error!(
+                    stage_id = %self.stage_id,
+                    action = ?action,
+                    error = %e,
+                    "Drift action failed"
+                );
+                (
+                    drift_action::DriftActionStatus::Failed,
+                    Some(format!("{:#}", e)),
+                )
+            }
+        };
+
+        if let Err(e) = DriftActionRepo::create(
+            db.conn(),
+            event_id,
+            self.pipeline_id,
+            action,
+            status,
+            details,
+            error,
+        )
+        .await
+        {
+            tracing::error!(stage_id = %self.stage_id, error = %e, "Failed to record drift action");
+        }
+    }
+
+    /// Loads the reference distributions from `baseline_uri`, falling back to
+    /// warm-up windows when it cannot be read.
+    async fn load_baseline(&self, detector: &mut FeatureSetDriftDetector) {
+        if self.baseline_uri.is_empty() {
+            return;
+        }
+
+        let baseline = match BaselineLocation::parse(&self.baseline_uri) {
+            Ok(location) => location.load().await,
+            Err(e) => Err(e),
+        };
+        match baseline {
+            Ok(baseline) => {
+                tracing::info!(
+                    stage_id = %self.stage_id,
+                    baseline_uri = %self.baseline_uri,
+                    features = baseline.features.len(),
+                    "Loaded drift baseline"
+                );
+                detector.set_baseline(&baseline);
+            }
+            Err(e) => {
+                tracing::warn!(
+                    stage_id = %self.stage_id,
+                    baseline_uri = %self.baseline_uri,
+                    error = %e,
+                    "Could not load drift baseline, using warm-up reference"
+                );
+            }
+        }
+    }
+
+    /// Picks up an event left unresolved by a previous run of this pipeline.
+    async fn restore_open_event(&self, db: &Database) -> Option<Uuid> {
+        match DriftEventRepo::find_open(db.conn(), self.pipeline_id, &self.model_id).await {
+            Ok(event) => event.map(|e| e.id),
+            Err(e) => {
+                tracing::debug!(stage_id = %self.stage_id, error = %e, "Could not load open drift events");
+                None
+            }
+        }
+    }
+}
+
+/// Runs a drift check. It is stable while no monitored value, output or label
+/// has drifted and accuracy has not dropped by more than `accuracy_drop`.
+fn check(
+    state: &DriftState,
+    accuracy_drop: Option<f64>,
+    performance: Option<PerformanceSnapshot>,
+) -> Verdict {
+    let result = state.detector.check_drift();
+    let inputs_drifted = result.is_drifted || !state.changes.is_empty();
+    let outputs = state.outputs.check_drift();
+    let accuracy_delta = performance
+        .filter(|p| p.examples >= MIN_ACCURACY_EXAMPLES)
+        .and_then(|p| p.accuracy_delta());
+    let dropped = match (accuracy_delta, accuracy_drop) {
+        (Some(delta), Some(drop)) if delta > drop => Some(delta),
+        _ => None,
+    };
+
+    let Some(drift_type) = DriftType::from_signals(
+        inputs_drifted,
+        outputs.prediction.is_drifted,
+        outputs.labels_checked().then_some(outputs.label.is_drifted),
+        dropped.is_some(),
+    ) else {
+        let compared = !result.feature_drifts.is_empty()
+            || !outputs.prediction.feature_drifts.is_empty()
+            || outputs.labels_checked()
+            || accuracy_delta.is_some();
+        return if compared { Verdict::Stable } else { Verdict::Unknown };
+    };
+
+    let severity = [&result, &outputs.prediction, &outputs.label]
+        .into_iter()
+        .filter(|r| r.is_drifted)
+        .map(|r| DriftSeverity::from_psi(r.psi_score))
+        .chain(state.changes.iter().map(|c| c.result.severity))
+        .chain(dropped.map(DriftSeverity::from_accuracy_delta))
+        .max()
+        .unwrap_or(DriftSeverity::None);
+
+    let features: Vec<FeatureDriftResult> = state
+        .changes
+        .iter()
+        .map(FeatureChange::feature_drift)
+        .chain(
+            result
+                .ranked_features()
+                .into_iter()
+                .chain(outputs.ranked_outputs())
+                .cloned(),
+        )
+        .collect();
+    Verdict::Drifted(DriftCheck {
+        drift_type,
+        severity,
+        psi_score: Some(result.psi_score),
+        kl_divergence: Some(result.kl_divergence),
+        accuracy_delta,
+        drifted: features
+            .iter()
+            .filter(|f| f.is_drifted)
+            .map(|f| f.feature_name.clone())
+            .collect(),
+        features,
+    })
+}
+
+/// Id and config of the closest ml-inference stage before `stage_id`.
+fn upstream_inference(
+    stages: &[FlywheelStage],
+    stage_id: &str,
+) -> Option<(String, MlInferenceConfig)> {
+    stages
+        .iter()
+        .take_while(|stage| stage.id != stage_id)
+        .filter(|stage| stage.stage_type == FlywheelStageType::MlInference)
+        .filter_map(|stage| {
+            let config = serde_json::from_value::<MlInferenceConfig>(stage.config.clone()).ok()?;
+            Some((stage.id.clone(), config))
+        })
+        .last()
+}
+
+fn change_detection(strategy: &DriftStrategy) -> Option<ChangeDetection> {
+    let (test, warm_up) = match *strategy {
+        DriftStrategy::Window => return None,
+        DriftStrategy::PageHinkley {
+            delta,
+            threshold,
+            warm_up,
+        } => (ChangeTest::PageHinkley { delta, threshold }, warm_up),
+        DriftStrategy::Cusum {
+            slack,
+            threshold,
+            warm_up,
+        } => (ChangeTest::Cusum { slack, threshold }, warm_up),
+        DriftStrategy::Adwin { delta, warm_up } => (ChangeTest::Adwin { delta }, warm_up),
+    };
+    Some(ChangeDetection { test, warm_up })
+}
+
+fn feature_tests(config: &DriftDetectionConfig) -> anyhow::Result<HashMap<String, FeatureTest>> {
+    config
+        .thresholds
+        .features
+        .iter()
+        .map(|(name, feature)| {
+            let threshold = feature
+                .threshold_or_default(&config.thresholds)
+                .with_context(|| format!("Missing drift threshold for feature '{}'", name))?;
+            let test = match feature.test {
+                flywheel_ml_dsl::DriftTest::Psi => DriftTest::Psi,
+                flywheel_ml_dsl::DriftTest::KlDivergence => DriftTest::KlDivergence,
+                flywheel_ml_dsl::DriftTest::Ks => DriftTest::Ks,
+                flywheel_ml_dsl::DriftTest::Wasserstein => DriftTest::Wasserstein,
+                flywheel_ml_dsl::DriftTest::JensenShannon => DriftTest::JensenShannon,
+                flywheel_ml_dsl::DriftTest::ChiSquared => DriftTest::ChiSquared,
+            };
+            Ok((name.clone(), FeatureTest { test, threshold }))
+        })
+        .collect()
+}
+
+fn db_drift_type(drift_type: DriftType) -> drift_event::DriftType {
+    match drift_type {
+        DriftType::Statistical => drift_event::DriftType::Statistical,
+        DriftType::Performance => drift_event::DriftType::Performance,
+        DriftType::Both => drift_event::DriftType::Both,
+        DriftType::Prediction => drift_event::DriftType::Prediction,
+        DriftType::Label => drift_event::DriftType::Label,
+        DriftType::Concept => drift_event::DriftType::Concept,
+    }
+}
+
+fn db_severity(severity: DriftSeverity) -> drift_event::DriftSeverity {
+    match severity {
+        // Performance-only drift can carry a PSI below the lowest band.
+        DriftSeverity::None | DriftSeverity::Low => drift_event::DriftSeverity::Low,
+        DriftSeverity::Medium => drift_event::DriftSeverity::Medium,
+        DriftSeverity::High => drift_event::DriftSeverity::High,
+        DriftSeverity::Critical => drift_event::DriftSeverity::Critical,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flywheel_ml_db::entity::drift_action::{DriftActionKind, DriftActionStatus};
+    use flywheel_ml_db::entity::pipeline;
+    use serde_json::json;
+
+    /// A pipeline whose drift stage watches `cpu` behind a `primary` model,
+    /// comparing every batch against the first 20 values.
+    fn spec(on_drift: &str) -> String {
+        format!(
+            r#"
+apiVersion: flywheel-ml.io/v1
+kind: FlywheelPipeline
+metadata:
+  name: drift-test
+  namespace: default
+spec:
+  source: test-input
+  stages:
+    - id: inference
+      type: ml-inference
+      config:
+        model_endpoint: builtin://zscore
+        model_id: primary
+        input_features: [cpu]
+        output_field: score
+    - id: drift
+      type: drift-detection
+      config:
+        baseline_uri: ""
+        window_size: 20
+        check_interval_secs: 0
+        features: [cpu]
+        thresholds:
+          psi: 0.25
+          kl_divergence: 0.1
+{on_drift}
+  sinks:
+    - name: out
+      all: true
+"#
+        )
+    }
+
+    struct Fixture {
+        db: Database,
+        pipeline: pipeline::Model,
+        model: Arc<ActiveModel>,
+        monitor: DriftMonitor,
+    }
+
+    impl Fixture {
+        async fn new(on_drift: &str) -> Self {
+            let db = crate::testing::database().await;
+            let pipeline = crate::testing::pipeline(&db, &spec(on_drift)).await;
+            Self::restart(db, pipeline)
+        }
+
+        /// Builds the stage afresh, as a new run of the pipeline does.
+        fn restart(db: Database, pipeline: pipeline::Model) -> Self {
+            let ctx = StageContext::for_pipeline(&pipeline, db.clone());
+            let stage = &ctx.spec.stages[1];
+            let config: DriftDetectionConfig =
+                serde_json::from_value(stage.config.clone()).unwrap();
+            let monitor = DriftMonitor::new(stage, &config, &ctx).unwrap();
+            Self {
+                model: ctx.models["inference"].clone(),
+                db,
+                pipeline,
+                monitor,
+            }
+        }
+
+        /// Feeds 20 records with `cpu` from `offset` on.
+        async fn feed(&self, offset: f64) -> DriftOutcome {
+            let records = (0..20)
+                .map(|i| PipelineRecord::new(json!({"cpu": offset + f64::from(i)})))
+                .collect();
+            self.monitor.process(&self.db, records).await
+        }
+
+        async fn open_event(&self) -> Option<drift_event::Model> {
+            DriftEventRepo::find_open(self.db.conn(), self.pipeline.id, "primary")
+                .await
+                .unwrap()
+        }
+
+        async fn actions(&self, event_id: Uuid) -> Vec<(DriftActionKind, DriftActionStatus)> {
+            DriftActionRepo::list_by_event(self.db.conn(), event_id)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|action| (action.action, action.status))
+                .collect()
+        }
+    }
+
+    const FALLBACK: &str = "        on_drift:
+          fallback:
+            to_model: backup";
+
+    #[tokio::test]
+    async fn test_fallback_is_served_while_drift_is_open() {
+        let fixture = Fixture::new(FALLBACK).await;
+        let primary = fixture.model.current();
+        fixture.feed(0.0).await;
+        assert!(fixture.open_event().await.is_none());
+
+        fixture.feed(100.0).await;
+        let event = fixture.open_event().await.expect("drift event is open");
+        assert_eq!(event.pipeline_id, fixture.pipeline.id);
+        assert_eq!(fixture.model.current().model_id(), "backup");
+        let actions = DriftActionRepo::list_by_event(fixture.db.conn(), event.id).await.unwrap();
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].action, DriftActionKind::Fallback);
+        assert_eq!(actions[0].status, DriftActionStatus::Succeeded);
+        assert_eq!(
+            actions[0].details_json,
+            json!({"from_model": "primary", "to_model": "backup"})
+        );
+
+        fixture.feed(0.0).await;
+        assert!(fixture.open_event().await.is_none());
+        // The configured model comes back as it was, not rebuilt.
+        assert!(Arc::ptr_eq(&fixture.model.current(), &primary));
+        assert_eq!(
+            fixture.actions(event.id).await,
+            vec![
+                (DriftActionKind::Fallback, DriftActionStatus::Succeeded),
+                (DriftActionKind::Restore, DriftActionStatus::Succeeded),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retrain_without_target_is_left_pending() {
+        let fixture = Fixture::new("        on_drift: retrain").await;
+        fixture.feed(0.0).await;
+        fixture.feed(100.0).await;
+
+        let event = fixture.open_event().await.expect("drift event is open");
+        assert_eq!(
+            fixture.actions(event.id).await,
+            vec![(DriftActionKind::Retrain, DriftActionStatus::Pending)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_open_event_survives_restart() {
+        let fixture = Fixture::new(FALLBACK).await;
+        fixture.feed(0.0).await;
+        fixture.feed(100.0).await;
+        let event = fixture.open_event().await.expect("drift event is open");
+
+        let fixture = Fixture::restart(fixture.db, fixture.pipeline);
+        assert_eq!(fixture.model.current().model_id(), "primary");
+
+        // The first batch rebuilds the reference, so nothing is compared yet.
+        fixture.feed(0.0).await;
+        assert_eq!(fixture.open_event().await.map(|e| e.id), Some(event.id));
+        assert_eq!(fixture.model.current().model_id(), "backup");
+
+        fixture.feed(0.0).await;
+        assert!(fixture.open_event().await.is_none());
+        assert_eq!(fixture.model.current().model_id(), "primary");
+        assert_eq!(
+            fixture.actions(event.id).await,
+            vec![
+                (DriftActionKind::Fallback, DriftActionStatus::Succeeded),
+                (DriftActionKind::Restore, DriftActionStatus::Succeeded),
+            ]
+        );
+    }
+}
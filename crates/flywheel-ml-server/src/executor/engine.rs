@@ -3,14 +3,17 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use flywheel_ml_db::{entity::pipeline::PipelineStatus, Database, PipelineRepo};
+use flywheel_ml_drift::PerformanceRegistry;
 use tokio::sync::RwLock;
 use tokio::task::JoinHandle;
 use uuid::Uuid;
 
-use super::PipelineRunner;
+use super::{BanditRegistry, PipelineRunner};
 
 pub struct ExecutionEngine {
     db: Database,
+    performance: Arc<PerformanceRegistry>,
+    bandits: Arc<BanditRegistry>,
     runners: Arc<RwLock<HashMap<Uuid, RunnerHandle>>>,
     poll_interval: Duration,
 }
@@ -21,14 +24,21 @@ struct RunnerHandle {
 }
 
 impl ExecutionEngine {
-    pub fn new(db: Database) -> Self {
+    pub fn new(
+        db: Database,
+        performance: Arc<PerformanceRegistry>,
+        bandits: Arc<BanditRegistry>,
+    ) -> Self {
         Self {
             db,
+            performance,
+            bandits,
             runners: Arc::new(RwLock::new(HashMap::new())),
             poll_interval: Duration::from_secs(5),
         }
     }
 
+    #[allow(dead_code)]
     pub fn with_poll_interval(mut self, interval: Duration) -> Self {
         self.poll_interval = interval;
         self
@@ -70,14 +80,20 @@ impl ExecutionEngine {
         }
 
         for pipeline in running_pipelines {
-            if !runners.contains_key(&pipeline.id) {
+            if let std::collections::hash_map::Entry::Vacant(slot) = runners.entry(pipeline.id) {
                 tracing::info!(
                     pipeline_id = %pipeline.id,
                     name = %pipeline.name,
                     "Starting pipeline runner"
                 );
 
-                match PipelineRunner::new(pipeline.clone(), self.db.clone()) {
+                let runner = PipelineRunner::new(
+                    pipeline.clone(),
+                    self.db.clone(),
+                    self.performance.clone(),
+                    self.bandits.clone(),
+                );
+                match runner {
                     Ok(runner) => {
                         let runner = Arc::new(runner);
                         let runner_clone = runner.clone();
@@ -85,7 +101,7 @@ impl ExecutionEngine {
                             runner_clone.run().await;
                         });
 
-                        runners.insert(pipeline.id, RunnerHandle { runner, task });
+                        slot.insert(RunnerHandle { runner, task });
                     }
                     Err(e) => {
                         tracing::error!(
@@ -109,6 +125,7 @@ impl ExecutionEngine {
         Ok(())
     }
 
+    #[allow(dead_code)]
     pub async fn active_count(&self) -> usize {
         self.runners.read().await.len()
     }
@@ -0,0 +1,233 @@
+use std::sync::Arc;
+
+use flywheel_ml_core::{FeedbackRecord, FeedbackSource, GroundTruth, JsonPath, LabeledExample};
+use flywheel_ml_db::{Database, ModelVersionRepo};
+use flywheel_ml_drift::PerformanceRegistry;
+use flywheel_ml_dsl::{FlywheelStage, ImplicitLabelSpec};
+use flywheel_ml_transform::feedback_transform::FeedbackJoinTransform;
+use uuid::Uuid;
+
+use super::model::BanditRegistry;
+use super::record::PipelineRecord;
+use super::stage::StageContext;
+
+/// Joins feedback events with the predictions they refer to and records the
+/// outcomes in the pipeline's `PerformanceRegistry`.
+///
+/// A feedback record names its prediction through the `join_key` of the
+/// pipeline's `feedback` spec (`$.prediction_id` without one). Its ground
+/// truth is either an explicit `ground_truth` field or an `event` matched
+/// against the spec's implicit `labels`. Joined records continue downstream
+/// as `LabeledExample`s; records carrying no feedback pass through untouched.
+/// Each joined outcome also rewards the bandit arm that served the
+/// prediction, when the pipeline has one.
+pub struct FeedbackJoin {
+    stage_id: String,
+    join_key: JsonPath,
+    labels: Vec<ImplicitLabelSpec>,
+    transform: FeedbackJoinTransform,
+    pipeline_id: Uuid,
+    outcomes: OutcomeRecorder,
+}
+
+/// What became of one batch.
+pub struct FeedbackOutcome {
+    pub records: Vec<PipelineRecord>,
+    pub failed: u64,
+}
+
+impl FeedbackJoin {
+    pub fn new(stage: &FlywheelStage, ctx: &StageContext) -> anyhow::Result<Self> {
+        let (join_key, labels, max_delay_hours) = match &ctx.spec.feedback {
+            Some(feedback) => (
+                feedback.join_key.as_str(),
+                feedback.labels.clone(),
+                feedback.max_delay_hours,
+            ),
+            None => ("$.prediction_id", Vec::new(), 24),
+        };
+
+        Ok(Self {
+            stage_id: stage.id.clone(),
+            join_key: JsonPath::parse(join_key)?,
+            labels,
+            transform: FeedbackJoinTransform::new(Arc::new(ctx.db.conn().clone()))
+                .with_max_delay((max_delay_hours * 3600) as i64),
+            pipeline_id: ctx.pipeline_id,
+            outcomes: OutcomeRecorder::new(
+                ctx.db.clone(),
+                ctx.performance.clone(),
+                ctx.bandits.clone(),
+            ),
+        })
+    }
+
+    pub async fn process(&self, records: Vec<PipelineRecord>) -> FeedbackOutcome {
+        let mut outcome = FeedbackOutcome {
+            records: Vec::with_capacity(records.len()),
+            failed: 0,
+        };
+
+        for record in records {
+            let Some(feedback) = self.feedback(&record) else {
+                outcome.records.push(record);
+                continue;
+            };
+
+            match self.transform.process(feedback.clone()).await {
+                Ok(Some(labeled)) => {
+                    self.outcomes
+                        .record(self.pipeline_id, &labeled, &self.transform, &feedback)
+                        .await;
+                    match serde_json::to_value(&labeled) {
+                        Ok(payload) => outcome.records.push(PipelineRecord {
+                            id: labeled.example_id.clone(),
+                            payload,
+                            features: None,
+                            prediction: None,
+                            replay_of: None,
+                        }),
+                        Err(e) => {
+                            outcome.failed += 1;
+                            tracing::warn!(stage_id = %self.stage_id, error = %e, "Failed to encode labeled example");
+                        }
+                    }
+                }
+                // Expired or too late to count.
+                Ok(None) => {}
+                Err(e) => {
+                    outcome.failed += 1;
+                    tracing::debug!(
+                        stage_id = %self.stage_id,
+                        record_id = %record.id,
+                        error = %e,
+                        "Feedback join failed for record"
+                    );
+                }
+            }
+        }
+
+        outcome
+    }
+
+    /// The feedback carried by `record`, if it names a prediction and a
+    /// ground truth.
+    fn feedback(&self, record: &PipelineRecord) -> Option<FeedbackRecord> {
+        let prediction_id = match self.join_key.select(&record.payload)? {
+            serde_json::Value::String(id) => id.clone(),
+            _ => return None,
+        };
+        let model_id = record
+            .payload
+            .get("model_id")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default();
+
+        let (ground_truth, source) = match record.payload.get("ground_truth") {
+            Some(value) => (
+                ground_truth(value)?,
+                FeedbackSource::automated(&self.stage_id, 1.0),
+            ),
+            None => {
+                let event = record.payload.get("event")?.as_str()?;
+                let rule = self.labels.iter().find(|l| l.event == event)?;
+                (
+                    GroundTruth::label(&rule.label),
+                    FeedbackSource::automated(event, rule.confidence),
+                )
+            }
+        };
+
+        Some(
+            FeedbackRecord::new(prediction_id, model_id, ground_truth, source)
+                .with_metadata("record_id", &record.id),
+        )
+    }
+}
+
+/// Counts joined feedback toward live model performance, tracked against
+/// the accuracy each model version was registered with, and rewards the
+/// bandit arm that served the prediction. Feedback joined in a
+/// `feedback-join` stage and feedback submitted to the server both go
+/// through it, so an outcome counts the same wherever it arrived.
+pub struct OutcomeRecorder {
+    db: Database,
+    performance: Arc<PerformanceRegistry>,
+    bandits: Arc<BanditRegistry>,
+}
+
+impl OutcomeRecorder {
+    pub fn new(
+        db: Database,
+        performance: Arc<PerformanceRegistry>,
+        bandits: Arc<BanditRegistry>,
+    ) -> Self {
+        Self {
+            db,
+            performance,
+            bandits,
+        }
+    }
+
+    /// Records the outcome of a prediction `pipeline_id` served, and scores
+    /// the shadow predictions made for the same record against the same
+    /// ground truth. Shadow outcomes only feed performance tracking.
+    pub async fn record(
+        &self,
+        pipeline_id: Uuid,
+        labeled: &LabeledExample,
+        join: &FeedbackJoinTransform,
+        feedback: &FeedbackRecord,
+    ) {
+        self.count(labeled).await;
+        self.bandits.reward(pipeline_id, labeled);
+        match join.process_shadows(feedback).await {
+            Ok(shadows) => {
+                for shadow in &shadows {
+                    self.count(shadow).await;
+                }
+            }
+            Err(e) => {
+                tracing::debug!(
+                    prediction_id = %feedback.prediction_id,
+                    error = %e,
+                    "Failed to join feedback with shadow predictions"
+                );
+            }
+        }
+    }
+
+    async fn count(&self, labeled: &LabeledExample) {
+        if !self
+            .performance
+            .contains(&labeled.model_id, &labeled.model_version)
+        {
+            let accuracy = match ModelVersionRepo::find_by_version(
+                self.db.conn(),
+                &labeled.model_id,
+                &labeled.model_version,
+            )
+            .await
+            {
+                Ok(version) => version.and_then(|v| v.accuracy),
+                Err(e) => {
+                    tracing::debug!(error = %e, "Could not load model version accuracy");
+                    None
+                }
+            };
+            self.performance
+                .seed(&labeled.model_id, &labeled.model_version, accuracy);
+        }
+        self.performance.record(labeled);
+    }
+}
+
+/// Accepts a tagged `GroundTruth` or a bare boolean, number or label.
+fn ground_truth(value: &serde_json::Value) -> Option<GroundTruth> {
+    match value {
+        serde_json::Value::Bool(b) => Some(GroundTruth::Binary(*b)),
+        serde_json::Value::Number(n) => n.as_f64().map(GroundTruth::Value),
+        serde_json::Value::String(s) => Some(GroundTruth::label(s)),
+        value => serde_json::from_value(value.clone()).ok(),
+    }
+}
@@ -1,6 +1,15 @@
+mod drift;
 mod engine;
+mod feedback;
+mod model;
+mod record;
 mod runner;
+mod sink;
+mod source;
 mod stage;
 
 pub use engine::ExecutionEngine;
+pub use model::BanditRegistry;
 pub use runner::PipelineRunner;
+pub(crate) use feedback::OutcomeRecorder;
+pub(crate) use stage::prediction_payload;
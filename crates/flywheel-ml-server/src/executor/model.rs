@@ -0,0 +1,342 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use anyhow::Context;
+use flywheel_ml_core::{JsonPath, LabeledExample, ModelMetadata, ModelType, Prediction};
+use flywheel_ml_dsl::{BanditSpec, CanarySpec, MlInferenceConfig};
+use flywheel_ml_inference::bandit::BanditAllocator;
+use flywheel_ml_inference::batch::BatchConfig;
+use flywheel_ml_inference::circuit_breaker::{CircuitBreaker, CircuitState};
+use flywheel_ml_transform::fallback::InferenceFallback;
+use flywheel_ml_transform::InferenceTransform;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use super::record::PipelineRecord;
+
+/// The model an ml-inference stage currently sends features to.
+///
+/// Drift fallbacks swap the active model while records keep flowing; a batch
+/// already in flight finishes against the model it started with. The stage's
+/// inference fallback, and the predictions it remembers, outlive swaps. The
+/// shadow, canary and bandit models are never swapped.
+///
+/// The configured model is kept while another serves in its place, so a
+/// restore brings back its circuit breaker and any state a local model
+/// learned rather than starting it afresh.
+pub struct ActiveModel {
+    config: MlInferenceConfig,
+    primary: Arc<InferenceTransform>,
+    current: RwLock<Arc<InferenceTransform>>,
+    fallback: Arc<InferenceFallback>,
+    shadow: Option<Arc<InferenceTransform>>,
+    canary: Option<Canary>,
+    bandit: Option<Bandit>,
+    /// The version each model last reported, which failed calls are
+    /// attributed to.
+    versions: Mutex<HashMap<String, String>>,
+}
+
+/// How a prediction was served, as stored with it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    Primary,
+    Canary,
+    Bandit,
+    Shadow,
+}
+
+impl Variant {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Variant::Primary => "primary",
+            Variant::Canary => "canary",
+            Variant::Bandit => "bandit",
+            Variant::Shadow => "shadow",
+        }
+    }
+}
+
+/// A model serving a share of the stage's records in place of its own.
+pub struct Canary {
+    transform: Arc<InferenceTransform>,
+    percent: f64,
+    sticky_key: Option<JsonPath>,
+}
+
+impl Canary {
+    fn from_spec(config: &MlInferenceConfig, spec: &CanarySpec) -> anyhow::Result<Self> {
+        let endpoint = spec.model_endpoint.as_deref().unwrap_or(&config.model_endpoint);
+        Ok(Self {
+            transform: build_transform(config, &spec.model_id, endpoint)?,
+            percent: spec.percent,
+            sticky_key: spec.sticky_key.as_deref().map(JsonPath::parse).transpose()?,
+        })
+    }
+
+    pub fn transform(&self) -> Arc<InferenceTransform> {
+        self.transform.clone()
+    }
+
+    /// Whether the canary serves `record`. Records are bucketed by a hash of
+    /// their sticky key, so a key always gets the same model for as long as
+    /// `percent` is unchanged.
+    pub fn serves(&self, record: &PipelineRecord) -> bool {
+        let key = match self.sticky_key.as_ref().and_then(|k| k.select(&record.payload)) {
+            Some(serde_json::Value::String(key)) => key.clone(),
+            Some(key) => key.to_string(),
+            None => record.id.clone(),
+        };
+        let digest = Sha256::digest(key.as_bytes());
+        let bucket = u64::from_be_bytes(digest[..8].try_into().unwrap()) % 10_000;
+        (bucket as f64) < self.percent * 100.0
+    }
+}
+
+/// Models competing with the stage's model for its records, with traffic
+/// shifting toward whichever feedback finds right most often.
+///
+/// Arm 0 of the allocator is the stage's configured model and arm `i` is
+/// `arms[i - 1]`. Arms are named by model id, which is how feedback finds
+/// the arm to reward. While a drift fallback serves in place of the
+/// configured model the bandit is not consulted, so arm 0 only ever counts
+/// predictions the configured model made.
+pub struct Bandit {
+    allocator: BanditAllocator,
+    arms: Vec<Arc<InferenceTransform>>,
+}
+
+impl Bandit {
+    fn from_spec(config: &MlInferenceConfig, spec: &BanditSpec) -> anyhow::Result<Self> {
+        let arms = spec
+            .arms
+            .iter()
+            .map(|arm| {
+                let endpoint = arm.model_endpoint.as_deref().unwrap_or(&config.model_endpoint);
+                build_transform(config, &arm.model_id, endpoint)
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let names = std::iter::once(config.model_id.clone())
+            .chain(spec.arms.iter().map(|arm| arm.model_id.clone()));
+        Ok(Self {
+            allocator: BanditAllocator::new(spec.into(), names),
+            arms,
+        })
+    }
+
+    pub fn allocator(&self) -> &BanditAllocator {
+        &self.allocator
+    }
+
+    /// The models of arms 1 and up, in arm order.
+    pub fn arms(&self) -> impl Iterator<Item = Arc<InferenceTransform>> + '_ {
+        self.arms.iter().cloned()
+    }
+}
+
+/// The bandits of every running pipeline, so feedback submitted to the
+/// server rewards the same arms as feedback joined inside a pipeline.
+#[derive(Default)]
+pub struct BanditRegistry {
+    pipelines: RwLock<HashMap<Uuid, Vec<Arc<ActiveModel>>>>,
+}
+
+impl BanditRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rewards the bandits among `models` for feedback on `pipeline_id`'s
+    /// predictions, until the pipeline is unregistered.
+    pub fn register<'a>(
+        &self,
+        pipeline_id: Uuid,
+        models: impl IntoIterator<Item = &'a Arc<ActiveModel>>,
+    ) {
+        let models: Vec<_> = models
+            .into_iter()
+            .filter(|model| model.bandit().is_some())
+            .cloned()
+            .collect();
+        if !models.is_empty() {
+            self.pipelines.write().unwrap().insert(pipeline_id, models);
+        }
+    }
+
+    pub fn unregister(&self, pipeline_id: Uuid) {
+        self.pipelines.write().unwrap().remove(&pipeline_id);
+    }
+
+    /// Credits the arm named by the model that made the labeled prediction,
+    /// in each bandit of the pipeline that served it.
+    pub fn reward(&self, pipeline_id: Uuid, labeled: &LabeledExample) {
+        let Some(correct) = labeled.is_correct else {
+            return;
+        };
+        let pipelines = self.pipelines.read().unwrap();
+        let models = pipelines.get(&pipeline_id).into_iter().flatten();
+        for bandit in models.filter_map(|model| model.bandit()) {
+            bandit.allocator().reward(&labeled.model_id, correct);
+        }
+    }
+}
+
+impl ActiveModel {
+    /// Fails when the stage runs a local model that cannot be loaded.
+    pub fn from_config(config: &MlInferenceConfig) -> anyhow::Result<Self> {
+        let shadow = match &config.shadow {
+            Some(spec) => {
+                let endpoint = spec.model_endpoint.as_deref().unwrap_or(&config.model_endpoint);
+                Some(build_transform(config, &spec.model_id, endpoint)?)
+            }
+            None => None,
+        };
+        let canary = match &config.canary {
+            Some(spec) => Some(Canary::from_spec(config, spec)?),
+            None => None,
+        };
+
+        anyhow::ensure!(
+            config.canary.is_none() || config.bandit.is_none(),
+            "A stage cannot have both a canary and a bandit"
+        );
+        let bandit = match &config.bandit {
+            Some(spec) => Some(Bandit::from_spec(config, spec)?),
+            None => None,
+        };
+
+        let primary = build_transform(config, &config.model_id, &config.model_endpoint)?;
+        Ok(Self {
+            current: RwLock::new(primary.clone()),
+            primary,
+            fallback: Arc::new(InferenceFallback::new((&config.fallback).into())),
+            config: config.clone(),
+            shadow,
+            canary,
+            bandit,
+            versions: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn config(&self) -> &MlInferenceConfig {
+        &self.config
+    }
+
+    pub fn current(&self) -> Arc<InferenceTransform> {
+        self.current.read().unwrap().clone()
+    }
+
+    /// What happens to records the model could not score.
+    pub fn fallback(&self) -> Arc<InferenceFallback> {
+        self.fallback.clone()
+    }
+
+    /// The model scored on a copy of every record, if any.
+    pub fn shadow(&self) -> Option<Arc<InferenceTransform>> {
+        self.shadow.clone()
+    }
+
+    pub fn canary(&self) -> Option<&Canary> {
+        self.canary.as_ref()
+    }
+
+    pub fn bandit(&self) -> Option<&Bandit> {
+        self.bandit.as_ref()
+    }
+
+    /// Whether another model serves in place of the configured one.
+    pub fn is_swapped(&self) -> bool {
+        !Arc::ptr_eq(&self.current(), &self.primary)
+    }
+
+    pub fn note_version(&self, prediction: &Prediction) {
+        self.remember_version(&prediction.model_id, &prediction.model_version);
+    }
+
+    /// Attributes `model_id` to `version` until it reports another.
+    pub fn remember_version(&self, model_id: &str, version: &str) {
+        let mut versions = self.versions.lock().unwrap();
+        if versions.get(model_id).map(String::as_str) != Some(version) {
+            versions.insert(model_id.to_string(), version.to_string());
+        }
+    }
+
+    /// The version `model_id` last reported, empty before its first prediction.
+    pub fn version_of(&self, model_id: &str) -> String {
+        self.versions.lock().unwrap().get(model_id).cloned().unwrap_or_default()
+    }
+
+    /// Whether predictions are compared across models, which needs every
+    /// prediction stored.
+    pub fn has_variants(&self) -> bool {
+        self.shadow.is_some() || self.canary.is_some() || self.bandit.is_some()
+    }
+
+    /// Routes subsequent batches to `model_id`, served by the same endpoint.
+    /// Returns the model id that was active before the swap.
+    pub fn swap_to(&self, model_id: &str) -> anyhow::Result<String> {
+        let transform = build_transform(&self.config, model_id, &self.config.model_endpoint)?;
+        let previous = std::mem::replace(&mut *self.current.write().unwrap(), transform);
+        Ok(previous.model_id().to_string())
+    }
+
+    /// Switches back to the model configured on the stage. Returns the model
+    /// id that was active before.
+    pub fn restore(&self) -> String {
+        let previous = std::mem::replace(&mut *self.current.write().unwrap(), self.primary.clone());
+        previous.model_id().to_string()
+    }
+}
+
+/// Builds the transform for `model_id`, served by `endpoint`: a model
+/// server, or a model run in-process for `onnx://`, `trees://` and
+/// `builtin://` endpoints. It takes the stage's inputs and call settings.
+fn build_transform(
+    config: &MlInferenceConfig,
+    model_id: &str,
+    endpoint: &str,
+) -> anyhow::Result<Arc<InferenceTransform>> {
+    let metadata = ModelMetadata::new(model_id, ModelType::Custom)
+        .with_endpoint(endpoint.to_string())
+        .with_input_features(config.input_features.clone())
+        .with_output_field(config.output_field.clone());
+    let timeout = Duration::from_millis(config.timeout_ms);
+    let model = flywheel_ml_inference::load_model(metadata, timeout)
+        .with_context(|| format!("Failed to load model '{}'", model_id))?;
+    let mut transform = InferenceTransform::new(model)
+        .with_timeout(timeout)
+        .with_circuit_breaker(circuit_breaker(config, model_id))
+        .with_batching(BatchConfig {
+            max_batch_size: config.batch_size,
+            max_linger: Duration::from_millis(config.batch_linger_ms),
+            max_in_flight: config.max_concurrent_batches,
+        });
+    if let Some(retry) = &config.retry {
+        transform = transform.with_retry(retry.into());
+    }
+    Ok(Arc::new(transform))
+}
+
+/// The stage's circuit breaker, logging each state change.
+fn circuit_breaker(config: &MlInferenceConfig, model_id: &str) -> CircuitBreaker {
+    let breaker_config = config
+        .circuit_breaker
+        .as_ref()
+        .map(|spec| (&flywheel_ml_core::CircuitBreakerConfig::from(spec)).into())
+        .unwrap_or_default();
+
+    let model_id = model_id.to_string();
+    CircuitBreaker::new(breaker_config).with_listener(Arc::new(move |from, to| {
+        if to == CircuitState::Open {
+            tracing::warn!(model_id = %model_id, from = ?from, "Circuit breaker opened");
+        } else {
+            tracing::info!(
+                model_id = %model_id,
+                from = ?from,
+                to = ?to,
+                "Circuit breaker changed state"
+            );
+        }
+    }))
+}
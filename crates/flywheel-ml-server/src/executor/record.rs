@@ -0,0 +1,45 @@
+use flywheel_ml_core::{FeatureVector, Prediction};
+use flywheel_ml_db::entity::dead_letter;
+use uuid::Uuid;
+
+/// A record flowing between the stages of a pipeline.
+///
+/// `payload` is the JSON body that is eventually delivered to sinks; stages
+/// enrich it as they go. `features` is populated by the feature-extraction
+/// stage and consumed by inference and drift detection; `prediction` is set
+/// by the inference stage. `replay_of` names the dead letter a replayed record
+/// came from, which the stage marks replayed once it has accepted the record.
+#[derive(Debug, Clone)]
+pub struct PipelineRecord {
+    pub id: String,
+    pub payload: serde_json::Value,
+    pub features: Option<FeatureVector>,
+    #[allow(dead_code)]
+    pub prediction: Option<Prediction>,
+    pub replay_of: Option<Uuid>,
+}
+
+impl PipelineRecord {
+    pub fn new(payload: serde_json::Value) -> Self {
+        Self {
+            id: flywheel_ml_core::record_id(&payload),
+            payload,
+            features: None,
+            prediction: None,
+            replay_of: None,
+        }
+    }
+
+    /// The record parked in `dead_letter`, to be replayed through its stage.
+    pub fn replay(dead_letter: dead_letter::Model) -> Self {
+        Self {
+            id: dead_letter.record_id,
+            payload: dead_letter.payload_json,
+            features: dead_letter
+                .features_json
+                .and_then(|f| serde_json::from_value(f).ok()),
+            prediction: None,
+            replay_of: Some(dead_letter.id),
+        }
+    }
+}
@@ -1,35 +1,107 @@
+use std::collections::{HashMap, HashSet};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use flywheel_ml_db::{entity::pipeline, Database};
-use flywheel_ml_dsl::{FlywheelPipelineManifest, FlywheelStageType};
+use anyhow::Context;
+use flywheel_ml_db::entity::{dead_letter::DeadLetterStatus, pipeline};
+use flywheel_ml_db::{BanditArmRepo, Database, DeadLetterRepo};
+use flywheel_ml_drift::PerformanceRegistry;
+use flywheel_ml_dsl::{FlywheelPipelineManifest, FlywheelStage, FlywheelStageType, MlInferenceConfig};
+use flywheel_ml_inference::bandit::ArmStats;
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
 
-use super::stage::{StageExecutor, StageContext};
+use super::model::{ActiveModel, BanditRegistry};
+use super::record::PipelineRecord;
+use super::sink::{SinkResult, SinkRouter, SinkStats};
+use super::source::{build_source, Source};
+use super::stage::{StageContext, StageExecutor, StageResult, StageStats};
+
+/// Capacity of the bounded channel in front of each stage.
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// Maximum number of records handed to the sinks per delivery.
+const SINK_BATCH_SIZE: usize = 64;
+
+/// How often a running pipeline looks for dead letters queued for replay.
+const REPLAY_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Maximum number of dead letters replayed per poll.
+const REPLAY_BATCH_SIZE: u64 = 100;
+
+/// How often a running pipeline saves what its bandits have learned.
+const BANDIT_SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+type StageModels = HashMap<String, Arc<ActiveModel>>;
 
 pub struct PipelineRunner {
     pipeline: pipeline::Model,
-    manifest: FlywheelPipelineManifest,
     db: Database,
+    manifest: FlywheelPipelineManifest,
     running: AtomicBool,
-    records_processed: AtomicU64,
-    predictions_made: AtomicU64,
-    errors: AtomicU64,
+    source: Mutex<Option<Box<dyn Source>>>,
+    executors: Mutex<Vec<StageExecutor>>,
+    models: Arc<StageModels>,
+    bandits: Arc<BanditRegistry>,
+    stage_stats: Vec<(String, FlywheelStageType, Arc<StageStats>)>,
+    sinks: Arc<SinkRouter>,
+    sink_stats: Vec<(String, Arc<SinkStats>)>,
+    records_processed: Arc<AtomicU64>,
 }
 
 impl PipelineRunner {
-    pub fn new(pipeline: pipeline::Model, db: Database) -> anyhow::Result<Self> {
+    pub fn new(
+        pipeline: pipeline::Model,
+        db: Database,
+        performance: Arc<PerformanceRegistry>,
+        bandits: Arc<BanditRegistry>,
+    ) -> anyhow::Result<Self> {
         let manifest = flywheel_ml_dsl::parser::parse_manifest(&pipeline.spec_yaml)
             .map_err(|e| anyhow::anyhow!("Failed to parse pipeline spec: {}", e))?;
 
+        let source = build_source(&manifest.spec.source)?;
+        let sinks = SinkRouter::from_specs(&manifest.spec.sinks)?;
+        let sink_stats = sinks.stats();
+
+        let models = Arc::new(build_models(&manifest.spec.stages)?);
+        let ctx = StageContext {
+            pipeline_id: pipeline.id,
+            pipeline_name: pipeline.name.clone(),
+            namespace: pipeline.namespace.clone(),
+            db: db.clone(),
+            spec: Arc::new(manifest.spec.clone()),
+            models: models.clone(),
+            performance,
+            bandits: bandits.clone(),
+        };
+
+        // Executors live for the whole run so they can keep state between records.
+        let executors = manifest
+            .spec
+            .stages
+            .iter()
+            .map(|stage| StageExecutor::for_stage(stage, &ctx))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let stage_stats = executors
+            .iter()
+            .map(|e| (e.stage().id.clone(), e.stage().stage_type, e.stats()))
+            .collect();
+
         Ok(Self {
             pipeline,
-            manifest,
             db,
+            manifest,
             running: AtomicBool::new(true),
-            records_processed: AtomicU64::new(0),
-            predictions_made: AtomicU64::new(0),
-            errors: AtomicU64::new(0),
+            source: Mutex::new(source),
+            executors: Mutex::new(executors),
+            models,
+            bandits,
+            stage_stats,
+            sinks: Arc::new(sinks),
+            sink_stats,
+            records_processed: Arc::new(AtomicU64::new(0)),
         })
     }
 
@@ -49,89 +121,651 @@ impl PipelineRunner {
             "Pipeline runner started"
         );
 
-        let ctx = StageContext {
-            pipeline_id: self.pipeline.id,
-            pipeline_name: self.pipeline.name.clone(),
-            namespace: self.pipeline.namespace.clone(),
-            db: self.db.clone(),
+        let executors = std::mem::take(&mut *self.executors.lock().unwrap());
+        if executors.is_empty() {
+            tracing::warn!(pipeline_id = %self.pipeline.id, "Pipeline runner has already run");
+            return;
+        }
+
+        // Bandits pick up where the last run left off before any record is served.
+        let has_bandits = self.models.values().any(|model| model.bandit().is_some());
+        if has_bandits {
+            restore_bandits(&self.db, self.pipeline.id, &self.models).await;
+            self.bandits.register(self.pipeline.id, self.models.values());
+        }
+
+        let mut tasks = JoinSet::new();
+        let (input, mut rx) = mpsc::channel::<PipelineRecord>(CHANNEL_CAPACITY);
+
+        // Dead letters are replayed into the input of the stage that parked them.
+        let mut replay_inputs = HashMap::new();
+        let mut stage_input = input.clone();
+        for executor in executors {
+            let (tx, next_rx) = mpsc::channel(CHANNEL_CAPACITY);
+            if matches!(
+                executor.stage().stage_type,
+                FlywheelStageType::MlInference | FlywheelStageType::DriftDetection
+            ) {
+                replay_inputs.insert(executor.stage().id.clone(), stage_input);
+            }
+            stage_input = tx.clone();
+            tasks.spawn(executor.run(rx, tx));
+            rx = next_rx;
+        }
+        drop(stage_input);
+        let replay_task = (!replay_inputs.is_empty()).then(|| {
+            tasks.spawn(replay_dead_letters(
+                self.db.clone(),
+                self.pipeline.id,
+                replay_inputs,
+            ))
+        });
+
+        let bandit_task = has_bandits.then(|| {
+            tasks.spawn(save_bandits_periodically(
+                self.db.clone(),
+                self.pipeline.id,
+                self.models.clone(),
+            ))
+        });
+
+        let records_processed = self.records_processed.clone();
+        let sinks = self.sinks.clone();
+        tasks.spawn(async move {
+            let mut batch = Vec::with_capacity(SINK_BATCH_SIZE);
+            while rx.recv_many(&mut batch, SINK_BATCH_SIZE).await > 0 {
+                records_processed.fetch_add(batch.len() as u64, Ordering::Relaxed);
+                sinks.deliver(&batch).await;
+                batch.clear();
+            }
+        });
+
+        let mut external_input = None;
+        let source_task = match self.source.lock().unwrap().take() {
+            Some(source) => {
+                let pipeline_id = self.pipeline.id;
+                Some(tasks.spawn(async move {
+                    // Sources may hand their sender to detached connection tasks, so
+                    // records pass through a channel this task owns. Aborting it on
+                    // stop closes the stage input no matter who still holds a sender.
+                    let (tx, mut rx) = mpsc::channel(CHANNEL_CAPACITY);
+                    let forward = async move {
+                        while let Some(record) = rx.recv().await {
+                            if input.send(record).await.is_err() {
+                                break;
+                            }
+                        }
+                    };
+
+                    let name = source.name().to_string();
+                    let (result, ()) = tokio::join!(source.run(tx), forward);
+                    if let Err(e) = result {
+                        tracing::error!(
+                            pipeline_id = %pipeline_id,
+                            source = %name,
+                            error = %e,
+                            "Pipeline source failed"
+                        );
+                    }
+                }))
+            }
+            None => {
+                tracing::info!(
+                    pipeline_id = %self.pipeline.id,
+                    source = %self.manifest.spec.source.name(),
+                    "Pipeline source is external, waiting for records"
+                );
+                external_input = Some(input);
+                None
+            }
         };
 
         while self.is_running() {
-            match self.execute_cycle(&ctx).await {
-                Ok(processed) => {
-                    self.records_processed.fetch_add(processed, Ordering::Relaxed);
-                }
-                Err(e) => {
-                    self.errors.fetch_add(1, Ordering::Relaxed);
-                    tracing::error!(
-                        pipeline_id = %self.pipeline.id,
-                        error = %e,
-                        "Pipeline execution cycle failed"
-                    );
-                }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        // Closing the input drains the stages in order before their tasks exit.
+        if let Some(source_task) = source_task {
+            source_task.abort();
+        }
+        if let Some(replay_task) = replay_task {
+            replay_task.abort();
+        }
+        if let Some(bandit_task) = bandit_task {
+            bandit_task.abort();
+        }
+        drop(external_input);
+        while let Some(result) = tasks.join_next().await {
+            if let Some(e) = result.err().filter(|e| !e.is_cancelled()) {
+                tracing::error!(
+                    pipeline_id = %self.pipeline.id,
+                    error = %e,
+                    "Pipeline stage task failed"
+                );
             }
+        }
 
-            tokio::time::sleep(Duration::from_millis(100)).await;
+        if has_bandits {
+            save_bandits(&self.db, self.pipeline.id, &self.models).await;
         }
 
+        let stats = self.stats();
+        for (stage_id, stage) in &stats.stages {
+            tracing::debug!(
+                pipeline_id = %self.pipeline.id,
+                stage_id = %stage_id,
+                received = stage.records_received,
+                processed = stage.records_processed,
+                failed = stage.records_failed,
+                fallbacks = stage.fallbacks.total(),
+                "Stage stopped"
+            );
+            if stage.records_blocked > 0 {
+                tracing::info!(
+                    pipeline_id = %self.pipeline.id,
+                    stage_id = %stage_id,
+                    blocked = stage.records_blocked,
+                    "Records parked while drift was open"
+                );
+            }
+            if stage.fallbacks.total() > 0 {
+                let fallbacks = &stage.fallbacks;
+                tracing::info!(
+                    pipeline_id = %self.pipeline.id,
+                    stage_id = %stage_id,
+                    pass_through = fallbacks.pass_through,
+                    return_null = fallbacks.return_null,
+                    use_default = fallbacks.use_default,
+                    use_last_known = fallbacks.use_last_known,
+                    send_to_dlq = fallbacks.send_to_dlq,
+                    error = fallbacks.error,
+                    "Inference fallbacks applied"
+                );
+            }
+        }
+        for (sink, result) in &stats.sinks {
+            tracing::debug!(
+                pipeline_id = %self.pipeline.id,
+                sink = %sink,
+                matched = result.records_matched,
+                delivered = result.records_delivered,
+                failed = result.records_failed,
+                "Sink stopped"
+            );
+        }
         tracing::info!(
             pipeline_id = %self.pipeline.id,
-            records_processed = self.records_processed.load(Ordering::Relaxed),
-            predictions_made = self.predictions_made.load(Ordering::Relaxed),
-            errors = self.errors.load(Ordering::Relaxed),
+            records_processed = stats.records_processed,
+            predictions_made = stats.predictions_made,
+            errors = stats.errors,
             "Pipeline runner stopped"
         );
     }
 
-    async fn execute_cycle(&self, ctx: &StageContext) -> anyhow::Result<u64> {
-        let mut records_in_cycle = 0u64;
+    pub fn stats(&self) -> PipelineStats {
+        let stages: Vec<(String, StageResult)> = self
+            .stage_stats
+            .iter()
+            .map(|(id, _, stats)| (id.clone(), stats.snapshot()))
+            .collect();
 
-        for stage in &self.manifest.spec.stages {
-            let executor = StageExecutor::for_stage(stage, ctx)?;
+        let predictions_made = self
+            .stage_stats
+            .iter()
+            .filter(|(_, stage_type, _)| *stage_type == FlywheelStageType::MlInference)
+            .map(|(_, _, stats)| stats.snapshot().records_processed)
+            .sum();
 
-            match executor.execute().await {
-                Ok(result) => {
-                    records_in_cycle += result.records_processed;
+        let sinks: Vec<(String, SinkResult)> = self
+            .sink_stats
+            .iter()
+            .map(|(name, stats)| (name.clone(), stats.snapshot()))
+            .collect();
 
-                    if stage.stage_type == FlywheelStageType::MlInference {
-                        self.predictions_made.fetch_add(result.records_processed, Ordering::Relaxed);
-                    }
+        let errors = stages.iter().map(|(_, s)| s.records_failed).sum::<u64>()
+            + sinks.iter().map(|(_, s)| s.records_failed).sum::<u64>();
 
-                    tracing::debug!(
-                        pipeline_id = %self.pipeline.id,
-                        stage_id = %stage.id,
-                        stage_type = ?stage.stage_type,
-                        records = result.records_processed,
-                        "Stage executed"
-                    );
-                }
-                Err(e) => {
-                    tracing::warn!(
-                        pipeline_id = %self.pipeline.id,
-                        stage_id = %stage.id,
-                        error = %e,
-                        "Stage execution failed"
-                    );
-                    return Err(e);
-                }
+        PipelineStats {
+            records_processed: self.records_processed.load(Ordering::Relaxed),
+            predictions_made,
+            errors,
+            stages,
+            sinks,
+        }
+    }
+}
+
+/// Sends dead letters queued for replay back through the stage that parked
+/// them, until the runner aborts it. The stage marks each one replayed once
+/// it has accepted the record, so dead letters still queued after being sent
+/// are in flight and are not sent again.
+async fn replay_dead_letters(
+    db: Database,
+    pipeline_id: uuid::Uuid,
+    stage_inputs: HashMap<String, mpsc::Sender<PipelineRecord>>,
+) {
+    let mut in_flight = HashSet::new();
+    loop {
+        tokio::time::sleep(REPLAY_POLL_INTERVAL).await;
+
+        let queued = match DeadLetterRepo::list_by_pipeline(
+            db.conn(),
+            pipeline_id,
+            Some(DeadLetterStatus::Queued),
+            REPLAY_BATCH_SIZE,
+        )
+        .await
+        {
+            Ok(queued) => queued,
+            Err(e) => {
+                tracing::warn!(
+                    pipeline_id = %pipeline_id,
+                    error = %e,
+                    "Failed to load dead letters"
+                );
+                continue;
+            }
+        };
+
+        // Dead letters no longer queued were accepted or parked again.
+        in_flight.retain(|id| queued.iter().any(|d| d.id == *id));
+        for dead_letter in queued {
+            if in_flight.contains(&dead_letter.id) {
+                continue;
             }
+            let Some(input) = stage_inputs.get(&dead_letter.stage_id) else {
+                tracing::debug!(
+                    pipeline_id = %pipeline_id,
+                    stage_id = %dead_letter.stage_id,
+                    "Dead letter belongs to a stage this pipeline no longer has"
+                );
+                continue;
+            };
+
+            let id = dead_letter.id;
+            if input.send(PipelineRecord::replay(dead_letter)).await.is_err() {
+                return;
+            }
+            in_flight.insert(id);
         }
+    }
+}
 
-        Ok(records_in_cycle)
+impl Drop for PipelineRunner {
+    /// The engine aborts runners it stops, so feedback stops reaching their
+    /// bandits here rather than at the end of `run`.
+    fn drop(&mut self) {
+        self.bandits.unregister(self.pipeline.id);
     }
+}
 
-    pub fn stats(&self) -> PipelineStats {
-        PipelineStats {
-            records_processed: self.records_processed.load(Ordering::Relaxed),
-            predictions_made: self.predictions_made.load(Ordering::Relaxed),
-            errors: self.errors.load(Ordering::Relaxed),
+/// Resumes each stage's bandit from the arms saved by an earlier run.
+async fn restore_bandits(db: &Database, pipeline_id: uuid::Uuid, models: &StageModels) {
+    for (stage_id, model) in models {
+        let Some(bandit) = model.bandit() else {
+            continue;
+        };
+        let saved = match BanditArmRepo::list_by_stage(db.conn(), pipeline_id, stage_id).await {
+            Ok(saved) => saved,
+            Err(e) => {
+                tracing::warn!(
+                    pipeline_id = %pipeline_id,
+                    stage_id = %stage_id,
+                    error = %e,
+                    "Failed to load bandit state, starting from scratch"
+                );
+                continue;
+            }
+        };
+
+        for arm in saved {
+            let stats = ArmStats {
+                arm: arm.model_id,
+                pulls: arm.pulls as u64,
+                successes: arm.successes as u64,
+                failures: arm.failures as u64,
+            };
+            // Arms removed from the spec since are left out.
+            if bandit.allocator().restore(&stats) && !arm.model_version.is_empty() {
+                model.remember_version(&stats.arm, &arm.model_version);
+            }
+        }
+    }
+}
+
+/// Saves every bandit arm with the share of traffic it is expected to serve.
+async fn save_bandits(db: &Database, pipeline_id: uuid::Uuid, models: &StageModels) {
+    for (stage_id, model) in models {
+        let Some(bandit) = model.bandit() else {
+            continue;
+        };
+        let allocator = bandit.allocator();
+        let policy = allocator.policy().type_name();
+        for (arm, share) in allocator.stats().into_iter().zip(allocator.shares()) {
+            if let Err(e) = BanditArmRepo::upsert(
+                db.conn(),
+                pipeline_id,
+                stage_id.clone(),
+                arm.arm.clone(),
+                model.version_of(&arm.arm),
+                policy.to_string(),
+                arm.pulls as i64,
+                arm.successes as i64,
+                arm.failures as i64,
+                share,
+            )
+            .await
+            {
+                tracing::warn!(
+                    pipeline_id = %pipeline_id,
+                    stage_id = %stage_id,
+                    model_id = %arm.arm,
+                    error = %e,
+                    "Failed to save bandit state"
+                );
+            }
         }
     }
 }
 
+/// Saves the pipeline's bandits every `BANDIT_SAVE_INTERVAL`, until the
+/// runner aborts it.
+async fn save_bandits_periodically(
+    db: Database,
+    pipeline_id: uuid::Uuid,
+    models: Arc<StageModels>,
+) {
+    loop {
+        tokio::time::sleep(BANDIT_SAVE_INTERVAL).await;
+        save_bandits(&db, pipeline_id, &models).await;
+    }
+}
+
+/// Creates the swappable model behind each ml-inference stage.
+pub(super) fn build_models(stages: &[FlywheelStage]) -> anyhow::Result<StageModels> {
+    stages
+        .iter()
+        .filter(|stage| stage.stage_type == FlywheelStageType::MlInference)
+        .map(|stage| {
+            let config: MlInferenceConfig = serde_json::from_value(stage.config.clone())
+                .with_context(|| format!("Invalid config for stage '{}'", stage.id))?;
+            let model = ActiveModel::from_config(&config)
+                .with_context(|| format!("Invalid model for stage '{}'", stage.id))?;
+            Ok((stage.id.clone(), Arc::new(model)))
+        })
+        .collect()
+}
+
 #[derive(Debug, Clone)]
 pub struct PipelineStats {
     pub records_processed: u64,
     pub predictions_made: u64,
     pub errors: u64,
+    pub stages: Vec<(String, StageResult)>,
+    pub sinks: Vec<(String, SinkResult)>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::sink::Sink;
+    use async_trait::async_trait;
+    use flywheel_ml_db::entity::pipeline::PipelineStatus;
+    use serde_json::json;
+    use std::sync::atomic::AtomicUsize;
+    use tokio::sync::Semaphore;
+
+    const SPEC: &str = r#"
+apiVersion: flywheel-ml.io/v1
+kind: FlywheelPipeline
+metadata:
+  name: runner-test
+  namespace: default
+spec:
+  source: test-input
+  stages:
+    - id: features
+      type: feature-extraction
+      config:
+        features:
+          - name: cpu
+            source_field: $.cpu
+        include_raw: true
+  sinks:
+    - name: out
+      all: true
+"#;
+
+    /// Feeds the pipeline from a channel the test holds.
+    struct ChannelSource(mpsc::Receiver<serde_json::Value>);
+
+    #[async_trait]
+    impl Source for ChannelSource {
+        fn name(&self) -> &str {
+            "channel"
+        }
+
+        async fn run(
+            mut self: Box<Self>,
+            output: mpsc::Sender<PipelineRecord>,
+        ) -> anyhow::Result<()> {
+            while let Some(value) = self.0.recv().await {
+                if output.send(PipelineRecord::new(value)).await.is_err() {
+                    break;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    /// Collects delivered records, waiting for a permit per batch.
+    struct ChannelSink {
+        output: mpsc::UnboundedSender<PipelineRecord>,
+        gate: Arc<Semaphore>,
+    }
+
+    #[async_trait]
+    impl Sink for ChannelSink {
+        fn name(&self) -> &str {
+            "channel"
+        }
+
+        async fn deliver(&self, records: &[PipelineRecord]) -> anyhow::Result<()> {
+            self.gate.acquire().await?.forget();
+            for record in records {
+                self.output.send(record.clone())?;
+            }
+            Ok(())
+        }
+    }
+
+    struct Harness {
+        runner: Arc<PipelineRunner>,
+        input: mpsc::Sender<serde_json::Value>,
+        delivered: mpsc::UnboundedReceiver<PipelineRecord>,
+        gate: Arc<Semaphore>,
+        task: tokio::task::JoinHandle<()>,
+    }
+
+    fn start(source_capacity: usize, open: bool) -> Harness {
+        let now = chrono::Utc::now();
+        let pipeline = pipeline::Model {
+            id: uuid::Uuid::new_v4(),
+            name: "runner-test".to_string(),
+            namespace: "default".to_string(),
+            spec_hash: String::new(),
+            spec_yaml: SPEC.to_string(),
+            status: PipelineStatus::Running,
+            conveyor_pipeline_id: None,
+            created_at: now,
+            updated_at: now,
+        };
+        let db = Database::new(sea_orm::DatabaseConnection::Disconnected);
+        let performance = Arc::new(PerformanceRegistry::new(Duration::from_secs(60)));
+        let bandits = Arc::new(BanditRegistry::new());
+        let mut runner = PipelineRunner::new(pipeline, db, performance, bandits).unwrap();
+
+        let (input, rx) = mpsc::channel(source_capacity);
+        runner.source = Mutex::new(Some(Box::new(ChannelSource(rx))));
+        let (output, delivered) = mpsc::unbounded_channel();
+        let permits = if open { Semaphore::MAX_PERMITS } else { 0 };
+        let gate = Arc::new(Semaphore::new(permits));
+        let sink = ChannelSink {
+            output,
+            gate: gate.clone(),
+        };
+        Arc::get_mut(&mut runner.sinks)
+            .unwrap()
+            .set_output("out", Box::new(sink));
+
+        let runner = Arc::new(runner);
+        let task = tokio::spawn({
+            let runner = runner.clone();
+            async move { runner.run().await }
+        });
+        Harness {
+            runner,
+            input,
+            delivered,
+            gate,
+            task,
+        }
+    }
+
+    async fn receive(
+        delivered: &mut mpsc::UnboundedReceiver<PipelineRecord>,
+        n: usize,
+    ) -> Vec<String> {
+        let mut ids = Vec::with_capacity(n);
+        while ids.len() < n {
+            let record = tokio::time::timeout(Duration::from_secs(10), delivered.recv())
+                .await
+                .expect("records are delivered")
+                .expect("sink is open");
+            ids.push(record.id);
+        }
+        ids
+    }
+
+    #[tokio::test]
+    async fn test_records_reach_sinks_in_order() {
+        let mut harness = start(16, true);
+        for i in 0..500 {
+            harness.input.send(json!({"id": i, "cpu": i})).await.unwrap();
+            if i == 250 {
+                // Fails extraction and is counted, not delivered.
+                harness.input.send(json!({"id": "bad"})).await.unwrap();
+            }
+        }
+
+        let ids = receive(&mut harness.delivered, 500).await;
+        let expected: Vec<String> = (0..500).map(|i| i.to_string()).collect();
+        assert_eq!(ids, expected);
+
+        harness.runner.stop();
+        harness.task.await.unwrap();
+
+        let stats = harness.runner.stats();
+        assert_eq!(stats.records_processed, 500);
+        assert_eq!(stats.predictions_made, 0);
+        assert_eq!(stats.errors, 1);
+        let (stage_id, stage) = &stats.stages[0];
+        assert_eq!(stage_id, "features");
+        assert_eq!(stage.records_received, 501);
+        assert_eq!(stage.records_processed, 500);
+        assert_eq!(stage.records_failed, 1);
+        let (sink, result) = &stats.sinks[0];
+        assert_eq!(sink, "out");
+        assert_eq!(result.records_matched, 500);
+        assert_eq!(result.records_delivered, 500);
+        assert_eq!(result.records_failed, 0);
+    }
+
+    #[tokio::test]
+    async fn test_blocked_sink_applies_backpressure() {
+        const RECORDS: usize = 20_000;
+
+        let mut harness = start(1, false);
+        let sent = Arc::new(AtomicUsize::new(0));
+        let producer = tokio::spawn({
+            let input = harness.input.clone();
+            let sent = sent.clone();
+            async move {
+                for i in 0..RECORDS {
+                    input.send(json!({"id": i, "cpu": 1.0})).await.unwrap();
+                    sent.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        });
+
+        // Each channel between the source and the sink fills, then the source stalls.
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        let stalled_at = sent.load(Ordering::Relaxed);
+        assert!(stalled_at < 4 * CHANNEL_CAPACITY, "source sent {stalled_at} records");
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(sent.load(Ordering::Relaxed), stalled_at);
+        assert!(harness.delivered.try_recv().is_err());
+
+        harness.gate.add_permits(Semaphore::MAX_PERMITS);
+        let ids = receive(&mut harness.delivered, RECORDS).await;
+        producer.await.unwrap();
+        assert!(ids.iter().enumerate().all(|(i, id)| *id == i.to_string()));
+
+        harness.runner.stop();
+        harness.task.await.unwrap();
+        let stats = harness.runner.stats();
+        assert_eq!(stats.records_processed, RECORDS as u64);
+        assert_eq!(stats.sinks[0].1.records_delivered, RECORDS as u64);
+    }
+
+    #[tokio::test]
+    async fn test_bandit_arms_are_saved_and_restored() {
+        let spec = r#"
+apiVersion: flywheel-ml.io/v1
+kind: FlywheelPipeline
+metadata:
+  name: bandit-test
+  namespace: default
+spec:
+  source: test-input
+  stages:
+    - id: inference
+      type: ml-inference
+      config:
+        model_endpoint: builtin://zscore
+        model_id: detector
+        input_features: [cpu]
+        output_field: score
+        bandit:
+          policy: epsilon_greedy
+          epsilon: 0.1
+          arms: [{model_id: challenger}]
+  sinks:
+    - name: out
+      all: true
+"#;
+        let db = crate::testing::database().await;
+        let pipeline = crate::testing::pipeline(&db, spec).await;
+        let manifest = flywheel_ml_dsl::parser::parse_manifest(spec).unwrap();
+
+        let models = build_models(&manifest.spec.stages).unwrap();
+        let allocator = models["inference"].bandit().unwrap().allocator();
+        allocator.reward("detector", true);
+        save_bandits(&db, pipeline.id, &models).await;
+        // Saving again updates the arms in place.
+        allocator.reward("challenger", false);
+        allocator.reward("challenger", true);
+        save_bandits(&db, pipeline.id, &models).await;
+        let saved = BanditArmRepo::list_by_stage(db.conn(), pipeline.id, "inference")
+            .await
+            .unwrap();
+        assert_eq!(saved.len(), 2);
+
+        let restored = build_models(&manifest.spec.stages).unwrap();
+        restore_bandits(&db, pipeline.id, &restored).await;
+        assert_eq!(
+            restored["inference"].bandit().unwrap().allocator().stats(),
+            allocator.stats()
+        );
+    }
 }
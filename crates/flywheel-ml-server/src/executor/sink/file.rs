@@ -0,0 +1,171 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use flywheel_ml_dsl::FileSinkConfig;
+use tokio::fs::{File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+use super::{PipelineRecord, Sink};
+
+/// Appends records to a JSON Lines file, creating it on first delivery.
+///
+/// With `max_bytes` set, a delivery that would take the file past it first
+/// rotates the file to `<path>.1`, shifting older files up to `max_files`
+/// and removing the oldest. A batch is never split across files.
+pub struct FileSink {
+    path: PathBuf,
+    max_bytes: Option<u64>,
+    max_files: u32,
+    file: Mutex<Option<OpenFile>>,
+}
+
+struct OpenFile {
+    file: File,
+    len: u64,
+}
+
+impl FileSink {
+    pub fn new(config: &FileSinkConfig) -> Self {
+        Self {
+            path: PathBuf::from(&config.path),
+            max_bytes: config.max_bytes,
+            max_files: config.max_files,
+            file: Mutex::new(None),
+        }
+    }
+
+    async fn open(&self) -> anyhow::Result<OpenFile> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await
+            .with_context(|| format!("Failed to open {}", self.path.display()))?;
+        let len = file.metadata().await?.len();
+        Ok(OpenFile { file, len })
+    }
+
+    fn rotated_path(&self, index: u32) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        PathBuf::from(path)
+    }
+
+    async fn rotate(&self) -> anyhow::Result<()> {
+        for index in (1..self.max_files).rev() {
+            let from = self.rotated_path(index);
+            if tokio::fs::try_exists(&from).await? {
+                tokio::fs::rename(&from, self.rotated_path(index + 1)).await?;
+            }
+        }
+        tokio::fs::rename(&self.path, self.rotated_path(1))
+            .await
+            .with_context(|| format!("Failed to rotate {}", self.path.display()))?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Sink for FileSink {
+    fn name(&self) -> &str {
+        "file"
+    }
+
+    async fn deliver(&self, records: &[PipelineRecord]) -> anyhow::Result<()> {
+        let lines = json_lines(records)?;
+        let mut file = self.file.lock().await;
+        if file.is_none() {
+            *file = Some(self.open().await?);
+        }
+
+        let full = file.as_ref().is_some_and(|open| {
+            self.max_bytes
+                .is_some_and(|max| open.len > 0 && open.len + lines.len() as u64 > max)
+        });
+        if full {
+            *file = None;
+            self.rotate().await?;
+            *file = Some(self.open().await?);
+        }
+
+        let open = file.as_mut().expect("file opened above");
+        open.file.write_all(&lines).await?;
+        open.file.flush().await?;
+        open.len += lines.len() as u64;
+        Ok(())
+    }
+}
+
+/// Writes records to the server's standard output as JSON Lines.
+pub struct StdoutSink;
+
+#[async_trait]
+impl Sink for StdoutSink {
+    fn name(&self) -> &str {
+        "stdout"
+    }
+
+    async fn deliver(&self, records: &[PipelineRecord]) -> anyhow::Result<()> {
+        let mut stdout = tokio::io::stdout();
+        stdout.write_all(&json_lines(records)?).await?;
+        stdout.flush().await?;
+        Ok(())
+    }
+}
+
+fn json_lines(records: &[PipelineRecord]) -> anyhow::Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    for record in records {
+        serde_json::to_writer(&mut buf, &record.payload)?;
+        buf.push(b'\n');
+    }
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::path::Path;
+
+    fn sink(path: &Path, max_bytes: Option<u64>) -> FileSink {
+        FileSink::new(&FileSinkConfig {
+            path: path.display().to_string(),
+            max_bytes,
+            max_files: 2,
+        })
+    }
+
+    fn batch(ids: &[&str]) -> Vec<PipelineRecord> {
+        ids.iter()
+            .map(|id| PipelineRecord::new(json!({ "id": id })))
+            .collect()
+    }
+
+    fn read(path: &Path) -> String {
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_file_sink_rotates_at_max_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.jsonl");
+        // Each record is 11 bytes, so two fit under the limit.
+        let sink = sink(&path, Some(25));
+
+        for ids in [&["a", "b"][..], &["c"], &["d", "e", "f"], &["g"]] {
+            sink.deliver(&batch(ids)).await.unwrap();
+        }
+
+        // A batch larger than the limit is written whole; the oldest file is dropped.
+        assert_eq!(read(&path), "{\"id\":\"g\"}\n");
+        assert_eq!(
+            read(&dir.path().join("out.jsonl.1")),
+            "{\"id\":\"d\"}\n{\"id\":\"e\"}\n{\"id\":\"f\"}\n"
+        );
+        assert_eq!(read(&dir.path().join("out.jsonl.2")), "{\"id\":\"c\"}\n");
+        assert!(!dir.path().join("out.jsonl.3").exists());
+    }
+}
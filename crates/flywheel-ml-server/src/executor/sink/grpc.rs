@@ -0,0 +1,166 @@
+use std::time::Duration;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use flywheel_ml_dsl::GrpcSinkConfig;
+use flywheel_ml_proto::ingest_service_client::IngestServiceClient;
+use flywheel_ml_proto::IngestRecord;
+use tonic::transport::{Channel, Endpoint};
+
+use super::{PipelineRecord, Sink};
+
+/// Pushes records to another server's `IngestService`, e.g. a downstream
+/// pipeline with a `grpc` source.
+pub struct GrpcSink {
+    endpoint: String,
+    channel: Channel,
+    timeout: Duration,
+}
+
+impl GrpcSink {
+    pub fn new(config: &GrpcSinkConfig) -> anyhow::Result<Self> {
+        let endpoint = if config.endpoint.contains("://") {
+            config.endpoint.clone()
+        } else {
+            format!("http://{}", config.endpoint)
+        };
+        let timeout = Duration::from_millis(config.timeout_ms);
+
+        // Connect lazily so a downstream that is not up yet only fails deliveries.
+        let channel = Endpoint::from_shared(endpoint.clone())
+            .with_context(|| format!("Invalid endpoint: {}", endpoint))?
+            .connect_timeout(timeout)
+            .connect_lazy();
+
+        Ok(Self {
+            endpoint,
+            channel,
+            timeout,
+        })
+    }
+}
+
+#[async_trait]
+impl Sink for GrpcSink {
+    fn name(&self) -> &str {
+        "grpc"
+    }
+
+    async fn deliver(&self, records: &[PipelineRecord]) -> anyhow::Result<()> {
+        let messages = records
+            .iter()
+            .map(|record| {
+                Ok(IngestRecord {
+                    id: record.id.clone(),
+                    payload_json: serde_json::to_vec(&record.payload)?,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let mut request = tonic::Request::new(tokio_stream::iter(messages));
+        request.set_timeout(self.timeout);
+
+        let response = IngestServiceClient::new(self.channel.clone())
+            .push_records(request)
+            .await
+            .with_context(|| format!("Failed to push records to {}", self.endpoint))?
+            .into_inner();
+
+        if response.rejected > 0 {
+            anyhow::bail!(
+                "{} rejected {} of {} records",
+                self.endpoint,
+                response.rejected,
+                records.len()
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flywheel_ml_proto::ingest_service_server::{IngestService, IngestServiceServer};
+    use flywheel_ml_proto::PushRecordsResponse;
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+    use tonic::{Request, Response, Status, Streaming};
+
+    /// Keeps pushed records.
+    #[derive(Clone, Default)]
+    struct Downstream {
+        received: Arc<Mutex<Vec<(String, serde_json::Value)>>>,
+    }
+
+    #[tonic::async_trait]
+    impl IngestService for Downstream {
+        async fn push_records(
+            &self,
+            request: Request<Streaming<IngestRecord>>,
+        ) -> Result<Response<PushRecordsResponse>, Status> {
+            let mut stream = request.into_inner();
+            let mut response = PushRecordsResponse::default();
+            while let Some(record) = stream.message().await? {
+                let payload = serde_json::from_slice(&record.payload_json).unwrap();
+                self.received.lock().unwrap().push((record.id, payload));
+                response.accepted += 1;
+            }
+            Ok(Response::new(response))
+        }
+    }
+
+    async fn serve() -> (String, Downstream) {
+        let downstream = Downstream::default();
+        let address = crate::executor::source::free_address();
+        let service = IngestServiceServer::new(downstream.clone());
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(service)
+                .serve(address),
+        );
+        (address.to_string(), downstream)
+    }
+
+    fn sink(endpoint: &str) -> GrpcSink {
+        GrpcSink::new(&GrpcSinkConfig {
+            endpoint: endpoint.to_string(),
+            timeout_ms: 1000,
+        })
+        .unwrap()
+    }
+
+    /// Delivers, retrying while the downstream server starts.
+    async fn deliver(sink: &GrpcSink, records: &[PipelineRecord]) -> anyhow::Result<()> {
+        let mut result = sink.deliver(records).await;
+        for _ in 0..100 {
+            match &result {
+                Err(e) if format!("{:?}", e).contains("transport error") => {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    result = sink.deliver(records).await;
+                }
+                _ => break,
+            }
+        }
+        result
+    }
+
+    #[tokio::test]
+    async fn test_grpc_sink_pushes_records() {
+        let (endpoint, downstream) = serve().await;
+        let records = vec![
+            PipelineRecord::new(json!({"id": "a", "score": 0.5})),
+            PipelineRecord::new(json!({"id": "b"})),
+        ];
+
+        deliver(&sink(&endpoint), &records).await.unwrap();
+
+        let received = downstream.received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        assert_eq!(
+            received[0],
+            ("a".to_string(), json!({"id": "a", "score": 0.5}))
+        );
+        assert_eq!(received[1].0, "b");
+    }
+}
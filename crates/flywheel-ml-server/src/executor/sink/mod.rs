@@ -0,0 +1,255 @@
+mod file;
+mod grpc;
+mod webhook;
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use flywheel_ml_dsl::condition::Condition;
+use flywheel_ml_dsl::{SinkConfig, SinkSpec};
+
+use super::record::PipelineRecord;
+
+pub use file::{FileSink, StdoutSink};
+pub use grpc::GrpcSink;
+pub use webhook::WebhookSink;
+
+/// Delivers records that leave the last stage of a pipeline.
+#[async_trait]
+pub trait Sink: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Delivers one batch. An error means none of the batch can be assumed delivered.
+    async fn deliver(&self, records: &[PipelineRecord]) -> anyhow::Result<()>;
+}
+
+/// Builds the connector for a sink output.
+pub fn build_sink(config: &SinkConfig) -> anyhow::Result<Box<dyn Sink>> {
+    let sink: Box<dyn Sink> = match config {
+        SinkConfig::File(c) => Box::new(FileSink::new(c)),
+        SinkConfig::Stdout => Box::new(StdoutSink),
+        SinkConfig::Webhook(c) => Box::new(WebhookSink::new(c)?),
+        SinkConfig::Grpc(c) => Box::new(GrpcSink::new(c)?),
+    };
+    Ok(sink)
+}
+
+/// Counters a sink updates as records are routed to it.
+#[derive(Debug, Default)]
+pub struct SinkStats {
+    records_matched: AtomicU64,
+    records_delivered: AtomicU64,
+    records_failed: AtomicU64,
+}
+
+impl SinkStats {
+    pub fn snapshot(&self) -> SinkResult {
+        SinkResult {
+            records_matched: self.records_matched.load(Ordering::Relaxed),
+            records_delivered: self.records_delivered.load(Ordering::Relaxed),
+            records_failed: self.records_failed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SinkResult {
+    pub records_matched: u64,
+    pub records_delivered: u64,
+    pub records_failed: u64,
+}
+
+struct SinkRoute {
+    name: String,
+    condition: Option<Condition>,
+    sink: Option<Box<dyn Sink>>,
+    stats: Arc<SinkStats>,
+}
+
+/// Evaluates each sink's condition per record and delivers the matches.
+pub struct SinkRouter {
+    routes: Vec<SinkRoute>,
+}
+
+impl SinkRouter {
+    pub fn from_specs(specs: &[SinkSpec]) -> anyhow::Result<Self> {
+        let routes = specs
+            .iter()
+            .map(|spec| {
+                // `all: true` and a missing condition both match every record;
+                // validation rejects sinks that set neither.
+                let condition = spec
+                    .condition
+                    .as_deref()
+                    .map(Condition::parse)
+                    .transpose()
+                    .with_context(|| format!("Invalid condition for sink '{}'", spec.name))?;
+                let sink = spec
+                    .output
+                    .as_ref()
+                    .map(build_sink)
+                    .transpose()
+                    .with_context(|| format!("Invalid output for sink '{}'", spec.name))?;
+
+                Ok(SinkRoute {
+                    name: spec.name.clone(),
+                    condition,
+                    sink,
+                    stats: Arc::new(SinkStats::default()),
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(Self { routes })
+    }
+
+    /// Replaces the output of the named sink, keeping its condition and stats.
+    #[cfg(test)]
+    pub fn set_output(&mut self, name: &str, sink: Box<dyn Sink>) {
+        let route = self
+            .routes
+            .iter_mut()
+            .find(|route| route.name == name)
+            .expect("sink is in the router");
+        route.sink = Some(sink);
+    }
+
+    pub fn stats(&self) -> Vec<(String, Arc<SinkStats>)> {
+        self.routes
+            .iter()
+            .map(|route| (route.name.clone(), route.stats.clone()))
+            .collect()
+    }
+
+    pub async fn deliver(&self, records: &[PipelineRecord]) {
+        for route in &self.routes {
+            let matched: Vec<PipelineRecord> = records
+                .iter()
+                .filter(|record| match &route.condition {
+                    Some(condition) => condition.evaluate(&record.payload),
+                    None => true,
+                })
+                .cloned()
+                .collect();
+            if matched.is_empty() {
+                continue;
+            }
+
+            let count = matched.len() as u64;
+            route.stats.records_matched.fetch_add(count, Ordering::Relaxed);
+
+            // Sinks without an output are consumed outside the server.
+            let Some(sink) = &route.sink else {
+                continue;
+            };
+
+            match sink.deliver(&matched).await {
+                Ok(()) => {
+                    route
+                        .stats
+                        .records_delivered
+                        .fetch_add(count, Ordering::Relaxed);
+                }
+                Err(e) => {
+                    route.stats.records_failed.fetch_add(count, Ordering::Relaxed);
+                    tracing::warn!(
+                        sink = %route.name,
+                        output = %sink.name(),
+                        records = count,
+                        error = %e,
+                        "Sink delivery failed"
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    /// Keeps delivered record ids.
+    struct MemorySink {
+        delivered: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl Sink for MemorySink {
+        fn name(&self) -> &str {
+            "memory"
+        }
+
+        async fn deliver(&self, records: &[PipelineRecord]) -> anyhow::Result<()> {
+            let mut delivered = self.delivered.lock().unwrap();
+            delivered.extend(records.iter().map(|r| r.id.clone()));
+            Ok(())
+        }
+    }
+
+    fn spec(name: &str, condition: Option<&str>) -> SinkSpec {
+        SinkSpec {
+            name: name.to_string(),
+            condition: condition.map(str::to_string),
+            all: condition.is_none(),
+            output: Some(SinkConfig::Stdout),
+        }
+    }
+
+    fn records() -> Vec<PipelineRecord> {
+        [("a", 0.9), ("b", 0.2), ("c", 0.95)]
+            .into_iter()
+            .map(|(id, score)| {
+                PipelineRecord::new(json!({"id": id, "prediction": {"score": score}}))
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_router_delivers_matching_records() {
+        let mut router = SinkRouter::from_specs(&[
+            spec("alerts", Some("prediction.score > 0.8")),
+            spec("archive", None),
+            SinkSpec {
+                output: None,
+                ..spec("external", Some("prediction.score < 0.5"))
+            },
+        ])
+        .unwrap();
+        let alerts = Arc::new(Mutex::new(Vec::new()));
+        let archive = Arc::new(Mutex::new(Vec::new()));
+        router.set_output(
+            "alerts",
+            Box::new(MemorySink {
+                delivered: alerts.clone(),
+            }),
+        );
+        router.set_output(
+            "archive",
+            Box::new(MemorySink {
+                delivered: archive.clone(),
+            }),
+        );
+
+        router.deliver(&records()).await;
+
+        assert_eq!(*alerts.lock().unwrap(), ["a", "c"]);
+        assert_eq!(*archive.lock().unwrap(), ["a", "b", "c"]);
+        let stats: HashMap<String, SinkResult> = router
+            .stats()
+            .into_iter()
+            .map(|(name, stats)| (name, stats.snapshot()))
+            .collect();
+        assert_eq!(stats["alerts"].records_matched, 2);
+        assert_eq!(stats["alerts"].records_delivered, 2);
+        assert_eq!(stats["archive"].records_delivered, 3);
+        // Consumed outside the server: matched, never delivered here.
+        assert_eq!(stats["external"].records_matched, 1);
+        assert_eq!(stats["external"].records_delivered, 0);
+    }
+}
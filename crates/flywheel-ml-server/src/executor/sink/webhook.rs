@@ -0,0 +1,176 @@
+use std::time::Duration;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use flywheel_ml_dsl::WebhookSinkConfig;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::StatusCode;
+
+use super::{PipelineRecord, Sink};
+
+/// POSTs each batch of records to a URL as a JSON array.
+///
+/// A POST that fails in a way that may pass later (no connection, a timeout,
+/// 429 or a 5xx) is retried up to `max_retries` times, waiting
+/// `retry_backoff_ms` before the first retry and twice as long each time
+/// after. Other responses fail the batch straight away.
+pub struct WebhookSink {
+    url: String,
+    client: reqwest::Client,
+    max_retries: u32,
+    retry_backoff: Duration,
+}
+
+impl WebhookSink {
+    pub fn new(config: &WebhookSinkConfig) -> anyhow::Result<Self> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &config.headers {
+            headers.insert(
+                HeaderName::from_bytes(name.as_bytes())
+                    .with_context(|| format!("Invalid header name: {}", name))?,
+                HeaderValue::from_str(value)
+                    .with_context(|| format!("Invalid value for header {}", name))?,
+            );
+        }
+
+        let client = reqwest::Client::builder()
+            .default_headers(headers)
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .build()?;
+
+        Ok(Self {
+            url: config.url.clone(),
+            client,
+            max_retries: config.max_retries,
+            retry_backoff: Duration::from_millis(config.retry_backoff_ms),
+        })
+    }
+
+    async fn post(&self, body: &[&serde_json::Value]) -> Result<(), reqwest::Error> {
+        self.client
+            .post(&self.url)
+            .json(body)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+fn is_retryable(error: &reqwest::Error) -> bool {
+    match error.status() {
+        Some(status) => status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS,
+        None => error.is_connect() || error.is_timeout() || error.is_request(),
+    }
+}
+
+#[async_trait]
+impl Sink for WebhookSink {
+    fn name(&self) -> &str {
+        "webhook"
+    }
+
+    async fn deliver(&self, records: &[PipelineRecord]) -> anyhow::Result<()> {
+        let body: Vec<&serde_json::Value> = records.iter().map(|r| &r.payload).collect();
+
+        let mut backoff = self.retry_backoff;
+        let mut attempt = 0;
+        loop {
+            let error = match self.post(&body).await {
+                Ok(()) => return Ok(()),
+                Err(e) => e,
+            };
+            if attempt == self.max_retries || !is_retryable(&error) {
+                return Err(error).with_context(|| {
+                    format!(
+                        "Failed to POST to {} after {} attempts",
+                        self.url,
+                        attempt + 1
+                    )
+                });
+            }
+            attempt += 1;
+            tracing::debug!(url = %self.url, attempt, error = %error, "Retrying webhook");
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::State;
+    use axum::http::HeaderMap as RequestHeaders;
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    /// Answers each POST with the next status, then 200, keeping what was sent.
+    #[derive(Clone, Default)]
+    struct Hook {
+        statuses: Arc<Mutex<Vec<u16>>>,
+        bodies: Arc<Mutex<Vec<serde_json::Value>>>,
+        tokens: Arc<Mutex<Vec<String>>>,
+    }
+
+    async fn receive(
+        State(hook): State<Hook>,
+        headers: RequestHeaders,
+        Json(body): Json<serde_json::Value>,
+    ) -> StatusCode {
+        let token = headers.get("x-token").and_then(|v| v.to_str().ok());
+        hook.tokens
+            .lock()
+            .unwrap()
+            .push(token.unwrap_or_default().to_string());
+        hook.bodies.lock().unwrap().push(body);
+        let mut statuses = hook.statuses.lock().unwrap();
+        match statuses.is_empty() {
+            true => StatusCode::OK,
+            false => StatusCode::from_u16(statuses.remove(0)).unwrap(),
+        }
+    }
+
+    async fn serve(statuses: &[u16]) -> (String, Hook) {
+        let hook = Hook::default();
+        hook.statuses.lock().unwrap().extend_from_slice(statuses);
+        let app = Router::new()
+            .route("/hook", post(receive))
+            .with_state(hook.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (url, hook)
+    }
+
+    fn sink(url: &str, max_retries: u32) -> WebhookSink {
+        WebhookSink::new(&WebhookSinkConfig {
+            url: url.to_string(),
+            headers: HashMap::from([("x-token".to_string(), "secret".to_string())]),
+            timeout_ms: 1000,
+            max_retries,
+            retry_backoff_ms: 1,
+        })
+        .unwrap()
+    }
+
+    fn records() -> Vec<PipelineRecord> {
+        vec![
+            PipelineRecord::new(json!({"id": "a"})),
+            PipelineRecord::new(json!({"id": "b"})),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_webhook_retries_transient_failures() {
+        let (url, hook) = serve(&[503, 429]).await;
+
+        sink(&url, 3).deliver(&records()).await.unwrap();
+
+        assert_eq!(hook.bodies.lock().unwrap().len(), 3);
+        assert_eq!(*hook.tokens.lock().unwrap(), ["secret"; 3]);
+    }
+}
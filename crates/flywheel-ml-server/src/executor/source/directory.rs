@@ -0,0 +1,207 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use flywheel_ml_dsl::{DirectorySourceConfig, RecordFormat};
+use tokio::io::BufReader;
+use tokio::sync::mpsc;
+
+use super::{forward_lines, PipelineRecord, Source};
+
+/// Reads every NDJSON or CSV file in a directory, in file name order.
+///
+/// When watching, a file is read once its size and modification time have
+/// held still for a poll, so one still being written is not read half way.
+pub struct DirectorySource {
+    path: PathBuf,
+    format: RecordFormat,
+    watch: bool,
+    poll_interval: Duration,
+}
+
+impl DirectorySource {
+    pub fn new(config: &DirectorySourceConfig) -> Self {
+        Self {
+            path: PathBuf::from(&config.path),
+            format: config.format,
+            watch: config.watch,
+            poll_interval: Duration::from_millis(config.poll_interval_ms),
+        }
+    }
+
+    async fn list_files(&self) -> anyhow::Result<Vec<PathBuf>> {
+        let mut entries = tokio::fs::read_dir(&self.path)
+            .await
+            .with_context(|| format!("Failed to read directory {}", self.path.display()))?;
+
+        let mut files = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let matches = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| self.format.extensions().contains(&ext));
+            if matches && entry.file_type().await?.is_file() {
+                files.push(path);
+            }
+        }
+
+        files.sort();
+        Ok(files)
+    }
+
+    async fn read_file(
+        &self,
+        path: &Path,
+        output: &mpsc::Sender<PipelineRecord>,
+    ) -> anyhow::Result<bool> {
+        match self.format {
+            RecordFormat::Ndjson => {
+                let file = tokio::fs::File::open(path).await?;
+                forward_lines(self.name(), BufReader::new(file), output).await
+            }
+            RecordFormat::Csv => {
+                let content = tokio::fs::read(path).await?;
+                for record in csv_records(&content)? {
+                    if output.send(PipelineRecord::new(record)).await.is_err() {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Source for DirectorySource {
+    fn name(&self) -> &str {
+        "directory"
+    }
+
+    async fn run(self: Box<Self>, output: mpsc::Sender<PipelineRecord>) -> anyhow::Result<()> {
+        let mut seen = HashSet::new();
+        let mut unsettled = HashMap::new();
+
+        loop {
+            for path in self.list_files().await? {
+                if seen.contains(&path) {
+                    continue;
+                }
+                if self.watch {
+                    let Some(state) = file_state(&path).await else {
+                        continue;
+                    };
+                    if unsettled.insert(path.clone(), state) != Some(state) {
+                        continue;
+                    }
+                    unsettled.remove(&path);
+                }
+                seen.insert(path.clone());
+
+                tracing::debug!(path = %path.display(), "Reading file");
+                match self.read_file(&path, &output).await {
+                    Ok(true) => {}
+                    Ok(false) => return Ok(()),
+                    Err(e) => {
+                        tracing::warn!(path = %path.display(), error = %e, "Failed to read file");
+                    }
+                }
+            }
+
+            if !self.watch || output.is_closed() {
+                return Ok(());
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+}
+
+/// The size and modification time of a file, if it still exists.
+async fn file_state(path: &Path) -> Option<(u64, Option<SystemTime>)> {
+    let metadata = tokio::fs::metadata(path).await.ok()?;
+    Some((metadata.len(), metadata.modified().ok()))
+}
+
+/// Converts CSV rows into JSON objects keyed by the header row.
+fn csv_records(content: &[u8]) -> anyhow::Result<Vec<serde_json::Value>> {
+    let mut reader = csv::Reader::from_reader(content);
+    let headers = reader.headers()?.clone();
+
+    reader
+        .records()
+        .map(|row| {
+            let row = row?;
+            let object = headers
+                .iter()
+                .zip(row.iter())
+                .map(|(name, field)| (name.to_string(), csv_value(field)))
+                .collect();
+            Ok(serde_json::Value::Object(object))
+        })
+        .collect()
+}
+
+fn csv_value(field: &str) -> serde_json::Value {
+    if field.is_empty() {
+        return serde_json::Value::Null;
+    }
+    if let Ok(v) = field.parse::<i64>() {
+        return v.into();
+    }
+    if let Ok(v) = field.parse::<f64>() {
+        return v.into();
+    }
+    if let Ok(v) = field.parse::<bool>() {
+        return v.into();
+    }
+    field.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn source(path: &Path, format: RecordFormat, watch: bool) -> Box<DirectorySource> {
+        Box::new(DirectorySource::new(&DirectorySourceConfig {
+            path: path.display().to_string(),
+            format,
+            watch,
+            poll_interval_ms: 200,
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_watched_directory_waits_for_files_to_settle() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("batch.jsonl");
+        let mut file = std::fs::File::create(&path).unwrap();
+
+        let (tx, mut rx) = mpsc::channel(64);
+        let task = tokio::spawn(source(dir.path(), RecordFormat::Ndjson, true).run(tx));
+
+        // Written across several polls, faster than the poll interval.
+        for i in 0..20 {
+            writeln!(file, "{{\"id\": \"{}\"}}", i).unwrap();
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        drop(file);
+
+        let mut ids = Vec::new();
+        while ids.len() < 20 {
+            let record = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+                .await
+                .expect("the file is read")
+                .unwrap();
+            ids.push(record.id);
+        }
+        let expected: Vec<String> = (0..20).map(|i| i.to_string()).collect();
+        assert_eq!(ids, expected);
+
+        drop(rx);
+        task.await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,161 @@
+use std::io::SeekFrom;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use flywheel_ml_dsl::FileSourceConfig;
+use tokio::fs::File;
+use tokio::io::{AsyncBufReadExt, AsyncSeekExt, BufReader};
+use tokio::sync::mpsc;
+
+use super::{parse_json_line, PipelineRecord, Source};
+
+/// Tails a JSON Lines file, emitting each complete line as it is appended.
+pub struct FileTailSource {
+    path: PathBuf,
+    from_beginning: bool,
+    poll_interval: Duration,
+}
+
+impl FileTailSource {
+    pub fn new(config: &FileSourceConfig) -> Self {
+        Self {
+            path: PathBuf::from(&config.path),
+            from_beginning: config.from_beginning,
+            poll_interval: Duration::from_millis(config.poll_interval_ms),
+        }
+    }
+
+    async fn open(&self, output: &mpsc::Sender<PipelineRecord>) -> Option<File> {
+        loop {
+            match File::open(&self.path).await {
+                Ok(file) => return Some(file),
+                Err(e) => {
+                    tracing::debug!(path = %self.path.display(), error = %e, "Waiting for file");
+                }
+            }
+            if output.is_closed() {
+                return None;
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+}
+
+#[async_trait]
+impl Source for FileTailSource {
+    fn name(&self) -> &str {
+        "file"
+    }
+
+    async fn run(self: Box<Self>, output: mpsc::Sender<PipelineRecord>) -> anyhow::Result<()> {
+        let Some(mut file) = self.open(&output).await else {
+            return Ok(());
+        };
+
+        let mut position = if self.from_beginning {
+            0
+        } else {
+            file.metadata().await?.len()
+        };
+        file.seek(SeekFrom::Start(position)).await?;
+
+        tracing::info!(path = %self.path.display(), position, "Tailing file");
+
+        let mut reader = BufReader::new(file);
+        let mut line = String::new();
+
+        loop {
+            let read = reader.read_line(&mut line).await?;
+            position += read as u64;
+
+            if !line.ends_with('\n') {
+                // At EOF, possibly holding a partial line. Start over if the file was truncated.
+                let len = tokio::fs::metadata(&self.path)
+                    .await
+                    .map(|m| m.len())
+                    .unwrap_or(0);
+                if len < position {
+                    tracing::info!(path = %self.path.display(), "File truncated, reading from start");
+                    let Some(file) = self.open(&output).await else {
+                        return Ok(());
+                    };
+                    reader = BufReader::new(file);
+                    position = 0;
+                    line.clear();
+                    continue;
+                }
+
+                if output.is_closed() {
+                    return Ok(());
+                }
+                tokio::time::sleep(self.poll_interval).await;
+                continue;
+            }
+
+            if let Some(value) = parse_json_line(self.name(), &line) {
+                if output.send(PipelineRecord::new(value)).await.is_err() {
+                    return Ok(());
+                }
+            }
+            line.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::path::Path;
+
+    fn source(path: &Path, from_beginning: bool) -> Box<FileTailSource> {
+        Box::new(FileTailSource::new(&FileSourceConfig {
+            path: path.display().to_string(),
+            from_beginning,
+            poll_interval_ms: 10,
+        }))
+    }
+
+    fn append(path: &Path, content: &str) {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+    }
+
+    async fn next_id(rx: &mut mpsc::Receiver<PipelineRecord>) -> String {
+        tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("a record is read")
+            .expect("source is running")
+            .id
+    }
+
+    #[tokio::test]
+    async fn test_file_source_follows_appends_and_truncation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("metrics.jsonl");
+        append(
+            &path,
+            "{\"id\": \"a\"}\nnot json\n{\"id\": \"b\"}\n{\"id\":",
+        );
+
+        let (tx, mut rx) = mpsc::channel(16);
+        let task = tokio::spawn(source(&path, true).run(tx));
+        assert_eq!(next_id(&mut rx).await, "a");
+        assert_eq!(next_id(&mut rx).await, "b");
+
+        // The partial line is held until it is finished.
+        append(&path, " \"c\"}\n");
+        assert_eq!(next_id(&mut rx).await, "c");
+
+        std::fs::write(&path, "{\"id\": \"d\"}\n").unwrap();
+        assert_eq!(next_id(&mut rx).await, "d");
+
+        drop(rx);
+        task.await.unwrap().unwrap();
+    }
+}
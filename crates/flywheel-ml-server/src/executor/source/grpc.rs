@@ -0,0 +1,85 @@
+use std::net::SocketAddr;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use flywheel_ml_dsl::GrpcSourceConfig;
+use flywheel_ml_proto::ingest_service_server::{IngestService, IngestServiceServer};
+use flywheel_ml_proto::{IngestRecord, PushRecordsResponse};
+use tokio::sync::mpsc;
+use tonic::{Request, Response, Status, Streaming};
+
+use super::{PipelineRecord, Source};
+
+/// Accepts records pushed through the `IngestService` gRPC API.
+pub struct GrpcSource {
+    bind_address: SocketAddr,
+}
+
+impl GrpcSource {
+    pub fn new(config: &GrpcSourceConfig) -> anyhow::Result<Self> {
+        Ok(Self {
+            bind_address: config
+                .bind_address
+                .parse()
+                .with_context(|| format!("Invalid bind_address: {}", config.bind_address))?,
+        })
+    }
+}
+
+#[async_trait]
+impl Source for GrpcSource {
+    fn name(&self) -> &str {
+        "grpc"
+    }
+
+    async fn run(self: Box<Self>, output: mpsc::Sender<PipelineRecord>) -> anyhow::Result<()> {
+        tracing::info!(address = %self.bind_address, "gRPC source listening");
+
+        let shutdown = output.clone();
+        tonic::transport::Server::builder()
+            .add_service(IngestServiceServer::new(IngestServiceImpl { output }))
+            .serve_with_shutdown(self.bind_address, async move { shutdown.closed().await })
+            .await?;
+        Ok(())
+    }
+}
+
+struct IngestServiceImpl {
+    output: mpsc::Sender<PipelineRecord>,
+}
+
+#[tonic::async_trait]
+impl IngestService for IngestServiceImpl {
+    async fn push_records(
+        &self,
+        request: Request<Streaming<IngestRecord>>,
+    ) -> Result<Response<PushRecordsResponse>, Status> {
+        let mut stream = request.into_inner();
+        let mut accepted = 0;
+        let mut rejected = 0;
+
+        while let Some(message) = stream.message().await? {
+            let payload: serde_json::Value = match serde_json::from_slice(&message.payload_json) {
+                Ok(payload) => payload,
+                Err(e) => {
+                    rejected += 1;
+                    tracing::debug!(error = %e, "Rejected pushed record with invalid JSON");
+                    continue;
+                }
+            };
+
+            let mut record = PipelineRecord::new(payload);
+            if !message.id.is_empty() {
+                record.id = message.id;
+            }
+
+            self.output
+                .send(record)
+                .await
+                .map_err(|_| Status::unavailable("pipeline is stopping"))?;
+            accepted += 1;
+        }
+
+        Ok(Response::new(PushRecordsResponse { accepted, rejected }))
+    }
+}
@@ -0,0 +1,142 @@
+use std::net::SocketAddr;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::post;
+use axum::{Json, Router};
+use flywheel_ml_dsl::HttpSourceConfig;
+use tokio::sync::mpsc;
+
+use super::{PipelineRecord, Source};
+
+/// Accepts records POSTed as a JSON object, a JSON array or NDJSON.
+pub struct HttpSource {
+    bind_address: SocketAddr,
+    path: String,
+}
+
+impl HttpSource {
+    pub fn new(config: &HttpSourceConfig) -> anyhow::Result<Self> {
+        Ok(Self {
+            bind_address: config
+                .bind_address
+                .parse()
+                .with_context(|| format!("Invalid bind_address: {}", config.bind_address))?,
+            path: config.path.clone(),
+        })
+    }
+}
+
+#[async_trait]
+impl Source for HttpSource {
+    fn name(&self) -> &str {
+        "http"
+    }
+
+    async fn run(self: Box<Self>, output: mpsc::Sender<PipelineRecord>) -> anyhow::Result<()> {
+        let listener = tokio::net::TcpListener::bind(self.bind_address).await?;
+        tracing::info!(address = %self.bind_address, path = %self.path, "HTTP source listening");
+
+        let shutdown = output.clone();
+        let app = Router::new()
+            .route(&self.path, post(push_records))
+            .with_state(output);
+
+        axum::serve(listener, app)
+            .with_graceful_shutdown(async move { shutdown.closed().await })
+            .await?;
+        Ok(())
+    }
+}
+
+async fn push_records(
+    State(output): State<mpsc::Sender<PipelineRecord>>,
+    body: String,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let records = match parse_body(&body) {
+        Ok(records) => records,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+        }
+    };
+
+    let accepted = records.len();
+    for record in records {
+        if output.send(PipelineRecord::new(record)).await.is_err() {
+            return (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(serde_json::json!({ "error": "pipeline is stopping" })),
+            );
+        }
+    }
+
+    (
+        StatusCode::ACCEPTED,
+        Json(serde_json::json!({ "accepted": accepted })),
+    )
+}
+
+fn parse_body(body: &str) -> Result<Vec<serde_json::Value>, serde_json::Error> {
+    match serde_json::from_str(body) {
+        Ok(serde_json::Value::Array(items)) => Ok(items),
+        Ok(value) => Ok(vec![value]),
+        // Not a single JSON document: treat the body as NDJSON.
+        Err(_) => body
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_http_source_accepts_posted_records() {
+        let address = super::super::free_address();
+        let source = HttpSource::new(&HttpSourceConfig {
+            bind_address: address.to_string(),
+            path: "/ingest".to_string(),
+        })
+        .unwrap();
+        let (tx, mut rx) = mpsc::channel(16);
+        let task = tokio::spawn(Box::new(source).run(tx));
+
+        let client = reqwest::Client::new();
+        let url = format!("http://{}/ingest", address);
+        let post = |body: &'static str| client.post(&url).body(body).send();
+        let mut response = post("[{\"id\": \"a\"}, {\"id\": \"b\"}]").await;
+        for _ in 0..100 {
+            if response.is_ok() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            response = post("[{\"id\": \"a\"}, {\"id\": \"b\"}]").await;
+        }
+        let response = response.unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["accepted"], 2);
+        assert_eq!(rx.recv().await.unwrap().id, "a");
+        assert_eq!(rx.recv().await.unwrap().id, "b");
+
+        let response = post("{\"id\": \"c\"}\n{\"id\": \"d\"}\n").await.unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        assert_eq!(rx.recv().await.unwrap().id, "c");
+        assert_eq!(rx.recv().await.unwrap().id, "d");
+
+        let response = post("{\"id\":").await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(rx.try_recv().is_err());
+
+        drop(rx);
+        task.await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,91 @@
+mod directory;
+mod file;
+mod grpc;
+mod http;
+mod stream;
+
+use async_trait::async_trait;
+use flywheel_ml_dsl::{SourceConfig, SourceSpec};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+use tokio::sync::mpsc;
+
+use super::record::PipelineRecord;
+
+pub use directory::DirectorySource;
+pub use file::FileTailSource;
+pub use grpc::GrpcSource;
+pub use http::HttpSource;
+pub use stream::{StdinSource, UnixSocketSource};
+
+/// Produces the records that enter the first stage of a pipeline.
+#[async_trait]
+pub trait Source: Send {
+    fn name(&self) -> &str;
+
+    /// Reads records into `output` until the source is exhausted or `output` closes.
+    async fn run(self: Box<Self>, output: mpsc::Sender<PipelineRecord>) -> anyhow::Result<()>;
+}
+
+/// Builds the connector for a manifest source. Named sources are fed outside
+/// the server and have no connector.
+pub fn build_source(spec: &SourceSpec) -> anyhow::Result<Option<Box<dyn Source>>> {
+    let config = match spec {
+        SourceSpec::Named(_) => return Ok(None),
+        SourceSpec::Connector(config) => config,
+    };
+
+    let source: Box<dyn Source> = match config {
+        SourceConfig::File(c) => Box::new(FileTailSource::new(c)),
+        SourceConfig::Directory(c) => Box::new(DirectorySource::new(c)),
+        SourceConfig::Stdin => Box::new(StdinSource),
+        SourceConfig::UnixSocket(c) => Box::new(UnixSocketSource::new(c)),
+        SourceConfig::Http(c) => Box::new(HttpSource::new(c)?),
+        SourceConfig::Grpc(c) => Box::new(GrpcSource::new(c)?),
+    };
+
+    Ok(Some(source))
+}
+
+/// Parses one JSON Lines entry, logging and skipping lines that are not JSON.
+fn parse_json_line(source: &str, line: &str) -> Option<serde_json::Value> {
+    let line = line.trim();
+    if line.is_empty() {
+        return None;
+    }
+
+    match serde_json::from_str(line) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            tracing::warn!(source = %source, error = %e, "Skipping invalid JSON record");
+            None
+        }
+    }
+}
+
+/// Forwards JSON Lines from `reader` until EOF. Returns `false` once `output` has closed.
+async fn forward_lines<R>(
+    source: &str,
+    reader: R,
+    output: &mpsc::Sender<PipelineRecord>,
+) -> anyhow::Result<bool>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut lines = reader.lines();
+    while let Some(line) = lines.next_line().await? {
+        if let Some(value) = parse_json_line(source, &line) {
+            if output.send(PipelineRecord::new(value)).await.is_err() {
+                return Ok(false);
+            }
+        }
+    }
+    Ok(true)
+}
+
+/// A loopback address with a port that was free a moment ago.
+#[cfg(test)]
+pub(crate) fn free_address() -> std::net::SocketAddr {
+    std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .unwrap()
+}
@@ -0,0 +1,125 @@
+use std::os::unix::fs::FileTypeExt;
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use flywheel_ml_dsl::UnixSocketSourceConfig;
+use tokio::io::BufReader;
+use tokio::net::UnixListener;
+use tokio::sync::mpsc;
+
+use super::{forward_lines, PipelineRecord, Source};
+
+/// Reads JSON Lines from the server's standard input until EOF.
+pub struct StdinSource;
+
+#[async_trait]
+impl Source for StdinSource {
+    fn name(&self) -> &str {
+        "stdin"
+    }
+
+    async fn run(self: Box<Self>, output: mpsc::Sender<PipelineRecord>) -> anyhow::Result<()> {
+        forward_lines(self.name(), BufReader::new(tokio::io::stdin()), &output).await?;
+        Ok(())
+    }
+}
+
+/// Accepts connections on a Unix domain socket, each streaming JSON Lines.
+pub struct UnixSocketSource {
+    path: PathBuf,
+}
+
+impl UnixSocketSource {
+    pub fn new(config: &UnixSocketSourceConfig) -> Self {
+        Self {
+            path: PathBuf::from(&config.path),
+        }
+    }
+}
+
+#[async_trait]
+impl Source for UnixSocketSource {
+    fn name(&self) -> &str {
+        "unix-socket"
+    }
+
+    async fn run(self: Box<Self>, output: mpsc::Sender<PipelineRecord>) -> anyhow::Result<()> {
+        // A socket left behind by a previous run would make bind fail. Anything
+        // else at the path is not ours to remove.
+        match tokio::fs::symlink_metadata(&self.path).await {
+            Ok(metadata) if metadata.file_type().is_socket() => {
+                tokio::fs::remove_file(&self.path).await?;
+            }
+            Ok(_) => anyhow::bail!("{} exists and is not a socket", self.path.display()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        let listener = UnixListener::bind(&self.path)?;
+        tracing::info!(path = %self.path.display(), "Listening on Unix socket");
+
+        loop {
+            let stream = tokio::select! {
+                accepted = listener.accept() => accepted?.0,
+                _ = output.closed() => break,
+            };
+
+            let output = output.clone();
+            tokio::spawn(async move {
+                if let Err(e) = forward_lines("unix-socket", BufReader::new(stream), &output).await
+                {
+                    tracing::warn!(error = %e, "Unix socket connection failed");
+                }
+            });
+        }
+
+        let _ = tokio::fs::remove_file(&self.path).await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use std::time::Duration;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::UnixStream;
+
+    fn source(path: &Path) -> Box<UnixSocketSource> {
+        Box::new(UnixSocketSource::new(&UnixSocketSourceConfig {
+            path: path.display().to_string(),
+        }))
+    }
+
+    async fn connect(path: &Path) -> UnixStream {
+        for _ in 0..100 {
+            if let Ok(stream) = UnixStream::connect(path).await {
+                return stream;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("source never listened on {}", path.display());
+    }
+
+    #[tokio::test]
+    async fn test_unix_socket_source_reads_each_connection() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ingest.sock");
+        // A socket left behind by an earlier run is replaced.
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+
+        let (tx, mut rx) = mpsc::channel(16);
+        let task = tokio::spawn(source(&path).run(tx));
+
+        for id in ["a", "b"] {
+            let mut stream = connect(&path).await;
+            let line = format!("{{\"id\": \"{}\"}}\nnot json\n", id);
+            stream.write_all(line.as_bytes()).await.unwrap();
+            assert_eq!(rx.recv().await.unwrap().id, id);
+        }
+
+        drop(rx);
+        task.await.unwrap().unwrap();
+        assert!(!path.exists());
+    }
+}
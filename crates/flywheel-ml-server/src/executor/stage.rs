@@ -1,26 +1,166 @@
-use flywheel_ml_db::Database;
-use flywheel_ml_dsl::{FlywheelStage, FlywheelStageType};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use anyhow::Context;
+use flywheel_ml_core::{FeatureVector, ModelError, Prediction, PredictionResult};
+use flywheel_ml_db::{Database, DeadLetterRepo, PredictionRepo};
+use flywheel_ml_drift::PerformanceRegistry;
+use flywheel_ml_dsl::{
+    DriftDetectionConfig, FeatureExtractionConfig, FlywheelPipelineSpec, FlywheelStage,
+    FlywheelStageType,
+};
+use flywheel_ml_transform::fallback::{Fallback, FallbackCounts, InferenceFallback};
+use flywheel_ml_transform::{FeatureExtractionTransform, FieldMapping, JsonPathFeatureExtractor};
+use tokio::sync::mpsc;
 use uuid::Uuid;
 
+use super::drift::DriftMonitor;
+use super::feedback::FeedbackJoin;
+use super::model::{ActiveModel, BanditRegistry, Variant};
+use super::record::PipelineRecord;
+
+/// Maximum number of queued records a stage pulls from its input per step.
+const STAGE_BATCH_SIZE: usize = 64;
+
 pub struct StageContext {
     pub pipeline_id: Uuid,
     pub pipeline_name: String,
     pub namespace: String,
     pub db: Database,
+    pub spec: Arc<FlywheelPipelineSpec>,
+    /// Active model of each ml-inference stage, keyed by stage id.
+    pub models: Arc<HashMap<String, Arc<ActiveModel>>>,
+    /// Live performance of every model version, shared across pipelines.
+    pub performance: Arc<PerformanceRegistry>,
+    /// Bandits of every running pipeline, rewarded by feedback.
+    pub bandits: Arc<BanditRegistry>,
+}
+
+#[cfg(test)]
+impl StageContext {
+    /// The context the stages of `pipeline` run in, as its runner builds it.
+    pub(crate) fn for_pipeline(
+        pipeline: &flywheel_ml_db::entity::pipeline::Model,
+        db: Database,
+    ) -> Self {
+        let manifest = flywheel_ml_dsl::parser::parse_manifest(&pipeline.spec_yaml).unwrap();
+        let models = super::runner::build_models(&manifest.spec.stages).unwrap();
+        let bandits = Arc::new(BanditRegistry::new());
+        bandits.register(pipeline.id, models.values());
+        Self {
+            pipeline_id: pipeline.id,
+            pipeline_name: pipeline.name.clone(),
+            namespace: pipeline.namespace.clone(),
+            db,
+            models: Arc::new(models),
+            spec: Arc::new(manifest.spec),
+            performance: Arc::new(PerformanceRegistry::new(std::time::Duration::from_secs(60))),
+            bandits,
+        }
+    }
 }
 
 pub struct StageExecutor {
     stage: FlywheelStage,
     ctx: StageContext,
+    stats: Arc<StageStats>,
+    feature_extraction: Option<FeatureExtraction>,
+    model: Option<Arc<ActiveModel>>,
+    drift: Option<DriftMonitor>,
+    feedback: Option<FeedbackJoin>,
+}
+
+struct FeatureExtraction {
+    transform: FeatureExtractionTransform,
+    include_raw: bool,
+}
+
+impl FeatureExtraction {
+    fn from_config(stage_id: &str, config: &FeatureExtractionConfig) -> anyhow::Result<Self> {
+        let fields = config
+            .features
+            .iter()
+            .map(|def| {
+                let field = FieldMapping::new(&def.name, &def.source_field)
+                    .with_context(|| format!("Invalid source_field for feature '{}'", def.name))?
+                    .with_nullable(def.nullable);
+                Ok(match &def.transform {
+                    Some(spec) => field.with_transform(spec.into()),
+                    None => field,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let extractor = JsonPathFeatureExtractor::new(stage_id, fields);
+        Ok(Self {
+            transform: FeatureExtractionTransform::new(Box::new(extractor)),
+            include_raw: config.include_raw,
+        })
+    }
+}
+
+/// Counters a stage updates as records flow through it.
+#[derive(Debug, Default)]
+pub struct StageStats {
+    records_received: AtomicU64,
+    records_processed: AtomicU64,
+    records_failed: AtomicU64,
+    /// Records a blocking drift-detection stage parked while drift was open.
+    records_blocked: AtomicU64,
+    /// The inference fallback of an ml-inference stage, which counts what it applied.
+    fallback: Option<Arc<InferenceFallback>>,
+}
+
+impl StageStats {
+    pub fn snapshot(&self) -> StageResult {
+        StageResult {
+            records_received: self.records_received.load(Ordering::Relaxed),
+            records_processed: self.records_processed.load(Ordering::Relaxed),
+            records_failed: self.records_failed.load(Ordering::Relaxed),
+            records_blocked: self.records_blocked.load(Ordering::Relaxed),
+            fallbacks: self.fallback.as_ref().map(|f| f.counts()).unwrap_or_default(),
+        }
+    }
 }
 
+#[derive(Debug, Clone, Copy, Default)]
 pub struct StageResult {
+    pub records_received: u64,
     pub records_processed: u64,
     pub records_failed: u64,
+    pub records_blocked: u64,
+    pub fallbacks: FallbackCounts,
 }
 
 impl StageExecutor {
     pub fn for_stage(stage: &FlywheelStage, ctx: &StageContext) -> anyhow::Result<Self> {
+        let feature_extraction = match stage.stage_type {
+            FlywheelStageType::FeatureExtraction => {
+                let config: FeatureExtractionConfig = serde_json::from_value(stage.config.clone())
+                    .with_context(|| format!("Invalid config for stage '{}'", stage.id))?;
+                Some(FeatureExtraction::from_config(&stage.id, &config)?)
+            }
+            _ => None,
+        };
+
+        let model = ctx.models.get(&stage.id).cloned();
+
+        let drift = match stage.stage_type {
+            FlywheelStageType::DriftDetection => {
+                let config: DriftDetectionConfig = serde_json::from_value(stage.config.clone())
+                    .with_context(|| format!("Invalid config for stage '{}'", stage.id))?;
+                Some(DriftMonitor::new(stage, &config, ctx)?)
+            }
+            _ => None,
+        };
+
+        let feedback = match stage.stage_type {
+            FlywheelStageType::FeedbackJoin => Some(FeedbackJoin::new(stage, ctx)?),
+            _ => None,
+        };
+
         Ok(Self {
             stage: stage.clone(),
             ctx: StageContext {
@@ -28,77 +168,760 @@ impl StageExecutor {
                 pipeline_name: ctx.pipeline_name.clone(),
                 namespace: ctx.namespace.clone(),
                 db: ctx.db.clone(),
+                spec: ctx.spec.clone(),
+                models: ctx.models.clone(),
+                performance: ctx.performance.clone(),
+                bandits: ctx.bandits.clone(),
             },
+            stats: Arc::new(StageStats {
+                fallback: model.as_ref().map(|m| m.fallback()),
+                ..Default::default()
+            }),
+            feature_extraction,
+            model,
+            drift,
+            feedback,
         })
     }
 
-    pub async fn execute(&self) -> anyhow::Result<StageResult> {
+    pub fn stage(&self) -> &FlywheelStage {
+        &self.stage
+    }
+
+    pub fn stats(&self) -> Arc<StageStats> {
+        self.stats.clone()
+    }
+
+    /// Consumes records from `input` until it closes, forwarding results to `output`.
+    ///
+    /// Sending blocks while `output` is full, so a slow stage applies backpressure
+    /// to everything upstream of it.
+    pub async fn run(
+        self,
+        mut input: mpsc::Receiver<PipelineRecord>,
+        output: mpsc::Sender<PipelineRecord>,
+    ) {
+        let mut batch = Vec::with_capacity(STAGE_BATCH_SIZE);
+
+        while input.recv_many(&mut batch, STAGE_BATCH_SIZE).await > 0 {
+            let received = batch.len() as u64;
+            self.stats
+                .records_received
+                .fetch_add(received, Ordering::Relaxed);
+
+            let records = match self.execute(std::mem::take(&mut batch)).await {
+                Ok(records) => records,
+                Err(e) => {
+                    self.stats
+                        .records_failed
+                        .fetch_add(received, Ordering::Relaxed);
+                    tracing::warn!(
+                        pipeline_id = %self.ctx.pipeline_id,
+                        stage_id = %self.stage.id,
+                        records = received,
+                        error = %e,
+                        "Stage execution failed"
+                    );
+                    continue;
+                }
+            };
+
+            self.stats
+                .records_processed
+                .fetch_add(records.len() as u64, Ordering::Relaxed);
+
+            for record in records {
+                if output.send(record).await.is_err() {
+                    tracing::debug!(stage_id = %self.stage.id, "Downstream closed, stopping stage");
+                    return;
+                }
+            }
+        }
+
+        tracing::debug!(stage_id = %self.stage.id, "Input closed, stage finished");
+    }
+
+    async fn execute(&self, input: Vec<PipelineRecord>) -> anyhow::Result<Vec<PipelineRecord>> {
         match self.stage.stage_type {
-            FlywheelStageType::FeatureExtraction => self.execute_feature_extraction().await,
-            FlywheelStageType::MlInference => self.execute_inference().await,
-            FlywheelStageType::DriftDetection => self.execute_drift_detection().await,
-            FlywheelStageType::FeedbackJoin => self.execute_feedback_join().await,
-            FlywheelStageType::TrainingExport => self.execute_training_export().await,
+            FlywheelStageType::FeatureExtraction => self.execute_feature_extraction(input).await,
+            FlywheelStageType::MlInference => self.execute_inference(input).await,
+            FlywheelStageType::DriftDetection => self.execute_drift_detection(input).await,
+            FlywheelStageType::FeedbackJoin => self.execute_feedback_join(input).await,
+            FlywheelStageType::TrainingExport => self.execute_training_export(input).await,
         }
     }
 
-    async fn execute_feature_extraction(&self) -> anyhow::Result<StageResult> {
+    async fn execute_feature_extraction(
+        &self,
+        input: Vec<PipelineRecord>,
+    ) -> anyhow::Result<Vec<PipelineRecord>> {
         tracing::trace!(
             stage_id = %self.stage.id,
+            records = input.len(),
             "Executing feature extraction"
         );
 
-        Ok(StageResult {
-            records_processed: 0,
-            records_failed: 0,
-        })
+        let extraction = self
+            .feature_extraction
+            .as_ref()
+            .context("Feature extraction stage was built without an extractor")?;
+
+        let mut records = Vec::with_capacity(input.len());
+
+        for record in input {
+            match extraction.transform.process(&record.payload).await {
+                Ok(mut features) => {
+                    features.source_record_id = record.id.clone();
+
+                    // Downstream stages and sinks see the extracted features, plus the
+                    // original record under `raw` when `include_raw` is set.
+                    let mut payload = serde_json::Map::new();
+                    for (name, value) in &features.features {
+                        payload.insert(name.clone(), serde_json::to_value(value)?);
+                    }
+                    if extraction.include_raw {
+                        payload.insert("raw".to_string(), record.payload);
+                    }
+
+                    records.push(PipelineRecord {
+                        id: record.id,
+                        payload: serde_json::Value::Object(payload),
+                        features: Some(features),
+                        prediction: None,
+                        replay_of: record.replay_of,
+                    });
+                }
+                Err(e) => {
+                    self.stats.records_failed.fetch_add(1, Ordering::Relaxed);
+                    tracing::debug!(
+                        stage_id = %self.stage.id,
+                        record_id = %record.id,
+                        error = %e,
+                        "Feature extraction failed for record"
+                    );
+                }
+            }
+        }
+
+        Ok(records)
     }
 
-    async fn execute_inference(&self) -> anyhow::Result<StageResult> {
+    async fn execute_inference(
+        &self,
+        input: Vec<PipelineRecord>,
+    ) -> anyhow::Result<Vec<PipelineRecord>> {
         tracing::trace!(
             stage_id = %self.stage.id,
+            records = input.len(),
             "Executing ML inference"
         );
 
-        Ok(StageResult {
-            records_processed: 0,
-            records_failed: 0,
-        })
+        let model = self
+            .model
+            .as_ref()
+            .context("Inference stage was built without a model")?;
+        let config = model.config();
+        // Resolve the model once so a fallback swap never splits a batch.
+        let transform = model.current();
+
+        let mut records = input;
+        // Records without extracted features have nothing to predict on and pass through.
+        let pending: Vec<usize> = (0..records.len())
+            .filter(|&i| records[i].features.is_some())
+            .collect();
+
+        let fallback = model.fallback();
+        // Records a fallback diverted or dropped; they do not continue downstream.
+        let mut removed = vec![false; records.len()];
+
+        let vectors: Vec<FeatureVector> = pending
+            .iter()
+            .map(|&i| select_features(records[i].features.as_ref().unwrap(), &config.input_features))
+            .collect();
+
+        // The models records can be served by: the stage's model first, then
+        // the canary or the bandit's other arms. A stage has one or the other.
+        // The bandit sits out while a drift fallback serves in place of the
+        // configured model, so its arm 0 learns nothing about the fallback.
+        let mut servers = vec![(Variant::Primary, transform.clone())];
+        let canary = model.canary();
+        if let Some(canary) = canary {
+            servers.push((Variant::Canary, canary.transform()));
+        }
+        let bandit = model.bandit().filter(|_| !model.is_swapped());
+        if let Some(bandit) = bandit {
+            servers.extend(bandit.arms().map(|arm| (Variant::Bandit, arm)));
+        }
+        let routes: Vec<usize> = pending
+            .iter()
+            .map(|&i| match (canary, bandit) {
+                (Some(canary), _) => usize::from(canary.serves(&records[i])),
+                (None, Some(bandit)) => bandit.allocator().choose(),
+                (None, None) => 0,
+            })
+            .collect();
+
+        // The transform batches records by `batch_size`; each batch succeeds or fails alone.
+        let batches = servers.iter().enumerate().map(|(server, (_, transform))| {
+            let vectors = vectors
+                .iter()
+                .zip(&routes)
+                .filter(|(_, &route)| route == server)
+                .map(|(v, _)| v.clone())
+                .collect();
+            timed(transform.process_batch(vectors))
+        });
+        let shadow = model.shadow();
+        let (served_results, shadow_results) = tokio::join!(
+            futures::future::join_all(batches),
+            async {
+                match &shadow {
+                    Some(shadow) => Some(timed(shadow.process_batch(vectors.clone())).await),
+                    None => None,
+                }
+            },
+        );
+        // Failed calls count as taking as long as the model they went to did.
+        let elapsed_ms: Vec<u64> = served_results.iter().map(|(_, ms)| *ms).collect();
+        let mut served_results: Vec<_> = served_results
+            .into_iter()
+            .map(|(results, _)| results.into_iter())
+            .collect();
+        let results = routes.iter().map(|&route| {
+            let result = served_results[route].next().expect("one result per record");
+            (route, result)
+        });
+
+        let store = self.ctx.spec.feedback.is_some() || model.has_variants();
+        let mut failed = 0;
+        let mut first_error = None;
+        for ((&i, features), (route, result)) in pending.iter().zip(&vectors).zip(results) {
+            let (variant, server) = &servers[route];
+            let e = match result {
+                Ok(mut prediction) => {
+                    model.note_version(&prediction);
+                    self.record_latency(&prediction);
+                    fallback.remember(features, &prediction);
+                    if store {
+                        self.store_prediction(&mut prediction, &records[i], *variant, None)
+                            .await;
+                    }
+                    let record = &mut records[i];
+                    if let Some(payload) = record.payload.as_object_mut() {
+                        payload.insert(config.output_field.clone(), prediction_payload(&prediction));
+                    }
+                    record.prediction = Some(prediction);
+                    continue;
+                }
+                Err(e) => e,
+            };
+
+            let model_id = server.model_id().to_string();
+            let version = model.version_of(&model_id);
+            self.ctx
+                .performance
+                .record_error(&model_id, &version, elapsed_ms[route]);
+            failed += 1;
+            first_error.get_or_insert_with(|| (model_id.clone(), e.to_string()));
+
+            let output = match fallback.resolve(features) {
+                Fallback::PassThrough => continue,
+                Fallback::Value(value) => value,
+                Fallback::LastKnown(prediction) => {
+                    let mut value = prediction_payload(&prediction);
+                    if let Some(object) = value.as_object_mut() {
+                        object.insert("fallback".to_string(), "use_last_known".into());
+                    }
+                    value
+                }
+                Fallback::DeadLetter => {
+                    // A record that cannot be parked passes through instead.
+                    removed[i] = self.dead_letter(&records[i], &model_id, &e.to_string()).await;
+                    continue;
+                }
+                Fallback::Drop => {
+                    // A replayed record goes back to its dead letter instead of being lost.
+                    if records[i].replay_of.is_some() {
+                        self.dead_letter(&records[i], &model_id, &e.to_string()).await;
+                    }
+                    removed[i] = true;
+                    continue;
+                }
+            };
+            if let Some(payload) = records[i].payload.as_object_mut() {
+                payload.insert(config.output_field.clone(), output);
+            }
+        }
+
+        if let Some((model_id, error)) = first_error {
+            self.stats.records_failed.fetch_add(failed, Ordering::Relaxed);
+            tracing::warn!(
+                stage_id = %self.stage.id,
+                model_id = %model_id,
+                records = failed,
+                fallback = %fallback.strategy().type_name(),
+                error = %error,
+                "Inference failed, applied fallback"
+            );
+        }
+
+        if let (Some(shadow), Some((results, elapsed_ms))) = (shadow, shadow_results) {
+            let shadow_id = shadow.model_id();
+            self.store_shadow_predictions(model, shadow_id, &pending, &records, results, elapsed_ms)
+                .await;
+        }
+
+        // Only replayed records that continue downstream were replayed.
+        for (record, _) in records.iter_mut().zip(&removed).filter(|(_, &removed)| !removed) {
+            if let Some(dead_letter_id) = record.replay_of.take() {
+                self.mark_replayed(dead_letter_id, &record.id).await;
+            }
+        }
+
+        Ok(records
+            .into_iter()
+            .zip(removed)
+            .filter_map(|(record, removed)| (!removed).then_some(record))
+            .collect())
+    }
+
+    /// Stores the shadow model's predictions, each linked to the prediction
+    /// served for its record. They are not written to the records, so they
+    /// never reach sinks, and a shadow failure never affects a record.
+    async fn store_shadow_predictions(
+        &self,
+        model: &ActiveModel,
+        model_id: &str,
+        pending: &[usize],
+        records: &[PipelineRecord],
+        results: Vec<Result<Prediction, ModelError>>,
+        elapsed_ms: u64,
+    ) {
+        let mut failed = 0;
+        let mut first_error = None;
+        for (&i, result) in pending.iter().zip(results) {
+            match result {
+                Ok(mut prediction) => {
+                    model.note_version(&prediction);
+                    self.record_latency(&prediction);
+                    let served = records[i]
+                        .prediction
+                        .as_ref()
+                        .and_then(|p| Uuid::parse_str(&p.prediction_id).ok());
+                    self.store_prediction(&mut prediction, &records[i], Variant::Shadow, served)
+                        .await;
+                }
+                Err(e) => {
+                    let version = model.version_of(model_id);
+                    self.ctx.performance.record_error(model_id, &version, elapsed_ms);
+                    failed += 1;
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+
+        if let Some(error) = first_error {
+            tracing::debug!(
+                stage_id = %self.stage.id,
+                model_id = %model_id,
+                records = failed,
+                error = %error,
+                "Shadow inference failed"
+            );
+        }
+    }
+
+    /// Parks a record in the dead-letter queue with its features, so it can be
+    /// replayed through this stage. A replayed record goes back to its dead
+    /// letter, pending again. Returns whether it was stored.
+    async fn dead_letter(
+        &self,
+        record: &PipelineRecord,
+        model_id: &str,
+        error: &str,
+    ) -> bool {
+        let stored = match record.replay_of {
+            Some(id) => {
+                DeadLetterRepo::return_to_pending(self.ctx.db.conn(), id, error.to_string())
+                    .await
+                    .map(|_| ())
+            }
+            None => {
+                let features =
+                    record.features.as_ref().and_then(|f| serde_json::to_value(f).ok());
+                DeadLetterRepo::create(
+                    self.ctx.db.conn(),
+                    self.ctx.pipeline_id,
+                    self.stage.id.clone(),
+                    record.id.clone(),
+                    model_id.to_string(),
+                    record.payload.clone(),
+                    features,
+                    error.to_string(),
+                )
+                .await
+                .map(|_| ())
+            }
+        };
+        match stored {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::warn!(
+                    stage_id = %self.stage.id,
+                    record_id = %record.id,
+                    error = %e,
+                    "Failed to store dead letter, passing record through"
+                );
+                false
+            }
+        }
+    }
+
+    /// Marks the dead letters replayed records came from as replayed, now
+    /// that this stage has accepted them. Stages further on treat the records
+    /// as new ones.
+    async fn accept_replayed(&self, records: &mut [PipelineRecord]) {
+        for record in records {
+            if let Some(dead_letter_id) = record.replay_of.take() {
+                self.mark_replayed(dead_letter_id, &record.id).await;
+            }
+        }
+    }
+
+    /// Marks the dead letter a replayed record came from as replayed.
+    async fn mark_replayed(&self, dead_letter_id: Uuid, record_id: &str) {
+        if let Err(e) = DeadLetterRepo::mark_replayed(self.ctx.db.conn(), dead_letter_id).await {
+            tracing::warn!(
+                stage_id = %self.stage.id,
+                record_id = %record_id,
+                error = %e,
+                "Failed to mark dead letter replayed"
+            );
+        }
     }
 
-    async fn execute_drift_detection(&self) -> anyhow::Result<StageResult> {
+    /// Counts a prediction the model made towards its live performance.
+    fn record_latency(&self, prediction: &Prediction) {
+        self.ctx.performance.record_latency(
+            &prediction.model_id,
+            &prediction.model_version,
+            prediction.latency_us / 1000,
+        );
+    }
+
+    /// Persists a prediction so feedback can later be joined with it. The
+    /// stored row's id becomes the prediction id.
+    async fn store_prediction(
+        &self,
+        prediction: &mut Prediction,
+        record: &PipelineRecord,
+        variant: Variant,
+        shadow_of: Option<Uuid>,
+    ) {
+        let features = record
+            .features
+            .as_ref()
+            .and_then(|f| serde_json::to_value(&f.features).ok())
+            .unwrap_or_default();
+        prediction.metadata.insert("variant".to_string(), variant.as_str().to_string());
+        match PredictionRepo::create_variant(
+            self.ctx.db.conn(),
+            self.ctx.pipeline_id,
+            prediction.model_id.clone(),
+            prediction.model_version.clone(),
+            features,
+            prediction_payload(prediction),
+            variant.as_str().to_string(),
+            shadow_of,
+        )
+        .await
+        {
+            Ok(stored) => prediction.prediction_id = stored.id.to_string(),
+            Err(e) => {
+                tracing::warn!(
+                    stage_id = %self.stage.id,
+                    record_id = %record.id,
+                    error = %e,
+                    "Failed to store prediction for feedback"
+                );
+            }
+        }
+    }
+
+    async fn execute_drift_detection(
+        &self,
+        input: Vec<PipelineRecord>,
+    ) -> anyhow::Result<Vec<PipelineRecord>> {
         tracing::trace!(
             stage_id = %self.stage.id,
+            records = input.len(),
             "Executing drift detection"
         );
 
-        Ok(StageResult {
-            records_processed: 0,
-            records_failed: 0,
-        })
+        let drift = self
+            .drift
+            .as_ref()
+            .context("Drift detection stage was built without a monitor")?;
+
+        let mut outcome = drift.process(&self.ctx.db, input).await;
+        if let Some(event_id) = outcome.blocked_by {
+            let reason = format!("Held back while drift event {} is open", event_id);
+            let mut blocked = 0;
+            for record in outcome.held {
+                // A record that cannot be parked is forwarded rather than lost.
+                if self.dead_letter(&record, drift.model_id(), &reason).await {
+                    blocked += 1;
+                } else {
+                    outcome.records.push(record);
+                }
+            }
+            self.stats.records_blocked.fetch_add(blocked, Ordering::Relaxed);
+        }
+        self.accept_replayed(&mut outcome.records).await;
+        Ok(outcome.records)
     }
 
-    async fn execute_feedback_join(&self) -> anyhow::Result<StageResult> {
+    async fn execute_feedback_join(
+        &self,
+        input: Vec<PipelineRecord>,
+    ) -> anyhow::Result<Vec<PipelineRecord>> {
         tracing::trace!(
             stage_id = %self.stage.id,
+            records = input.len(),
             "Executing feedback join"
         );
 
-        Ok(StageResult {
-            records_processed: 0,
-            records_failed: 0,
-        })
+        let feedback = self
+            .feedback
+            .as_ref()
+            .context("Feedback join stage was built without a joiner")?;
+
+        let outcome = feedback.process(input).await;
+        self.stats
+            .records_failed
+            .fetch_add(outcome.failed, Ordering::Relaxed);
+        Ok(outcome.records)
     }
 
-    async fn execute_training_export(&self) -> anyhow::Result<StageResult> {
+    async fn execute_training_export(
+        &self,
+        input: Vec<PipelineRecord>,
+    ) -> anyhow::Result<Vec<PipelineRecord>> {
         tracing::trace!(
             stage_id = %self.stage.id,
             "Executing training export"
         );
 
-        Ok(StageResult {
-            records_processed: 0,
-            records_failed: 0,
-        })
+        Ok(input)
+    }
+}
+
+/// Restricts a feature vector to the model's declared inputs; an empty list keeps every feature.
+fn select_features(features: &FeatureVector, inputs: &[String]) -> FeatureVector {
+    if inputs.is_empty() {
+        return features.clone();
+    }
+
+    let mut selected = features.clone();
+    selected.features.retain(|name, _| inputs.contains(name));
+    selected
+}
+
+/// Runs a model call, returning its results with how long it took in milliseconds.
+async fn timed<T>(call: impl std::future::Future<Output = T>) -> (T, u64) {
+    let started = Instant::now();
+    let output = call.await;
+    (output, started.elapsed().as_millis() as u64)
+}
+
+/// The JSON written under the stage's `output_field`, e.g.
+/// `{"type": "anomaly", "score": 0.93, "is_anomaly": true, "model_id": ...}`.
+pub(crate) fn prediction_payload(prediction: &Prediction) -> serde_json::Value {
+    let mut value = match &prediction.result {
+        PredictionResult::Custom(value) => serde_json::json!({ "type": "custom", "value": value }),
+        result => serde_json::to_value(result).unwrap_or_default(),
+    };
+
+    if let Some(object) = value.as_object_mut() {
+        object.insert("model_id".to_string(), prediction.model_id.clone().into());
+        object.insert("model_version".to_string(), prediction.model_version.clone().into());
+        object.insert("prediction_id".to_string(), prediction.prediction_id.clone().into());
+        if let Some(confidence) = prediction.confidence {
+            object.insert("confidence".to_string(), confidence.into());
+        }
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flywheel_ml_db::entity::dead_letter::DeadLetterStatus;
+    use flywheel_ml_db::DriftEventRepo;
+    use serde_json::json;
+
+    const BLOCKING_SPEC: &str = r#"
+apiVersion: flywheel-ml.io/v1
+kind: FlywheelPipeline
+metadata:
+  name: blocking-test
+  namespace: default
+spec:
+  source: test-input
+  stages:
+    - id: drift
+      type: drift-detection
+      config:
+        mode: blocking
+        baseline_uri: ""
+        window_size: 20
+        check_interval_secs: 0
+        features: [cpu]
+        model_id: detector
+        thresholds:
+          psi: 0.25
+          kl_divergence: 0.1
+  sinks:
+    - name: out
+      all: true
+"#;
+
+    const INFERENCE_SPEC: &str = r#"
+apiVersion: flywheel-ml.io/v1
+kind: FlywheelPipeline
+metadata:
+  name: inference-test
+  namespace: default
+spec:
+  source: test-input
+  stages:
+    - id: inference
+      type: ml-inference
+      config:
+        model_endpoint: builtin://zscore
+        model_id: detector
+        input_features: [cpu]
+        output_field: score
+  sinks:
+    - name: out
+      all: true
+"#;
+
+    fn batch(ids: std::ops::Range<u32>, offset: f64) -> Vec<PipelineRecord> {
+        ids.map(|i| PipelineRecord::new(json!({"id": i, "cpu": offset + f64::from(i % 20)})))
+            .collect()
+    }
+
+    /// The first stage of `spec`, in a pipeline registered for the test.
+    async fn first_stage(spec: &str) -> (StageContext, StageExecutor) {
+        let db = crate::testing::database().await;
+        let pipeline = crate::testing::pipeline(&db, spec).await;
+        let ctx = StageContext::for_pipeline(&pipeline, db);
+        let executor = StageExecutor::for_stage(&ctx.spec.stages[0], &ctx).unwrap();
+        (ctx, executor)
+    }
+
+    #[tokio::test]
+    async fn test_unstored_predictions_count_towards_performance() {
+        // No feedback and no variants, so predictions are not stored.
+        let (ctx, executor) = first_stage(INFERENCE_SPEC).await;
+
+        let records = batch(0..20, 0.0)
+            .into_iter()
+            .map(|mut record| {
+                let cpu = record.payload["cpu"].as_f64().unwrap();
+                let features = FeatureVector::new(record.id.clone())
+                    .with_feature("cpu", flywheel_ml_core::FeatureValue::Float(cpu));
+                record.features = Some(features);
+                record
+            })
+            .collect();
+        let records = executor.execute(records).await.unwrap();
+        assert!(records.iter().all(|record| record.prediction.is_some()));
+
+        let performance = ctx.performance.snapshot_models(&["detector"]).unwrap();
+        assert_eq!(performance.tracker.predictions(), 20);
+        assert_eq!(performance.tracker.error_rate(), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_blocking_drift_parks_records_until_resolved() {
+        let (ctx, executor) = first_stage(BLOCKING_SPEC).await;
+        let (db, pipeline_id) = (&ctx.db, ctx.pipeline_id);
+        let dead_letters = |status| {
+            DeadLetterRepo::list_by_pipeline(db.conn(), pipeline_id, Some(status), 100)
+        };
+
+        // The first window becomes the reference; the second drifts.
+        assert_eq!(executor.execute(batch(0..20, 0.0)).await.unwrap().len(), 20);
+        assert!(executor.execute(batch(20..40, 100.0)).await.unwrap().is_empty());
+
+        let event = DriftEventRepo::find_open(db.conn(), pipeline_id, "detector")
+            .await
+            .unwrap()
+            .expect("drift event is open");
+        let parked = dead_letters(DeadLetterStatus::Pending).await.unwrap();
+        assert_eq!(parked.len(), 20);
+        assert_eq!(parked[0].stage_id, "drift");
+        assert_eq!(parked[0].model_id, "detector");
+        assert_eq!(
+            parked[0].error_message,
+            format!("Held back while drift event {} is open", event.id)
+        );
+        assert_eq!(executor.stats().snapshot().records_blocked, 20);
+
+        // Replayed while the event is still open, they go back to pending.
+        let replayed = parked.iter().cloned().map(PipelineRecord::replay).collect();
+        assert!(executor.execute(replayed).await.unwrap().is_empty());
+        assert_eq!(dead_letters(DeadLetterStatus::Pending).await.unwrap().len(), 20);
+
+        // Once values are back in range the event resolves and replays go through.
+        assert_eq!(executor.execute(batch(40..60, 0.0)).await.unwrap().len(), 20);
+        assert!(DriftEventRepo::find_open(db.conn(), pipeline_id, "detector")
+            .await
+            .unwrap()
+            .is_none());
+        let replayed = parked.into_iter().map(PipelineRecord::replay).collect();
+        let forwarded = executor.execute(replayed).await.unwrap();
+        assert_eq!(forwarded.len(), 20);
+        assert!(forwarded.iter().all(|record| record.replay_of.is_none()));
+        assert!(dead_letters(DeadLetterStatus::Pending).await.unwrap().is_empty());
+        assert_eq!(dead_letters(DeadLetterStatus::Replayed).await.unwrap().len(), 20);
+        assert_eq!(executor.stats().snapshot().records_blocked, 40);
+    }
+
+    #[tokio::test]
+    async fn test_replays_failed_again_are_not_marked_replayed() {
+        let spec = INFERENCE_SPEC.replace(
+            "output_field: score",
+            "output_field: score\n        fallback: error",
+        );
+        let (ctx, executor) = first_stage(&spec).await;
+        let (db, pipeline_id) = (&ctx.db, ctx.pipeline_id);
+
+        // A text feature the model cannot score.
+        let features = FeatureVector::new("r1")
+            .with_feature("cpu", flywheel_ml_core::FeatureValue::String("high".into()));
+        let dead_letter = DeadLetterRepo::create(
+            db.conn(),
+            pipeline_id,
+            "inference".to_string(),
+            "r1".to_string(),
+            "detector".to_string(),
+            json!({"cpu": "high"}),
+            Some(serde_json::to_value(features).unwrap()),
+            "model unavailable".to_string(),
+        )
+        .await
+        .unwrap();
+
+        let replayed = vec![PipelineRecord::replay(dead_letter)];
+        assert!(executor.execute(replayed).await.unwrap().is_empty());
+        let status = Some(DeadLetterStatus::Pending);
+        let pending = DeadLetterRepo::list_by_pipeline(db.conn(), pipeline_id, status, 10)
+            .await
+            .unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_ne!(pending[0].error_message, "model unavailable");
     }
 }
@@ -0,0 +1,341 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use flywheel_ml_core::{
+    FeedbackCollector, FeedbackError, FeedbackRecord, FeedbackSource, GroundTruth,
+};
+use flywheel_ml_db::entity::feedback;
+use flywheel_ml_db::{Database, FeedbackRepo, PredictionRepo};
+use flywheel_ml_drift::PerformanceRegistry;
+use flywheel_ml_transform::feedback_transform::FeedbackJoinTransform;
+use sea_orm::{DbErr, SqlErr};
+use uuid::Uuid;
+
+use crate::executor::{BanditRegistry, OutcomeRecorder};
+
+/// Longest summary kept in the `ground_truth` column.
+const GROUND_TRUTH_SUMMARY_LEN: usize = 255;
+
+/// Stores feedback in the `feedback` table, linked to the prediction it
+/// names, with its ground truth and source kept as reported.
+///
+/// Feedback is joined with its prediction as it is stored, like feedback
+/// flowing through a `feedback-join` stage: whether the prediction was right
+/// is stored with it, and the outcome, and those of the shadow predictions
+/// made for the same record, count toward live model performance. The
+/// outcome also rewards the bandit arm that served the prediction, while its
+/// pipeline runs. A prediction takes one feedback; more is rejected.
+pub struct DbFeedbackCollector {
+    db: Database,
+    join: FeedbackJoinTransform,
+    outcomes: OutcomeRecorder,
+}
+
+impl DbFeedbackCollector {
+    pub fn new(
+        db: Database,
+        performance: Arc<PerformanceRegistry>,
+        bandits: Arc<BanditRegistry>,
+    ) -> Self {
+        // Feedback arriving late is still stored and judged.
+        let join = FeedbackJoinTransform::new(Arc::new(db.conn().clone())).with_max_delay(i64::MAX);
+        Self {
+            outcomes: OutcomeRecorder::new(db.clone(), performance, bandits),
+            db,
+            join,
+        }
+    }
+}
+
+#[async_trait]
+impl FeedbackCollector for DbFeedbackCollector {
+    async fn collect(&self, mut feedback: FeedbackRecord) -> Result<(), FeedbackError> {
+        if feedback.prediction_id.is_empty() {
+            return Err(FeedbackError::MissingPredictionId);
+        }
+        let id = Uuid::parse_str(&feedback.feedback_id).map_err(|_| {
+            FeedbackError::StorageFailed(format!(
+                "feedback id '{}' is not a UUID",
+                feedback.feedback_id
+            ))
+        })?;
+        let prediction_id = Uuid::parse_str(&feedback.prediction_id).map_err(|_| {
+            FeedbackError::PredictionNotFound(format!(
+                "Invalid prediction ID format: {}",
+                feedback.prediction_id
+            ))
+        })?;
+
+        let prediction = PredictionRepo::find_by_id(self.db.conn(), prediction_id)
+            .await
+            .map_err(storage_failed)?
+            .ok_or_else(|| FeedbackError::PredictionNotFound(feedback.prediction_id.clone()))?;
+        if feedback.model_id.is_empty() {
+            feedback.model_id = prediction.model_id.clone();
+        } else if feedback.model_id != prediction.model_id {
+            return Err(FeedbackError::JoinFailed(format!(
+                "prediction {} was made by '{}', not '{}'",
+                feedback.prediction_id, prediction.model_id, feedback.model_id
+            )));
+        }
+        // A prediction is judged once; more feedback on it would count twice.
+        if let Some(existing) = prediction.feedback_id {
+            return Err(FeedbackError::AlreadyExists(existing.to_string()));
+        }
+        if feedback.delay_ms == 0 {
+            feedback = feedback.with_delay(prediction.created_at);
+        }
+
+        let labeled = self.join.process(feedback.clone()).await?;
+
+        FeedbackRepo::create_record(
+            self.db.conn(),
+            id,
+            prediction_id,
+            feedback.model_id.clone(),
+            ground_truth_summary(&feedback.ground_truth),
+            serde_json::to_value(&feedback.ground_truth).unwrap_or_default(),
+            source_kind(&feedback.source),
+            serde_json::to_value(&feedback.source).unwrap_or_default(),
+            feedback.source.confidence(),
+            serde_json::to_value(&feedback.metadata).unwrap_or_default(),
+            feedback.feedback_time,
+            feedback.delay_ms as i64,
+            labeled.as_ref().and_then(|l| l.is_correct),
+        )
+        .await
+        .map_err(|e| match e.sql_err() {
+            Some(SqlErr::UniqueConstraintViolation(_)) => {
+                FeedbackError::AlreadyExists(feedback.feedback_id.clone())
+            }
+            // Other feedback was linked since the prediction was read.
+            _ if matches!(e, DbErr::RecordNotUpdated) => FeedbackError::AlreadyExists(format!(
+                "feedback on prediction {}",
+                feedback.prediction_id
+            )),
+            _ => storage_failed(e),
+        })?;
+
+        // Only stored feedback counts. An expired prediction is stored but no
+        // longer judged.
+        let Some(labeled) = labeled else {
+            return Ok(());
+        };
+        self.outcomes
+            .record(prediction.pipeline_id, &labeled, &self.join, &feedback)
+            .await;
+        Ok(())
+    }
+
+    async fn query_feedback(
+        &self,
+        prediction_ids: &[String],
+    ) -> Result<Vec<FeedbackRecord>, FeedbackError> {
+        let ids = prediction_ids
+            .iter()
+            .filter_map(|id| Uuid::parse_str(id).ok())
+            .collect();
+        let rows = FeedbackRepo::list_by_predictions(self.db.conn(), ids)
+            .await
+            .map_err(storage_failed)?;
+        Ok(rows.into_iter().map(feedback_record).collect())
+    }
+
+    /// Feedback received per prediction served over the window.
+    async fn get_feedback_rate(
+        &self,
+        model_id: &str,
+        window: Duration,
+    ) -> Result<f64, FeedbackError> {
+        let since = Utc::now() - window;
+        let served = PredictionRepo::count_served_since(self.db.conn(), model_id, since)
+            .await
+            .map_err(storage_failed)?;
+        if served == 0 {
+            return Ok(0.0);
+        }
+        let counts = FeedbackRepo::count_by_model_since(self.db.conn(), model_id, since)
+            .await
+            .map_err(storage_failed)?;
+        Ok(counts.total as f64 / served as f64)
+    }
+
+    async fn get_accuracy(
+        &self,
+        model_id: &str,
+        window: Duration,
+    ) -> Result<Option<f64>, FeedbackError> {
+        let counts =
+            FeedbackRepo::count_by_model_since(self.db.conn(), model_id, Utc::now() - window)
+                .await
+                .map_err(storage_failed)?;
+        let judged = counts.correct + counts.incorrect;
+        Ok((judged > 0).then(|| counts.correct as f64 / judged as f64))
+    }
+}
+
+fn storage_failed(error: DbErr) -> FeedbackError {
+    FeedbackError::StorageFailed(format!("Database error: {}", error))
+}
+
+/// A readable form of the ground truth for the `ground_truth` column.
+fn ground_truth_summary(ground_truth: &GroundTruth) -> String {
+    let summary = match ground_truth {
+        GroundTruth::Label(label) => label.clone(),
+        GroundTruth::Value(value) => value.to_string(),
+        GroundTruth::Binary(b) => b.to_string(),
+        GroundTruth::Ranking(items) | GroundTruth::MultiLabel(items) => items.join(","),
+        GroundTruth::Custom(value) => value.to_string(),
+    };
+    summary.chars().take(GROUND_TRUTH_SUMMARY_LEN).collect()
+}
+
+fn source_kind(source: &FeedbackSource) -> feedback::FeedbackSource {
+    match source {
+        FeedbackSource::Explicit { .. } => feedback::FeedbackSource::Explicit,
+        FeedbackSource::Implicit { .. } => feedback::FeedbackSource::Implicit,
+        FeedbackSource::Automated { .. } => feedback::FeedbackSource::Automated,
+        FeedbackSource::Manual { .. } => feedback::FeedbackSource::Manual,
+    }
+}
+
+/// Rebuilds stored feedback. Rows stored before ground truths and sources
+/// were kept whole come back with a label and a source of the right kind.
+fn feedback_record(row: feedback::Model) -> FeedbackRecord {
+    let ground_truth = row
+        .ground_truth_json
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_else(|| GroundTruth::label(&row.ground_truth));
+    let source = row
+        .source_json
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_else(|| match row.source {
+            feedback::FeedbackSource::Explicit => FeedbackSource::explicit("", ""),
+            feedback::FeedbackSource::Implicit => FeedbackSource::implicit(""),
+            feedback::FeedbackSource::Automated => FeedbackSource::automated("", row.confidence),
+            feedback::FeedbackSource::Manual => FeedbackSource::manual(""),
+        });
+
+    FeedbackRecord {
+        feedback_id: row.id.to_string(),
+        prediction_id: row.prediction_id.to_string(),
+        model_id: row.model_id,
+        ground_truth,
+        feedback_time: row.feedback_time.unwrap_or(row.received_at),
+        delay_ms: row.delay_ms.unwrap_or(0).max(0) as u64,
+        source,
+        metadata: row
+            .metadata_json
+            .and_then(|value| serde_json::from_value(value).ok())
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flywheel_ml_core::PredictionResult;
+    use flywheel_ml_db::entity::prediction;
+    use sea_orm::EntityTrait;
+
+    async fn served_prediction(db: &Database) -> prediction::Model {
+        let pipeline = crate::testing::pipeline(db, "").await;
+        let result = PredictionResult::Anomaly {
+            score: 0.9,
+            is_anomaly: true,
+            threshold: 0.5,
+            contributing_features: Vec::new(),
+        };
+        PredictionRepo::create(
+            db.conn(),
+            pipeline.id,
+            "detector".to_string(),
+            "v1".to_string(),
+            serde_json::json!({}),
+            serde_json::to_value(result).unwrap(),
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_second_feedback_on_a_prediction_is_rejected() {
+        let db = crate::testing::database().await;
+        let performance = Arc::new(PerformanceRegistry::new(std::time::Duration::from_secs(60)));
+        let collector =
+            DbFeedbackCollector::new(db.clone(), performance.clone(), Default::default());
+        let prediction = served_prediction(&db).await;
+
+        let feedback = FeedbackRecord::new(
+            prediction.id.to_string(),
+            "",
+            GroundTruth::binary(true),
+            FeedbackSource::manual("oncall"),
+        );
+        collector.collect(feedback.clone()).await.unwrap();
+
+        let linked = PredictionRepo::find_by_id(db.conn(), prediction.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            linked.feedback_id.map(|id| id.to_string()),
+            Some(feedback.feedback_id.clone())
+        );
+        let stored = feedback::Entity::find().all(db.conn()).await.unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].model_id, "detector");
+        assert_eq!(stored[0].is_correct, Some(true));
+
+        let err = collector.collect(feedback.clone()).await.unwrap_err();
+        assert!(
+            matches!(&err, FeedbackError::AlreadyExists(id) if *id == feedback.feedback_id),
+            "{err}"
+        );
+        // Nor is other feedback on the same prediction.
+        let other = FeedbackRecord::new(
+            prediction.id.to_string(),
+            "",
+            GroundTruth::binary(false),
+            FeedbackSource::manual("oncall"),
+        );
+        let err = collector.collect(other).await.unwrap_err();
+        assert!(matches!(err, FeedbackError::AlreadyExists(_)), "{err}");
+        assert_eq!(
+            feedback::Entity::find().all(db.conn()).await.unwrap().len(),
+            1
+        );
+        // Only the stored feedback counts toward performance.
+        assert_eq!(performance.snapshot("detector", "v1").unwrap().examples, 1);
+    }
+
+    #[tokio::test]
+    async fn test_feedback_is_not_kept_when_linking_fails() {
+        let db = crate::testing::database().await;
+        let missing_prediction = Uuid::new_v4();
+
+        let result = FeedbackRepo::create_record(
+            db.conn(),
+            Uuid::new_v4(),
+            missing_prediction,
+            "detector".to_string(),
+            "true".to_string(),
+            serde_json::json!(true),
+            feedback::FeedbackSource::Manual,
+            serde_json::json!({}),
+            1.0,
+            serde_json::json!({}),
+            Utc::now(),
+            0,
+            None,
+        )
+        .await;
+        assert!(result.is_err());
+        assert!(feedback::Entity::find()
+            .all(db.conn())
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -1,13 +1,24 @@
 use chrono::Utc;
-use flywheel_ml_db::{entity::pipeline, Database, ModelVersionRepo, PipelineRepo};
+use flywheel_ml_db::entity::bandit_arm;
+use flywheel_ml_db::entity::model_promotion::{self, PromotionTrigger};
+use flywheel_ml_db::entity::model_version::ModelStatus;
+use flywheel_ml_db::entity::{dead_letter::DeadLetterStatus, pipeline};
+use flywheel_ml_db::{
+    BanditArmRepo, Database, DeadLetterRepo, ModelPromotionRepo, ModelVersionRepo, PipelineRepo,
+};
 use flywheel_ml_proto::control_service_server::ControlService;
 use flywheel_ml_proto::{
-    CreatePipelineRequest, CreatePipelineResponse, DeletePipelineRequest, DeletePipelineResponse,
-    DisablePipelineRequest, DisablePipelineResponse, EnablePipelineRequest, EnablePipelineResponse,
-    GetModelRequest, GetModelResponse, GetPipelineRequest, GetPipelineResponse,
-    ListModelsRequest, ListModelsResponse, ListPipelinesRequest, ListPipelinesResponse,
-    ModelInfo, PipelineInfo, PipelineStats, RegisterModelRequest, RegisterModelResponse,
-    UnregisterModelRequest, UnregisterModelResponse, UpdatePipelineRequest, UpdatePipelineResponse,
+    BanditAllocation, CreatePipelineRequest, CreatePipelineResponse, DeadLetter, DeletePipelineRequest,
+    DeletePipelineResponse, DisablePipelineRequest, DisablePipelineResponse,
+    EnablePipelineRequest, EnablePipelineResponse, GetModelRequest, GetModelResponse,
+    GetPipelineRequest, GetPipelineResponse, ListDeadLettersRequest, ListDeadLettersResponse,
+    ListModelPromotionsRequest, ListModelPromotionsResponse, ListModelsRequest,
+    ListModelsResponse, ListPipelinesRequest, ListPipelinesResponse, ModelInfo, ModelPromotion,
+    PipelineInfo, PipelineStats, PromoteModelRequest, PromoteModelResponse,
+    RegisterModelRequest, RegisterModelResponse, ReplayDeadLettersRequest,
+    ReplayDeadLettersResponse, RollbackModelRequest, RollbackModelResponse,
+    UnregisterModelRequest, UnregisterModelResponse, UpdatePipelineRequest,
+    UpdatePipelineResponse,
 };
 use prost_types::Timestamp;
 use sha2::{Digest, Sha256};
@@ -35,6 +46,54 @@ impl ControlServiceImpl {
             nanos: dt.timestamp_subsec_nanos() as i32,
         })
     }
+
+    fn promotion_info(promotion: model_promotion::Model) -> ModelPromotion {
+        ModelPromotion {
+            promotion_id: promotion.id.to_string(),
+            model_id: promotion.model_id,
+            version: promotion.version,
+            active_version: promotion.active_version.unwrap_or_default(),
+            previous_version: promotion.previous_version.unwrap_or_default(),
+            action: format!("{:?}", promotion.action).to_lowercase(),
+            trigger: format!("{:?}", promotion.trigger).to_lowercase(),
+            reasons: serde_json::from_value(promotion.reasons_json).unwrap_or_default(),
+            metrics_json: serde_json::to_vec(&promotion.metrics_json).unwrap_or_default(),
+            decided_at: Self::datetime_to_timestamp(promotion.decided_at),
+        }
+    }
+
+    fn bandit_allocation(arm: bandit_arm::Model) -> BanditAllocation {
+        BanditAllocation {
+            pipeline_id: arm.pipeline_id.to_string(),
+            stage_id: arm.stage_id,
+            policy: arm.policy,
+            model_id: arm.model_id,
+            model_version: arm.model_version,
+            pulls: arm.pulls as u64,
+            successes: arm.successes as u64,
+            failures: arm.failures as u64,
+            traffic_share: arm.traffic_share,
+            updated_at: Self::datetime_to_timestamp(arm.updated_at),
+        }
+    }
+
+    /// The operator's reason, or `default` when none was given.
+    fn manual_reasons(reason: String, default: &str) -> Vec<String> {
+        if reason.is_empty() {
+            vec![default.to_string()]
+        } else {
+            vec![reason]
+        }
+    }
+
+    fn parse_dead_letter_status(status: &str) -> Option<DeadLetterStatus> {
+        match status.to_ascii_lowercase().as_str() {
+            "pending" => Some(DeadLetterStatus::Pending),
+            "queued" => Some(DeadLetterStatus::Queued),
+            "replayed" => Some(DeadLetterStatus::Replayed),
+            _ => None,
+        }
+    }
 }
 
 #[tonic::async_trait]
@@ -309,6 +368,24 @@ impl ControlService for ControlServiceImpl {
             .map_err(|e| Status::internal(format!("Database error: {}", e)))?
             .ok_or_else(|| Status::not_found("Model not found"))?;
 
+        // Every arm of each bandit stage the model competes in, so its share
+        // can be read against the others'.
+        let arms = BanditArmRepo::list_by_model(self.db.conn(), &req.model_id)
+            .await
+            .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+        let mut stages = std::collections::HashSet::new();
+        let mut allocations = Vec::new();
+        for arm in arms {
+            if !stages.insert((arm.pipeline_id, arm.stage_id.clone())) {
+                continue;
+            }
+            let stage_arms =
+                BanditArmRepo::list_by_stage(self.db.conn(), arm.pipeline_id, &arm.stage_id)
+                    .await
+                    .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+            allocations.extend(stage_arms.into_iter().map(Self::bandit_allocation));
+        }
+
         let response = GetModelResponse {
             model: Some(ModelInfo {
                 model_id: model.model_id,
@@ -321,6 +398,7 @@ impl ControlService for ControlServiceImpl {
                 latency_p99_ms: model.latency_p99_ms.unwrap_or(0) as u64,
                 deployed_at: Self::datetime_to_timestamp(model.deployed_at),
                 labels: std::collections::HashMap::new(),
+                allocations,
             }),
         };
 
@@ -351,6 +429,7 @@ impl ControlService for ControlServiceImpl {
                 latency_p99_ms: m.latency_p99_ms.unwrap_or(0) as u64,
                 deployed_at: Self::datetime_to_timestamp(m.deployed_at),
                 labels: std::collections::HashMap::new(),
+                allocations: Vec::new(),
             })
             .collect();
 
@@ -361,4 +440,179 @@ impl ControlService for ControlServiceImpl {
 
         Ok(Response::new(response))
     }
+
+    async fn promote_model(
+        &self,
+        request: Request<PromoteModelRequest>,
+    ) -> Result<Response<PromoteModelResponse>, Status> {
+        let req = request.into_inner();
+
+        let version =
+            ModelVersionRepo::find_by_version(self.db.conn(), &req.model_id, &req.version)
+                .await
+                .map_err(|e| Status::internal(format!("Database error: {}", e)))?
+                .ok_or_else(|| {
+                    Status::not_found(format!(
+                        "Model version not found: {} {}",
+                        req.model_id, req.version
+                    ))
+                })?;
+        if version.status == ModelStatus::Active {
+            return Err(Status::failed_precondition(format!(
+                "{} {} is already the active version",
+                req.model_id, req.version
+            )));
+        }
+
+        let promotion = crate::promotion::promote(
+            &self.db,
+            &version,
+            PromotionTrigger::Manual,
+            Self::manual_reasons(req.reason, "promoted manually"),
+            serde_json::json!({}),
+        )
+        .await
+        .map_err(|e| Status::internal(format!("Failed to promote model: {}", e)))?;
+
+        Ok(Response::new(PromoteModelResponse {
+            promotion: Some(Self::promotion_info(promotion)),
+        }))
+    }
+
+    async fn rollback_model(
+        &self,
+        request: Request<RollbackModelRequest>,
+    ) -> Result<Response<RollbackModelResponse>, Status> {
+        let req = request.into_inner();
+        let db_error = |e: sea_orm::DbErr| Status::internal(format!("Database error: {}", e));
+
+        let active = ModelVersionRepo::find_by_model_id(self.db.conn(), &req.model_id)
+            .await
+            .map_err(db_error)?;
+        let version = if req.version.is_empty() {
+            active.clone()
+        } else {
+            ModelVersionRepo::find_by_version(self.db.conn(), &req.model_id, &req.version)
+                .await
+                .map_err(db_error)?
+        }
+        .ok_or_else(|| Status::not_found(format!("Model version not found: {}", req.model_id)))?;
+
+        // Rolling back the active version needs another to serve instead; a
+        // pending or deprecated version is simply retired.
+        let restore = if version.status != ModelStatus::Active {
+            active
+        } else if req.to_version.is_empty() {
+            let previous = ModelVersionRepo::find_previous(self.db.conn(), &req.model_id)
+                .await
+                .map_err(db_error)?;
+            Some(previous.ok_or_else(|| {
+                Status::failed_precondition(format!(
+                    "{} has no previous version to roll back to",
+                    req.model_id
+                ))
+            })?)
+        } else {
+            let target =
+                ModelVersionRepo::find_by_version(self.db.conn(), &req.model_id, &req.to_version)
+                    .await
+                    .map_err(db_error)?;
+            Some(target.ok_or_else(|| {
+                Status::not_found(format!(
+                    "Model version not found: {} {}",
+                    req.model_id, req.to_version
+                ))
+            })?)
+        };
+
+        let promotion = crate::promotion::roll_back(
+            &self.db,
+            &version,
+            restore.as_ref(),
+            PromotionTrigger::Manual,
+            Self::manual_reasons(req.reason, "rolled back manually"),
+            serde_json::json!({}),
+        )
+        .await
+        .map_err(|e| Status::internal(format!("Failed to roll back model: {}", e)))?;
+
+        Ok(Response::new(RollbackModelResponse {
+            promotion: Some(Self::promotion_info(promotion)),
+        }))
+    }
+
+    async fn list_model_promotions(
+        &self,
+        request: Request<ListModelPromotionsRequest>,
+    ) -> Result<Response<ListModelPromotionsResponse>, Status> {
+        let req = request.into_inner();
+        let limit = if req.limit > 0 { req.limit as u64 } else { 50 };
+
+        let promotions = ModelPromotionRepo::list_by_model(self.db.conn(), &req.model_id, limit)
+            .await
+            .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+
+        Ok(Response::new(ListModelPromotionsResponse {
+            promotions: promotions.into_iter().map(Self::promotion_info).collect(),
+        }))
+    }
+
+    async fn list_dead_letters(
+        &self,
+        request: Request<ListDeadLettersRequest>,
+    ) -> Result<Response<ListDeadLettersResponse>, Status> {
+        let req = request.into_inner();
+        let pipeline_id = Uuid::parse_str(&req.pipeline_id)
+            .map_err(|_| Status::invalid_argument("Invalid pipeline ID format"))?;
+        let status = match req.status.as_str() {
+            "" => None,
+            status => Some(Self::parse_dead_letter_status(status).ok_or_else(|| {
+                Status::invalid_argument(format!("Unknown dead letter status: {}", status))
+            })?),
+        };
+        let limit = if req.limit > 0 { req.limit as u64 } else { 100 };
+
+        let dead_letters =
+            DeadLetterRepo::list_by_pipeline(self.db.conn(), pipeline_id, status, limit)
+                .await
+                .map_err(|e| Status::internal(format!("Database error: {}", e)))?;
+
+        let response = ListDeadLettersResponse {
+            dead_letters: dead_letters
+                .into_iter()
+                .map(|d| DeadLetter {
+                    dead_letter_id: d.id.to_string(),
+                    pipeline_id: d.pipeline_id.to_string(),
+                    stage_id: d.stage_id,
+                    record_id: d.record_id,
+                    model_id: d.model_id,
+                    error_message: d.error_message,
+                    status: format!("{:?}", d.status).to_lowercase(),
+                    payload_json: serde_json::to_vec(&d.payload_json).unwrap_or_default(),
+                    created_at: Self::datetime_to_timestamp(d.created_at),
+                    replayed_at: d.replayed_at.and_then(Self::datetime_to_timestamp),
+                })
+                .collect(),
+        };
+
+        Ok(Response::new(response))
+    }
+
+    async fn replay_dead_letters(
+        &self,
+        request: Request<ReplayDeadLettersRequest>,
+    ) -> Result<Response<ReplayDeadLettersResponse>, Status> {
+        let req = request.into_inner();
+        let pipeline_id = Uuid::parse_str(&req.pipeline_id)
+            .map_err(|_| Status::invalid_argument("Invalid pipeline ID format"))?;
+        let limit = if req.limit > 0 { req.limit as u64 } else { 1000 };
+
+        let queued = DeadLetterRepo::queue_replay(self.db.conn(), pipeline_id, limit)
+            .await
+            .map_err(|e| Status::internal(format!("Failed to queue dead letters: {}", e)))?;
+
+        tracing::info!(pipeline_id = %pipeline_id, queued, "Dead letters queued for replay");
+
+        Ok(Response::new(ReplayDeadLettersResponse { queued }))
+    }
 }
@@ -0,0 +1,310 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use flywheel_ml_core::{
+    FeedbackCollector, FeedbackError, FeedbackRecord, FeedbackSource, GroundTruth,
+};
+use flywheel_ml_proto as proto;
+use flywheel_ml_proto::feedback_service_server::FeedbackService;
+use flywheel_ml_proto::{
+    FeedbackRejection, SubmitFeedbackBatchRequest, SubmitFeedbackBatchResponse,
+    SubmitFeedbackRequest, SubmitFeedbackResponse,
+};
+use tonic::{Request, Response, Status, Streaming};
+use uuid::Uuid;
+
+/// Most feedback items one `SubmitFeedbackBatch` call may carry.
+const MAX_BATCH_SIZE: usize = 1000;
+
+/// Accepts ground truth for served predictions from outside any pipeline,
+/// one at a time, in batches or as a stream.
+pub struct FeedbackServiceImpl {
+    collector: Arc<dyn FeedbackCollector>,
+}
+
+impl FeedbackServiceImpl {
+    pub fn new(collector: Arc<dyn FeedbackCollector>) -> Self {
+        Self { collector }
+    }
+
+    /// Stores one item of feedback, returning its id.
+    async fn submit(&self, feedback: proto::Feedback) -> Result<String, SubmitError> {
+        let record = feedback_from_proto(feedback)?;
+        let feedback_id = record.feedback_id.clone();
+        self.collector.collect(record).await?;
+        Ok(feedback_id)
+    }
+
+    /// Stores each item of a batch, rejecting those that fail on their own.
+    async fn submit_all(
+        &self,
+        batch: impl IntoIterator<Item = proto::Feedback>,
+    ) -> SubmitFeedbackBatchResponse {
+        let mut response = SubmitFeedbackBatchResponse::default();
+        for (index, feedback) in batch.into_iter().enumerate() {
+            self.tally(&mut response, index as u64, feedback).await;
+        }
+        response
+    }
+
+    async fn tally(
+        &self,
+        response: &mut SubmitFeedbackBatchResponse,
+        index: u64,
+        feedback: proto::Feedback,
+    ) {
+        let prediction_id = feedback.prediction_id.clone();
+        match self.submit(feedback).await {
+            Ok(_) => response.accepted += 1,
+            Err(e) => {
+                tracing::debug!(%prediction_id, error = %e, "Rejected feedback");
+                response.rejected += 1;
+                response.rejections.push(FeedbackRejection {
+                    index,
+                    prediction_id,
+                    reason: e.to_string(),
+                });
+            }
+        }
+    }
+}
+
+enum SubmitError {
+    Invalid(String),
+    Feedback(FeedbackError),
+}
+
+impl From<FeedbackError> for SubmitError {
+    fn from(error: FeedbackError) -> Self {
+        SubmitError::Feedback(error)
+    }
+}
+
+impl std::fmt::Display for SubmitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SubmitError::Invalid(message) => f.write_str(message),
+            SubmitError::Feedback(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<SubmitError> for Status {
+    fn from(error: SubmitError) -> Self {
+        let message = error.to_string();
+        match error {
+            SubmitError::Invalid(_)
+            | SubmitError::Feedback(
+                FeedbackError::MissingPredictionId | FeedbackError::InvalidGroundTruth(_),
+            ) => Status::invalid_argument(message),
+            SubmitError::Feedback(
+                FeedbackError::NotFound(_) | FeedbackError::PredictionNotFound(_),
+            ) => Status::not_found(message),
+            SubmitError::Feedback(FeedbackError::AlreadyExists(_)) => {
+                Status::already_exists(message)
+            }
+            SubmitError::Feedback(FeedbackError::JoinFailed(_)) => {
+                Status::failed_precondition(message)
+            }
+            SubmitError::Feedback(FeedbackError::StorageFailed(_)) => Status::internal(message),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl FeedbackService for FeedbackServiceImpl {
+    async fn submit_feedback(
+        &self,
+        request: Request<SubmitFeedbackRequest>,
+    ) -> Result<Response<SubmitFeedbackResponse>, Status> {
+        let feedback = request
+            .into_inner()
+            .feedback
+            .ok_or_else(|| Status::invalid_argument("feedback is required"))?;
+        let feedback_id = self.submit(feedback).await?;
+        Ok(Response::new(SubmitFeedbackResponse { feedback_id }))
+    }
+
+    async fn submit_feedback_batch(
+        &self,
+        request: Request<SubmitFeedbackBatchRequest>,
+    ) -> Result<Response<SubmitFeedbackBatchResponse>, Status> {
+        let batch = request.into_inner().feedback;
+        if batch.len() > MAX_BATCH_SIZE {
+            return Err(Status::invalid_argument(format!(
+                "a batch holds at most {} feedback items, got {}; use StreamFeedback for more",
+                MAX_BATCH_SIZE,
+                batch.len()
+            )));
+        }
+        Ok(Response::new(self.submit_all(batch).await))
+    }
+
+    async fn stream_feedback(
+        &self,
+        request: Request<Streaming<proto::Feedback>>,
+    ) -> Result<Response<SubmitFeedbackBatchResponse>, Status> {
+        let mut stream = request.into_inner();
+        let mut response = SubmitFeedbackBatchResponse::default();
+        let mut index = 0;
+        while let Some(feedback) = stream.message().await? {
+            self.tally(&mut response, index, feedback).await;
+            index += 1;
+        }
+        Ok(Response::new(response))
+    }
+}
+
+fn feedback_from_proto(feedback: proto::Feedback) -> Result<FeedbackRecord, SubmitError> {
+    if feedback.prediction_id.is_empty() {
+        return Err(FeedbackError::MissingPredictionId.into());
+    }
+    let ground_truth = ground_truth_from_proto(feedback.ground_truth)?;
+    let source = source_from_proto(feedback.source)?;
+
+    let mut record = FeedbackRecord::new(
+        feedback.prediction_id,
+        feedback.model_id,
+        ground_truth,
+        source,
+    );
+    if !feedback.feedback_id.is_empty() {
+        Uuid::parse_str(&feedback.feedback_id).map_err(|_| {
+            SubmitError::Invalid(format!(
+                "feedback_id '{}' is not a UUID",
+                feedback.feedback_id
+            ))
+        })?;
+        record.feedback_id = feedback.feedback_id;
+    }
+    if let Some(time) = feedback.feedback_time {
+        record.feedback_time = u32::try_from(time.nanos)
+            .ok()
+            .and_then(|nanos| DateTime::<Utc>::from_timestamp(time.seconds, nanos))
+            .ok_or_else(|| SubmitError::Invalid("feedback_time is out of range".to_string()))?;
+    }
+    record.metadata = feedback.metadata;
+    Ok(record)
+}
+
+fn ground_truth_from_proto(
+    ground_truth: Option<proto::GroundTruth>,
+) -> Result<GroundTruth, SubmitError> {
+    use proto::ground_truth::Truth;
+
+    let ground_truth = match ground_truth.and_then(|g| g.truth) {
+        Some(Truth::Label(label)) => GroundTruth::label(label),
+        Some(Truth::Value(value)) if value.is_finite() => GroundTruth::value(value),
+        Some(Truth::Value(value)) => {
+            return Err(
+                FeedbackError::InvalidGroundTruth(format!("value {} is not finite", value)).into(),
+            )
+        }
+        Some(Truth::Binary(b)) => GroundTruth::binary(b),
+        Some(Truth::Ranking(items)) => GroundTruth::ranking(items.values),
+        Some(Truth::MultiLabel(labels)) => GroundTruth::multi_label(labels.values),
+        Some(Truth::CustomJson(json)) => {
+            let value = serde_json::from_slice(&json).map_err(|e| {
+                FeedbackError::InvalidGroundTruth(format!("custom_json is not valid JSON: {}", e))
+            })?;
+            GroundTruth::Custom(value)
+        }
+        None => {
+            return Err(FeedbackError::InvalidGroundTruth("ground_truth is required".into()).into())
+        }
+    };
+    Ok(ground_truth)
+}
+
+fn source_from_proto(source: Option<proto::FeedbackSource>) -> Result<FeedbackSource, SubmitError> {
+    use proto::feedback_source::Source;
+
+    match source.and_then(|s| s.source) {
+        Some(Source::Explicit(s)) => Ok(FeedbackSource::explicit(s.user_id, s.action)),
+        Some(Source::Implicit(s)) => Ok(FeedbackSource::implicit_with_context(
+            s.event_type,
+            s.context,
+        )),
+        Some(Source::Automated(s)) if (0.0..=1.0).contains(&s.confidence) => {
+            Ok(FeedbackSource::automated(s.rule, s.confidence))
+        }
+        Some(Source::Automated(s)) => Err(SubmitError::Invalid(format!(
+            "automated source confidence must be between 0 and 1, got {}",
+            s.confidence
+        ))),
+        Some(Source::Manual(s)) => Ok(FeedbackSource::manual(s.annotator_id)),
+        None => Err(SubmitError::Invalid("source is required".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proto::feedback_source::Source;
+    use proto::ground_truth::Truth;
+    use tonic::Code;
+
+    fn feedback(truth: Truth, source: Source) -> proto::Feedback {
+        proto::Feedback {
+            prediction_id: Uuid::new_v4().to_string(),
+            ground_truth: Some(proto::GroundTruth { truth: Some(truth) }),
+            source: Some(proto::FeedbackSource {
+                source: Some(source),
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn automated(confidence: f64) -> Source {
+        Source::Automated(proto::AutomatedSource {
+            rule: "rule".to_string(),
+            confidence,
+        })
+    }
+
+    fn accept(feedback: proto::Feedback) -> FeedbackRecord {
+        feedback_from_proto(feedback).map_err(Status::from).unwrap()
+    }
+
+    fn rejection(feedback: proto::Feedback) -> Status {
+        feedback_from_proto(feedback)
+            .map_err(Status::from)
+            .unwrap_err()
+    }
+
+    #[test]
+    fn test_automated_confidence_bounds() {
+        for confidence in [0.0, 0.7, 1.0] {
+            let record = accept(feedback(Truth::Binary(true), automated(confidence)));
+            assert_eq!(record.source.confidence(), confidence);
+        }
+        for confidence in [-0.1, 1.5, f64::NAN] {
+            let status = rejection(feedback(Truth::Binary(true), automated(confidence)));
+            assert_eq!(status.code(), Code::InvalidArgument);
+            assert!(status
+                .message()
+                .contains("confidence must be between 0 and 1"));
+        }
+    }
+
+    #[test]
+    fn test_errors_map_to_status_codes() {
+        let code = |error: FeedbackError| Status::from(SubmitError::from(error)).code();
+        assert_eq!(
+            code(FeedbackError::AlreadyExists("id".into())),
+            Code::AlreadyExists
+        );
+        assert_eq!(
+            code(FeedbackError::PredictionNotFound("id".into())),
+            Code::NotFound
+        );
+        assert_eq!(
+            code(FeedbackError::JoinFailed("model".into())),
+            Code::FailedPrecondition
+        );
+        assert_eq!(
+            code(FeedbackError::StorageFailed("db".into())),
+            Code::Internal
+        );
+    }
+}
@@ -1,11 +1,13 @@
 use chrono::Utc;
-use flywheel_ml_db::{Database, DriftEventRepo, PipelineRepo};
+use flywheel_ml_db::{entity::drift_event, Database, DriftEventRepo, PipelineRepo};
+use flywheel_ml_drift::{FeatureDriftResult, PerformanceRegistry, PerformanceSnapshot};
+use flywheel_ml_dsl::{FlywheelStageType, MlInferenceConfig};
 use flywheel_ml_proto::health_service_server::HealthService;
 use flywheel_ml_proto::{
-    DatabaseHealth, DriftEvent, DriftSummary, GetDriftStatusRequest, GetDriftStatusResponse,
+    DatabaseHealth, DriftEvent, DriftSummary, FeatureDrift, GetDriftStatusRequest, GetDriftStatusResponse,
     GetHealthRequest, GetHealthResponse, GetPipelineHealthRequest, GetPipelineHealthResponse,
     ListDriftEventsRequest, ListDriftEventsResponse, PipelineMetrics, PerformanceDrift,
-    StatisticalDrift, StageHealth,
+    StatisticalDrift,
 };
 use prost_types::Timestamp;
 use std::sync::atomic::{AtomicU32, Ordering};
@@ -16,15 +18,18 @@ use uuid::Uuid;
 
 pub struct HealthServiceImpl {
     db: Database,
+    performance: Arc<PerformanceRegistry>,
     start_time: Instant,
+    #[allow(dead_code)]
     pipeline_count: Arc<AtomicU32>,
     model_count: Arc<AtomicU32>,
 }
 
 impl HealthServiceImpl {
-    pub fn new(db: Database) -> Self {
+    pub fn new(db: Database, performance: Arc<PerformanceRegistry>) -> Self {
         Self {
             db,
+            performance,
             start_time: Instant::now(),
             pipeline_count: Arc::new(AtomicU32::new(0)),
             model_count: Arc::new(AtomicU32::new(0)),
@@ -37,6 +42,69 @@ impl HealthServiceImpl {
             nanos: dt.timestamp_subsec_nanos() as i32,
         })
     }
+
+    /// Models served by the pipeline's ml-inference stages.
+    fn pipeline_models(spec_yaml: &str) -> Vec<String> {
+        let Ok(manifest) = flywheel_ml_dsl::parser::parse_manifest(spec_yaml) else {
+            return vec![];
+        };
+        manifest
+            .spec
+            .stages
+            .iter()
+            .filter(|stage| stage.stage_type == FlywheelStageType::MlInference)
+            .filter_map(|stage| {
+                serde_json::from_value::<MlInferenceConfig>(stage.config.clone()).ok()
+            })
+            .map(|config| config.model_id)
+            .collect()
+    }
+
+    /// Live performance of the given models from joined feedback.
+    fn snapshot(&self, model_ids: &[String]) -> Option<PerformanceSnapshot> {
+        let model_ids: Vec<&str> = model_ids.iter().map(String::as_str).collect();
+        self.performance.snapshot_models(&model_ids)
+    }
+
+    /// Metrics over the performance window. The feedback rate is the share
+    /// of the window's predictions that feedback has labeled.
+    fn pipeline_metrics(&self, performance: Option<&PerformanceSnapshot>) -> PipelineMetrics {
+        let Some(performance) = performance else {
+            return PipelineMetrics::default();
+        };
+        let predictions = performance.tracker.predictions() as f64;
+        let window = self.performance.window().as_secs_f64();
+        PipelineMetrics {
+            predictions_per_second: if window > 0.0 { predictions / window } else { 0.0 },
+            error_rate: performance.tracker.error_rate(),
+            avg_latency_ms: performance.tracker.latency_mean(),
+            p99_latency_ms: performance.tracker.latency_p99(),
+            current_accuracy: performance.accuracy.unwrap_or(0.0),
+            feedback_rate: if predictions > 0.0 {
+                (performance.examples as f64 / predictions).min(1.0)
+            } else {
+                0.0
+            },
+        }
+    }
+
+    fn feature_drifts(event: &drift_event::Model) -> Vec<FeatureDrift> {
+        let Some(json) = &event.feature_drifts_json else {
+            return vec![];
+        };
+
+        serde_json::from_value::<Vec<FeatureDriftResult>>(json.clone())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|f| FeatureDrift {
+                feature_name: f.feature_name,
+                psi_score: f.psi_score,
+                kl_divergence: f.kl_divergence,
+                is_drifted: f.is_drifted,
+                direction: f.direction.as_str().to_string(),
+            })
+            .collect()
+    }
 }
 
 #[tonic::async_trait]
@@ -98,18 +166,12 @@ impl HealthService for HealthServiceImpl {
             last_checked: Self::datetime_to_timestamp(event.detected_at),
         });
 
+        let performance = self.snapshot(&Self::pipeline_models(&pipeline.spec_yaml));
+
         let response = GetPipelineHealthResponse {
             pipeline_id: pipeline.id.to_string(),
             status: format!("{:?}", pipeline.status),
-            metrics: Some(PipelineMetrics {
-                records_per_second: 0.0,
-                predictions_per_second: 0.0,
-                error_rate: 0.0,
-                avg_latency_ms: 0,
-                p99_latency_ms: 0,
-                current_accuracy: 0.0,
-                feedback_rate: 0.0,
-            }),
+            metrics: Some(self.pipeline_metrics(performance.as_ref())),
             stages: vec![],
             drift: drift_summary,
         };
@@ -131,6 +193,17 @@ impl HealthService for HealthServiceImpl {
 
         let event = drift_events.first();
 
+        let model_ids = if req.model_id.is_empty() {
+            PipelineRepo::find_by_id(self.db.conn(), pipeline_id)
+                .await
+                .map_err(|e| Status::internal(format!("Database error: {}", e)))?
+                .map(|p| Self::pipeline_models(&p.spec_yaml))
+                .unwrap_or_default()
+        } else {
+            vec![req.model_id.clone()]
+        };
+        let performance = self.snapshot(&model_ids);
+
         let response = GetDriftStatusResponse {
             pipeline_id: req.pipeline_id,
             model_id: req.model_id,
@@ -140,17 +213,27 @@ impl HealthService for HealthServiceImpl {
             statistical: event.map(|e| StatisticalDrift {
                 psi_score: e.psi_score.unwrap_or(0.0),
                 kl_divergence: e.kl_divergence.unwrap_or(0.0),
-                feature_drifts: vec![],
-            }),
-            performance: event.map(|e| PerformanceDrift {
-                accuracy: 0.0,
-                accuracy_baseline: 0.0,
-                accuracy_delta: e.accuracy_delta.unwrap_or(0.0),
-                precision: 0.0,
-                recall: 0.0,
-                latency_p99_ms: 0,
-                error_rate: 0.0,
+                feature_drifts: Self::feature_drifts(e),
             }),
+            performance: match (&performance, event) {
+                (None, None) => None,
+                (Some(p), _) => Some(PerformanceDrift {
+                    accuracy: p.accuracy.unwrap_or(0.0),
+                    accuracy_baseline: p.baseline_accuracy.unwrap_or(0.0),
+                    accuracy_delta: p
+                        .accuracy_delta()
+                        .or(event.and_then(|e| e.accuracy_delta))
+                        .unwrap_or(0.0),
+                    precision: p.tracker.precision(),
+                    recall: p.tracker.recall(),
+                    latency_p99_ms: p.tracker.latency_p99(),
+                    error_rate: p.tracker.error_rate(),
+                }),
+                (None, Some(e)) => Some(PerformanceDrift {
+                    accuracy_delta: e.accuracy_delta.unwrap_or(0.0),
+                    ..Default::default()
+                }),
+            },
             detected_at: event.and_then(|e| Self::datetime_to_timestamp(e.detected_at)),
         };
 
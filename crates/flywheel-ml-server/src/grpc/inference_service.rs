@@ -1,98 +1,214 @@
 use std::collections::HashMap;
 use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use chrono::Utc;
-use flywheel_ml_db::{Database, ModelVersionRepo, PredictionRepo};
+use flywheel_ml_core::{FeatureVector, ModelError, ModelMetadata, ModelType, Prediction};
+use flywheel_ml_db::{entity::model_version, Database, ModelVersionRepo, PredictionRepo};
+use flywheel_ml_drift::PerformanceRegistry;
+use flywheel_ml_inference::convert;
 use flywheel_ml_proto::inference_service_server::InferenceService;
 use flywheel_ml_proto::{
-    prediction_result, AnomalyResult, BatchStats, HealthCheckRequest, HealthCheckResponse,
-    ModelInfoRequest, ModelInfoResponse, PredictBatchRequest, PredictBatchResponse,
-    PredictRequest, PredictResponse, PredictionResult,
+    BatchStats, HealthCheckRequest, HealthCheckResponse, ModelInfoRequest, ModelInfoResponse,
+    PredictBatchRequest, PredictBatchResponse, PredictError, PredictRequest, PredictResponse,
 };
-use prost_types::Timestamp;
+use flywheel_ml_transform::InferenceTransform;
 use tokio_stream::Stream;
 use tonic::{Request, Response, Status, Streaming};
 use uuid::Uuid;
 
+use crate::executor::prediction_payload;
+
+/// Serves predictions from the active version of each registered model,
+/// whether it runs behind a model server or in-process.
 pub struct InferenceServiceImpl {
     db: Database,
+    models: Arc<ServedModels>,
 }
 
 impl InferenceServiceImpl {
-    pub fn new(db: Database) -> Self {
-        Self { db }
+    pub fn new(db: Database, performance: Arc<PerformanceRegistry>, timeout: Duration) -> Self {
+        Self {
+            models: Arc::new(ServedModels {
+                db: db.clone(),
+                performance,
+                timeout,
+                loaded: Mutex::new(HashMap::new()),
+            }),
+            db,
+        }
     }
+}
+
+/// The models the service has loaded, by model id, along with the registry
+/// row they were loaded from. A model is loaded on its first request and
+/// reloaded once another version becomes active, so in-process models keep
+/// their state between requests.
+struct ServedModels {
+    db: Database,
+    performance: Arc<PerformanceRegistry>,
+    timeout: Duration,
+    loaded: Mutex<HashMap<String, (Uuid, Arc<InferenceTransform>)>>,
+}
+
+enum ServeError {
+    Database(String),
+    NotFound(String),
+    Model(ModelError),
+}
 
-    fn now_timestamp() -> Option<Timestamp> {
-        let now = Utc::now();
-        Some(Timestamp {
-            seconds: now.timestamp(),
-            nanos: now.timestamp_subsec_nanos() as i32,
-        })
+impl From<ServeError> for Status {
+    fn from(error: ServeError) -> Self {
+        match error {
+            ServeError::Database(message) => Status::internal(message),
+            ServeError::NotFound(model_id) => {
+                Status::not_found(format!("Model not found: {}", model_id))
+            }
+            ServeError::Model(e) => convert::model_error_to_status(&e),
+        }
     }
 }
 
-#[tonic::async_trait]
-impl InferenceService for InferenceServiceImpl {
-    async fn predict(
+impl ServedModels {
+    async fn resolve(
         &self,
-        request: Request<PredictRequest>,
-    ) -> Result<Response<PredictResponse>, Status> {
-        let req = request.into_inner();
-        let prediction_id = Uuid::new_v4().to_string();
-
-        let model = ModelVersionRepo::find_by_model_id(self.db.conn(), &req.model_id)
+        model_id: &str,
+    ) -> Result<(model_version::Model, Arc<InferenceTransform>), ServeError> {
+        let version = ModelVersionRepo::find_by_model_id(self.db.conn(), model_id)
             .await
-            .map_err(|e| Status::internal(format!("Database error: {}", e)))?
-            .ok_or_else(|| Status::not_found(format!("Model not found: {}", req.model_id)))?;
+            .map_err(|e| ServeError::Database(format!("Database error: {}", e)))?
+            .ok_or_else(|| ServeError::NotFound(model_id.to_string()))?;
+
+        if let Some((id, transform)) = self.loaded.lock().unwrap().get(model_id) {
+            if *id == version.id {
+                return Ok((version, transform.clone()));
+            }
+        }
+
+        let model_type = ModelType::from_name(&version.model_type).unwrap_or(ModelType::Custom);
+        let metadata = ModelMetadata::new(version.model_id.clone(), model_type)
+            .with_version(version.version.clone())
+            .with_endpoint(version.endpoint.clone());
+        let model =
+            flywheel_ml_inference::load_model(metadata, self.timeout).map_err(ServeError::Model)?;
+        let transform = Arc::new(InferenceTransform::new(model).with_timeout(self.timeout));
+        tracing::info!(
+            model_id = %version.model_id,
+            version = %version.version,
+            endpoint = %version.endpoint,
+            "Loaded model for inference"
+        );
+
+        // A concurrent request may have loaded the same version; either copy
+        // serves it.
+        self.loaded
+            .lock()
+            .unwrap()
+            .insert(model_id.to_string(), (version.id, transform.clone()));
+        Ok((version, transform))
+    }
+
+    async fn predict(&self, request: PredictRequest) -> Result<PredictResponse, ServeError> {
+        let model_id = request.model_id.clone();
+        let prediction = self
+            .predict_all(&model_id, vec![request])
+            .await?
+            .pop()
+            .expect("one result per request")?;
+        Ok(convert::prediction_to_response(&prediction))
+    }
+
+    /// Scores requests for one model in a single call to it. Results are in
+    /// request order; a failed prediction fails only its own request.
+    async fn predict_all(
+        &self,
+        model_id: &str,
+        requests: Vec<PredictRequest>,
+    ) -> Result<Vec<Result<Prediction, ServeError>>, ServeError> {
+        let (version, transform) = self.resolve(model_id).await?;
+        let features: Vec<FeatureVector> =
+            requests.iter().map(convert::feature_vector_from_request).collect();
 
-        let features_json = serde_json::json!({
-            "feature_count": req.features.len(),
-        });
+        let started = Instant::now();
+        let results = transform.process_batch(features.clone()).await;
+        let elapsed_us = started.elapsed().as_micros() as u64;
 
-        let anomaly_score = 0.3;
-        let is_anomaly = anomaly_score > 0.5;
+        let mut predictions = Vec::with_capacity(results.len());
+        for (result, features) in results.into_iter().zip(&features) {
+            predictions.push(match result {
+                Ok(mut prediction) => {
+                    prediction.model_id = version.model_id.clone();
+                    prediction.model_version = version.version.clone();
+                    // What the caller waited, not only what the model reports.
+                    prediction.latency_us = prediction.latency_us.max(elapsed_us);
+                    self.store(&mut prediction, features).await.map(|()| prediction)
+                }
+                Err(e) => {
+                    self.performance.record_error(
+                        &version.model_id,
+                        &version.version,
+                        elapsed_us / 1000,
+                    );
+                    Err(ServeError::Model(e))
+                }
+            });
+        }
+        Ok(predictions)
+    }
 
-        let prediction_json = serde_json::json!({
-            "score": anomaly_score,
-            "is_anomaly": is_anomaly,
-            "model_version": model.version,
-        });
+    /// Records the prediction's latency and persists it with the features it
+    /// was made from, so feedback can be joined with it. The stored row's id
+    /// becomes the prediction id.
+    async fn store(
+        &self,
+        prediction: &mut Prediction,
+        features: &FeatureVector,
+    ) -> Result<(), ServeError> {
+        self.performance.record_latency(
+            &prediction.model_id,
+            &prediction.model_version,
+            prediction.latency_us / 1000,
+        );
 
-        let prediction = PredictionRepo::create(
+        let stored = PredictionRepo::create(
             self.db.conn(),
             Uuid::nil(),
-            req.model_id.clone(),
-            model.version.clone(),
-            features_json,
-            prediction_json,
+            prediction.model_id.clone(),
+            prediction.model_version.clone(),
+            serde_json::to_value(&features.features).unwrap_or_default(),
+            prediction_payload(prediction),
         )
         .await
-        .map_err(|e| Status::internal(format!("Failed to store prediction: {}", e)))?;
+        .map_err(|e| ServeError::Database(format!("Failed to store prediction: {}", e)))?;
+        prediction.prediction_id = stored.id.to_string();
 
         tracing::debug!(
-            model_id = %req.model_id,
-            prediction_id = %prediction.id,
+            model_id = %prediction.model_id,
+            prediction_id = %prediction.prediction_id,
             "Prediction made"
         );
+        Ok(())
+    }
+}
 
-        let response = PredictResponse {
-            prediction_id: prediction.id.to_string(),
-            model_id: req.model_id,
-            model_version: model.version,
-            result: Some(PredictionResult {
-                result: Some(prediction_result::Result::Anomaly(AnomalyResult {
-                    score: anomaly_score,
-                    is_anomaly,
-                    threshold: 0.5,
-                    contributing_features: vec![],
-                })),
-            }),
-            confidence: 0.95,
-            latency_us: 100,
-            timestamp: Self::now_timestamp(),
-        };
+/// Reports a request that failed without failing the batch or stream it
+/// came in.
+fn predict_error(index: usize, request_id: &str, status: &Status) -> PredictError {
+    PredictError {
+        index: index as u32,
+        request_id: request_id.to_string(),
+        code: status.code() as i32,
+        message: status.message().to_string(),
+    }
+}
 
+#[tonic::async_trait]
+impl InferenceService for InferenceServiceImpl {
+    async fn predict(
+        &self,
+        request: Request<PredictRequest>,
+    ) -> Result<Response<PredictResponse>, Status> {
+        let response = self.models.predict(request.into_inner()).await?;
         Ok(Response::new(response))
     }
 
@@ -101,34 +217,90 @@ impl InferenceService for InferenceServiceImpl {
         request: Request<PredictBatchRequest>,
     ) -> Result<Response<PredictBatchResponse>, Status> {
         let req = request.into_inner();
-        let mut responses = Vec::with_capacity(req.requests.len());
-        let mut succeeded = 0u32;
-        let mut failed = 0u32;
-
-        for predict_req in req.requests {
-            let inner_request = Request::new(predict_req);
-            match self.predict(inner_request).await {
-                Ok(resp) => {
-                    responses.push(resp.into_inner());
-                    succeeded += 1;
+        let total = req.requests.len();
+        let request_ids: Vec<String> = req.requests.iter().map(|r| r.request_id.clone()).collect();
+
+        // Requests without a model id use the batch's.
+        let mut by_model: Vec<(String, Vec<usize>, Vec<PredictRequest>)> = Vec::new();
+        for (index, mut predict_req) in req.requests.into_iter().enumerate() {
+            if predict_req.model_id.is_empty() {
+                predict_req.model_id = req.model_id.clone();
+            }
+            match by_model.iter_mut().find(|(id, ..)| *id == predict_req.model_id) {
+                Some((_, indices, requests)) => {
+                    indices.push(index);
+                    requests.push(predict_req);
+                }
+                None => {
+                    let model_id = predict_req.model_id.clone();
+                    by_model.push((model_id, vec![index], vec![predict_req]));
+                }
+            }
+        }
+
+        let mut results: Vec<Option<Prediction>> = vec![None; total];
+        let mut errors = Vec::new();
+        for (model_id, indices, requests) in by_model {
+            let count = requests.len();
+            match self.models.predict_all(&model_id, requests).await {
+                Ok(predictions) => {
+                    for (index, prediction) in indices.into_iter().zip(predictions) {
+                        match prediction {
+                            Ok(prediction) => results[index] = Some(prediction),
+                            Err(e) => {
+                                let status = Status::from(e);
+                                tracing::warn!(
+                                    model_id = %model_id,
+                                    error = %status.message(),
+                                    "Batch prediction failed for one request"
+                                );
+                                errors.push(predict_error(index, &request_ids[index], &status));
+                            }
+                        }
+                    }
                 }
                 Err(e) => {
-                    tracing::warn!(error = %e, "Batch prediction failed for one request");
-                    failed += 1;
+                    let status = Status::from(e);
+                    tracing::warn!(
+                        model_id = %model_id,
+                        requests = count,
+                        error = %status.message(),
+                        "Batch prediction failed for every request of a model"
+                    );
+                    errors.extend(
+                        indices
+                            .into_iter()
+                            .map(|index| predict_error(index, &request_ids[index], &status)),
+                    );
                 }
             }
         }
+        errors.sort_by_key(|error| error.index);
+
+        let mut predictions = Vec::with_capacity(total - errors.len());
+        let mut responses = Vec::with_capacity(total - errors.len());
+        for (prediction, request_id) in results.into_iter().zip(request_ids) {
+            if let Some(prediction) = prediction {
+                responses.push(PredictResponse {
+                    request_id,
+                    ..convert::prediction_to_response(&prediction)
+                });
+                predictions.push(prediction);
+            }
+        }
+        let stats = flywheel_ml_core::BatchStats::from_predictions(&predictions);
 
         let response = PredictBatchResponse {
             batch_id: req.batch_id,
             responses,
             stats: Some(BatchStats {
-                total: succeeded + failed,
-                succeeded,
-                failed,
-                avg_latency_us: 100,
-                p99_latency_us: 200,
+                total: total as u32,
+                succeeded: stats.succeeded as u32,
+                failed: errors.len() as u32,
+                avg_latency_us: stats.avg_latency_us,
+                p99_latency_us: stats.p99_latency_us,
             }),
+            errors,
         };
 
         Ok(Response::new(response))
@@ -142,34 +314,25 @@ impl InferenceService for InferenceServiceImpl {
         request: Request<Streaming<PredictRequest>>,
     ) -> Result<Response<Self::PredictStreamStream>, Status> {
         let mut stream = request.into_inner();
-        let db = self.db.clone();
+        let models = self.models.clone();
 
+        // A request that fails is answered with its error; the stream goes on.
         let output = async_stream::try_stream! {
+            let mut index = 0;
             while let Some(req) = stream.message().await? {
-                let model = ModelVersionRepo::find_by_model_id(db.conn(), &req.model_id)
-                    .await
-                    .map_err(|e| Status::internal(format!("Database error: {}", e)))?
-                    .ok_or_else(|| Status::not_found(format!("Model not found: {}", req.model_id)))?;
-
-                let anomaly_score = 0.3;
-                let is_anomaly = anomaly_score > 0.5;
-
-                yield PredictResponse {
-                    prediction_id: Uuid::new_v4().to_string(),
-                    model_id: req.model_id,
-                    model_version: model.version,
-                    result: Some(PredictionResult {
-                        result: Some(prediction_result::Result::Anomaly(AnomalyResult {
-                            score: anomaly_score,
-                            is_anomaly,
-                            threshold: 0.5,
-                            contributing_features: vec![],
-                        })),
-                    }),
-                    confidence: 0.95,
-                    latency_us: 100,
-                    timestamp: InferenceServiceImpl::now_timestamp(),
+                let request_id = req.request_id.clone();
+                yield match models.predict(req).await {
+                    Ok(response) => PredictResponse {
+                        request_id,
+                        ..response
+                    },
+                    Err(e) => PredictResponse {
+                        error: Some(predict_error(index, &request_id, &Status::from(e))),
+                        request_id,
+                        ..Default::default()
+                    },
                 };
+                index += 1;
             }
         };
 
@@ -226,3 +389,62 @@ impl InferenceService for InferenceServiceImpl {
         Ok(Response::new(response))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flywheel_ml_db::entity::pipeline;
+    use sea_orm::{ActiveModelTrait, Set};
+
+    #[tokio::test]
+    async fn test_failed_batch_requests_are_reported_by_index() {
+        let db = crate::testing::database().await;
+        // Predictions served outside a pipeline are stored under the nil id.
+        let now = chrono::Utc::now();
+        let no_pipeline = pipeline::ActiveModel {
+            id: Set(Uuid::nil()),
+            name: Set("none".to_string()),
+            namespace: Set("default".to_string()),
+            spec_hash: Set(String::new()),
+            spec_yaml: Set(String::new()),
+            status: Set(pipeline::PipelineStatus::Pending),
+            conveyor_pipeline_id: Set(None),
+            created_at: Set(now),
+            updated_at: Set(now),
+        };
+        no_pipeline.insert(db.conn()).await.unwrap();
+        let version = ModelVersionRepo::create(
+            db.conn(),
+            "detector".to_string(),
+            "v1".to_string(),
+            "anomaly_detection".to_string(),
+            "builtin://zscore".to_string(),
+        )
+        .await
+        .unwrap();
+        ModelVersionRepo::activate(db.conn(), &version).await.unwrap();
+        let performance = Arc::new(PerformanceRegistry::new(Duration::from_secs(60)));
+        let service = InferenceServiceImpl::new(db, performance, Duration::from_secs(1));
+
+        let request = |request_id: &str, model_id: &str| PredictRequest {
+            request_id: request_id.to_string(),
+            model_id: model_id.to_string(),
+            ..Default::default()
+        };
+        let batch = PredictBatchRequest {
+            batch_id: "b1".to_string(),
+            model_id: "detector".to_string(),
+            requests: vec![request("r0", ""), request("r1", "missing"), request("r2", "")],
+        };
+        let response = service.predict_batch(Request::new(batch)).await.unwrap();
+        let response = response.into_inner();
+
+        let served: Vec<_> = response.responses.iter().map(|r| r.request_id.as_str()).collect();
+        assert_eq!(served, ["r0", "r2"]);
+        assert_eq!(response.errors.len(), 1);
+        assert_eq!(response.errors[0].index, 1);
+        assert_eq!(response.errors[0].request_id, "r1");
+        assert_eq!(response.errors[0].code, tonic::Code::NotFound as i32);
+        assert_eq!(response.stats.unwrap().failed, 1);
+    }
+}
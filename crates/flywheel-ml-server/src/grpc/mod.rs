@@ -1,7 +1,9 @@
 mod control_service;
+mod feedback_service;
 mod health_service;
 mod inference_service;
 
 pub use control_service::ControlServiceImpl;
+pub use feedback_service::FeedbackServiceImpl;
 pub use health_service::HealthServiceImpl;
 pub use inference_service::InferenceServiceImpl;
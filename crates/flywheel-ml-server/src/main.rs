@@ -5,9 +5,15 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 mod config;
 mod executor;
+mod feedback;
 mod grpc;
+#[allow(dead_code)]
 mod health;
+mod promotion;
+#[allow(dead_code)]
 mod registry;
+#[cfg(test)]
+mod testing;
 
 #[derive(Parser)]
 #[command(name = "flywheel-ml-server")]
@@ -66,9 +72,21 @@ async fn main() -> anyhow::Result<()> {
     flywheel_ml_db::migration::Migrator::up(db.conn(), None).await?;
     tracing::info!("Migrations complete");
 
+    // Live model performance, fed by the pipelines and read by the health service
+    let performance = Arc::new(flywheel_ml_drift::PerformanceRegistry::new(
+        std::time::Duration::from_secs(config.performance.window_secs),
+    ));
+
+    // Bandits of the running pipelines, rewarded by feedback from either side
+    let bandits = Arc::new(executor::BanditRegistry::new());
+
     // Start execution engine
     tracing::info!("Starting execution engine...");
-    let engine = Arc::new(executor::ExecutionEngine::new(db.clone()));
+    let engine = Arc::new(executor::ExecutionEngine::new(
+        db.clone(),
+        performance.clone(),
+        bandits.clone(),
+    ));
     let engine_handle = {
         let engine = engine.clone();
         tokio::spawn(async move {
@@ -76,12 +94,30 @@ async fn main() -> anyhow::Result<()> {
         })
     };
 
+    // Promote or roll back pending model versions from their live performance
+    if config.promotion.enabled {
+        let controller = promotion::PromotionController::new(
+            db.clone(),
+            performance.clone(),
+            config.promotion.gates(),
+            std::time::Duration::from_secs(config.promotion.interval_secs),
+        );
+        tokio::spawn(controller.run());
+    }
+
     // Start gRPC server
     tracing::info!("Starting gRPC server on {}", cli.bind_address);
 
     let control_service = grpc::ControlServiceImpl::new(db.clone());
-    let health_service = grpc::HealthServiceImpl::new(db.clone());
-    let inference_service = grpc::InferenceServiceImpl::new(db.clone());
+    let health_service = grpc::HealthServiceImpl::new(db.clone(), performance.clone());
+    let feedback_service = grpc::FeedbackServiceImpl::new(Arc::new(
+        feedback::DbFeedbackCollector::new(db.clone(), performance.clone(), bandits),
+    ));
+    let inference_service = grpc::InferenceServiceImpl::new(
+        db.clone(),
+        performance.clone(),
+        std::time::Duration::from_millis(config.inference.timeout_ms),
+    );
 
     let server = tonic::transport::Server::builder()
         .add_service(flywheel_ml_proto::control_service_server::ControlServiceServer::new(
@@ -93,6 +129,9 @@ async fn main() -> anyhow::Result<()> {
         .add_service(flywheel_ml_proto::inference_service_server::InferenceServiceServer::new(
             inference_service,
         ))
+        .add_service(flywheel_ml_proto::feedback_service_server::FeedbackServiceServer::new(
+            feedback_service,
+        ))
         .serve(cli.bind_address);
 
     tokio::select! {
@@ -0,0 +1,252 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use flywheel_ml_db::entity::model_promotion::{self, PromotionAction, PromotionTrigger};
+use flywheel_ml_db::entity::model_version::{self, ModelStatus};
+use flywheel_ml_db::{Database, ModelPromotionRepo, ModelVersionRepo};
+use flywheel_ml_drift::{
+    PerformanceRegistry, PerformanceSnapshot, PerformanceTracker, PromotionGates,
+    PromotionVerdict,
+};
+use sea_orm::{DbErr, TransactionTrait};
+
+/// Promotes pending model versions that did well on the shadow or canary
+/// traffic they served, and rolls back those that did not.
+///
+/// A pending version is judged once the feedback joined with its predictions
+/// reaches `min_feedback`, against the active version of the same model.
+/// Every decision is recorded with its reasons in `model_promotions`.
+pub struct PromotionController {
+    db: Database,
+    performance: Arc<PerformanceRegistry>,
+    gates: PromotionGates,
+    interval: Duration,
+}
+
+impl PromotionController {
+    pub fn new(
+        db: Database,
+        performance: Arc<PerformanceRegistry>,
+        gates: PromotionGates,
+        interval: Duration,
+    ) -> Self {
+        Self {
+            db,
+            performance,
+            gates,
+            interval,
+        }
+    }
+
+    pub async fn run(self) {
+        let mut ticker = tokio::time::interval(self.interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = self.evaluate_pending().await {
+                tracing::warn!(error = %e, "Failed to evaluate pending model versions");
+            }
+        }
+    }
+
+    async fn evaluate_pending(&self) -> Result<(), DbErr> {
+        let pending = ModelVersionRepo::list_by_status(self.db.conn(), ModelStatus::Pending).await?;
+        for candidate in pending {
+            // Versions that have served no traffic yet have nothing to judge.
+            let Some(snapshot) = self
+                .performance
+                .snapshot(&candidate.model_id, &candidate.version)
+            else {
+                continue;
+            };
+
+            let active = ModelVersionRepo::find_by_model_id(self.db.conn(), &candidate.model_id)
+                .await?;
+            let active_snapshot = active.as_ref().map(|active| {
+                self.performance
+                    .snapshot(&active.model_id, &active.version)
+                    .unwrap_or_else(|| PerformanceSnapshot {
+                        accuracy: None,
+                        baseline_accuracy: active.accuracy,
+                        examples: 0,
+                        tracker: PerformanceTracker::new(),
+                    })
+            });
+
+            let decision = self.gates.evaluate(&snapshot, active_snapshot.as_ref());
+            let metrics = serde_json::to_value(&decision.metrics).unwrap_or_default();
+            match decision.verdict {
+                PromotionVerdict::Wait => {
+                    tracing::debug!(
+                        model_id = %candidate.model_id,
+                        version = %candidate.version,
+                        reason = %decision.reasons.join("; "),
+                        "Model version not ready for promotion"
+                    );
+                }
+                PromotionVerdict::Promote => {
+                    promote(
+                        &self.db,
+                        &candidate,
+                        PromotionTrigger::Automatic,
+                        decision.reasons,
+                        metrics,
+                    )
+                    .await?;
+                }
+                PromotionVerdict::Rollback => {
+                    roll_back(
+                        &self.db,
+                        &candidate,
+                        active.as_ref(),
+                        PromotionTrigger::Automatic,
+                        decision.reasons,
+                        metrics,
+                    )
+                    .await?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Makes `version` the active version of its model, deprecating the one it
+/// replaces, and records the decision with the version replaced. The change
+/// and its record are made together or not at all.
+pub async fn promote(
+    db: &Database,
+    version: &model_version::Model,
+    trigger: PromotionTrigger,
+    reasons: Vec<String>,
+    metrics: serde_json::Value,
+) -> Result<model_promotion::Model, DbErr> {
+    let txn = db.conn().begin().await?;
+    let previous = ModelVersionRepo::find_by_model_id(&txn, &version.model_id).await?;
+    ModelVersionRepo::activate(&txn, version).await?;
+    let promotion = ModelPromotionRepo::create(
+        &txn,
+        version.model_id.clone(),
+        version.version.clone(),
+        previous.as_ref().map(|p| p.version.clone()),
+        Some(version.version.clone()),
+        PromotionAction::Promote,
+        trigger.clone(),
+        serde_json::json!(reasons),
+        metrics,
+    )
+    .await?;
+    txn.commit().await?;
+
+    tracing::info!(
+        model_id = %version.model_id,
+        version = %version.version,
+        previous = ?previous.as_ref().map(|p| &p.version),
+        trigger = ?trigger,
+        reasons = %reasons.join("; "),
+        "Model version promoted"
+    );
+    Ok(promotion)
+}
+
+/// Marks `version` failed so it is neither served nor promoted, makes
+/// `restore` the active version instead when given, and records the decision
+/// with the version that was active before. The change and its record are
+/// made together or not at all.
+pub async fn roll_back(
+    db: &Database,
+    version: &model_version::Model,
+    restore: Option<&model_version::Model>,
+    trigger: PromotionTrigger,
+    reasons: Vec<String>,
+    metrics: serde_json::Value,
+) -> Result<model_promotion::Model, DbErr> {
+    let txn = db.conn().begin().await?;
+    let previous = ModelVersionRepo::find_by_model_id(&txn, &version.model_id).await?;
+    ModelVersionRepo::set_status(&txn, version.id, ModelStatus::Failed).await?;
+    if let Some(restore) = restore.filter(|r| r.status != ModelStatus::Active) {
+        ModelVersionRepo::activate(&txn, restore).await?;
+    }
+    let promotion = ModelPromotionRepo::create(
+        &txn,
+        version.model_id.clone(),
+        version.version.clone(),
+        previous.map(|p| p.version),
+        restore.map(|r| r.version.clone()),
+        PromotionAction::Rollback,
+        trigger.clone(),
+        serde_json::json!(reasons),
+        metrics,
+    )
+    .await?;
+    txn.commit().await?;
+
+    tracing::warn!(
+        model_id = %version.model_id,
+        version = %version.version,
+        active = ?restore.map(|r| &r.version),
+        trigger = ?trigger,
+        reasons = %reasons.join("; "),
+        "Model version rolled back"
+    );
+    Ok(promotion)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_decisions_record_the_version_replaced() {
+        let db = crate::testing::database().await;
+        let mut versions = Vec::new();
+        for version in ["v1", "v2"] {
+            let created = ModelVersionRepo::create(
+                db.conn(),
+                "detector".to_string(),
+                version.to_string(),
+                "custom".to_string(),
+                "builtin://zscore".to_string(),
+            )
+            .await
+            .unwrap();
+            versions.push(created);
+        }
+        let reasons = || vec!["test".to_string()];
+
+        let promotion = promote(&db, &versions[0], PromotionTrigger::Manual, reasons(), json!({}))
+            .await
+            .unwrap();
+        assert_eq!(promotion.previous_version, None);
+        let promotion = promote(&db, &versions[1], PromotionTrigger::Manual, reasons(), json!({}))
+            .await
+            .unwrap();
+        assert_eq!(promotion.previous_version.as_deref(), Some("v1"));
+
+        let v2 = ModelVersionRepo::find_by_model_id(db.conn(), "detector")
+            .await
+            .unwrap()
+            .unwrap();
+        let v1 = ModelVersionRepo::find_previous(db.conn(), "detector")
+            .await
+            .unwrap()
+            .unwrap();
+        let rollback = roll_back(
+            &db,
+            &v2,
+            Some(&v1),
+            PromotionTrigger::Manual,
+            reasons(),
+            json!({}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(rollback.previous_version.as_deref(), Some("v2"));
+        assert_eq!(rollback.active_version.as_deref(), Some("v1"));
+        let active = ModelVersionRepo::find_by_model_id(db.conn(), "detector")
+            .await
+            .unwrap();
+        assert_eq!(active.map(|v| v.version).as_deref(), Some("v1"));
+    }
+}
@@ -0,0 +1,102 @@
+//! Helpers shared by the server's tests.
+
+use flywheel_ml_db::entity::pipeline;
+use flywheel_ml_db::migration::Migrator;
+use flywheel_ml_db::{Database, PipelineRepo};
+use sea_orm::ConnectOptions;
+use sea_orm_migration::MigratorTrait;
+use uuid::Uuid;
+
+/// An in-memory SQLite database with every migration applied.
+pub async fn database() -> Database {
+    // A single connection, as each SQLite connection has its own in-memory database.
+    let mut options = ConnectOptions::new("sqlite::memory:");
+    options.max_connections(1).sqlx_logging(false);
+    let conn = sea_orm::Database::connect(options).await.unwrap();
+    Migrator::up(&conn, None).await.unwrap();
+    Database::new(conn)
+}
+
+/// Registers a pipeline running `spec_yaml`, for rows that reference one.
+pub async fn pipeline(db: &Database, spec_yaml: &str) -> pipeline::Model {
+    let name = format!("test-{}", Uuid::new_v4());
+    PipelineRepo::create(
+        db.conn(),
+        name,
+        "default".to_string(),
+        String::new(),
+        spec_yaml.to_string(),
+    )
+    .await
+    .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flywheel_ml_db::entity;
+    use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, Schema, Statement};
+    use std::collections::BTreeMap;
+
+    async fn query(conn: &DatabaseConnection, sql: &str, column: &str) -> Vec<String> {
+        let statement = Statement::from_string(DbBackend::Sqlite, sql.to_string());
+        let rows = conn.query_all(statement).await.unwrap();
+        rows.iter().map(|row| row.try_get("", column).unwrap()).collect()
+    }
+
+    /// Every table's columns, and each unique index's columns.
+    async fn shape(conn: &DatabaseConnection) -> BTreeMap<String, Vec<String>> {
+        let mut shape = BTreeMap::new();
+        let tables = "SELECT name FROM sqlite_master WHERE type = 'table' \
+                      AND name NOT LIKE 'sqlite_%' AND name != 'seaql_migrations'";
+        for table in query(conn, tables, "name").await {
+            let mut columns = query(conn, &format!("PRAGMA table_info({})", table), "name").await;
+            columns.sort();
+            shape.insert(table, columns);
+        }
+        let indexes = "SELECT name FROM sqlite_master WHERE type = 'index' AND sql LIKE '%UNIQUE%'";
+        for index in query(conn, indexes, "name").await {
+            let columns = query(conn, &format!("PRAGMA index_info({})", index), "name").await;
+            shape.insert(index, columns);
+        }
+        shape
+    }
+
+    #[tokio::test]
+    async fn test_migrations_match_the_entities() {
+        let db = database().await;
+
+        let conn = sea_orm::Database::connect("sqlite::memory:").await.unwrap();
+        let schema = Schema::new(DbBackend::Sqlite);
+        let tables = [
+            schema.create_table_from_entity(entity::Pipeline),
+            schema.create_table_from_entity(entity::PipelineRun),
+            schema.create_table_from_entity(entity::ModelVersion),
+            schema.create_table_from_entity(entity::ModelPromotion),
+            schema.create_table_from_entity(entity::Feedback),
+            schema.create_table_from_entity(entity::Prediction),
+            schema.create_table_from_entity(entity::DriftEvent),
+            schema.create_table_from_entity(entity::DriftAction),
+            schema.create_table_from_entity(entity::DeadLetter),
+            schema.create_table_from_entity(entity::BanditArm),
+        ];
+        for table in &tables {
+            conn.execute(DbBackend::Sqlite.build(table)).await.unwrap();
+        }
+        conn.execute(DbBackend::Sqlite.build(&entity::bandit_arm::arm_key_index()))
+            .await
+            .unwrap();
+
+        // Entities declare only some of the unique indexes the migrations create.
+        let mut migrated = shape(db.conn()).await;
+        let declared = shape(&conn).await;
+        assert!(declared.contains_key("idx_bandit_arms_pipeline_stage_model"));
+        migrated.retain(|name, _| declared.contains_key(name) || !name.starts_with("idx_"));
+        assert_eq!(migrated, declared);
+
+        // Every migration can be rolled back and applied again.
+        Migrator::down(db.conn(), None).await.unwrap();
+        assert!(shape(db.conn()).await.is_empty());
+        Migrator::up(db.conn(), None).await.unwrap();
+    }
+}
@@ -64,7 +64,7 @@ impl<W: Write> FormatWriter for CsvWriter<W> {
                     "feedback_confidence",
                     "is_correct",
                 ])
-                .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+                .map_err(std::io::Error::other)?;
             self.headers_written = true;
         }
 
@@ -86,14 +86,14 @@ impl<W: Write> FormatWriter for CsvWriter<W> {
                     .map(|b| b.to_string())
                     .unwrap_or_default(),
             ])
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+            .map_err(std::io::Error::other)?;
         Ok(())
     }
 
     fn flush(&mut self) -> Result<(), std::io::Error> {
         self.writer
             .flush()
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))
+            .map_err(std::io::Error::other)
     }
 }
 
@@ -108,7 +108,7 @@ impl<W: Write + Send> ParquetBatchWriter<W> {
     pub fn new(writer: W, batch_size: usize) -> Result<Self, std::io::Error> {
         let schema = Arc::new(Self::schema());
         let arrow_writer = ArrowWriter::try_new(writer, schema.clone(), None)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+            .map_err(std::io::Error::other)?;
         Ok(Self {
             writer: Some(arrow_writer),
             buffer: Vec::with_capacity(batch_size),
@@ -144,7 +144,7 @@ impl<W: Write + Send> ParquetBatchWriter<W> {
         if let Some(writer) = self.writer.as_mut() {
             writer
                 .write(&batch)
-                .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+                .map_err(std::io::Error::other)?;
         }
 
         self.buffer.clear();
@@ -213,7 +213,7 @@ impl<W: Write + Send> ParquetBatchWriter<W> {
         ];
 
         RecordBatch::try_new(self.schema.clone(), columns)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))
+            .map_err(std::io::Error::other)
     }
 }
 
@@ -231,7 +231,7 @@ impl<W: Write + Send> FormatWriter for ParquetBatchWriter<W> {
         if let Some(writer) = self.writer.take() {
             writer
                 .close()
-                .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+                .map_err(std::io::Error::other)?;
         }
         Ok(())
     }
@@ -279,11 +279,11 @@ mod tests {
     #[test]
     fn test_csv_writer() {
         let mut buffer = Vec::new();
-        let mut writer = CsvWriter::new(&mut buffer);
-        let example = make_test_example();
-
-        writer.write(&example).unwrap();
-        writer.flush().unwrap();
+        {
+            let mut writer = CsvWriter::new(&mut buffer);
+            writer.write(&make_test_example()).unwrap();
+            writer.flush().unwrap();
+        }
 
         let output = String::from_utf8(buffer).unwrap();
         assert!(output.contains("example_id"));
@@ -293,11 +293,11 @@ mod tests {
     #[test]
     fn test_parquet_writer() {
         let mut buffer = Vec::new();
-        let mut writer = ParquetBatchWriter::new(&mut buffer, 100).unwrap();
-        let example = make_test_example();
-
-        writer.write(&example).unwrap();
-        writer.flush().unwrap();
+        {
+            let mut writer = ParquetBatchWriter::new(&mut buffer, 100).unwrap();
+            writer.write(&make_test_example()).unwrap();
+            writer.flush().unwrap();
+        }
 
         assert!(!buffer.is_empty());
     }
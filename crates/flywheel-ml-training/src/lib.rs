@@ -1,8 +1,10 @@
 pub mod exporter;
 pub mod format;
 pub mod labeler;
+pub mod reader;
 pub mod sampling;
 
 pub use exporter::*;
 pub use format::{CsvWriter, FormatWriter, JsonLinesWriter, ParquetBatchWriter};
+pub use reader::{read_exported_features, ExportedFeatures};
 pub use sampling::Sampler;
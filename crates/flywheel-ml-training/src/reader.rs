@@ -0,0 +1,192 @@
+use arrow::array::{Array, StringArray};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use crate::exporter::{ExportError, ExportFormat};
+
+/// The features of one exported example.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportedFeatures {
+    pub model_id: String,
+    pub features: serde_json::Value,
+}
+
+/// Reads the features of every example exported under `path`, which may be a
+/// single file or a directory walked recursively. Files are recognized by the
+/// extensions written by `LocalExporter`; anything else is skipped.
+pub fn read_exported_features(path: &Path) -> Result<Vec<ExportedFeatures>, ExportError> {
+    let mut files = Vec::new();
+    collect_files(path, &mut files)?;
+    files.sort();
+
+    let mut examples = Vec::new();
+    for file in files {
+        match export_format(&file) {
+            Some(ExportFormat::JsonLines) => read_jsonl(&file, &mut examples)?,
+            Some(ExportFormat::Csv) => read_csv(&file, &mut examples)?,
+            Some(ExportFormat::Parquet) => read_parquet(&file, &mut examples)?,
+            None => {}
+        }
+    }
+    Ok(examples)
+}
+
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>) -> Result<(), ExportError> {
+    if path.is_dir() {
+        for entry in std::fs::read_dir(path)? {
+            collect_files(&entry?.path(), files)?;
+        }
+    } else {
+        files.push(path.to_path_buf());
+    }
+    Ok(())
+}
+
+fn export_format(path: &Path) -> Option<ExportFormat> {
+    match path.extension()?.to_str()? {
+        "jsonl" => Some(ExportFormat::JsonLines),
+        "csv" => Some(ExportFormat::Csv),
+        "parquet" => Some(ExportFormat::Parquet),
+        _ => None,
+    }
+}
+
+fn parse_error(path: &Path, e: impl std::fmt::Display) -> ExportError {
+    ExportError::Serialization(format!("{}: {}", path.display(), e))
+}
+
+fn parse_features(
+    path: &Path,
+    model_id: &str,
+    features: &str,
+) -> Result<ExportedFeatures, ExportError> {
+    Ok(ExportedFeatures {
+        model_id: model_id.to_string(),
+        features: serde_json::from_str(features).map_err(|e| parse_error(path, e))?,
+    })
+}
+
+fn read_jsonl(path: &Path, examples: &mut Vec<ExportedFeatures>) -> Result<(), ExportError> {
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut value: serde_json::Value =
+            serde_json::from_str(&line).map_err(|e| parse_error(path, e))?;
+        examples.push(ExportedFeatures {
+            model_id: value["model_id"].as_str().unwrap_or_default().to_string(),
+            features: value["features"].take(),
+        });
+    }
+    Ok(())
+}
+
+fn read_csv(path: &Path, examples: &mut Vec<ExportedFeatures>) -> Result<(), ExportError> {
+    let mut reader = csv::Reader::from_path(path).map_err(|e| parse_error(path, e))?;
+    let headers = reader.headers().map_err(|e| parse_error(path, e))?.clone();
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|h| h == name)
+            .ok_or_else(|| parse_error(path, format!("missing '{}' column", name)))
+    };
+    let model_id = column("model_id")?;
+    let features = column("features")?;
+
+    for record in reader.records() {
+        let record = record.map_err(|e| parse_error(path, e))?;
+        examples.push(parse_features(
+            path,
+            record.get(model_id).unwrap_or_default(),
+            record.get(features).unwrap_or_default(),
+        )?);
+    }
+    Ok(())
+}
+
+fn read_parquet(path: &Path, examples: &mut Vec<ExportedFeatures>) -> Result<(), ExportError> {
+    let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(path)?)
+        .and_then(|builder| builder.build())
+        .map_err(|e| parse_error(path, e))?;
+
+    for batch in reader {
+        let batch = batch.map_err(|e| parse_error(path, e))?;
+        let column = |name: &str| {
+            batch
+                .column_by_name(name)
+                .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+                .ok_or_else(|| parse_error(path, format!("missing '{}' column", name)))
+        };
+        let model_ids = column("model_id")?;
+        let features = column("features")?;
+
+        for i in 0..batch.num_rows() {
+            if features.is_null(i) {
+                continue;
+            }
+            examples.push(parse_features(path, model_ids.value(i), features.value(i))?);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::{CsvWriter, FormatWriter, JsonLinesWriter, ParquetBatchWriter};
+    use chrono::Utc;
+    use flywheel_ml_core::{GroundTruth, LabeledExample};
+    use std::collections::HashMap;
+
+    fn example(cpu: f64) -> LabeledExample {
+        LabeledExample {
+            example_id: format!("ex-{}", cpu),
+            prediction_id: "pred-1".to_string(),
+            model_id: "model-1".to_string(),
+            model_version: "v1".to_string(),
+            features: serde_json::json!({"cpu": cpu, "region": "us"}),
+            prediction: serde_json::json!({"type": "anomaly", "score": 0.9}),
+            ground_truth: GroundTruth::Binary(true),
+            prediction_timestamp: Utc::now(),
+            feedback_timestamp: Utc::now(),
+            delay_ms: 1000,
+            feedback_confidence: 0.95,
+            is_correct: Some(true),
+            metadata: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_reads_every_export_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("model-1/2024-01-01");
+        std::fs::create_dir_all(&nested).unwrap();
+
+        let mut writer = JsonLinesWriter::new(File::create(nested.join("a.jsonl")).unwrap());
+        writer.write(&example(0.1)).unwrap();
+        writer.flush().unwrap();
+
+        let mut writer = CsvWriter::new(File::create(nested.join("b.csv")).unwrap());
+        writer.write(&example(0.2)).unwrap();
+        writer.flush().unwrap();
+
+        let mut writer =
+            ParquetBatchWriter::new(File::create(nested.join("c.parquet")).unwrap(), 10).unwrap();
+        writer.write(&example(0.3)).unwrap();
+        writer.flush().unwrap();
+
+        std::fs::write(nested.join("README.txt"), "ignored").unwrap();
+
+        let examples = read_exported_features(dir.path()).unwrap();
+        let cpus: Vec<f64> = examples
+            .iter()
+            .map(|e| e.features["cpu"].as_f64().unwrap())
+            .collect();
+        assert_eq!(cpus, vec![0.1, 0.2, 0.3]);
+        assert!(examples.iter().all(|e| e.model_id == "model-1"));
+        assert_eq!(examples[2].features["region"], "us");
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use flywheel_ml_core::{FallbackStrategy, FeatureVector, Prediction};
+use parking_lot::Mutex;
+
+/// Predictions kept for `FallbackStrategy::UseLastKnown`.
+const LAST_KNOWN_CAPACITY: usize = 10_000;
+
+/// What to do with one record whose prediction failed.
+#[derive(Debug, Clone)]
+pub enum Fallback {
+    /// Forward the record without a prediction.
+    PassThrough,
+    /// Write this value in place of the prediction.
+    Value(serde_json::Value),
+    /// Use an earlier prediction for the same features or source record.
+    LastKnown(Box<Prediction>),
+    /// Park the record in the dead-letter queue.
+    DeadLetter,
+    /// Drop the record.
+    Drop,
+}
+
+/// Applies a `FallbackStrategy` to records an inference call failed for,
+/// counting how often each fallback fires.
+#[derive(Debug)]
+pub struct InferenceFallback {
+    strategy: FallbackStrategy,
+    last_known: Mutex<LastKnownCache>,
+    counts: FallbackCounters,
+}
+
+#[derive(Debug, Default)]
+struct FallbackCounters {
+    pass_through: AtomicU64,
+    return_null: AtomicU64,
+    use_default: AtomicU64,
+    use_last_known: AtomicU64,
+    send_to_dlq: AtomicU64,
+    error: AtomicU64,
+}
+
+/// How many records each fallback was applied to. A `UseLastKnown` miss
+/// counts as a pass-through.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FallbackCounts {
+    pub pass_through: u64,
+    pub return_null: u64,
+    pub use_default: u64,
+    pub use_last_known: u64,
+    pub send_to_dlq: u64,
+    pub error: u64,
+}
+
+impl FallbackCounts {
+    pub fn total(&self) -> u64 {
+        self.pass_through
+            + self.return_null
+            + self.use_default
+            + self.use_last_known
+            + self.send_to_dlq
+            + self.error
+    }
+}
+
+impl InferenceFallback {
+    pub fn new(strategy: FallbackStrategy) -> Self {
+        Self {
+            strategy,
+            last_known: Mutex::new(LastKnownCache::new(LAST_KNOWN_CAPACITY)),
+            counts: FallbackCounters::default(),
+        }
+    }
+
+    pub fn strategy(&self) -> &FallbackStrategy {
+        &self.strategy
+    }
+
+    /// Remembers a successful prediction. Only kept under `UseLastKnown`.
+    pub fn remember(&self, features: &FeatureVector, prediction: &Prediction) {
+        if matches!(self.strategy, FallbackStrategy::UseLastKnown) {
+            self.last_known.lock().insert(features, prediction);
+        }
+    }
+
+    /// The fallback for a record whose features could not be scored.
+    pub fn resolve(&self, features: &FeatureVector) -> Fallback {
+        let (fallback, counter) = match &self.strategy {
+            FallbackStrategy::PassThrough => (Fallback::PassThrough, &self.counts.pass_through),
+            FallbackStrategy::ReturnNull => (
+                Fallback::Value(serde_json::Value::Null),
+                &self.counts.return_null,
+            ),
+            FallbackStrategy::UseDefault(value) => {
+                (Fallback::Value(value.clone()), &self.counts.use_default)
+            }
+            FallbackStrategy::UseLastKnown => match self.last_known.lock().get(features) {
+                Some(prediction) => (
+                    Fallback::LastKnown(Box::new(prediction)),
+                    &self.counts.use_last_known,
+                ),
+                None => (Fallback::PassThrough, &self.counts.pass_through),
+            },
+            FallbackStrategy::SendToDlq => (Fallback::DeadLetter, &self.counts.send_to_dlq),
+            FallbackStrategy::Error => (Fallback::Drop, &self.counts.error),
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        fallback
+    }
+
+    pub fn counts(&self) -> FallbackCounts {
+        FallbackCounts {
+            pass_through: self.counts.pass_through.load(Ordering::Relaxed),
+            return_null: self.counts.return_null.load(Ordering::Relaxed),
+            use_default: self.counts.use_default.load(Ordering::Relaxed),
+            use_last_known: self.counts.use_last_known.load(Ordering::Relaxed),
+            send_to_dlq: self.counts.send_to_dlq.load(Ordering::Relaxed),
+            error: self.counts.error.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Recent predictions keyed by `FeatureVector::hash` and by source record id,
+/// evicting the oldest once full.
+#[derive(Debug)]
+struct LastKnownCache {
+    capacity: usize,
+    predictions: HashMap<String, Prediction>,
+    order: VecDeque<String>,
+}
+
+impl LastKnownCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            predictions: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    fn keys(features: &FeatureVector) -> impl Iterator<Item = String> {
+        let source = (!features.source_record_id.is_empty())
+            .then(|| format!("source:{}", features.source_record_id));
+        std::iter::once(format!("hash:{}", features.hash())).chain(source)
+    }
+
+    fn insert(&mut self, features: &FeatureVector, prediction: &Prediction) {
+        for key in Self::keys(features) {
+            if self
+                .predictions
+                .insert(key.clone(), prediction.clone())
+                .is_none()
+            {
+                self.order.push_back(key);
+            }
+        }
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.predictions.remove(&oldest);
+            }
+        }
+    }
+
+    fn get(&self, features: &FeatureVector) -> Option<Prediction> {
+        Self::keys(features).find_map(|key| self.predictions.get(&key).cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flywheel_ml_core::{FeatureValue, PredictionResult};
+
+    fn features(source: &str, cpu: f64) -> FeatureVector {
+        FeatureVector::new(source).with_feature("cpu", FeatureValue::Float(cpu))
+    }
+
+    #[test]
+    fn test_value_fallbacks() {
+        let fallback = InferenceFallback::new(FallbackStrategy::UseDefault(
+            serde_json::json!({"score": 0.0}),
+        ));
+        assert!(matches!(
+            fallback.resolve(&features("a", 0.5)),
+            Fallback::Value(v) if v["score"] == 0.0
+        ));
+
+        let fallback = InferenceFallback::new(FallbackStrategy::ReturnNull);
+        assert!(matches!(
+            fallback.resolve(&features("a", 0.5)),
+            Fallback::Value(serde_json::Value::Null)
+        ));
+        assert_eq!(fallback.counts().return_null, 1);
+    }
+
+    #[test]
+    fn test_last_known_by_hash_or_source() {
+        let fallback = InferenceFallback::new(FallbackStrategy::UseLastKnown);
+        let prediction = Prediction::new("model-1", PredictionResult::anomaly(0.9, 0.5));
+        fallback.remember(&features("host-1", 0.5), &prediction);
+
+        // Same features from another record, then new features from the same record.
+        for f in [features("host-2", 0.5), features("host-1", 0.7)] {
+            match fallback.resolve(&f) {
+                Fallback::LastKnown(p) => assert_eq!(p.prediction_id, prediction.prediction_id),
+                other => panic!("expected last known prediction, got {:?}", other),
+            }
+        }
+
+        assert!(matches!(
+            fallback.resolve(&features("host-3", 0.1)),
+            Fallback::PassThrough
+        ));
+        let counts = fallback.counts();
+        assert_eq!(counts.use_last_known, 2);
+        assert_eq!(counts.pass_through, 1);
+        assert_eq!(counts.total(), 3);
+    }
+}
@@ -1,4 +1,8 @@
-use flywheel_ml_core::{FeatureError, FeatureExtractor, FeatureVector};
+use async_trait::async_trait;
+use flywheel_ml_core::{
+    record_id, FeatureDefinition, FeatureError, FeatureExtractor, FeatureSchema,
+    FeatureTransform, FeatureType, FeatureValue, FeatureVector, JsonPath,
+};
 
 pub struct FeatureExtractionTransform {
     extractor: Box<dyn FeatureExtractor>,
@@ -20,3 +24,205 @@ impl FeatureExtractionTransform {
         self.extractor.extract_batch(records).await
     }
 }
+
+/// A feature read from a JSON record by path, with an optional transform applied.
+///
+/// A record missing the field fails extraction unless the mapping is
+/// nullable, in which case the feature is `Null`.
+#[derive(Debug, Clone)]
+pub struct FieldMapping {
+    pub name: String,
+    pub path: JsonPath,
+    pub transform: Option<FeatureTransform>,
+    pub nullable: bool,
+}
+
+impl FieldMapping {
+    pub fn new(name: impl Into<String>, source_field: &str) -> Result<Self, FeatureError> {
+        Ok(Self {
+            name: name.into(),
+            path: JsonPath::parse(source_field)?,
+            transform: None,
+            nullable: false,
+        })
+    }
+
+    pub fn with_transform(mut self, transform: FeatureTransform) -> Self {
+        self.transform = Some(transform);
+        self
+    }
+
+    pub fn with_nullable(mut self, nullable: bool) -> Self {
+        self.nullable = nullable;
+        self
+    }
+
+    fn extract(&self, record: &serde_json::Value) -> Result<FeatureValue, FeatureError> {
+        let raw = match self.path.select(record) {
+            Some(raw) => raw,
+            None if self.nullable => return Ok(FeatureValue::Null),
+            None => return Err(FeatureError::MissingField(self.path.to_string())),
+        };
+        let value = json_to_feature_value(&self.name, raw)?;
+
+        match &self.transform {
+            Some(transform) if !value.is_null() => transform.apply(&value).map_err(|e| match e {
+                FeatureError::InvalidValue { reason, .. } => FeatureError::InvalidValue {
+                    feature: self.name.clone(),
+                    reason,
+                },
+                other => other,
+            }),
+            _ => Ok(value),
+        }
+    }
+}
+
+/// Extracts features from JSON records using JSONPath expressions such as
+/// `$.metrics.cpu.usage_percent`.
+pub struct JsonPathFeatureExtractor {
+    fields: Vec<FieldMapping>,
+    schema: FeatureSchema,
+}
+
+impl JsonPathFeatureExtractor {
+    pub fn new(name: impl Into<String>, fields: Vec<FieldMapping>) -> Self {
+        let features = fields
+            .iter()
+            .map(|field| FeatureDefinition {
+                name: field.name.clone(),
+                feature_type: output_type(field.transform.as_ref()),
+                nullable: field.nullable,
+                description: Some(field.path.to_string()),
+                default_value: None,
+            })
+            .collect();
+
+        Self {
+            schema: FeatureSchema {
+                name: name.into(),
+                features,
+            },
+            fields,
+        }
+    }
+}
+
+#[async_trait]
+impl FeatureExtractor for JsonPathFeatureExtractor {
+    fn name(&self) -> &str {
+        &self.schema.name
+    }
+
+    fn schema(&self) -> &FeatureSchema {
+        &self.schema
+    }
+
+    async fn extract(&self, record: &serde_json::Value) -> Result<FeatureVector, FeatureError> {
+        let mut vector = FeatureVector::new(record_id(record));
+        for field in &self.fields {
+            vector
+                .features
+                .insert(field.name.clone(), field.extract(record)?);
+        }
+        Ok(vector)
+    }
+}
+
+fn output_type(transform: Option<&FeatureTransform>) -> FeatureType {
+    match transform {
+        Some(FeatureTransform::Bucketize { .. }) => FeatureType::Int,
+        Some(FeatureTransform::OneHot { .. }) => FeatureType::FloatArray,
+        // Untransformed fields are assumed to be numeric metrics.
+        _ => FeatureType::Float,
+    }
+}
+
+fn json_to_feature_value(
+    feature: &str,
+    value: &serde_json::Value,
+) -> Result<FeatureValue, FeatureError> {
+    use serde_json::Value;
+
+    let invalid = |reason: &str| FeatureError::InvalidValue {
+        feature: feature.to_string(),
+        reason: reason.to_string(),
+    };
+
+    match value {
+        Value::Null => Ok(FeatureValue::Null),
+        Value::Bool(b) => Ok(FeatureValue::Boolean(*b)),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => Ok(FeatureValue::Int(i)),
+            None => n
+                .as_f64()
+                .map(FeatureValue::Float)
+                .ok_or_else(|| invalid("number out of range")),
+        },
+        Value::String(s) => Ok(FeatureValue::String(s.clone())),
+        Value::Array(items) => items
+            .iter()
+            .map(|item| item.as_f64())
+            .collect::<Option<Vec<_>>>()
+            .map(FeatureValue::FloatArray)
+            .ok_or_else(|| invalid("arrays must contain only numbers")),
+        Value::Object(_) => Err(invalid("objects cannot be used as feature values")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn extractor() -> JsonPathFeatureExtractor {
+        JsonPathFeatureExtractor::new(
+            "infra",
+            vec![
+                FieldMapping::new("cpu_usage", "$.metrics.cpu.usage_percent")
+                    .unwrap()
+                    .with_transform(FeatureTransform::Normalize {
+                        min: 0.0,
+                        max: 100.0,
+                    }),
+                FieldMapping::new("host", "$.metadata.host").unwrap(),
+                FieldMapping::new("error_rate", "$.metrics.http.error_rate").unwrap(),
+            ],
+        )
+    }
+
+    #[tokio::test]
+    async fn test_extract_with_transforms() {
+        let record = json!({
+            "id": "rec-1",
+            "metrics": {"cpu": {"usage_percent": 50}, "http": {"error_rate": 0.02}},
+            "metadata": {"host": "web-1"}
+        });
+
+        let vector = extractor().extract(&record).await.unwrap();
+        assert_eq!(vector.source_record_id, "rec-1");
+        assert_eq!(vector.get_float("cpu_usage"), Some(0.5));
+        assert_eq!(vector.get_string("host"), Some("web-1"));
+        assert_eq!(vector.get_float("error_rate"), Some(0.02));
+    }
+
+    #[tokio::test]
+    async fn test_missing_nullable_field_is_null() {
+        let extractor = JsonPathFeatureExtractor::new(
+            "infra",
+            vec![
+                FieldMapping::new("error_rate", "$.metrics.http.error_rate").unwrap(),
+                FieldMapping::new("cpu_usage", "$.metrics.cpu.usage_percent")
+                    .unwrap()
+                    .with_transform(FeatureTransform::Log1p)
+                    .with_nullable(true),
+            ],
+        );
+        let record = json!({"id": 7, "metrics": {"http": {"error_rate": 0.5}}});
+
+        let vector = extractor.extract(&record).await.unwrap();
+        assert_eq!(vector.source_record_id, "7");
+        assert_eq!(vector.get("cpu_usage"), Some(&FeatureValue::Null));
+        assert!(extractor.validate(&vector).is_ok());
+    }
+}
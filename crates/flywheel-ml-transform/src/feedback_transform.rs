@@ -40,6 +40,7 @@ impl FeedbackJoinTransform {
             .map_err(|e| FeedbackError::JoinFailed(format!("Database error: {}", e)))?
             .ok_or_else(|| FeedbackError::PredictionNotFound(feedback.prediction_id.clone()))?;
 
+        let variant = prediction_model.variant.clone();
         let stored = self.convert_to_stored_prediction(prediction_model)?;
 
         if stored.is_expired() {
@@ -65,7 +66,8 @@ impl FeedbackJoinTransform {
             return Ok(None);
         }
 
-        let labeled = LabeledExample::from_prediction_and_feedback(&stored, &feedback);
+        let mut labeled = LabeledExample::from_prediction_and_feedback(&stored, &feedback);
+        labeled.metadata.insert("variant".to_string(), variant);
 
         tracing::info!(
             example_id = %labeled.example_id,
@@ -78,6 +80,33 @@ impl FeedbackJoinTransform {
         Ok(Some(labeled))
     }
 
+    /// Labels the shadow predictions made for the same record as the
+    /// feedback's prediction with the same ground truth, so a shadow model's
+    /// accuracy can be compared with the model it shadows. Call it once
+    /// `process` has joined the feedback.
+    pub async fn process_shadows(
+        &self,
+        feedback: &FeedbackRecord,
+    ) -> Result<Vec<LabeledExample>, FeedbackError> {
+        let Ok(prediction_uuid) = uuid::Uuid::parse_str(&feedback.prediction_id) else {
+            return Ok(Vec::new());
+        };
+
+        let shadows = PredictionRepo::find_shadows(&self.db, prediction_uuid)
+            .await
+            .map_err(|e| FeedbackError::JoinFailed(format!("Database error: {}", e)))?;
+
+        let mut labeled = Vec::with_capacity(shadows.len());
+        for shadow in shadows {
+            let variant = shadow.variant.clone();
+            let stored = self.convert_to_stored_prediction(shadow)?;
+            let mut example = LabeledExample::from_prediction_and_feedback(&stored, feedback);
+            example.metadata.insert("variant".to_string(), variant);
+            labeled.push(example);
+        }
+        Ok(labeled)
+    }
+
     pub async fn process_batch(
         &self,
         feedbacks: Vec<FeedbackRecord>,
@@ -161,4 +190,11 @@ mod tests {
         });
         assert_eq!(extract_confidence(&json_no_conf), None);
     }
+
+    #[tokio::test]
+    async fn test_invalid_prediction_id_rejected() {
+        let transform = FeedbackJoinTransform::new(Arc::new(DatabaseConnection::Disconnected));
+        let result = transform.process(make_test_feedback("not-a-uuid")).await;
+        assert!(matches!(result, Err(FeedbackError::PredictionNotFound(_))));
+    }
 }
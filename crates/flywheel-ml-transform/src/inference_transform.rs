@@ -1,58 +1,295 @@
-use flywheel_ml_core::{FeatureVector, Model, ModelError, Prediction};
+use flywheel_ml_core::{FeatureVector, Model, ModelError, Prediction, RetryConfig};
+use flywheel_ml_inference::batch::{BatchConfig, MicroBatcher};
 use flywheel_ml_inference::circuit_breaker::CircuitBreaker;
-use std::sync::Arc;
+use rand::Rng;
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
 
+/// Sends features to a model behind a circuit breaker, the default one
+/// unless `with_circuit_breaker` supplies another.
+///
+/// With a `RetryConfig`, calls failing with a retryable `ModelError` are sent
+/// again after a jittered exponential backoff. Every attempt goes through the
+/// circuit breaker, so retries stop as soon as it opens. With a timeout, each
+/// attempt that takes longer fails with `ModelError::Timeout`.
+///
+/// With a `BatchConfig`, records are grouped into micro-batches by a
+/// `MicroBatcher`, started on first use, and each batch is sent as one call.
 pub struct InferenceTransform {
+    caller: Arc<Caller>,
+    batching: Option<BatchConfig>,
+    batcher: OnceLock<MicroBatcher>,
+}
+
+struct Caller {
     model: Arc<dyn Model>,
     circuit_breaker: CircuitBreaker,
+    retry: Option<RetryConfig>,
+    timeout: Option<Duration>,
 }
 
 impl InferenceTransform {
     pub fn new(model: Arc<dyn Model>) -> Self {
         Self {
-            model,
-            circuit_breaker: CircuitBreaker::new(Default::default()),
+            caller: Arc::new(Caller {
+                model,
+                circuit_breaker: CircuitBreaker::new(Default::default()),
+                retry: None,
+                timeout: None,
+            }),
+            batching: None,
+            batcher: OnceLock::new(),
         }
     }
 
-    pub async fn process(&self, features: FeatureVector) -> Result<Prediction, ModelError> {
-        if !self.circuit_breaker.can_execute() {
-            return Err(ModelError::CircuitBreakerOpen(
-                self.model.metadata().model_id.clone(),
-            ));
-        }
+    pub fn with_circuit_breaker(mut self, circuit_breaker: CircuitBreaker) -> Self {
+        self.caller_mut().circuit_breaker = circuit_breaker;
+        self
+    }
 
-        match self.model.predict(features).await {
-            Ok(prediction) => {
-                self.circuit_breaker.record_success();
-                Ok(prediction)
-            }
-            Err(e) => {
-                self.circuit_breaker.record_failure();
-                Err(e)
+    pub fn with_retry(mut self, retry: RetryConfig) -> Self {
+        self.caller_mut().retry = Some(retry);
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.caller_mut().timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_batching(mut self, config: BatchConfig) -> Self {
+        self.batching = Some(config);
+        self
+    }
+
+    fn caller_mut(&mut self) -> &mut Caller {
+        // The caller is only shared once the batcher starts, after building.
+        Arc::get_mut(&mut self.caller).expect("InferenceTransform configured after first use")
+    }
+
+    pub fn model_id(&self) -> &str {
+        self.caller.model_id()
+    }
+
+    pub async fn process(&self, features: FeatureVector) -> Result<Prediction, ModelError> {
+        match self.batcher() {
+            Some(batcher) => batcher.submit(features).await,
+            None => {
+                self.caller
+                    .call(|| self.caller.model.predict(features.clone()))
+                    .await
             }
         }
     }
 
+    /// Predicts every record, returning results in the order of `features`.
+    ///
+    /// Without batching the records are sent as one call and share its
+    /// outcome. With batching they are split into micro-batches, which may be
+    /// in flight together and fail independently.
     pub async fn process_batch(
         &self,
         features: Vec<FeatureVector>,
-    ) -> Result<Vec<Prediction>, ModelError> {
-        if !self.circuit_breaker.can_execute() {
-            return Err(ModelError::CircuitBreakerOpen(
-                self.model.metadata().model_id.clone(),
-            ));
+    ) -> Vec<Result<Prediction, ModelError>> {
+        if let Some(batcher) = self.batcher() {
+            return batcher.submit_all(features).await;
         }
 
-        match self.model.predict_batch(features).await {
+        let count = features.len();
+        let results = self
+            .caller
+            .call(|| self.caller.model.predict_batch(features.clone()))
+            .await;
+        match results {
+            Ok(predictions) if predictions.len() == count => {
+                predictions.into_iter().map(Ok).collect()
+            }
             Ok(predictions) => {
-                self.circuit_breaker.record_success();
-                Ok(predictions)
+                let error = ModelError::InferenceFailed(format!(
+                    "model returned {} predictions for a batch of {}",
+                    predictions.len(),
+                    count
+                ));
+                vec![Err(error); count]
+            }
+            Err(e) => vec![Err(e); count],
+        }
+    }
+
+    fn batcher(&self) -> Option<&MicroBatcher> {
+        let config = self.batching.as_ref()?;
+        Some(self.batcher.get_or_init(|| {
+            let caller = self.caller.clone();
+            MicroBatcher::spawn(config.clone(), move |features| {
+                let caller = caller.clone();
+                async move {
+                    caller
+                        .call(|| caller.model.predict_batch(features.clone()))
+                        .await
+                }
+            })
+        }))
+    }
+}
+
+impl Caller {
+    fn model_id(&self) -> &str {
+        &self.model.metadata().model_id
+    }
+
+    async fn call<T, F, Fut>(&self, mut attempt: F) -> Result<T, ModelError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, ModelError>>,
+    {
+        let max_retries = self.retry.as_ref().map_or(0, |r| r.max_retries);
+        let mut retries = 0;
+
+        loop {
+            let Some(permit) = self.circuit_breaker.try_acquire() else {
+                return Err(ModelError::CircuitBreakerOpen(self.model_id().to_string()));
+            };
+
+            let error = match self.with_deadline(attempt()).await {
+                Ok(result) => {
+                    permit.succeed();
+                    return Ok(result);
+                }
+                // A rejected input says nothing about the model's health.
+                Err(e @ ModelError::InvalidInput(_)) => e,
+                Err(e) => {
+                    permit.fail();
+                    e
+                }
+            };
+
+            let retry = match &self.retry {
+                Some(retry) if retries < max_retries && error.is_retryable() => retry,
+                _ => return Err(error),
+            };
+            let delay = jittered(retry.delay(retries));
+            tracing::debug!(
+                model_id = %self.model_id(),
+                retry = retries + 1,
+                delay_ms = delay.as_millis() as u64,
+                error = %error,
+                "Retrying inference"
+            );
+            tokio::time::sleep(delay).await;
+            retries += 1;
+        }
+    }
+
+    async fn with_deadline<T>(
+        &self,
+        fut: impl Future<Output = Result<T, ModelError>>,
+    ) -> Result<T, ModelError> {
+        match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, fut)
+                .await
+                .unwrap_or_else(|_| Err(ModelError::Timeout(timeout.as_millis() as u64))),
+            None => fut.await,
+        }
+    }
+}
+
+/// Picks a delay between half and all of `delay`, so callers that failed
+/// together do not retry in lockstep.
+fn jittered(delay: Duration) -> Duration {
+    delay.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use flywheel_ml_core::{ModelMetadata, ModelType, PredictionResult};
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Fails its first `failures` calls with `error`, then scores 0.1.
+    struct FlakyModel {
+        metadata: ModelMetadata,
+        failures: u32,
+        error: fn() -> ModelError,
+        calls: AtomicU32,
+    }
+
+    impl FlakyModel {
+        fn new(failures: u32, error: fn() -> ModelError) -> Self {
+            Self {
+                metadata: ModelMetadata::new("flaky", ModelType::Custom),
+                failures,
+                error,
+                calls: AtomicU32::new(0),
             }
-            Err(e) => {
-                self.circuit_breaker.record_failure();
-                Err(e)
+        }
+
+        fn calls(&self) -> u32 {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl Model for FlakyModel {
+        fn metadata(&self) -> &ModelMetadata {
+            &self.metadata
+        }
+
+        async fn predict(&self, _features: FeatureVector) -> Result<Prediction, ModelError> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            if call < self.failures {
+                return Err((self.error)());
             }
+            Ok(Prediction::new(
+                "flaky",
+                PredictionResult::anomaly(0.1, 0.5),
+            ))
+        }
+    }
+
+    fn retry(max_retries: u32) -> RetryConfig {
+        RetryConfig {
+            max_retries,
+            initial_delay_ms: 1,
+            max_delay_ms: 5,
+            multiplier: 2.0,
         }
     }
+
+    fn unavailable() -> ModelError {
+        ModelError::Unavailable("down".to_string())
+    }
+
+    #[tokio::test]
+    async fn test_retries_transient_failures() {
+        let model = Arc::new(FlakyModel::new(2, unavailable));
+        let transform = InferenceTransform::new(model.clone()).with_retry(retry(3));
+
+        let result = transform.process(FeatureVector::new("rec-1")).await;
+        assert!(result.is_ok());
+        assert_eq!(model.calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_retries() {
+        let model = Arc::new(FlakyModel::new(10, unavailable));
+        let transform = InferenceTransform::new(model.clone()).with_retry(retry(2));
+
+        let result = transform.process(FeatureVector::new("rec-1")).await;
+        assert!(matches!(result, Err(ModelError::Unavailable(_))));
+        assert_eq!(model.calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_input_is_not_retried() {
+        let model = Arc::new(FlakyModel::new(1, || {
+            ModelError::InvalidInput("missing feature: cpu".to_string())
+        }));
+        let transform = InferenceTransform::new(model.clone()).with_retry(retry(3));
+
+        let result = transform.process(FeatureVector::new("rec-1")).await;
+        assert!(matches!(result, Err(ModelError::InvalidInput(_))));
+        assert_eq!(model.calls(), 1);
+    }
 }
@@ -1,4 +1,5 @@
 pub mod drift_transform;
+pub mod fallback;
 pub mod feature_transform;
 pub mod feedback_transform;
 pub mod inference_transform;
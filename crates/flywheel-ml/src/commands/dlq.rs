@@ -0,0 +1,105 @@
+use clap::{Args, Subcommand};
+
+use super::Context;
+
+#[derive(Args)]
+pub struct DlqArgs {
+    #[command(subcommand)]
+    pub command: DlqCommand,
+}
+
+#[derive(Subcommand)]
+pub enum DlqCommand {
+    #[command(about = "List records parked by send_to_dlq or blocking drift detection")]
+    List {
+        #[arg(short, long)]
+        pipeline: String,
+
+        /// pending, queued or replayed (default: all)
+        #[arg(long)]
+        status: Option<String>,
+
+        #[arg(long, default_value = "20")]
+        limit: i32,
+
+        /// Print each record's payload
+        #[arg(long)]
+        payload: bool,
+    },
+    #[command(about = "Send pending dead letters back through the stage that parked them")]
+    Replay {
+        #[arg(short, long)]
+        pipeline: String,
+
+        #[arg(long, default_value = "1000")]
+        limit: i32,
+    },
+}
+
+pub async fn run(ctx: &Context, args: DlqArgs) -> anyhow::Result<()> {
+    let client = ctx.client().await?;
+
+    match args.command {
+        DlqCommand::List {
+            pipeline,
+            status,
+            limit,
+            payload,
+        } => {
+            let response = client.list_dead_letters(&pipeline, status, limit).await?;
+
+            println!("Dead letters for pipeline: {}", pipeline);
+            println!();
+            println!(
+                "{:<20}  {:<16}  {:<24}  {:<8}  ERROR",
+                "TIME", "STAGE", "RECORD", "STATUS"
+            );
+            println!("{}", "-".repeat(100));
+
+            for dead_letter in &response.dead_letters {
+                let timestamp = dead_letter
+                    .created_at
+                    .as_ref()
+                    .and_then(|ts| chrono::DateTime::from_timestamp(ts.seconds, ts.nanos as u32))
+                    .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_else(|| "unknown".to_string());
+
+                println!(
+                    "{:<20}  {:<16}  {:<24}  {:<8}  {}",
+                    timestamp,
+                    truncate(&dead_letter.stage_id, 16),
+                    truncate(&dead_letter.record_id, 24),
+                    dead_letter.status,
+                    dead_letter.error_message
+                );
+                if payload {
+                    println!("    {}", String::from_utf8_lossy(&dead_letter.payload_json));
+                }
+            }
+
+            if response.dead_letters.is_empty() {
+                println!("No dead letters.");
+            }
+        }
+        DlqCommand::Replay { pipeline, limit } => {
+            let response = client.replay_dead_letters(&pipeline, limit).await?;
+            println!(
+                "Queued {} dead letter(s) for replay on pipeline {}",
+                response.queued, pipeline
+            );
+            if response.queued > 0 {
+                println!("The pipeline must be running for them to be replayed.");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn truncate(s: &str, max_len: usize) -> String {
+    if s.len() > max_len {
+        format!("{}...", &s[..max_len.saturating_sub(3)])
+    } else {
+        s.to_string()
+    }
+}
@@ -1,4 +1,7 @@
 use clap::{Args, Subcommand};
+use flywheel_ml_core::{FeatureValue, FeatureVector};
+use flywheel_ml_drift::{BaselineBuilder, BaselineLocation};
+use std::path::PathBuf;
 
 use super::Context;
 
@@ -29,13 +32,30 @@ pub enum DriftCommand {
         #[arg(long, default_value = "10")]
         limit: i32,
     },
+    #[command(about = "Build a drift baseline from exported training data")]
+    Baseline {
+        /// Exported file or directory (jsonl, csv or parquet)
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Baseline URI (file://, s3:// or a local path)
+        #[arg(short, long)]
+        output: String,
+
+        /// Only use examples of this model
+        #[arg(short, long)]
+        model: Option<String>,
+
+        /// Features to include (default: all)
+        #[arg(short, long, value_delimiter = ',')]
+        features: Vec<String>,
+    },
 }
 
 pub async fn run(ctx: &Context, args: DriftArgs) -> anyhow::Result<()> {
-    let client = ctx.client().await?;
-
     match args.command {
         DriftCommand::Status { pipeline, model } => {
+            let client = ctx.client().await?;
             let pipeline_id = pipeline.unwrap_or_default();
             let model_id = model.unwrap_or_default();
 
@@ -71,6 +91,7 @@ pub async fn run(ctx: &Context, args: DriftArgs) -> anyhow::Result<()> {
             }
         }
         DriftCommand::History { pipeline, model, limit } => {
+            let client = ctx.client().await?;
             let response = client.list_drift_events(&pipeline, model, limit).await?;
 
             println!("Drift History for pipeline: {} (last {})", pipeline, limit);
@@ -108,7 +129,67 @@ pub async fn run(ctx: &Context, args: DriftArgs) -> anyhow::Result<()> {
                 println!("No drift events recorded.");
             }
         }
+        DriftCommand::Baseline {
+            input,
+            output,
+            model,
+            features,
+        } => {
+            let examples = flywheel_ml_training::read_exported_features(&input)?;
+
+            let mut builder = BaselineBuilder::new();
+            let mut used = 0;
+            for example in &examples {
+                if model.as_ref().is_some_and(|m| *m != example.model_id) {
+                    continue;
+                }
+                builder.observe(&feature_vector(&example.features, &features));
+                used += 1;
+            }
+            if used == 0 {
+                anyhow::bail!("No exported examples found in {}", input.display());
+            }
+
+            let baseline = builder.build(model);
+            let location = BaselineLocation::parse(&output)?;
+            location.save(&baseline).await?;
+
+            println!("Baseline written to {}", output);
+            println!("Examples: {}", used);
+            println!();
+            println!("{:<24}  {:<12}  {:>8}", "FEATURE", "TYPE", "COUNT");
+            println!("{}", "-".repeat(48));
+            for (name, feature) in &baseline.features {
+                println!("{:<24}  {:<12}  {:>8}", name, feature.type_name(), feature.count());
+            }
+        }
     }
 
     Ok(())
 }
+
+/// Converts an exported feature object the way the drift stage reads record
+/// fields: numbers and booleans as numeric features, strings as categories.
+fn feature_vector(features: &serde_json::Value, only: &[String]) -> FeatureVector {
+    let mut vector = FeatureVector::new("baseline");
+    let Some(object) = features.as_object() else {
+        return vector;
+    };
+
+    for (name, value) in object {
+        if !only.is_empty() && !only.contains(name) {
+            continue;
+        }
+        let value = match value {
+            serde_json::Value::Number(n) => match n.as_f64() {
+                Some(v) => FeatureValue::Float(v),
+                None => continue,
+            },
+            serde_json::Value::Bool(b) => FeatureValue::Boolean(*b),
+            serde_json::Value::String(s) => FeatureValue::Categorical(s.clone()),
+            _ => continue,
+        };
+        vector.features.insert(name.clone(), value);
+    }
+    vector
+}
@@ -34,7 +34,7 @@ pub async fn run(ctx: &Context, args: GraphArgs) -> anyhow::Result<()> {
                 println!();
 
                 if let Ok(manifest) = flywheel_ml_dsl::parser::parse_manifest(&pipeline.spec_yaml) {
-                    print_source(&manifest.spec.source);
+                    print_source(manifest.spec.source.name());
 
                     for (i, stage) in manifest.spec.stages.iter().enumerate() {
                         print_connector();
@@ -45,11 +45,11 @@ pub async fn run(ctx: &Context, args: HealthArgs) -> anyhow::Result<()> {
 
             if let Some(metrics) = health.metrics {
                 println!("\nMetrics:");
-                println!("  Records/sec: {:.0}", metrics.records_per_second);
                 println!("  Predictions/sec: {:.0}", metrics.predictions_per_second);
                 println!("  Error Rate: {:.2}%", metrics.error_rate * 100.0);
                 println!("  Avg Latency: {}ms", metrics.avg_latency_ms);
                 println!("  P99 Latency: {}ms", metrics.p99_latency_ms);
+                println!("  Feedback Rate: {:.2}%", metrics.feedback_rate * 100.0);
                 println!("  Accuracy: {:.2}", metrics.current_accuracy);
             }
 
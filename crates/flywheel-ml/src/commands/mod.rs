@@ -1,3 +1,4 @@
+pub mod dlq;
 pub mod drift;
 pub mod export;
 pub mod graph;
@@ -13,6 +14,7 @@ use flywheel_ml_client::FlywheelClient;
 pub struct Context {
     pub server: String,
     pub namespace: String,
+    #[allow(dead_code)]
     pub verbose: bool,
 }
 
@@ -29,6 +29,41 @@ pub enum ModelCommand {
         model_a: String,
         model_b: String,
     },
+
+    #[command(about = "Make a model version the active one")]
+    Promote {
+        model_id: String,
+        version: String,
+
+        /// Recorded with the decision
+        #[arg(long)]
+        reason: Option<String>,
+    },
+
+    #[command(about = "Take a model version out of service")]
+    Rollback {
+        model_id: String,
+
+        /// The version to roll back (default: the active version)
+        #[arg(long)]
+        version: Option<String>,
+
+        /// The version to serve instead (default: the previously active version)
+        #[arg(long)]
+        to: Option<String>,
+
+        /// Recorded with the decision
+        #[arg(long)]
+        reason: Option<String>,
+    },
+
+    #[command(about = "Show promotion and rollback decisions")]
+    Promotions {
+        model_id: String,
+
+        #[arg(long, default_value = "20")]
+        limit: i32,
+    },
 }
 
 pub async fn run(ctx: &Context, args: ModelArgs) -> anyhow::Result<()> {
@@ -76,6 +111,32 @@ pub async fn run(ctx: &Context, args: ModelArgs) -> anyhow::Result<()> {
                 println!("Performance:");
                 println!("  Accuracy:     {:.2}", model.accuracy);
                 println!("  Latency P99:  {}ms", model.latency_p99_ms);
+
+                if !model.allocations.is_empty() {
+                    println!();
+                    println!("Traffic allocation:");
+                    println!(
+                        "  {:<20}  {:<20}  {:<10}  {:>8}  {:>8}  {:>7}",
+                        "STAGE", "MODEL", "VERSION", "PULLS", "REWARD", "SHARE"
+                    );
+                    for arm in &model.allocations {
+                        let rewarded = arm.successes + arm.failures;
+                        let reward = if rewarded > 0 {
+                            format!("{:.3}", arm.successes as f64 / rewarded as f64)
+                        } else {
+                            "-".to_string()
+                        };
+                        println!(
+                            "  {:<20}  {:<20}  {:<10}  {:>8}  {:>8}  {:>6.1}%",
+                            truncate(&arm.stage_id, 20),
+                            truncate(&arm.model_id, 20),
+                            truncate(&arm.model_version, 10),
+                            arm.pulls,
+                            reward,
+                            arm.traffic_share * 100.0
+                        );
+                    }
+                }
             } else {
                 println!("Model not found: {}", model_id);
             }
@@ -130,6 +191,68 @@ pub async fn run(ctx: &Context, args: ModelArgs) -> anyhow::Result<()> {
                 }
             }
         }
+        ModelCommand::Promote {
+            model_id,
+            version,
+            reason,
+        } => {
+            let response = client.promote_model(&model_id, &version, reason).await?;
+            if let Some(promotion) = response.promotion {
+                println!("Promoted {} {}", promotion.model_id, promotion.version);
+                if !promotion.previous_version.is_empty() {
+                    println!("Replaced version: {}", promotion.previous_version);
+                }
+            }
+        }
+        ModelCommand::Rollback {
+            model_id,
+            version,
+            to,
+            reason,
+        } => {
+            let response = client.rollback_model(&model_id, version, to, reason).await?;
+            if let Some(promotion) = response.promotion {
+                println!("Rolled back {} {}", promotion.model_id, promotion.version);
+                if !promotion.active_version.is_empty() {
+                    println!("Active version: {}", promotion.active_version);
+                }
+            }
+        }
+        ModelCommand::Promotions { model_id, limit } => {
+            let response = client.list_model_promotions(&model_id, limit).await?;
+
+            println!("Promotion decisions for {}", model_id);
+            println!();
+            println!(
+                "{:<20}  {:<8}  {:<8}  {:<9}  {:<8}  {:<8}  REASONS",
+                "TIME", "ACTION", "VERSION", "TRIGGER", "PREVIOUS", "ACTIVE"
+            );
+            println!("{}", "-".repeat(100));
+
+            for promotion in &response.promotions {
+                let timestamp = promotion
+                    .decided_at
+                    .as_ref()
+                    .and_then(|ts| chrono::DateTime::from_timestamp(ts.seconds, ts.nanos as u32))
+                    .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_else(|| "unknown".to_string());
+
+                println!(
+                    "{:<20}  {:<8}  {:<8}  {:<9}  {:<8}  {:<8}  {}",
+                    timestamp,
+                    promotion.action,
+                    truncate(&promotion.version, 8),
+                    promotion.trigger,
+                    truncate(&promotion.previous_version, 8),
+                    truncate(&promotion.active_version, 8),
+                    promotion.reasons.join("; ")
+                );
+            }
+
+            if response.promotions.is_empty() {
+                println!("No decisions recorded.");
+            }
+        }
     }
 
     Ok(())
@@ -43,8 +43,8 @@ pub async fn run(ctx: &Context, args: StatsArgs) -> anyhow::Result<()> {
             println!("{}", "=".repeat(70));
             println!();
             println!(
-                "{:<26}  {:<10}  {:<10}  {:<12}",
-                "PIPELINE", "RECORDS/s", "PREDS/s", "ERROR_RATE"
+                "{:<26}  {:<10}  {:<12}",
+                "PIPELINE", "PREDS/s", "ERROR_RATE"
             );
             println!("{}", "-".repeat(70));
 
@@ -54,17 +54,16 @@ pub async fn run(ctx: &Context, args: StatsArgs) -> anyhow::Result<()> {
                 if let Ok(health_resp) = health {
                     if let Some(metrics) = health_resp.metrics {
                         println!(
-                            "{:<26}  {:<10.0}  {:<10.0}  {:<.2}%",
+                            "{:<26}  {:<10.0}  {:<.2}%",
                             truncate(&pipeline.name, 26),
-                            metrics.records_per_second,
                             metrics.predictions_per_second,
                             metrics.error_rate * 100.0
                         );
                     } else {
                         println!(
-                            "{:<26}  {:<10}  {:<10}  {:<12}",
+                            "{:<26}  {:<10}  {:<12}",
                             truncate(&pipeline.name, 26),
-                            "-", "-", "-"
+                            "-", "-"
                         );
                     }
                 }
@@ -100,8 +99,8 @@ pub async fn run(ctx: &Context, args: StatsArgs) -> anyhow::Result<()> {
 
             if let Some(metrics) = health.metrics {
                 println!("Current Metrics:");
-                println!("  Records/sec:      {:.0}", metrics.records_per_second);
-                println!("  Error Rate:       {:.2}%", metrics.error_rate * 100.0);
+                println!("  Feedback Rate:    {:.2}%", metrics.feedback_rate * 100.0);
+                println!("  Current Accuracy: {:.2}", metrics.current_accuracy);
                 println!();
                 println!("Note: Detailed feedback statistics require");
                 println!("      the feedback collection to be enabled.");
@@ -2,6 +2,7 @@ use clap::{Parser, Subcommand};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 mod commands;
+#[allow(dead_code)]
 mod output;
 
 #[derive(Parser)]
@@ -43,6 +44,9 @@ enum Commands {
     #[command(about = "Drift detection status and history")]
     Drift(commands::drift::DriftArgs),
 
+    #[command(about = "Inspect and replay dead-lettered records")]
+    Dlq(commands::dlq::DlqArgs),
+
     #[command(about = "Model management")]
     Model(commands::model::ModelArgs),
 
@@ -77,6 +81,7 @@ async fn main() -> anyhow::Result<()> {
         Commands::Graph(args) => commands::graph::run(&ctx, args).await?,
         Commands::Export(args) => commands::export::run(&ctx, args).await?,
         Commands::Drift(args) => commands::drift::run(&ctx, args).await?,
+        Commands::Dlq(args) => commands::dlq::run(&ctx, args).await?,
         Commands::Model(args) => commands::model::run(&ctx, args).await?,
         Commands::Stats(args) => commands::stats::run(&ctx, args).await?,
         Commands::Validate(args) => commands::validate::run(&ctx, args).await?,
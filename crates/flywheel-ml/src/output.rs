@@ -6,7 +6,7 @@ pub enum OutputFormat {
     Yaml,
 }
 
-pub fn print_table<T: Serialize>(items: &[T], headers: &[&str]) {
+pub fn print_table<T: Serialize>(_items: &[T], headers: &[&str]) {
     // Print header
     for (i, h) in headers.iter().enumerate() {
         if i > 0 {